@@ -4,7 +4,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     fs::create_dir("src/pb").unwrap_or(());
     tonic_build::configure()
         .build_client(true)
-        .build_server(false) // Only clients needed
+        .build_server(true)
         .out_dir("src/pb")
         .include_file("mod.rs")
         .compile_protos(
@@ -14,6 +14,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "protos/generation.proto",
                 "protos/caikit_data_model_caikit_nlp.proto",
                 "protos/health_check.proto",
+                "protos/orchestrator.proto",
             ],
             &["protos"],
         )
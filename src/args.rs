@@ -27,6 +27,8 @@ pub struct Args {
     pub http_port: u16,
     #[clap(default_value = "8034", long, env)]
     pub health_http_port: u16,
+    #[clap(long, env)]
+    pub grpc_port: Option<u16>,
     #[clap(
         default_value = "config/config.yaml",
         long,
@@ -34,6 +36,8 @@ pub struct Args {
     )]
     pub config_path: PathBuf,
     #[clap(long, env)]
+    pub config_watch_interval: Option<u64>,
+    #[clap(long, env)]
     pub tls_cert_path: Option<PathBuf>,
     #[clap(long, env)]
     pub tls_key_path: Option<PathBuf>,
@@ -41,6 +45,8 @@ pub struct Args {
     pub tls_client_ca_cert_path: Option<PathBuf>,
     #[clap(default_value = "false", long, env)]
     pub start_up_health_check: bool,
+    #[clap(long, env)]
+    pub health_probe_interval: Option<u64>,
     #[clap(long, env, value_delimiter = ',')]
     pub otlp_export: Vec<OtlpExport>,
     #[clap(default_value_t = LogFormat::default(), long, env)]
@@ -0,0 +1,997 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+//! Audit log of guardrail decisions.
+//!
+//! Handlers start an [`Audit`] of each task, recording the detectors requested, and finish it
+//! with the task result, recording the detections and outcome of the response. Records are
+//! written to the configured [`AuditSink`] by a background task, off the request path.
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use http::{HeaderMap, HeaderName, HeaderValue};
+use opentelemetry::trace::TraceId;
+use ring::hmac;
+use serde::Serialize;
+use tokio::{fs::File, io::AsyncWriteExt, sync::mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{error, warn};
+
+use crate::{
+    clients::{
+        detector::ContentAnalysisResponse,
+        openai::{
+            ChatCompletion, ChatCompletionChunk, ChatCompletionsResponse, ChatDetections,
+            Completion, CompletionsResponse, OrchestratorWarning,
+        },
+    },
+    config::{AuditConfig, AuditContent, AuditSinkConfig, PolicyDirection, PolicyOutcome},
+    models::{
+        ChatDetectionResult, ClassifiedGeneratedTextResult, ClassifiedGeneratedTextStreamResult,
+        ContextDocsResult, DetectionOnGenerationResult, DetectionResult, DetectionWarning,
+        DetectionWarningReason, DetectorParams, GenerationWithDetectionResult, PolicyMatch,
+        StreamingContentDetectionResponse, TextContentDetectionResult,
+        TextGenTokenClassificationResults,
+    },
+    orchestrator::{
+        Error,
+        handlers::batch_text_content_detection::BatchRecordResult,
+        types::{Detection, Tenant},
+    },
+    utils::metrics,
+};
+
+/// Number of records buffered for the sink, records are dropped when full.
+const AUDIT_BUFFER_SIZE: usize = 1024;
+
+/// Audit record of a task.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord {
+    /// Unix timestamp (in milliseconds) of when the task started
+    pub timestamp: u64,
+    /// Trace ID
+    pub trace_id: String,
+    /// Tenant ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// Endpoint of the task
+    pub endpoint: &'static str,
+    /// Detectors requested and their params
+    pub detectors: Vec<AuditDetector>,
+    /// Detections returned
+    pub detections: Vec<AuditDetection>,
+    /// Outcome of the task
+    pub outcome: AuditOutcome,
+    /// Error failing the task
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Duration of the task in milliseconds, until the response or its stream ended
+    pub duration_ms: u64,
+}
+
+/// Detector requested for a task.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditDetector {
+    pub detector_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<PolicyDirection>,
+    pub params: DetectorParams,
+}
+
+/// Detection returned for a task.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditDetection {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detector_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<PolicyDirection>,
+    pub detection_type: String,
+    pub detection: String,
+    pub score: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<usize>,
+    /// Detected text, hashed or left out depending on the audit config
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Outcome of a task, ordered by severity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// No policy was applied to detections
+    #[default]
+    Allowed,
+    /// Detections were returned with a warning
+    Warned,
+    /// Detected spans were masked
+    Redacted,
+    /// The input or output was blocked
+    Blocked,
+    /// The task failed
+    Failed,
+}
+
+impl From<PolicyOutcome> for AuditOutcome {
+    fn from(value: PolicyOutcome) -> Self {
+        match value {
+            PolicyOutcome::Allow => Self::Allowed,
+            PolicyOutcome::Warn => Self::Warned,
+            PolicyOutcome::Redact => Self::Redacted,
+            PolicyOutcome::Block => Self::Blocked,
+        }
+    }
+}
+
+/// Writes audit records to the configured sink.
+#[derive(Debug)]
+pub struct Auditor {
+    content: AuditContent,
+    /// Key detected text is hashed with
+    hash_key: Option<hmac::Key>,
+    tx: mpsc::Sender<AuditRecord>,
+}
+
+impl Auditor {
+    /// Creates an auditor, spawning the task writing records to the sink.
+    /// Errors if the hash key cannot be read.
+    pub fn new(config: &AuditConfig) -> Result<Self, AuditError> {
+        let hash_key = config
+            .hash_key_path
+            .as_ref()
+            .map(|path| {
+                let key = std::fs::read(path)?;
+                let key = key.trim_ascii();
+                if key.is_empty() {
+                    return Err(AuditError::HashKey(format!(
+                        "hash key file `{}` is empty",
+                        path.display()
+                    )));
+                }
+                Ok(hmac::Key::new(hmac::HMAC_SHA256, key))
+            })
+            .transpose()?;
+        let mut sink: Box<dyn AuditSink> = match &config.sink {
+            AuditSinkConfig::File {
+                path,
+                max_size_mb,
+                max_files,
+            } => Box::new(FileSink::new(
+                path.clone(),
+                max_size_mb * 1024 * 1024,
+                *max_files,
+            )),
+            AuditSinkConfig::Webhook { url, headers } => {
+                Box::new(WebhookSink::new(url.clone(), headers))
+            }
+        };
+        let (tx, mut rx) = mpsc::channel::<AuditRecord>(AUDIT_BUFFER_SIZE);
+        tokio::spawn(async move {
+            while let Some(record) = rx.recv().await {
+                if let Err(error) = sink.write(&record).await {
+                    error!(trace_id = record.trace_id, %error, "failed to write audit record");
+                }
+            }
+        });
+        Ok(Self {
+            content: config.content,
+            hash_key,
+            tx,
+        })
+    }
+
+    /// Starts an audit of a task.
+    pub fn start(
+        self: &Arc<Self>,
+        endpoint: &'static str,
+        trace_id: TraceId,
+        tenant: Option<&Tenant>,
+    ) -> Audit {
+        Audit::new(Some(self.clone()), endpoint, trace_id, tenant)
+    }
+}
+
+/// Audit of a task in progress, a no-op if auditing is disabled.
+#[derive(Debug)]
+pub struct Audit {
+    auditor: Option<Arc<Auditor>>,
+    record: AuditRecord,
+    started: Instant,
+    /// Detectors with the `redact` action, by direction
+    redacting: HashSet<(Option<PolicyDirection>, String)>,
+    /// Set by the task if it anonymized its input
+    anonymized: AnonymizedFlag,
+}
+
+/// Flag set by a task when it replaces entities of its input with placeholders.
+/// Anonymized detections are not returned, so the audit cannot record them from the response.
+#[derive(Debug, Clone, Default)]
+pub struct AnonymizedFlag(Arc<AtomicBool>);
+
+impl AnonymizedFlag {
+    pub fn set(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    fn is_set(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Audit {
+    fn new(
+        auditor: Option<Arc<Auditor>>,
+        endpoint: &'static str,
+        trace_id: TraceId,
+        tenant: Option<&Tenant>,
+    ) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        Self {
+            auditor,
+            record: AuditRecord {
+                timestamp,
+                trace_id: trace_id.to_string(),
+                tenant: tenant.map(|tenant| tenant.id.clone()),
+                endpoint,
+                detectors: Vec::new(),
+                detections: Vec::new(),
+                outcome: AuditOutcome::default(),
+                error: None,
+                duration_ms: 0,
+            },
+            started: Instant::now(),
+            redacting: HashSet::new(),
+            anonymized: AnonymizedFlag::default(),
+        }
+    }
+
+    /// Returns an audit recording nothing.
+    pub fn disabled() -> Self {
+        Self::new(None, "", TraceId::INVALID, None)
+    }
+
+    /// Returns `true` if the audit is recorded.
+    pub fn enabled(&self) -> bool {
+        self.auditor.is_some()
+    }
+
+    /// Records the detectors requested for a direction.
+    pub fn detectors(
+        mut self,
+        direction: Option<PolicyDirection>,
+        detectors: &HashMap<String, DetectorParams>,
+    ) -> Self {
+        if self.enabled() {
+            let mut detectors = detectors
+                .iter()
+                .map(|(detector_id, params)| AuditDetector {
+                    detector_id: detector_id.clone(),
+                    direction,
+                    params: params.clone(),
+                })
+                .collect::<Vec<_>>();
+            detectors.sort_by(|a, b| a.detector_id.cmp(&b.detector_id));
+            self.record.detectors.extend(detectors);
+        }
+        self
+    }
+
+    /// Records detectors with the `redact` action for a direction.
+    /// Detections of these detectors are returned once their spans are masked,
+    /// so the task is recorded as redacted when one is recorded.
+    pub fn redactions(
+        mut self,
+        direction: Option<PolicyDirection>,
+        detectors: impl IntoIterator<Item = String>,
+    ) -> Self {
+        if self.enabled() {
+            self.redacting.extend(
+                detectors
+                    .into_iter()
+                    .map(|detector_id| (direction, detector_id)),
+            );
+        }
+        self
+    }
+
+    /// Returns the flag the task sets if it anonymizes its input,
+    /// recording the task as redacted.
+    pub fn anonymized_flag(&self) -> AnonymizedFlag {
+        self.anonymized.clone()
+    }
+
+    /// Records a detection.
+    pub fn detection(&mut self, direction: Option<PolicyDirection>, detection: Detection) {
+        let Some(auditor) = &self.auditor else {
+            return;
+        };
+        let span = detection.span();
+        let text = match auditor.content {
+            AuditContent::Omit => None,
+            AuditContent::Hash => detection
+                .text
+                .as_deref()
+                .zip(auditor.hash_key.as_ref())
+                .map(|(text, key)| hash(key, text)),
+            AuditContent::Include => detection.text,
+        };
+        if let Some(detector_id) = &detection.detector_id {
+            // Detections without a span are not redacted
+            if span.is_some() && self.redacting.contains(&(direction, detector_id.clone())) {
+                self.outcome(AuditOutcome::Redacted);
+            }
+        }
+        // Detectors of tasks providing them with their input stream are recorded from detections
+        if let Some(detector_id) = &detection.detector_id {
+            if !self
+                .record
+                .detectors
+                .iter()
+                .any(|detector| &detector.detector_id == detector_id)
+            {
+                self.record.detectors.push(AuditDetector {
+                    detector_id: detector_id.clone(),
+                    direction,
+                    params: DetectorParams::default(),
+                });
+            }
+        }
+        self.record.detections.push(AuditDetection {
+            detector_id: detection.detector_id,
+            direction,
+            detection_type: detection.detection_type,
+            detection: detection.detection,
+            score: detection.score,
+            start: detection.start,
+            end: detection.end,
+            text,
+        });
+    }
+
+    /// Records an outcome, keeping the most severe one.
+    pub fn outcome(&mut self, outcome: AuditOutcome) {
+        self.record.outcome = self.record.outcome.max(outcome);
+    }
+
+    /// Records the outcome reported by a warning.
+    /// Policies block input and warn on output by default when no rule fires.
+    fn warning(&mut self, reason: Option<DetectionWarningReason>, policy: Option<&PolicyMatch>) {
+        let default = match reason {
+            Some(DetectionWarningReason::UnsuitableInput) => PolicyOutcome::Block,
+            Some(DetectionWarningReason::UnsuitableOutput) => PolicyOutcome::Warn,
+            _ => return,
+        };
+        let outcome = policy.map(|policy| policy.outcome).unwrap_or(default);
+        self.outcome(outcome.into());
+    }
+
+    /// Records an error failing the task.
+    pub fn fail(&mut self, error: &Error) {
+        self.outcome(AuditOutcome::Failed);
+        self.record.error = Some(error.to_string());
+    }
+
+    /// Finishes the audit with the task result.
+    /// Streaming responses are recorded as their items are returned and emitted when they end.
+    pub fn finish<R: AuditResponse>(mut self, result: Result<R, Error>) -> Result<R, Error> {
+        if !self.enabled() {
+            return result;
+        }
+        match result {
+            Ok(response) => Ok(response.audit(self)),
+            Err(error) => {
+                self.fail(&error);
+                self.emit();
+                Err(error)
+            }
+        }
+    }
+
+    /// Sends the record to the sink.
+    fn emit(mut self) {
+        let Some(auditor) = self.auditor.take() else {
+            return;
+        };
+        if self.anonymized.is_set() {
+            self.outcome(AuditOutcome::Redacted);
+        }
+        self.record.duration_ms = self.started.elapsed().as_millis() as u64;
+        if let Err(error) = auditor.tx.try_send(self.record) {
+            metrics::on_audit_record_dropped();
+            warn!(%error, "audit record dropped");
+        }
+    }
+}
+
+/// Returns the HMAC-SHA256 of a text, hex encoded.
+fn hash(key: &hmac::Key, text: &str) -> String {
+    hmac::sign(key, text.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Responses recorded by an audit.
+pub trait AuditResponse {
+    /// Records the response and emits the audit, returning the response.
+    fn audit(self, audit: Audit) -> Self;
+}
+
+/// Responses and stream items recorded by an audit.
+pub trait AuditItem {
+    fn record(&self, audit: &mut Audit);
+}
+
+impl<T: AuditItem> AuditResponse for T {
+    fn audit(self, mut audit: Audit) -> Self {
+        self.record(&mut audit);
+        audit.emit();
+        self
+    }
+}
+
+impl<T: AuditItem + Send + 'static> AuditResponse for ReceiverStream<T> {
+    fn audit(self, audit: Audit) -> Self {
+        ReceiverStream::new(audit_receiver(self.into_inner(), audit))
+    }
+}
+
+impl AuditResponse for ChatCompletionsResponse {
+    fn audit(self, audit: Audit) -> Self {
+        match self {
+            ChatCompletionsResponse::Unary(chat_completion) => {
+                ChatCompletionsResponse::Unary(chat_completion.audit(audit))
+            }
+            ChatCompletionsResponse::Streaming(rx) => {
+                ChatCompletionsResponse::Streaming(audit_receiver(rx, audit))
+            }
+        }
+    }
+}
+
+impl AuditResponse for CompletionsResponse {
+    fn audit(self, audit: Audit) -> Self {
+        match self {
+            CompletionsResponse::Unary(completion) => {
+                CompletionsResponse::Unary(completion.audit(audit))
+            }
+            CompletionsResponse::Streaming(rx) => {
+                CompletionsResponse::Streaming(audit_receiver(rx, audit))
+            }
+        }
+    }
+}
+
+/// Records items of a stream as they are forwarded, emitting the audit when it ends.
+fn audit_receiver<T: AuditItem + Send + 'static>(
+    mut rx: mpsc::Receiver<T>,
+    mut audit: Audit,
+) -> mpsc::Receiver<T> {
+    let (tx, audited_rx) = mpsc::channel(32);
+    tokio::spawn(async move {
+        while let Some(item) = rx.recv().await {
+            item.record(&mut audit);
+            if tx.send(item).await.is_err() {
+                // Client disconnected
+                break;
+            }
+        }
+        audit.emit();
+    });
+    audited_rx
+}
+
+impl<T: AuditItem> AuditItem for Box<T> {
+    fn record(&self, audit: &mut Audit) {
+        (**self).record(audit);
+    }
+}
+
+impl<T: AuditItem> AuditItem for Option<T> {
+    fn record(&self, audit: &mut Audit) {
+        if let Some(item) = self {
+            item.record(audit);
+        }
+    }
+}
+
+impl<T: AuditItem> AuditItem for Result<T, Error> {
+    fn record(&self, audit: &mut Audit) {
+        match self {
+            Ok(item) => item.record(audit),
+            Err(error) => audit.fail(error),
+        }
+    }
+}
+
+impl AuditItem for TextContentDetectionResult {
+    fn record(&self, audit: &mut Audit) {
+        record_content_analysis(audit, None, &self.detections);
+        record_warnings(audit, &self.warnings);
+    }
+}
+
+impl AuditItem for ContextDocsResult {
+    fn record(&self, audit: &mut Audit) {
+        record_detection_results(audit, &self.detections);
+        record_warnings(audit, &self.warnings);
+    }
+}
+
+impl AuditItem for ChatDetectionResult {
+    fn record(&self, audit: &mut Audit) {
+        record_detection_results(audit, &self.detections);
+        record_warnings(audit, &self.warnings);
+    }
+}
+
+impl AuditItem for DetectionOnGenerationResult {
+    fn record(&self, audit: &mut Audit) {
+        record_detection_results(audit, &self.detections);
+        record_warnings(audit, &self.warnings);
+    }
+}
+
+impl AuditItem for GenerationWithDetectionResult {
+    fn record(&self, audit: &mut Audit) {
+        record_detection_results(audit, &self.detections);
+        record_warnings(audit, &self.warnings);
+    }
+}
+
+impl AuditItem for StreamingContentDetectionResponse {
+    fn record(&self, audit: &mut Audit) {
+        record_content_analysis(audit, None, &self.detections);
+        record_warnings(audit, &self.warnings);
+    }
+}
+
+impl AuditItem for ClassifiedGeneratedTextResult {
+    fn record(&self, audit: &mut Audit) {
+        record_token_classifications(audit, &self.token_classification_results);
+        record_warnings(audit, self.warnings.iter().flatten());
+    }
+}
+
+impl AuditItem for ClassifiedGeneratedTextStreamResult {
+    fn record(&self, audit: &mut Audit) {
+        record_token_classifications(audit, &self.token_classification_results);
+        record_warnings(audit, self.warnings.iter().flatten());
+    }
+}
+
+impl AuditItem for BatchRecordResult {
+    fn record(&self, audit: &mut Audit) {
+        // Records failing individually do not fail the batch
+        if let Ok(result) = &self.result {
+            result.record(audit);
+        }
+    }
+}
+
+impl AuditItem for ChatCompletion {
+    fn record(&self, audit: &mut Audit) {
+        record_chat_detections(audit, self.detections.as_ref());
+        record_orchestrator_warnings(audit, &self.warnings);
+    }
+}
+
+impl AuditItem for ChatCompletionChunk {
+    fn record(&self, audit: &mut Audit) {
+        record_chat_detections(audit, self.detections.as_ref());
+        record_orchestrator_warnings(audit, &self.warnings);
+    }
+}
+
+impl AuditItem for Completion {
+    fn record(&self, audit: &mut Audit) {
+        record_chat_detections(audit, self.detections.as_ref());
+        record_orchestrator_warnings(audit, &self.warnings);
+    }
+}
+
+fn record_content_analysis(
+    audit: &mut Audit,
+    direction: Option<PolicyDirection>,
+    detections: &[ContentAnalysisResponse],
+) {
+    for detection in detections {
+        audit.detection(direction, detection.clone().into());
+    }
+}
+
+fn record_detection_results(audit: &mut Audit, detections: &[DetectionResult]) {
+    for detection in detections {
+        audit.detection(None, detection.clone().into());
+    }
+}
+
+fn record_token_classifications(audit: &mut Audit, results: &TextGenTokenClassificationResults) {
+    let directions = [
+        (PolicyDirection::Input, &results.input),
+        (PolicyDirection::Output, &results.output),
+    ];
+    for (direction, detections) in directions {
+        for detection in detections.iter().flatten() {
+            audit.detection(Some(direction), detection.clone().into());
+        }
+    }
+}
+
+fn record_chat_detections(audit: &mut Audit, detections: Option<&ChatDetections>) {
+    let Some(detections) = detections else {
+        return;
+    };
+    for result in &detections.input {
+        record_content_analysis(audit, Some(PolicyDirection::Input), &result.results);
+    }
+    for result in &detections.output {
+        record_content_analysis(audit, Some(PolicyDirection::Output), &result.results);
+    }
+}
+
+fn record_warnings<'a>(
+    audit: &mut Audit,
+    warnings: impl IntoIterator<Item = &'a DetectionWarning>,
+) {
+    for warning in warnings {
+        audit.warning(warning.id, warning.policy.as_ref());
+    }
+}
+
+fn record_orchestrator_warnings(audit: &mut Audit, warnings: &[OrchestratorWarning]) {
+    for warning in warnings {
+        audit.warning(Some(warning.warning_type()), warning.policy());
+    }
+}
+
+/// Audit sink errors.
+#[derive(Debug, thiserror::Error)]
+pub enum AuditError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("json serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("webhook request failed: {0}")]
+    Webhook(String),
+    #[error("invalid hash key: {0}")]
+    HashKey(String),
+}
+
+/// A sink audit records are written to.
+#[async_trait]
+pub trait AuditSink: Send + 'static {
+    async fn write(&mut self, record: &AuditRecord) -> Result<(), AuditError>;
+}
+
+/// Writes records to a local JSONL file, rotated when it exceeds its maximum size.
+/// Rotated files are suffixed with their generation, e.g. `audit.jsonl.1` is the most recent.
+pub struct FileSink {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Option<(File, u64)>,
+}
+
+impl FileSink {
+    pub fn new(path: PathBuf, max_bytes: u64, max_files: usize) -> Self {
+        Self {
+            path,
+            max_bytes,
+            max_files,
+            file: None,
+        }
+    }
+
+    /// Opens the file for appending, returning it with its size.
+    async fn open(&self) -> Result<(File, u64), std::io::Error> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        let size = file.metadata().await?.len();
+        Ok((file, size))
+    }
+
+    /// Rotates the file, removing the oldest one.
+    async fn rotate(&self) -> Result<(), std::io::Error> {
+        if self.max_files == 0 {
+            return tokio::fs::remove_file(&self.path).await;
+        }
+        for generation in (1..self.max_files).rev() {
+            let from = rotated_path(&self.path, generation);
+            if tokio::fs::try_exists(&from).await? {
+                tokio::fs::rename(&from, rotated_path(&self.path, generation + 1)).await?;
+            }
+        }
+        tokio::fs::rename(&self.path, rotated_path(&self.path, 1)).await
+    }
+}
+
+#[async_trait]
+impl AuditSink for FileSink {
+    async fn write(&mut self, record: &AuditRecord) -> Result<(), AuditError> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        let (mut file, mut size) = match self.file.take() {
+            Some(file) => file,
+            None => self.open().await?,
+        };
+        if size > 0 && size + line.len() as u64 > self.max_bytes {
+            drop(file);
+            self.rotate().await?;
+            (file, size) = self.open().await?;
+        }
+        file.write_all(&line).await?;
+        file.flush().await?;
+        self.file = Some((file, size + line.len() as u64));
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, generation: usize) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(format!(".{generation}"));
+    path.into()
+}
+
+/// Sends records as JSON to an HTTP webhook.
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+    headers: HeaderMap,
+}
+
+impl WebhookSink {
+    pub fn new(url: String, headers: &HashMap<String, String>) -> Self {
+        let headers = headers
+            .iter()
+            .filter_map(|(name, value)| {
+                match (
+                    HeaderName::try_from(name.as_str()),
+                    HeaderValue::try_from(value.as_str()),
+                ) {
+                    (Ok(name), Ok(value)) => Some((name, value)),
+                    _ => {
+                        warn!(header = name, "ignoring invalid audit webhook header");
+                        None
+                    }
+                }
+            })
+            .collect();
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            url,
+            headers,
+        }
+    }
+}
+
+#[async_trait]
+impl AuditSink for WebhookSink {
+    async fn write(&mut self, record: &AuditRecord) -> Result<(), AuditError> {
+        let response = self
+            .client
+            .post(&self.url)
+            .headers(self.headers.clone())
+            .json(record)
+            .send()
+            .await
+            .map_err(|error| AuditError::Webhook(error.to_string()))?;
+        if !response.status().is_success() {
+            return Err(AuditError::Webhook(format!(
+                "unexpected status code: {}",
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::clients::openai::OutputDetectionResult;
+
+    fn auditor(content: AuditContent) -> (Arc<Auditor>, mpsc::Receiver<AuditRecord>) {
+        let (tx, rx) = mpsc::channel(8);
+        let auditor = Auditor {
+            content,
+            hash_key: Some(hash_key()),
+            tx,
+        };
+        (Arc::new(auditor), rx)
+    }
+
+    fn hash_key() -> hmac::Key {
+        hmac::Key::new(hmac::HMAC_SHA256, b"secret")
+    }
+
+    fn detection() -> ContentAnalysisResponse {
+        ContentAnalysisResponse {
+            start: 0,
+            end: 5,
+            text: "hello".into(),
+            detection: "has_HAP".into(),
+            detection_type: "hap".into(),
+            detector_id: Some("hap".into()),
+            score: 0.9,
+            evidence: None,
+            metadata: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit() {
+        let (auditor, mut rx) = auditor(AuditContent::Hash);
+        let detectors = HashMap::from([("hap".to_string(), DetectorParams::default())]);
+        let audit = auditor
+            .start("/api/v2/text/detection/content", TraceId::INVALID, None)
+            .detectors(None, &detectors);
+        let response = TextContentDetectionResult {
+            detections: vec![detection()],
+            warnings: vec![],
+        };
+        let _ = audit.finish(Ok(response));
+        let record = rx.recv().await.unwrap();
+        assert_eq!(record.endpoint, "/api/v2/text/detection/content");
+        assert_eq!(record.detectors.len(), 1);
+        assert_eq!(record.detections.len(), 1);
+        assert_eq!(record.detections[0].text, Some(hash(&hash_key(), "hello")));
+        // Hashes depend on the key
+        let other_key = hmac::Key::new(hmac::HMAC_SHA256, b"other");
+        assert_ne!(hash(&hash_key(), "hello"), hash(&other_key, "hello"));
+        assert_eq!(record.outcome, AuditOutcome::Allowed);
+
+        // Input blocked by default when no rule fires
+        let audit = auditor.start(
+            "/api/v1/task/classification-with-text-generation",
+            TraceId::INVALID,
+            None,
+        );
+        let response = ClassifiedGeneratedTextResult {
+            warnings: Some(vec![DetectionWarning::unsuitable_input()]),
+            ..Default::default()
+        };
+        let _ = audit.finish(Ok(response));
+        let record = rx.recv().await.unwrap();
+        assert_eq!(record.outcome, AuditOutcome::Blocked);
+
+        // Failed task
+        let audit = auditor.start("/api/v2/text/detection/content", TraceId::INVALID, None);
+        let _ = audit.finish::<TextContentDetectionResult>(Err(Error::Cancelled));
+        let record = rx.recv().await.unwrap();
+        assert_eq!(record.outcome, AuditOutcome::Failed);
+        assert_eq!(record.error, Some("cancelled".into()));
+    }
+
+    #[tokio::test]
+    async fn test_audit_redacted() {
+        let (auditor, mut rx) = auditor(AuditContent::Omit);
+        let chat_completion = ChatCompletion {
+            detections: Some(ChatDetections {
+                output: vec![OutputDetectionResult {
+                    choice_index: 0,
+                    results: vec![detection()],
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // Detections of detectors with the `redact` action were masked
+        let audit = auditor
+            .start("/api/v2/chat/completions-detection", TraceId::INVALID, None)
+            .redactions(Some(PolicyDirection::Output), ["hap".to_string()]);
+        let _ = audit.finish(Ok(chat_completion.clone()));
+        let record = rx.recv().await.unwrap();
+        assert_eq!(record.outcome, AuditOutcome::Redacted);
+
+        // Detector with the `redact` action on input only
+        let audit = auditor
+            .start("/api/v2/chat/completions-detection", TraceId::INVALID, None)
+            .redactions(Some(PolicyDirection::Input), ["hap".to_string()]);
+        let _ = audit.finish(Ok(chat_completion));
+        let record = rx.recv().await.unwrap();
+        assert_eq!(record.outcome, AuditOutcome::Allowed);
+
+        // Input anonymized by the task
+        let audit = auditor.start("/api/v2/chat/completions-detection", TraceId::INVALID, None);
+        audit.anonymized_flag().set();
+        let _ = audit.finish(Ok(ChatCompletion::default()));
+        let record = rx.recv().await.unwrap();
+        assert_eq!(record.outcome, AuditOutcome::Redacted);
+    }
+
+    #[tokio::test]
+    async fn test_audit_stream() {
+        let (auditor, mut rx) = auditor(AuditContent::Omit);
+        let audit = auditor.start(
+            "/api/v2/text/detection/stream-content",
+            TraceId::INVALID,
+            None,
+        );
+        let (tx, stream_rx) = mpsc::channel(4);
+        let mut stream = audit
+            .finish(Ok(ReceiverStream::new(stream_rx)))
+            .unwrap()
+            .into_inner();
+        for _ in 0..2 {
+            let response = StreamingContentDetectionResponse {
+                detections: vec![detection()],
+                ..Default::default()
+            };
+            tx.send(Ok::<_, Error>(response)).await.unwrap();
+            assert!(stream.recv().await.is_some());
+        }
+        drop(tx);
+        assert!(stream.recv().await.is_none());
+        // Emitted when the stream ends, detectors recorded from detections
+        let record = rx.recv().await.unwrap();
+        assert_eq!(record.detectors.len(), 1);
+        assert_eq!(record.detections.len(), 2);
+        assert_eq!(record.detections[0].text, None);
+    }
+
+    #[tokio::test]
+    async fn test_file_sink_rotation() {
+        let dir = std::env::temp_dir().join(format!("audit-{}", Uuid::new_v4().simple()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("audit.jsonl");
+        let record = Audit::new(None, "/health", TraceId::INVALID, None).record;
+        let line_len = serde_json::to_vec(&record).unwrap().len() as u64 + 1;
+        // Rotate after every 2 records
+        let mut sink = FileSink::new(path.clone(), line_len * 2, 2);
+        for _ in 0..7 {
+            sink.write(&record).await.unwrap();
+        }
+        let lines = |path: PathBuf| async move {
+            tokio::fs::read_to_string(path)
+                .await
+                .unwrap()
+                .lines()
+                .count()
+        };
+        assert_eq!(lines(path.clone()).await, 1);
+        assert_eq!(lines(rotated_path(&path, 1)).await, 2);
+        assert_eq!(lines(rotated_path(&path, 2)).await, 2);
+        assert!(!rotated_path(&path, 3).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -21,12 +21,13 @@ use std::{
     collections::{HashMap, hash_map},
     fmt::Debug,
     pin::Pin,
+    sync::Arc,
     time::Duration,
 };
 
 use async_trait::async_trait;
 use axum::http::{Extensions, HeaderMap};
-use futures::Stream;
+use futures::{Future, Stream, TryFutureExt};
 use ginepro::{LoadBalancedChannel, ResolutionStrategy};
 use hyper_timeout::TimeoutConnector;
 use hyper_util::rt::TokioExecutor;
@@ -39,7 +40,8 @@ use url::Url;
 use crate::{
     config::{ServiceConfig, Tls},
     health::HealthCheckResult,
-    utils::{tls, trace::with_traceparent_header},
+    orchestrator::types::TENANT_HEADER,
+    utils::{deadline::Deadline, tls, trace::with_traceparent_header},
 };
 
 pub mod errors;
@@ -48,6 +50,12 @@ pub use errors::Error;
 pub mod http;
 pub use http::{HttpClient, http_trace_layer};
 
+pub mod circuit_breaker;
+pub use circuit_breaker::CircuitBreaker;
+
+pub mod retry;
+pub use retry::RetryPolicy;
+
 pub mod chunker;
 
 pub mod detector;
@@ -92,6 +100,11 @@ pub trait Client: Send + Sync + 'static {
 
     /// Performs a client health check.
     async fn health(&self) -> HealthCheckResult;
+
+    /// Returns the circuit breaker of the client, if configured.
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        None
+    }
 }
 
 impl dyn Client {
@@ -132,7 +145,7 @@ impl dyn Client {
 
 /// A map containing different types of clients.
 #[derive(Default)]
-pub struct ClientMap(HashMap<String, Box<dyn Client>>);
+pub struct ClientMap(HashMap<String, Arc<dyn Client>>);
 
 impl ClientMap {
     /// Creates an empty `ClientMap`.
@@ -144,7 +157,13 @@ impl ClientMap {
     /// Inserts a client into the map.
     #[inline]
     pub fn insert<V: Client>(&mut self, key: String, value: V) {
-        self.0.insert(key, Box::new(value));
+        self.0.insert(key, Arc::new(value));
+    }
+
+    /// Inserts a shared client into the map.
+    #[inline]
+    pub fn insert_shared(&mut self, key: String, value: Arc<dyn Client>) {
+        self.0.insert(key, value);
     }
 
     /// Returns a reference to the client trait object.
@@ -153,10 +172,17 @@ impl ClientMap {
         self.0.get(key).map(|v| v.as_ref())
     }
 
-    /// Returns a mutable reference to the client trait object.
+    /// Returns a shared reference to the client trait object.
+    #[inline]
+    pub fn get_shared(&self, key: &str) -> Option<Arc<dyn Client>> {
+        self.0.get(key).cloned()
+    }
+
+    /// Returns a mutable reference to the client trait object,
+    /// if it is not shared.
     #[inline]
     pub fn get_mut(&mut self, key: &str) -> Option<&mut dyn Client> {
-        self.0.get_mut(key).map(|v| v.as_mut())
+        Arc::get_mut(self.0.get_mut(key)?)
     }
 
     /// Downcasts and returns a reference to the concrete client type.
@@ -165,33 +191,34 @@ impl ClientMap {
         self.0.get(key)?.downcast_ref::<V>()
     }
 
-    /// Downcasts and returns a mutable reference to the concrete client type.
+    /// Downcasts and returns a mutable reference to the concrete client type,
+    /// if it is not shared.
     #[inline]
     pub fn get_mut_as<V: Client>(&mut self, key: &str) -> Option<&mut V> {
-        self.0.get_mut(key)?.downcast_mut::<V>()
+        Arc::get_mut(self.0.get_mut(key)?)?.downcast_mut::<V>()
     }
 
     /// Removes a client from the map.
     #[inline]
-    pub fn remove(&mut self, key: &str) -> Option<Box<dyn Client>> {
+    pub fn remove(&mut self, key: &str) -> Option<Arc<dyn Client>> {
         self.0.remove(key)
     }
 
     /// An iterator visiting all key-value pairs in arbitrary order.
     #[inline]
-    pub fn iter(&self) -> hash_map::Iter<'_, String, Box<dyn Client>> {
+    pub fn iter(&self) -> hash_map::Iter<'_, String, Arc<dyn Client>> {
         self.0.iter()
     }
 
     /// An iterator visiting all keys in arbitrary order.
     #[inline]
-    pub fn keys(&self) -> hash_map::Keys<'_, String, Box<dyn Client>> {
+    pub fn keys(&self) -> hash_map::Keys<'_, String, Arc<dyn Client>> {
         self.0.keys()
     }
 
     /// An iterator visiting all values in arbitrary order.
     #[inline]
-    pub fn values(&self) -> hash_map::Values<'_, String, Box<dyn Client>> {
+    pub fn values(&self) -> hash_map::Values<'_, String, Arc<dyn Client>> {
         self.0.values()
     }
 
@@ -255,7 +282,14 @@ pub async fn create_http_client(
         .layer(http_trace_layer())
         .layer(TimeoutLayer::new(request_timeout))
         .service(client);
-    Ok(HttpClient::new(base_url, client))
+    let mut client = HttpClient::new(base_url, client);
+    if let Some(retry_policy) = create_retry_policy(default_port, service_config) {
+        client = client.with_retry_policy(retry_policy);
+    }
+    if let Some(circuit_breaker) = create_circuit_breaker(default_port, service_config) {
+        client = client.with_circuit_breaker(circuit_breaker);
+    }
+    Ok(client)
 }
 
 pub async fn create_grpc_client<C: Debug + Clone>(
@@ -341,6 +375,28 @@ pub async fn create_grpc_client<C: Debug + Clone>(
     new(channel)
 }
 
+/// Creates a retry policy for a service, if configured.
+pub fn create_retry_policy(
+    default_port: u16,
+    service_config: &ServiceConfig,
+) -> Option<RetryPolicy> {
+    service_config.retry.as_ref().map(|config| {
+        let port = service_config.port.unwrap_or(default_port);
+        RetryPolicy::new(format!("{}:{}", service_config.hostname, port), config)
+    })
+}
+
+/// Creates a circuit breaker for a service, if configured.
+pub fn create_circuit_breaker(
+    default_port: u16,
+    service_config: &ServiceConfig,
+) -> Option<CircuitBreaker> {
+    service_config.circuit_breaker.as_ref().map(|config| {
+        let port = service_config.port.unwrap_or(default_port);
+        CircuitBreaker::new(format!("{}:{}", service_config.hostname, port), config)
+    })
+}
+
 /// Returns `true` if hostname is valid according to [IETF RFC 1123](https://tools.ietf.org/html/rfc1123).
 ///
 /// Conditions:
@@ -369,11 +425,35 @@ pub fn is_valid_hostname(hostname: &str) -> bool {
 
 /// Turns a gRPC client request body of type `T` and header map into a `tonic::Request<T>`.
 /// Will also inject the current `traceparent` header into the request based on the current span.
-fn grpc_request_with_headers<T>(request: T, headers: HeaderMap) -> Request<T> {
+/// The request deadline, if any, is sent as the gRPC timeout.
+fn grpc_request_with_headers<T>(request: T, mut headers: HeaderMap) -> Request<T> {
+    let deadline = Deadline::take_from_headers(&mut headers);
+    headers.remove(TENANT_HEADER);
     let ctx = Span::current().context();
     let headers = with_traceparent_header(&ctx, headers);
     let metadata = MetadataMap::from_headers(headers);
-    Request::from_parts(metadata, Extensions::new(), request)
+    let mut request = Request::from_parts(metadata, Extensions::new(), request);
+    if let Some(deadline) = deadline {
+        request.set_timeout(deadline.remaining());
+    }
+    request
+}
+
+/// Sends a gRPC request with the retry policy and circuit breaker, if configured.
+/// `request` is called for each attempt.
+async fn grpc_call<T, F, Fut>(
+    retry_policy: Option<&RetryPolicy>,
+    circuit_breaker: Option<&CircuitBreaker>,
+    mut request: F,
+) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, tonic::Status>>,
+{
+    retry::with_retry_policy(retry_policy, || {
+        circuit_breaker::with_circuit_breaker(circuit_breaker, request().map_err(Into::into))
+    })
+    .await
 }
 
 #[cfg(test)]
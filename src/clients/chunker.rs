@@ -15,18 +15,19 @@
 
 */
 
-use std::pin::Pin;
+use std::{pin::Pin, sync::Arc};
 
 use async_trait::async_trait;
 use axum::http::HeaderMap;
-use futures::{Future, StreamExt, TryStreamExt};
+use futures::{Future, StreamExt, TryFutureExt, TryStreamExt};
 use ginepro::LoadBalancedChannel;
 use tonic::{Code, Request, Response, Status, Streaming};
 use tracing::Span;
 
 use super::{
-    BoxStream, Client, Error, create_grpc_client, errors::grpc_to_http_code,
-    grpc_request_with_headers, otel_grpc::OtelGrpcService,
+    BoxStream, CircuitBreaker, Client, Error, RetryPolicy, circuit_breaker::with_circuit_breaker,
+    create_circuit_breaker, create_grpc_client, create_retry_policy, errors::grpc_to_http_code,
+    grpc_call, grpc_request_with_headers, otel_grpc::OtelGrpcService,
 };
 use crate::{
     config::ServiceConfig,
@@ -39,7 +40,7 @@ use crate::{
         caikit_data_model::nlp::{ChunkerTokenizationStreamResult, TokenizationResults},
         grpc::health::v1::{HealthCheckRequest, health_client::HealthClient},
     },
-    utils::trace::trace_context_from_grpc_response,
+    utils::{deadline::Deadline, trace::trace_context_from_grpc_response},
 };
 
 const DEFAULT_PORT: u16 = 8085;
@@ -54,15 +55,21 @@ type StreamingTokenizationResult =
 pub struct ChunkerClient {
     client: ChunkersServiceClient<OtelGrpcService<LoadBalancedChannel>>,
     health_client: HealthClient<OtelGrpcService<LoadBalancedChannel>>,
+    retry_policy: Option<Arc<RetryPolicy>>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
 }
 
 impl ChunkerClient {
     pub async fn new(config: &ServiceConfig) -> Self {
         let client = create_grpc_client(DEFAULT_PORT, config, ChunkersServiceClient::new).await;
         let health_client = create_grpc_client(DEFAULT_PORT, config, HealthClient::new).await;
+        let retry_policy = create_retry_policy(DEFAULT_PORT, config).map(Arc::new);
+        let circuit_breaker = create_circuit_breaker(DEFAULT_PORT, config).map(Arc::new);
         Self {
             client,
             health_client,
+            retry_policy,
+            circuit_breaker,
         }
     }
 
@@ -70,10 +77,18 @@ impl ChunkerClient {
         &self,
         model_id: &str,
         request: ChunkerTokenizationTaskRequest,
+        deadline: Option<Deadline>,
     ) -> Result<TokenizationResults, Error> {
-        let mut client = self.client.clone();
-        let request = request_with_headers(request, model_id);
-        let response = client.chunker_tokenization_task_predict(request).await?;
+        let response = grpc_call(
+            self.retry_policy.as_deref(),
+            self.circuit_breaker.as_deref(),
+            || {
+                let mut client = self.client.clone();
+                let request = request_with_headers(request.clone(), model_id, deadline);
+                async move { client.chunker_tokenization_task_predict(request).await }
+            },
+        )
+        .await?;
         let span = Span::current();
         trace_context_from_grpc_response(&span, &response);
         Ok(response.into_inner())
@@ -83,14 +98,20 @@ impl ChunkerClient {
         &self,
         model_id: &str,
         request_stream: BoxStream<BidiStreamingChunkerTokenizationTaskRequest>,
+        deadline: Option<Deadline>,
     ) -> Result<BoxStream<Result<ChunkerTokenizationStreamResult, Error>>, Error> {
         let mut client = self.client.clone();
-        let request = request_with_headers(request_stream, model_id);
+        let request = request_with_headers(request_stream, model_id, deadline);
         // NOTE: this is an ugly workaround to avoid bogus higher-ranked lifetime errors.
         // https://github.com/rust-lang/rust/issues/110338
         let response_stream_fut: Pin<Box<dyn Future<Output = StreamingTokenizationResult> + Send>> =
             Box::pin(client.bidi_streaming_chunker_tokenization_task_predict(request));
-        let response_stream = response_stream_fut.await?;
+        // Not retried, as the request stream cannot be replayed
+        let response_stream = with_circuit_breaker(
+            self.circuit_breaker.as_deref(),
+            response_stream_fut.map_err(Into::into),
+        )
+        .await?;
         let span = Span::current();
         trace_context_from_grpc_response(&span, &response_stream);
         Ok(response_stream.into_inner().map_err(Into::into).boxed())
@@ -126,12 +147,21 @@ impl Client for ChunkerClient {
             reason: None,
         }
     }
+
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.circuit_breaker.as_deref()
+    }
 }
 
 /// Turns a chunker client gRPC request body of type `T` into a `tonic::Request<T>` with headers.
 /// Adds the provided `model_id` as a header as well as injects `traceparent` from the current span.
-fn request_with_headers<T>(request: T, model_id: &str) -> Request<T> {
-    let mut request = grpc_request_with_headers(request, HeaderMap::new());
+/// The request deadline, if any, is sent as the gRPC timeout.
+fn request_with_headers<T>(request: T, model_id: &str, deadline: Option<Deadline>) -> Request<T> {
+    let mut headers = HeaderMap::new();
+    if let Some(deadline) = deadline {
+        deadline.insert_header(&mut headers);
+    }
+    let mut request = grpc_request_with_headers(request, headers);
     request
         .metadata_mut()
         .insert(MODEL_ID_HEADER_NAME, model_id.parse().unwrap());
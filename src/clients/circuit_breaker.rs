@@ -0,0 +1,336 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+//! Client circuit breaker
+use std::{
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use hyper::StatusCode;
+use serde::Serialize;
+
+use super::Error;
+use crate::{
+    config::CircuitBreakerConfig,
+    utils::trace::{on_circuit_breaker_rejected, on_circuit_breaker_transition},
+};
+
+/// State of a circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests are sent to the service
+    Closed,
+    /// Requests fail fast without being sent to the service
+    Open,
+    /// A limited number of trial requests are sent to the service
+    HalfOpen,
+}
+
+impl std::fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitState::Closed => write!(f, "closed"),
+            CircuitState::Open => write!(f, "open"),
+            CircuitState::HalfOpen => write!(f, "half_open"),
+        }
+    }
+}
+
+/// Circuit breaker status reported by the info endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CircuitBreakerStatus {
+    pub state: CircuitState,
+    /// Consecutive failures while closed
+    pub consecutive_failures: u32,
+    /// Number of state transitions since the client was created
+    pub transitions: u64,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_requests: u32,
+    transitions: u64,
+}
+
+/// A circuit breaker failing requests fast while a service is failing.
+///
+/// The circuit opens after `failure_threshold` consecutive failures. After `open_seconds`,
+/// it becomes half-open and allows up to `half_open_max_requests` trial requests:
+/// a successful trial closes the circuit, a failed or cancelled one opens it again.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    service: String,
+    failure_threshold: u32,
+    open_duration: Duration,
+    half_open_max_requests: u32,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(service: String, config: &CircuitBreakerConfig) -> Self {
+        Self {
+            service,
+            failure_threshold: config.failure_threshold,
+            open_duration: Duration::from_secs(config.open_seconds),
+            half_open_max_requests: config.half_open_max_requests,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                trial_requests: 0,
+                transitions: 0,
+            }),
+        }
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> CircuitState {
+        self.inner.lock().unwrap().state
+    }
+
+    /// Returns the current status.
+    pub fn status(&self) -> CircuitBreakerStatus {
+        let inner = self.inner.lock().unwrap();
+        CircuitBreakerStatus {
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            transitions: inner.transitions,
+        }
+    }
+
+    /// Admits a request, returning a permit to record its outcome with, or an error
+    /// if the circuit is open or the half-open trial requests are exhausted.
+    pub fn acquire(&self) -> Result<CircuitBreakerPermit<'_>, Error> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            CircuitState::Closed => return Ok(CircuitBreakerPermit::new(self, false)),
+            CircuitState::Open
+                if inner
+                    .opened_at
+                    .is_some_and(|opened_at| opened_at.elapsed() >= self.open_duration) =>
+            {
+                self.transition(&mut inner, CircuitState::HalfOpen);
+                inner.trial_requests = 1;
+                return Ok(CircuitBreakerPermit::new(self, true));
+            }
+            CircuitState::HalfOpen if inner.trial_requests < self.half_open_max_requests => {
+                inner.trial_requests += 1;
+                return Ok(CircuitBreakerPermit::new(self, true));
+            }
+            _ => {}
+        }
+        drop(inner);
+        on_circuit_breaker_rejected(&self.service);
+        Err(Error::CircuitOpen {
+            service: self.service.clone(),
+        })
+    }
+
+    /// Records the outcome of an admitted request.
+    fn record(&self, success: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        match (inner.state, success) {
+            (CircuitState::Closed, true) => inner.consecutive_failures = 0,
+            (CircuitState::Closed, false) => {
+                inner.consecutive_failures += 1;
+                if inner.consecutive_failures >= self.failure_threshold {
+                    self.transition(&mut inner, CircuitState::Open);
+                }
+            }
+            (CircuitState::HalfOpen, true) => self.transition(&mut inner, CircuitState::Closed),
+            (CircuitState::HalfOpen, false) => self.transition(&mut inner, CircuitState::Open),
+            // Outcome of a request admitted before the circuit opened
+            (CircuitState::Open, _) => {}
+        }
+    }
+
+    /// Runs a request through the circuit breaker.
+    pub async fn call<T>(
+        &self,
+        request: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        let permit = self.acquire()?;
+        let result = request.await;
+        permit.record(
+            result
+                .as_ref()
+                .map_or_else(|error| !is_failure(error), |_| true),
+        );
+        result
+    }
+
+    fn transition(&self, inner: &mut Inner, state: CircuitState) {
+        on_circuit_breaker_transition(&self.service, inner.state, state);
+        inner.state = state;
+        inner.transitions += 1;
+        inner.consecutive_failures = 0;
+        inner.trial_requests = 0;
+        inner.opened_at = (state == CircuitState::Open).then(Instant::now);
+    }
+}
+
+/// Permit of a request admitted by a [`CircuitBreaker`].
+///
+/// If a half-open trial request is dropped before its outcome is recorded, e.g. when
+/// the caller disconnects or the request deadline is exceeded, it is recorded as a
+/// failure, so the circuit opens again instead of rejecting requests indefinitely.
+/// Other requests dropped before completion are not recorded.
+#[must_use]
+pub struct CircuitBreakerPermit<'a> {
+    circuit_breaker: &'a CircuitBreaker,
+    trial: bool,
+    recorded: bool,
+}
+
+impl<'a> CircuitBreakerPermit<'a> {
+    fn new(circuit_breaker: &'a CircuitBreaker, trial: bool) -> Self {
+        Self {
+            circuit_breaker,
+            trial,
+            recorded: false,
+        }
+    }
+
+    /// Records the outcome of the request.
+    pub fn record(mut self, success: bool) {
+        self.recorded = true;
+        self.circuit_breaker.record(success);
+    }
+}
+
+impl Drop for CircuitBreakerPermit<'_> {
+    fn drop(&mut self) {
+        if !self.recorded && self.trial {
+            self.circuit_breaker.record(false);
+        }
+    }
+}
+
+/// Runs a request through a circuit breaker, if provided.
+pub async fn with_circuit_breaker<T>(
+    circuit_breaker: Option<&CircuitBreaker>,
+    request: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    match circuit_breaker {
+        Some(circuit_breaker) => circuit_breaker.call(request).await,
+        None => request.await,
+    }
+}
+
+/// Returns `true` if an error indicates a failing service, i.e. a server error or timeout.
+pub fn is_failure(error: &Error) -> bool {
+    let code = error.status_code();
+    code.is_server_error() || code == StatusCode::REQUEST_TIMEOUT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circuit_breaker(failure_threshold: u32) -> CircuitBreaker {
+        CircuitBreaker::new(
+            "localhost:8000".into(),
+            &CircuitBreakerConfig {
+                failure_threshold,
+                open_seconds: 30,
+                half_open_max_requests: 1,
+            },
+        )
+    }
+
+    #[test]
+    fn test_open_after_consecutive_failures() {
+        let breaker = circuit_breaker(2);
+        breaker.acquire().unwrap().record(false);
+        breaker.acquire().unwrap().record(true);
+        breaker.acquire().unwrap().record(false);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.acquire().unwrap().record(false);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        let error = breaker.acquire().err().unwrap();
+        assert_eq!(error.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(breaker.status().transitions, 1);
+    }
+
+    #[test]
+    fn test_half_open() {
+        let breaker = circuit_breaker(1);
+        breaker.acquire().unwrap().record(false);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        // Open duration elapsed
+        breaker.inner.lock().unwrap().opened_at = Some(Instant::now() - Duration::from_secs(30));
+        let permit = breaker.acquire().unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        // Trial requests exhausted
+        assert!(breaker.acquire().is_err());
+        // Failed trial opens the circuit again
+        permit.record(false);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        // Successful trial closes the circuit
+        breaker.inner.lock().unwrap().opened_at = Some(Instant::now() - Duration::from_secs(30));
+        breaker.acquire().unwrap().record(true);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.status().transitions, 4);
+    }
+
+    #[test]
+    fn test_dropped_permit() {
+        let breaker = circuit_breaker(1);
+        // Dropped requests are not recorded while closed
+        drop(breaker.acquire().unwrap());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.acquire().unwrap().record(false);
+        // Dropped trial request opens the circuit again
+        breaker.inner.lock().unwrap().opened_at = Some(Instant::now() - Duration::from_secs(30));
+        drop(breaker.acquire().unwrap());
+        assert_eq!(breaker.state(), CircuitState::Open);
+        // Circuit is half-open again after the open duration
+        breaker.inner.lock().unwrap().opened_at = Some(Instant::now() - Duration::from_secs(30));
+        assert!(breaker.acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_call() {
+        let breaker = circuit_breaker(1);
+        let result = breaker
+            .call(async {
+                Err::<(), _>(Error::Http {
+                    code: StatusCode::BAD_REQUEST,
+                    message: "bad request".into(),
+                })
+            })
+            .await;
+        assert!(result.is_err());
+        // Client errors do not count as failures
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        let _ = breaker
+            .call(async {
+                Err::<(), _>(Error::Http {
+                    code: StatusCode::REQUEST_TIMEOUT,
+                    message: "client request timeout".into(),
+                })
+            })
+            .await;
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+}
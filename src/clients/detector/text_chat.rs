@@ -23,7 +23,7 @@ use tracing::info;
 use super::{DEFAULT_PORT, DetectorClient, DetectorClientExt};
 use crate::{
     clients::{
-        Client, Error, HttpClient, create_http_client,
+        CircuitBreaker, Client, Error, HttpClient, create_http_client,
         http::HttpClientExt,
         openai::{Message, Tool},
     },
@@ -86,6 +86,10 @@ impl Client for TextChatDetectorClient {
             self.client.health().await
         }
     }
+
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.client.circuit_breaker()
+    }
 }
 
 impl DetectorClient for TextChatDetectorClient {}
@@ -24,7 +24,7 @@ use tracing::info;
 
 use super::{DEFAULT_PORT, DetectorClient, DetectorClientExt};
 use crate::{
-    clients::{Client, Error, HttpClient, create_http_client, http::HttpClientExt},
+    clients::{CircuitBreaker, Client, Error, HttpClient, create_http_client, http::HttpClientExt},
     config::ServiceConfig,
     health::HealthCheckResult,
     models::{DetectorParams, EvidenceObj, Metadata},
@@ -84,6 +84,10 @@ impl Client for TextContentsDetectorClient {
             self.client.health().await
         }
     }
+
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.client.circuit_breaker()
+    }
 }
 
 impl DetectorClient for TextContentsDetectorClient {}
@@ -22,7 +22,7 @@ use tracing::info;
 
 use super::{DEFAULT_PORT, DetectorClient, DetectorClientExt};
 use crate::{
-    clients::{Client, Error, HttpClient, create_http_client, http::HttpClientExt},
+    clients::{CircuitBreaker, Client, Error, HttpClient, create_http_client, http::HttpClientExt},
     config::ServiceConfig,
     health::HealthCheckResult,
     models::{DetectionResult, DetectorParams},
@@ -82,6 +82,10 @@ impl Client for TextGenerationDetectorClient {
             self.client.health().await
         }
     }
+
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.client.circuit_breaker()
+    }
 }
 
 impl DetectorClient for TextGenerationDetectorClient {}
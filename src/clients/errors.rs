@@ -22,11 +22,17 @@ use tracing::error;
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum Error {
     #[error("{}", .message)]
-    Grpc { code: StatusCode, message: String },
+    Grpc {
+        code: StatusCode,
+        grpc_code: tonic::Code,
+        message: String,
+    },
     #[error("{}", .message)]
     Http { code: StatusCode, message: String },
     #[error("model not found: {model_id}")]
     ModelNotFound { model_id: String },
+    #[error("circuit breaker open for `{service}`")]
+    CircuitOpen { service: String },
 }
 
 impl Error {
@@ -40,6 +46,8 @@ impl Error {
             Error::Http { code, .. } => *code,
             // Return 404 for model not found
             Error::ModelNotFound { .. } => StatusCode::NOT_FOUND,
+            // Return 503 when failing fast on an open circuit
+            Error::CircuitOpen { .. } => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
 }
@@ -57,6 +65,7 @@ impl From<tonic::Status> for Error {
     fn from(value: tonic::Status) -> Self {
         Self::Grpc {
             code: grpc_to_http_code(value.code()),
+            grpc_code: value.code(),
             message: value.message().to_string(),
         }
     }
@@ -17,10 +17,9 @@
 
 use async_trait::async_trait;
 use futures::{StreamExt, TryStreamExt};
-use hyper::{HeaderMap, StatusCode};
-use tracing::warn;
+use hyper::HeaderMap;
 
-use super::{BoxStream, Client, Error, NlpClient, TgisClient};
+use super::{BoxStream, CircuitBreaker, Client, Error, NlpClient, TgisClient};
 use crate::{
     health::HealthCheckResult,
     models::{
@@ -39,57 +38,8 @@ use crate::{
     },
 };
 
-async fn retry_function<F, Fut, Res>(max_retries: usize, func: F) -> Result<Res, Error>
-where
-    F: Fn() -> Fut,
-    Fut: std::future::Future<Output = Result<Res, Error>>,
-{
-    let mut attempt = 0;
-
-    let allowed_retry_codes = [
-        StatusCode::BAD_GATEWAY,
-        StatusCode::SERVICE_UNAVAILABLE,
-        StatusCode::GATEWAY_TIMEOUT,
-        StatusCode::HTTP_VERSION_NOT_SUPPORTED,
-        StatusCode::VARIANT_ALSO_NEGOTIATES,
-    ];
-    loop {
-        attempt += 1;
-
-        match func().await {
-            Ok(res) => return Ok(res),
-
-            Err(error) => {
-                if allowed_retry_codes.contains(&error.status_code()) {
-                    // Only retry when status code is within list
-                    if attempt > max_retries {
-                        warn!(
-                            "Final attempt failed to connect to server. attempt: {}, error: {}",
-                            attempt, &error
-                        );
-                        return Err(error);
-                    }
-                    // Exponential backoff for retries.
-                    tokio::time::sleep(std::time::Duration::from_millis(
-                        2_u64.pow(attempt as u32 - 1),
-                    ))
-                    .await;
-                    warn!(
-                        "failed to connect to server. attempt: {}, error: {}",
-                        attempt, &error
-                    );
-                    continue;
-                }
-
-                // Else return error
-                return Err(error);
-            }
-        }
-    }
-}
-
 #[derive(Clone)]
-pub struct GenerationClient(Option<GenerationClientInner>, usize);
+pub struct GenerationClient(Option<GenerationClientInner>);
 
 #[derive(Clone)]
 enum GenerationClientInner {
@@ -98,16 +48,16 @@ enum GenerationClientInner {
 }
 
 impl GenerationClient {
-    pub fn tgis(client: TgisClient, max_retries: usize) -> Self {
-        Self(Some(GenerationClientInner::Tgis(client)), max_retries)
+    pub fn tgis(client: TgisClient) -> Self {
+        Self(Some(GenerationClientInner::Tgis(client)))
     }
 
-    pub fn nlp(client: NlpClient, max_retries: usize) -> Self {
-        Self(Some(GenerationClientInner::Nlp(client)), max_retries)
+    pub fn nlp(client: NlpClient) -> Self {
+        Self(Some(GenerationClientInner::Nlp(client)))
     }
 
     pub fn not_configured() -> Self {
-        Self(None, 0)
+        Self(None)
     }
 
     pub async fn tokenize(
@@ -131,10 +81,9 @@ impl GenerationClient {
             }
             Some(GenerationClientInner::Nlp(client)) => {
                 let request = TokenizationTaskRequest { text };
-                let response = retry_function(self.1, || {
-                    client.tokenization_task_predict(&model_id, request.clone(), headers.clone())
-                })
-                .await?;
+                let response = client
+                    .tokenization_task_predict(&model_id, request, headers)
+                    .await?;
                 let tokens = response
                     .results
                     .into_iter()
@@ -197,10 +146,9 @@ impl GenerationClient {
                         ..Default::default()
                     }
                 };
-                let response = retry_function(self.1, || {
-                    client.text_generation_task_predict(&model_id, request.clone(), headers.clone())
-                })
-                .await?;
+                let response = client
+                    .text_generation_task_predict(&model_id, request, headers)
+                    .await?;
                 Ok(response.into())
             }
             None => Err(Error::ModelNotFound { model_id }),
@@ -263,16 +211,11 @@ impl GenerationClient {
                     }
                 };
 
-                let response_stream = retry_function(self.1, || {
-                    client.server_streaming_text_generation_task_predict(
-                        &model_id,
-                        request.clone(),
-                        headers.clone(),
-                    )
-                })
-                .await?
-                .map_ok(Into::into)
-                .boxed();
+                let response_stream = client
+                    .server_streaming_text_generation_task_predict(&model_id, request, headers)
+                    .await?
+                    .map_ok(Into::into)
+                    .boxed();
 
                 Ok(response_stream)
             }
@@ -294,4 +237,12 @@ impl Client for GenerationClient {
             None => unimplemented!(),
         }
     }
+
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        match &self.0 {
+            Some(GenerationClientInner::Tgis(client)) => client.circuit_breaker(),
+            Some(GenerationClientInner::Nlp(client)) => client.circuit_breaker(),
+            None => None,
+        }
+    }
 }
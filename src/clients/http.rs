@@ -15,7 +15,7 @@
 
 */
 
-use std::{fmt::Debug, ops::Deref, time::Duration};
+use std::{fmt::Debug, ops::Deref, sync::Arc, time::Duration};
 
 use http::header::HeaderValue;
 use http_body_util::{BodyExt, Full, combinators::BoxBody};
@@ -41,10 +41,19 @@ use tracing::{Span, error, info, info_span};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 use url::Url;
 
-use super::{Client, Error};
+use super::{
+    Client, Error,
+    circuit_breaker::{CircuitBreaker, is_failure},
+    retry::RetryPolicy,
+};
 use crate::{
     health::{HealthCheckResult, HealthStatus, OptionalHealthCheckResponseBody},
-    utils::{AsUriExt, trace},
+    orchestrator::types::TENANT_HEADER,
+    utils::{
+        AsUriExt,
+        deadline::{Deadline, REQUEST_TIMEOUT_HEADER},
+        trace,
+    },
 };
 
 pub const JSON_CONTENT_TYPE: HeaderValue = HeaderValue::from_static("application/json");
@@ -120,6 +129,8 @@ pub struct HttpClient {
     base_url: Url,
     health_url: Url,
     inner: HttpClientInner,
+    retry_policy: Option<Arc<RetryPolicy>>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
 }
 
 impl HttpClient {
@@ -129,9 +140,27 @@ impl HttpClient {
             base_url,
             health_url,
             inner,
+            retry_policy: None,
+            circuit_breaker: None,
         }
     }
 
+    /// Sets a retry policy retrying failed requests.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(Arc::new(retry_policy));
+        self
+    }
+
+    /// Sets a circuit breaker failing requests fast while the service is failing.
+    pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(Arc::new(circuit_breaker));
+        self
+    }
+
+    pub fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.circuit_breaker.as_deref()
+    }
+
     pub fn base_url(&self) -> &Url {
         &self.base_url
     }
@@ -165,6 +194,62 @@ impl HttpClient {
         headers: HeaderMap,
         body: impl RequestBody,
     ) -> Result<Response, Error> {
+        let body = Bytes::from(serde_json::to_vec(&body).map_err(|e| Error::Http {
+            code: StatusCode::INTERNAL_SERVER_ERROR,
+            message: format!("client request serialization failed: {}", e),
+        })?);
+        let Some(retry_policy) = &self.retry_policy else {
+            return self.send_attempt(url, method, headers, body).await;
+        };
+        // Retries are decided on the response status, before the response body is read
+        retry_policy
+            .call(
+                || self.send_attempt(url.clone(), method.clone(), headers.clone(), body.clone()),
+                |result| match result {
+                    Ok(response) => retry_policy.is_retryable_status(response.status()),
+                    Err(error) => retry_policy.is_retryable(error),
+                },
+            )
+            .await
+    }
+
+    /// Sends a request attempt through the circuit breaker, if configured.
+    async fn send_attempt(
+        &self,
+        url: Url,
+        method: Method,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<Response, Error> {
+        let Some(circuit_breaker) = &self.circuit_breaker else {
+            return self.send_request(url, method, headers, body).await;
+        };
+        // The permit records a failure if the request is dropped during a half-open trial
+        let permit = circuit_breaker.acquire()?;
+        let result = self.send_request(url, method, headers, body).await;
+        let success = match &result {
+            Ok(response) => !response.status().is_server_error(),
+            Err(error) => !is_failure(error),
+        };
+        permit.record(success);
+        result
+    }
+
+    async fn send_request(
+        &self,
+        url: Url,
+        method: Method,
+        mut headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<Response, Error> {
+        // Send the time remaining until the request deadline, if any
+        if let Some(deadline) = Deadline::take_from_headers(&mut headers) {
+            headers.insert(
+                REQUEST_TIMEOUT_HEADER,
+                HeaderValue::from(deadline.remaining().as_millis() as u64),
+            );
+        }
+        headers.remove(TENANT_HEADER);
         let ctx = Span::current().context();
         let headers = trace::with_traceparent_header(&ctx, headers);
         let mut builder = hyper::http::request::Builder::new()
@@ -173,14 +258,7 @@ impl HttpClient {
         match builder.headers_mut() {
             Some(headers_mut) => {
                 headers_mut.extend(headers);
-                let body =
-                    Full::new(Bytes::from(serde_json::to_vec(&body).map_err(|e| {
-                        Error::Http {
-                            code: StatusCode::INTERNAL_SERVER_ERROR,
-                            message: format!("client request serialization failed: {}", e)
-                        }
-                    })?))
-                        .map_err(|err| match err {});
+                let body = Full::new(body).map_err(|err| match err {});
                 let request = builder
                     .body(body.boxed())
                     .map_err(|e| {
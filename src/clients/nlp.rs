@@ -15,6 +15,8 @@
 
 */
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use axum::http::HeaderMap;
 use futures::{StreamExt, TryStreamExt};
@@ -23,7 +25,8 @@ use tonic::{Code, Request};
 use tracing::{Span, debug, instrument};
 
 use super::{
-    BoxStream, Client, Error, create_grpc_client, errors::grpc_to_http_code,
+    BoxStream, CircuitBreaker, Client, Error, RetryPolicy, create_circuit_breaker,
+    create_grpc_client, create_retry_policy, errors::grpc_to_http_code, grpc_call,
     grpc_request_with_headers, otel_grpc::OtelGrpcService,
 };
 use crate::{
@@ -51,15 +54,21 @@ const MODEL_ID_HEADER_NAME: &str = "mm-model-id";
 pub struct NlpClient {
     client: NlpServiceClient<OtelGrpcService<LoadBalancedChannel>>,
     health_client: HealthClient<OtelGrpcService<LoadBalancedChannel>>,
+    retry_policy: Option<Arc<RetryPolicy>>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
 }
 
 impl NlpClient {
     pub async fn new(config: &ServiceConfig) -> Self {
         let client = create_grpc_client(DEFAULT_PORT, config, NlpServiceClient::new).await;
         let health_client = create_grpc_client(DEFAULT_PORT, config, HealthClient::new).await;
+        let retry_policy = create_retry_policy(DEFAULT_PORT, config).map(Arc::new);
+        let circuit_breaker = create_circuit_breaker(DEFAULT_PORT, config).map(Arc::new);
         Self {
             client,
             health_client,
+            retry_policy,
+            circuit_breaker,
         }
     }
 
@@ -70,10 +79,17 @@ impl NlpClient {
         request: TokenizationTaskRequest,
         headers: HeaderMap,
     ) -> Result<TokenizationResults, Error> {
-        let mut client = self.client.clone();
-        let request = request_with_headers(request, model_id, headers);
         debug!(?request, "sending request to NLP gRPC service");
-        let response = client.tokenization_task_predict(request).await?;
+        let response = grpc_call(
+            self.retry_policy.as_deref(),
+            self.circuit_breaker.as_deref(),
+            || {
+                let mut client = self.client.clone();
+                let request = request_with_headers(request.clone(), model_id, headers.clone());
+                async move { client.tokenization_task_predict(request).await }
+            },
+        )
+        .await?;
         let span = Span::current();
         trace_context_from_grpc_response(&span, &response);
         Ok(response.into_inner())
@@ -87,10 +103,17 @@ impl NlpClient {
         headers: HeaderMap,
     ) -> Result<TokenClassificationResults, Error> {
         let span = Span::current();
-        let mut client = self.client.clone();
-        let request = request_with_headers(request, model_id, headers);
         debug!(?request, "sending request to NLP gRPC service");
-        let response = client.token_classification_task_predict(request).await?;
+        let response = grpc_call(
+            self.retry_policy.as_deref(),
+            self.circuit_breaker.as_deref(),
+            || {
+                let mut client = self.client.clone();
+                let request = request_with_headers(request.clone(), model_id, headers.clone());
+                async move { client.token_classification_task_predict(request).await }
+            },
+        )
+        .await?;
         trace_context_from_grpc_response(&span, &response);
         Ok(response.into_inner())
     }
@@ -102,10 +125,17 @@ impl NlpClient {
         request: TextGenerationTaskRequest,
         headers: HeaderMap,
     ) -> Result<GeneratedTextResult, Error> {
-        let mut client = self.client.clone();
-        let request = request_with_headers(request, model_id, headers);
         debug!(?request, "sending request to NLP gRPC service");
-        let response = client.text_generation_task_predict(request).await?;
+        let response = grpc_call(
+            self.retry_policy.as_deref(),
+            self.circuit_breaker.as_deref(),
+            || {
+                let mut client = self.client.clone();
+                let request = request_with_headers(request.clone(), model_id, headers.clone());
+                async move { client.text_generation_task_predict(request).await }
+            },
+        )
+        .await?;
         let span: Span = Span::current();
         trace_context_from_grpc_response(&span, &response);
         Ok(response.into_inner())
@@ -118,12 +148,21 @@ impl NlpClient {
         request: ServerStreamingTextGenerationTaskRequest,
         headers: HeaderMap,
     ) -> Result<BoxStream<Result<GeneratedTextStreamResult, Error>>, Error> {
-        let mut client = self.client.clone();
-        let request = request_with_headers(request, model_id, headers);
         debug!(?request, "sending stream request to NLP gRPC service");
-        let response = client
-            .server_streaming_text_generation_task_predict(request)
-            .await?;
+        let response = grpc_call(
+            self.retry_policy.as_deref(),
+            self.circuit_breaker.as_deref(),
+            || {
+                let mut client = self.client.clone();
+                let request = request_with_headers(request.clone(), model_id, headers.clone());
+                async move {
+                    client
+                        .server_streaming_text_generation_task_predict(request)
+                        .await
+                }
+            },
+        )
+        .await?;
         let span = Span::current();
         trace_context_from_grpc_response(&span, &response);
         let response_stream = response.into_inner().map_err(Into::into).boxed();
@@ -160,6 +199,10 @@ impl Client for NlpClient {
             reason: None,
         }
     }
+
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.circuit_breaker.as_deref()
+    }
 }
 
 /// Turns an NLP client gRPC request body of type `T` and headers into a `tonic::Request<T>`.
@@ -28,15 +28,18 @@ use tokio::sync::mpsc;
 use url::Url;
 
 use super::{
-    Client, Error, HttpClient, create_http_client,
+    CircuitBreaker, Client, Error, HttpClient, create_http_client,
     detector::ContentAnalysisResponse,
     http::{HttpClientExt, RequestBody},
 };
 use crate::{
-    config::ServiceConfig,
+    config::{GuardrailProfile, OrchestratorConfig, ServiceConfig},
     health::HealthCheckResult,
-    models::{DetectionWarningReason, DetectorParams, ValidationError},
+    models::{
+        DetectionWarningReason, DetectorParams, PolicyMatch, ValidationError, get_guardrail_profile,
+    },
     orchestrator,
+    utils::task_group,
 };
 
 const DEFAULT_PORT: u16 = 8080;
@@ -139,7 +142,7 @@ impl OpenAiClient {
                 // Create event stream
                 let mut event_stream = response.0.into_data_stream().eventsource();
                 // Spawn task to consume event stream and send messages to receiver
-                tokio::spawn(async move {
+                task_group::spawn(async move {
                     while let Some(result) = event_stream.next().await {
                         match result {
                             Ok(event) if event.data == "[DONE]" => {
@@ -200,6 +203,10 @@ impl Client for OpenAiClient {
             self.client.health().await
         }
     }
+
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.client.circuit_breaker()
+    }
 }
 
 impl HttpClientExt for OpenAiClient {
@@ -249,6 +256,12 @@ pub struct ChatCompletionsRequest {
     /// Detector config.
     #[serde(default, skip_serializing)]
     pub detectors: DetectorConfig,
+    /// Name of a guardrail profile providing detectors, overridden by `detectors`.
+    #[serde(default, skip_serializing)]
+    pub guardrail_profile: Option<String>,
+    /// Context documents for output detectors of type `text_context_doc`.
+    #[serde(default, skip_serializing)]
+    pub context_docs: Option<Vec<String>>,
     /// Stream parameter.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
@@ -262,6 +275,56 @@ pub struct ChatCompletionsRequest {
 }
 
 impl ChatCompletionsRequest {
+    /// Applies the guardrail profile, if specified, to `detectors`.
+    pub fn apply_guardrail_profile(
+        &mut self,
+        config: &OrchestratorConfig,
+    ) -> Result<(), ValidationError> {
+        if let Some(name) = &self.guardrail_profile {
+            self.detectors
+                .apply_profile(get_guardrail_profile(config, name)?);
+        }
+        Ok(())
+    }
+
+    /// Returns the text of the last user message, the prompt for output detectors
+    /// of type `text_generation`.
+    pub fn prompt(&self) -> String {
+        self.messages
+            .iter()
+            .rev()
+            .find(|message| message.role == Role::User)
+            .and_then(|message| match &message.content {
+                Some(Content::Text(text)) => Some(text.clone()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns context documents for output detectors of type `text_context_doc`,
+    /// `context_docs` if provided, otherwise the text of `system` and `tool` messages.
+    pub fn context(&self) -> Vec<String> {
+        if let Some(context_docs) = &self.context_docs {
+            return context_docs.clone();
+        }
+        self.messages
+            .iter()
+            .filter(|message| matches!(message.role, Role::System | Role::Tool))
+            .filter_map(|message| match &message.content {
+                Some(Content::Text(text)) if !text.is_empty() => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the tools of the request, which are passed through in `extra`.
+    pub fn tools(&self) -> Vec<Tool> {
+        self.extra
+            .get("tools")
+            .and_then(|tools| serde_json::from_value(tools.clone()).ok())
+            .unwrap_or_default()
+    }
+
     pub fn validate(&self) -> Result<(), ValidationError> {
         if self.model.is_empty() {
             return Err(ValidationError::Invalid("`model` must not be empty".into()));
@@ -307,6 +370,12 @@ impl ChatCompletionsRequest {
 /// the downstream server implementation.
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CompletionsRequest {
+    /// Detector config.
+    #[serde(default, skip_serializing)]
+    pub detectors: DetectorConfig,
+    /// Name of a guardrail profile providing detectors, overridden by `detectors`.
+    #[serde(default, skip_serializing)]
+    pub guardrail_profile: Option<String>,
     /// Stream parameter.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
@@ -320,6 +389,18 @@ pub struct CompletionsRequest {
 }
 
 impl CompletionsRequest {
+    /// Applies the guardrail profile, if specified, to `detectors`.
+    pub fn apply_guardrail_profile(
+        &mut self,
+        config: &OrchestratorConfig,
+    ) -> Result<(), ValidationError> {
+        if let Some(name) = &self.guardrail_profile {
+            self.detectors
+                .apply_profile(get_guardrail_profile(config, name)?);
+        }
+        Ok(())
+    }
+
     pub fn validate(&self) -> Result<(), ValidationError> {
         if self.model.is_empty() {
             return Err(ValidationError::Invalid("`model` must not be empty".into()));
@@ -343,6 +424,14 @@ pub struct DetectorConfig {
     pub output: HashMap<String, DetectorParams>,
 }
 
+impl DetectorConfig {
+    /// Applies a guardrail profile, with detectors of this config overriding the profile.
+    pub fn apply_profile(&mut self, profile: &GuardrailProfile) {
+        self.input = profile.input_detectors(std::mem::take(&mut self.input));
+        self.output = profile.output_detectors(std::mem::take(&mut self.output));
+    }
+}
+
 /// Response format.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResponseFormat {
@@ -644,6 +733,18 @@ pub struct ChatCompletionMessage {
     pub refusal: Option<String>,
 }
 
+impl From<ChatCompletionMessage> for Message {
+    fn from(value: ChatCompletionMessage) -> Self {
+        Self {
+            role: value.role,
+            content: value.content.map(Content::Text),
+            refusal: value.refusal,
+            tool_calls: (!value.tool_calls.is_empty()).then_some(value.tool_calls),
+            ..Default::default()
+        }
+    }
+}
+
 /// Chat completion logprobs.
 #[derive(Debug, Default, Clone, Deserialize, Serialize, PartialEq)]
 pub struct ChatCompletionLogprobs {
@@ -776,6 +877,14 @@ pub struct Completion {
     /// This fingerprint represents the backend configuration that the model runs with.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub system_fingerprint: Option<String>,
+    /// Detections
+    ///
+    /// Input detections on the prompt are reported with a `message_index` of 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detections: Option<ChatDetections>,
+    /// Warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<OrchestratorWarning>,
 }
 
 /// Completion (legacy) choice.
@@ -900,6 +1009,8 @@ pub struct OutputDetectionResult {
 pub struct OrchestratorWarning {
     r#type: DetectionWarningReason,
     message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    policy: Option<PolicyMatch>,
 }
 
 impl OrchestratorWarning {
@@ -907,8 +1018,25 @@ impl OrchestratorWarning {
         Self {
             r#type: warning_type,
             message: message.to_string(),
+            policy: None,
         }
     }
+
+    /// Sets the policy rule that fired.
+    pub fn with_policy(mut self, policy: Option<PolicyMatch>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns the warning type.
+    pub fn warning_type(&self) -> DetectionWarningReason {
+        self.r#type
+    }
+
+    /// Returns the policy rule that fired, if any.
+    pub fn policy(&self) -> Option<&PolicyMatch> {
+        self.policy.as_ref()
+    }
 }
 
 #[cfg(test)]
@@ -941,6 +1069,8 @@ mod test {
             request,
             ChatCompletionsRequest {
                 detectors,
+                guardrail_profile: None,
+                context_docs: None,
                 stream: None,
                 model: "test".into(),
                 messages: messages.clone(),
@@ -958,6 +1088,8 @@ mod test {
             request,
             ChatCompletionsRequest {
                 detectors: DetectorConfig::default(),
+                guardrail_profile: None,
+                context_docs: None,
                 stream: None,
                 model: "test".into(),
                 messages: messages.clone(),
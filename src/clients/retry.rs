@@ -0,0 +1,310 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+//! Client retry policy
+use std::{
+    future::Future,
+    hash::{BuildHasher, RandomState},
+    sync::Mutex,
+    time::Duration,
+};
+
+use hyper::StatusCode;
+use tonic::Code;
+use tracing::warn;
+
+use super::Error;
+use crate::{
+    config::RetryConfig,
+    utils::trace::{on_client_retry, on_retry_budget_exhausted},
+};
+
+/// Returns the gRPC code for a name, e.g. `UNAVAILABLE`.
+pub fn grpc_code_from_name(name: &str) -> Option<Code> {
+    let code = match name.to_uppercase().as_str() {
+        "OK" => Code::Ok,
+        "CANCELLED" => Code::Cancelled,
+        "UNKNOWN" => Code::Unknown,
+        "INVALID_ARGUMENT" => Code::InvalidArgument,
+        "DEADLINE_EXCEEDED" => Code::DeadlineExceeded,
+        "NOT_FOUND" => Code::NotFound,
+        "ALREADY_EXISTS" => Code::AlreadyExists,
+        "PERMISSION_DENIED" => Code::PermissionDenied,
+        "RESOURCE_EXHAUSTED" => Code::ResourceExhausted,
+        "FAILED_PRECONDITION" => Code::FailedPrecondition,
+        "ABORTED" => Code::Aborted,
+        "OUT_OF_RANGE" => Code::OutOfRange,
+        "UNIMPLEMENTED" => Code::Unimplemented,
+        "INTERNAL" => Code::Internal,
+        "UNAVAILABLE" => Code::Unavailable,
+        "DATA_LOSS" => Code::DataLoss,
+        "UNAUTHENTICATED" => Code::Unauthenticated,
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// A retry budget, limiting retries to a ratio of requests.
+///
+/// Each request deposits `ratio` tokens and each retry withdraws one,
+/// with up to `min_retries` tokens kept for bursts of retries.
+#[derive(Debug)]
+struct RetryBudget {
+    ratio: f64,
+    max_tokens: f64,
+    tokens: Mutex<f64>,
+}
+
+impl RetryBudget {
+    fn deposit(&self) {
+        let mut tokens = self.tokens.lock().unwrap();
+        *tokens = (*tokens + self.ratio).min(self.max_tokens);
+    }
+
+    fn withdraw(&self) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// A retry policy for client calls.
+///
+/// Only calls that have not returned data yet are retried: for streaming calls, the
+/// call establishing the stream is retried, never the stream once it has been returned.
+#[derive(Debug)]
+pub struct RetryPolicy {
+    service: String,
+    max_attempts: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    status_codes: Vec<StatusCode>,
+    grpc_codes: Vec<Code>,
+    budget: RetryBudget,
+}
+
+impl RetryPolicy {
+    pub fn new(service: String, config: &RetryConfig) -> Self {
+        let min_retries = config.budget.min_retries as f64;
+        Self {
+            service,
+            max_attempts: config.max_attempts,
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            max_backoff: Duration::from_millis(config.max_backoff_ms),
+            jitter: config.jitter,
+            status_codes: config
+                .retryable_status_codes
+                .iter()
+                .filter_map(|&code| StatusCode::from_u16(code).ok())
+                .collect(),
+            grpc_codes: config
+                .retryable_grpc_codes
+                .iter()
+                .map(String::as_str)
+                .filter_map(grpc_code_from_name)
+                .collect(),
+            budget: RetryBudget {
+                ratio: config.budget.ratio,
+                max_tokens: min_retries.max(1.0),
+                tokens: Mutex::new(min_retries),
+            },
+        }
+    }
+
+    /// Returns `true` if a response with this status code is retryable.
+    pub fn is_retryable_status(&self, code: StatusCode) -> bool {
+        self.status_codes.contains(&code)
+    }
+
+    /// Returns `true` if an error is retryable.
+    pub fn is_retryable(&self, error: &Error) -> bool {
+        match error {
+            Error::Grpc { grpc_code, .. } => self.grpc_codes.contains(grpc_code),
+            Error::Http { code, .. } => self.status_codes.contains(code),
+            // Not sent to the service
+            Error::ModelNotFound { .. } | Error::CircuitOpen { .. } => false,
+        }
+    }
+
+    /// Sends a request, retrying while `should_retry` returns `true` for its result
+    /// and attempts and retry budget remain.
+    pub async fn call<T, F, Fut>(
+        &self,
+        mut request: F,
+        should_retry: impl Fn(&Result<T, Error>) -> bool,
+    ) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        self.budget.deposit();
+        let mut attempt = 1;
+        loop {
+            let result = request().await;
+            if attempt >= self.max_attempts || !should_retry(&result) {
+                return result;
+            }
+            if !self.budget.withdraw() {
+                on_retry_budget_exhausted(&self.service);
+                return result;
+            }
+            let backoff = self.backoff(attempt);
+            if let Err(error) = &result {
+                warn!(service = %self.service, attempt, %error, ?backoff, "client request failed, retrying");
+            } else {
+                warn!(service = %self.service, attempt, ?backoff, "client request failed, retrying");
+            }
+            on_client_retry(&self.service);
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+
+    /// Returns the backoff before retrying after an attempt.
+    fn backoff(&self, attempt: usize) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2_u32.saturating_pow(attempt as u32 - 1))
+            .min(self.max_backoff);
+        if self.jitter {
+            let random = RandomState::new().hash_one(attempt) as f64 / u64::MAX as f64;
+            backoff.mul_f64(0.5 + random / 2.0)
+        } else {
+            backoff
+        }
+    }
+}
+
+/// Sends a request with a retry policy, if provided, retrying retryable errors.
+pub async fn with_retry_policy<T, F, Fut>(
+    retry_policy: Option<&RetryPolicy>,
+    mut request: F,
+) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    match retry_policy {
+        Some(retry_policy) => {
+            retry_policy
+                .call(request, |result| {
+                    result
+                        .as_ref()
+                        .is_err_and(|error| retry_policy.is_retryable(error))
+                })
+                .await
+        }
+        None => request().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::config::RetryBudgetConfig;
+
+    fn retry_policy(max_attempts: usize, min_retries: u32) -> RetryPolicy {
+        RetryPolicy::new(
+            "localhost:8000".into(),
+            &RetryConfig {
+                max_attempts,
+                initial_backoff_ms: 1,
+                max_backoff_ms: 1,
+                budget: RetryBudgetConfig {
+                    ratio: 0.0,
+                    min_retries,
+                },
+                ..Default::default()
+            },
+        )
+    }
+
+    fn unavailable() -> Error {
+        tonic::Status::unavailable("unavailable").into()
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        let policy = retry_policy(3, 10);
+        let attempts = &AtomicUsize::new(0);
+        let result = with_retry_policy(Some(&policy), || async move {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(unavailable()),
+                _ => Ok(()),
+            }
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        // Max attempts
+        let attempts = &AtomicUsize::new(0);
+        let result = with_retry_policy(Some(&policy), || async move {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(unavailable())
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        // Non-retryable errors
+        let attempts = &AtomicUsize::new(0);
+        let _ = with_retry_policy(Some(&policy), || async move {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(tonic::Status::invalid_argument("invalid").into())
+        })
+        .await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget() {
+        let policy = retry_policy(3, 1);
+        let attempts = &AtomicUsize::new(0);
+        let _ = with_retry_policy(Some(&policy), || async move {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(unavailable())
+        })
+        .await;
+        // Budget allows a single retry
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::new(
+            "localhost:8000".into(),
+            &RetryConfig {
+                initial_backoff_ms: 100,
+                max_backoff_ms: 300,
+                ..Default::default()
+            },
+        );
+        for (attempt, max) in [(1, 100), (2, 200), (3, 300), (4, 300)] {
+            let backoff = policy.backoff(attempt);
+            assert!(backoff >= Duration::from_millis(max / 2));
+            assert!(backoff <= Duration::from_millis(max));
+        }
+    }
+}
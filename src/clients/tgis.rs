@@ -15,6 +15,8 @@
 
 */
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use axum::http::HeaderMap;
 use futures::{StreamExt, TryStreamExt};
@@ -23,7 +25,8 @@ use tonic::Code;
 use tracing::Span;
 
 use super::{
-    BoxStream, Client, Error, create_grpc_client, errors::grpc_to_http_code,
+    BoxStream, CircuitBreaker, Client, Error, RetryPolicy, create_circuit_breaker,
+    create_grpc_client, create_retry_policy, errors::grpc_to_http_code, grpc_call,
     grpc_request_with_headers, otel_grpc::OtelGrpcService,
 };
 use crate::{
@@ -42,12 +45,20 @@ const DEFAULT_PORT: u16 = 8033;
 #[derive(Clone)]
 pub struct TgisClient {
     client: GenerationServiceClient<OtelGrpcService<LoadBalancedChannel>>,
+    retry_policy: Option<Arc<RetryPolicy>>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
 }
 
 impl TgisClient {
     pub async fn new(config: &ServiceConfig) -> Self {
         let client = create_grpc_client(DEFAULT_PORT, config, GenerationServiceClient::new).await;
-        Self { client }
+        let retry_policy = create_retry_policy(DEFAULT_PORT, config).map(Arc::new);
+        let circuit_breaker = create_circuit_breaker(DEFAULT_PORT, config).map(Arc::new);
+        Self {
+            client,
+            retry_policy,
+            circuit_breaker,
+        }
     }
 
     pub async fn generate(
@@ -55,9 +66,16 @@ impl TgisClient {
         request: BatchedGenerationRequest,
         headers: HeaderMap,
     ) -> Result<BatchedGenerationResponse, Error> {
-        let request = grpc_request_with_headers(request, headers);
-        let mut client = self.client.clone();
-        let response = client.generate(request).await?;
+        let response = grpc_call(
+            self.retry_policy.as_deref(),
+            self.circuit_breaker.as_deref(),
+            || {
+                let mut client = self.client.clone();
+                let request = grpc_request_with_headers(request.clone(), headers.clone());
+                async move { client.generate(request).await }
+            },
+        )
+        .await?;
         let span = Span::current();
         trace_context_from_grpc_response(&span, &response);
         Ok(response.into_inner())
@@ -68,9 +86,16 @@ impl TgisClient {
         request: SingleGenerationRequest,
         headers: HeaderMap,
     ) -> Result<BoxStream<Result<GenerationResponse, Error>>, Error> {
-        let request = grpc_request_with_headers(request, headers);
-        let mut client = self.client.clone();
-        let response = client.generate_stream(request).await?;
+        let response = grpc_call(
+            self.retry_policy.as_deref(),
+            self.circuit_breaker.as_deref(),
+            || {
+                let mut client = self.client.clone();
+                let request = grpc_request_with_headers(request.clone(), headers.clone());
+                async move { client.generate_stream(request).await }
+            },
+        )
+        .await?;
         let span = Span::current();
         trace_context_from_grpc_response(&span, &response);
         Ok(response.into_inner().map_err(Into::into).boxed())
@@ -81,9 +106,16 @@ impl TgisClient {
         request: BatchedTokenizeRequest,
         headers: HeaderMap,
     ) -> Result<BatchedTokenizeResponse, Error> {
-        let mut client = self.client.clone();
-        let request = grpc_request_with_headers(request, headers);
-        let response = client.tokenize(request).await?;
+        let response = grpc_call(
+            self.retry_policy.as_deref(),
+            self.circuit_breaker.as_deref(),
+            || {
+                let mut client = self.client.clone();
+                let request = grpc_request_with_headers(request.clone(), headers.clone());
+                async move { client.tokenize(request).await }
+            },
+        )
+        .await?;
         let span = Span::current();
         trace_context_from_grpc_response(&span, &response);
         Ok(response.into_inner())
@@ -130,4 +162,8 @@ impl Client for TgisClient {
             reason: None,
         }
     }
+
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.circuit_breaker.as_deref()
+    }
 }
@@ -20,10 +20,14 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use serde::Deserialize;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 
-use crate::clients::{chunker::DEFAULT_CHUNKER_ID, is_valid_hostname};
+use crate::{
+    clients::{chunker::DEFAULT_CHUNKER_ID, is_valid_hostname, retry::grpc_code_from_name},
+    models::{DetectorParams, validate_detector_params},
+};
 
 /// Default allowed headers to passthrough to clients.
 const DEFAULT_ALLOWED_HEADERS: &[&str] = &[];
@@ -60,11 +64,31 @@ pub enum Error {
     InvalidGenerationProvider(String),
     #[error("invalid hostname: {0}")]
     InvalidHostname(String),
+    #[error("invalid policy: {0}")]
+    InvalidPolicy(String),
+    #[error("invalid profile: {0}")]
+    InvalidProfile(String),
+    #[error("invalid auth config: {0}")]
+    InvalidAuthConfig(String),
+    #[error("invalid limits config: {0}")]
+    InvalidLimitsConfig(String),
+    #[error("invalid jobs config: {0}")]
+    InvalidJobsConfig(String),
+    #[error("invalid audit config: {0}")]
+    InvalidAuditConfig(String),
+    #[error(
+        "invalid cache config for detector `{0}`: max_entries and ttl_seconds must be greater than 0"
+    )]
+    InvalidDetectorCacheConfig(String),
+    #[error("invalid circuit breaker config: {0}")]
+    InvalidCircuitBreakerConfig(String),
+    #[error("invalid retry config: {0}")]
+    InvalidRetryConfig(String),
 }
 
 /// Configuration for service needed for
 /// orchestrator to communicate with it
-#[derive(Default, Clone, Debug, Deserialize)]
+#[derive(Default, Clone, Debug, Deserialize, PartialEq)]
 pub struct ServiceConfig {
     /// Hostname for service
     pub hostname: String,
@@ -80,8 +104,15 @@ pub struct ServiceConfig {
     pub resolution_strategy: Option<String>,
     /// Resolution strategy timeout in seconds
     pub resolution_strategy_timeout: Option<u64>,
-    /// Max retries for client calls [currently only for grpc generation]
+    /// Max retries for generation calls, deprecated in favor of `retry`
     pub max_retries: Option<usize>,
+    /// Retry policy for client calls, if omitted calls are not retried
+    pub retry: Option<RetryConfig>,
+    /// Circuit breaker failing requests fast while the service is failing
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// Whether the orchestrator is not ready while the service is not healthy
+    #[serde(default)]
+    pub required: bool,
 }
 
 impl ServiceConfig {
@@ -95,12 +126,90 @@ impl ServiceConfig {
             resolution_strategy: None,
             resolution_strategy_timeout: None,
             max_retries: None,
+            retry: None,
+            circuit_breaker: None,
+            required: false,
+        }
+    }
+}
+
+/// Retry policy configuration.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Maximum number of attempts, including the first one
+    pub max_attempts: usize,
+    /// Backoff before the first retry in milliseconds, doubled on each retry
+    pub initial_backoff_ms: u64,
+    /// Maximum backoff in milliseconds
+    pub max_backoff_ms: u64,
+    /// Whether backoffs are randomized between half and the full backoff
+    pub jitter: bool,
+    /// HTTP status codes of retryable responses
+    pub retryable_status_codes: Vec<u16>,
+    /// gRPC codes of retryable responses, e.g. `UNAVAILABLE`
+    pub retryable_grpc_codes: Vec<String>,
+    /// Retry budget limiting retries to a ratio of requests
+    pub budget: RetryBudgetConfig,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 2000,
+            jitter: true,
+            retryable_status_codes: vec![502, 503, 504],
+            retryable_grpc_codes: vec!["UNAVAILABLE".into()],
+            budget: RetryBudgetConfig::default(),
+        }
+    }
+}
+
+/// Retry budget configuration.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryBudgetConfig {
+    /// Retries allowed per request, e.g. `0.2` allows retrying 1 in 5 requests
+    pub ratio: f64,
+    /// Retries allowed regardless of the ratio, e.g. at low request rates
+    pub min_retries: u32,
+}
+
+impl Default for RetryBudgetConfig {
+    fn default() -> Self {
+        Self {
+            ratio: 0.2,
+            min_retries: 10,
+        }
+    }
+}
+
+/// Circuit breaker configuration.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures (5xx responses, timeouts and connection errors) opening the circuit
+    pub failure_threshold: u32,
+    /// Time the circuit stays open before trial requests are allowed, in seconds
+    pub open_seconds: u64,
+    /// Number of concurrent trial requests allowed while the circuit is half-open
+    pub half_open_max_requests: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_seconds: 30,
+            half_open_max_requests: 1,
         }
     }
 }
 
 /// TLS provider
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq)]
 #[serde(untagged)]
 pub enum Tls {
     Name(String),
@@ -108,7 +217,7 @@ pub enum Tls {
 }
 
 /// Client TLS configuration
-#[derive(Default, Clone, Debug, Deserialize)]
+#[derive(Default, Clone, Debug, Deserialize, PartialEq)]
 pub struct TlsConfig {
     pub cert_path: Option<PathBuf>,
     pub key_path: Option<PathBuf>,
@@ -117,7 +226,7 @@ pub struct TlsConfig {
 }
 
 /// Generation service provider
-#[derive(Default, Clone, Copy, Debug, Deserialize)]
+#[derive(Default, Clone, Copy, Debug, Deserialize, PartialEq)]
 pub enum GenerationProvider {
     #[default]
     #[serde(rename = "tgis")]
@@ -127,7 +236,7 @@ pub enum GenerationProvider {
 }
 
 /// Generation service configuration
-#[derive(Default, Clone, Debug, Deserialize)]
+#[derive(Default, Clone, Debug, Deserialize, PartialEq)]
 pub struct GenerationConfig {
     /// Generation service provider
     pub provider: GenerationProvider,
@@ -136,7 +245,7 @@ pub struct GenerationConfig {
 }
 
 /// OpenAI service configuration
-#[derive(Default, Clone, Debug, Deserialize)]
+#[derive(Default, Clone, Debug, Deserialize, PartialEq)]
 pub struct OpenAiConfig {
     /// Generation service connection information
     pub service: ServiceConfig,
@@ -145,7 +254,7 @@ pub struct OpenAiConfig {
 }
 
 /// Chunker parser type
-#[derive(Default, Clone, Copy, Debug, Deserialize)]
+#[derive(Default, Clone, Copy, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ChunkerType {
     #[default]
@@ -176,9 +285,64 @@ pub struct DetectorConfig {
     /// Type of detection this detector performs
     #[serde(rename = "type")]
     pub r#type: DetectorType,
+    /// Action to take on detections from this detector
+    #[serde(default)]
+    pub action: DetectorAction,
+    /// Placeholder replacing spans redacted by this detector,
+    /// if omitted, the uppercased detection class is used, e.g. `[EMAIL]`
+    pub redaction_placeholder: Option<String>,
+    /// Cache for results of this detector, if omitted results are not cached
+    pub cache: Option<DetectorCacheConfig>,
+    /// Behavior when requests to this detector fail
+    #[serde(default)]
+    pub on_error: DetectorOnError,
 }
 
-#[derive(Default, Clone, Debug, Deserialize, PartialEq)]
+/// Detector result cache configuration.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DetectorCacheConfig {
+    /// Maximum number of entries, least recently used entries are evicted first
+    pub max_entries: usize,
+    /// Time entries are kept in the cache, in seconds, if omitted entries do not expire
+    pub ttl_seconds: Option<u64>,
+}
+
+impl Default for DetectorCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 1024,
+            ttl_seconds: None,
+        }
+    }
+}
+
+/// Action to take on detections.
+#[derive(Default, Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectorAction {
+    /// Return detections alongside the output
+    #[default]
+    Flag,
+    /// Mask detected spans in the output with a placeholder
+    Redact,
+    /// Replace detected spans in the input with placeholders before generation,
+    /// restoring the original values in the generated output
+    Anonymize,
+}
+
+/// Behavior when requests to a detector fail.
+#[derive(Default, Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectorOnError {
+    /// Fail the whole request
+    #[default]
+    Fail,
+    /// Skip the detector, returning partial results with a warning naming it
+    Skip,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum DetectorType {
@@ -189,6 +353,283 @@ pub enum DetectorType {
     TextContextDoc,
 }
 
+/// Outcome of a policy rule.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyOutcome {
+    /// Block the input or output
+    Block,
+    /// Return the input or output with detections and a warning
+    Warn,
+    /// Mask detected spans with a placeholder
+    Redact,
+    /// Ignore detections
+    Allow,
+}
+
+/// Direction of detections a policy rule applies to.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyDirection {
+    Input,
+    Output,
+}
+
+/// Policy rule mapping detections to an outcome.
+/// All specified conditions must hold for a detection to match the rule.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PolicyRule {
+    /// Rule name, reported in warnings when the rule fires
+    pub name: String,
+    /// Direction the rule applies to, if omitted the rule applies to input and output
+    pub applies_to: Option<PolicyDirection>,
+    /// Detector ID to match
+    pub detector_id: Option<String>,
+    /// Detection class to match
+    pub detection: Option<String>,
+    /// Detection type to match
+    pub detection_type: Option<String>,
+    /// Minimum score (inclusive) to match
+    pub min_score: Option<f64>,
+    /// Maximum score (inclusive) to match
+    pub max_score: Option<f64>,
+    /// Minimum number of matching detections for the rule to fire
+    #[serde(default = "default_policy_min_count")]
+    pub min_count: usize,
+    /// Maximum number of matching detections for the rule to fire
+    pub max_count: Option<usize>,
+    /// Outcome when the rule fires
+    pub outcome: PolicyOutcome,
+}
+
+const fn default_policy_min_count() -> usize {
+    1
+}
+
+/// Authentication configuration for the guardrails server.
+#[derive(Default, Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthConfig {
+    /// API keys, each identifying a tenant
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+    /// JWT bearer token configuration
+    pub jwt: Option<JwtConfig>,
+    /// Restrictions per tenant, tenants not listed are unrestricted
+    #[serde(default)]
+    pub tenants: HashMap<String, TenantConfig>,
+}
+
+/// API key configuration.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    /// Tenant identified by the API key
+    pub tenant: String,
+    /// Path to file containing the API key
+    pub key_path: PathBuf,
+}
+
+/// JWT signing algorithm.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum JwtAlgorithm {
+    HS256,
+    RS256,
+}
+
+/// JWT bearer token configuration.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JwtConfig {
+    /// Signing algorithm
+    pub algorithm: JwtAlgorithm,
+    /// Path to file containing the HS256 secret or RS256 public key (PEM)
+    pub key_path: PathBuf,
+    /// Expected issuer (`iss` claim), optional
+    pub issuer: Option<String>,
+    /// Expected audience (`aud` claim), optional
+    pub audience: Option<String>,
+    /// Claim identifying the tenant
+    #[serde(default = "default_tenant_claim")]
+    pub tenant_claim: String,
+}
+
+fn default_tenant_claim() -> String {
+    "sub".into()
+}
+
+/// Tenant restrictions.
+#[derive(Default, Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantConfig {
+    /// Detectors the tenant may use, if omitted all detectors are allowed
+    pub detectors: Option<HashSet<String>>,
+    /// Generation models the tenant may use, if omitted all models are allowed
+    pub models: Option<HashSet<String>>,
+}
+
+/// Request rate and concurrency limits for the guardrails server.
+#[derive(Default, Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Limits shared by all requests
+    #[serde(default)]
+    pub global: LimitConfig,
+    /// Default limits for each tenant not listed in `tenants`
+    #[serde(default)]
+    pub per_tenant: LimitConfig,
+    /// Limits for specific tenants
+    #[serde(default)]
+    pub tenants: HashMap<String, LimitConfig>,
+}
+
+/// Request rate and concurrency limits.
+#[derive(Default, Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LimitConfig {
+    /// Sustained request rate, in requests per second
+    pub requests_per_second: Option<f64>,
+    /// Maximum request burst, defaults to `requests_per_second` (rounded up)
+    pub burst: Option<u32>,
+    /// Maximum concurrent streaming requests
+    pub max_concurrent_streams: Option<usize>,
+}
+
+impl LimitConfig {
+    /// Returns `true` if no limits are set.
+    pub fn is_empty(&self) -> bool {
+        self.requests_per_second.is_none() && self.max_concurrent_streams.is_none()
+    }
+}
+
+/// Asynchronous jobs configuration.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobsConfig {
+    /// Maximum number of jobs kept in the store
+    pub max_jobs: usize,
+    /// Time finished jobs are kept in the store, in seconds
+    pub ttl_seconds: u64,
+    /// Directory where jobs are persisted to survive restarts, if omitted jobs are kept in memory only
+    pub store_path: Option<PathBuf>,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            max_jobs: 1000,
+            ttl_seconds: 3600,
+            store_path: None,
+        }
+    }
+}
+
+/// Audit log configuration.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AuditConfig {
+    /// Sink audit records are written to
+    pub sink: AuditSinkConfig,
+    /// How detected text is recorded
+    #[serde(default)]
+    pub content: AuditContent,
+    /// Path to file containing the secret key detected text is hashed with,
+    /// required if `content` is `hash`
+    pub hash_key_path: Option<PathBuf>,
+}
+
+/// Sink audit records are written to.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditSinkConfig {
+    /// Local JSONL file, rotated when it exceeds `max_size_mb`
+    File {
+        /// Path to the file
+        path: PathBuf,
+        /// Maximum size of the file in megabytes before it is rotated
+        #[serde(default = "default_audit_max_size_mb")]
+        max_size_mb: u64,
+        /// Number of rotated files kept
+        #[serde(default = "default_audit_max_files")]
+        max_files: usize,
+    },
+    /// HTTP webhook receiving each record as JSON in a `POST` request
+    Webhook {
+        /// Webhook URL
+        url: String,
+        /// Headers sent with each request, e.g. for authorization
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+}
+
+fn default_audit_max_size_mb() -> u64 {
+    100
+}
+
+fn default_audit_max_files() -> usize {
+    5
+}
+
+/// How detected text is recorded in audit records.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditContent {
+    /// Detected text is left out
+    #[default]
+    Omit,
+    /// Detected text is recorded as its HMAC-SHA256, keyed with the configured secret
+    Hash,
+    /// Detected text is recorded as is
+    Include,
+}
+
+/// Named guardrail profile, defining input and output detectors with their params.
+#[derive(Default, Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GuardrailProfile {
+    /// Input detectors and their params
+    #[serde(default)]
+    pub input: HashMap<String, DetectorParams>,
+    /// Output detectors and their params
+    #[serde(default)]
+    pub output: HashMap<String, DetectorParams>,
+}
+
+impl GuardrailProfile {
+    /// Returns input detectors of the profile with `overrides` applied.
+    pub fn input_detectors(
+        &self,
+        overrides: HashMap<String, DetectorParams>,
+    ) -> HashMap<String, DetectorParams> {
+        merge_detectors(&self.input, overrides)
+    }
+
+    /// Returns output detectors of the profile with `overrides` applied.
+    pub fn output_detectors(
+        &self,
+        overrides: HashMap<String, DetectorParams>,
+    ) -> HashMap<String, DetectorParams> {
+        merge_detectors(&self.output, overrides)
+    }
+}
+
+/// Merges detectors, adding detectors in `overrides` and overriding params of existing detectors.
+fn merge_detectors(
+    detectors: &HashMap<String, DetectorParams>,
+    overrides: HashMap<String, DetectorParams>,
+) -> HashMap<String, DetectorParams> {
+    let mut detectors = detectors.clone();
+    for (detector_id, params) in overrides {
+        detectors.entry(detector_id).or_default().extend(
+            params
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+    }
+    detectors
+}
+
 /// Overall orchestrator server configuration
 #[derive(Clone, Debug, Deserialize)]
 pub struct OrchestratorConfig {
@@ -215,6 +656,22 @@ pub struct OrchestratorConfig {
     /// Number of chunker requests to send concurrently for a task.
     #[serde(default = "default_chunker_concurrent_requests")]
     pub chunker_concurrent_requests: usize,
+    /// Policy rules mapping detections to outcomes, evaluated in order.
+    /// The first rule to fire determines the outcome.
+    #[serde(default)]
+    pub policies: Vec<PolicyRule>,
+    /// Named guardrail profiles that can be referenced in requests with `guardrail_profile`.
+    #[serde(default)]
+    pub profiles: HashMap<String, GuardrailProfile>,
+    /// Authentication configuration, if omitted authentication is disabled
+    pub auth: Option<AuthConfig>,
+    /// Request rate and concurrency limits, if omitted requests are not limited
+    pub limits: Option<LimitsConfig>,
+    /// Asynchronous jobs configuration
+    #[serde(default)]
+    pub jobs: JobsConfig,
+    /// Audit log configuration, if omitted guardrail decisions are not audited
+    pub audit: Option<AuditConfig>,
 }
 
 impl OrchestratorConfig {
@@ -310,6 +767,13 @@ impl OrchestratorConfig {
         self.validate_openai_configs()?;
         self.validate_detector_configs()?;
         self.validate_chunker_configs()?;
+        self.validate_policies()?;
+        self.validate_profiles()?;
+        self.validate_auth_config()?;
+        self.validate_limits_config()?;
+        self.validate_jobs_config()?;
+        self.validate_audit_config()?;
+        self.validate_client_policies()?;
 
         Ok(())
     }
@@ -370,6 +834,14 @@ impl OrchestratorConfig {
                     chunker_id: detector.chunker_id.clone(),
                 });
             }
+            // Cache is valid
+            let invalid_cache = detector
+                .cache
+                .as_ref()
+                .is_some_and(|cache| cache.max_entries == 0 || cache.ttl_seconds == Some(0));
+            if invalid_cache {
+                return Err(Error::InvalidDetectorCacheConfig(detector_id.clone()));
+            }
         }
         Ok(())
     }
@@ -389,6 +861,207 @@ impl OrchestratorConfig {
         Ok(())
     }
 
+    /// Validates policy rules.
+    fn validate_policies(&self) -> Result<(), Error> {
+        let mut names = HashSet::with_capacity(self.policies.len());
+        for rule in &self.policies {
+            // Name is unique
+            if rule.name.is_empty() {
+                return Err(Error::InvalidPolicy("rule `name` must not be empty".into()));
+            }
+            if !names.insert(rule.name.as_str()) {
+                return Err(Error::InvalidPolicy(format!(
+                    "rule `{}` is defined more than once",
+                    rule.name
+                )));
+            }
+            // Detector exists
+            if let Some(detector_id) = &rule.detector_id {
+                if !self.detectors.contains_key(detector_id) {
+                    return Err(Error::InvalidPolicy(format!(
+                        "rule `{}` references detector `{detector_id}` which is not configured",
+                        rule.name
+                    )));
+                }
+            }
+            // Ranges are valid
+            if let (Some(min_score), Some(max_score)) = (rule.min_score, rule.max_score) {
+                if min_score > max_score {
+                    return Err(Error::InvalidPolicy(format!(
+                        "rule `{}` has `min_score` greater than `max_score`",
+                        rule.name
+                    )));
+                }
+            }
+            if rule.min_count == 0 {
+                return Err(Error::InvalidPolicy(format!(
+                    "rule `{}` must have `min_count` of at least 1",
+                    rule.name
+                )));
+            }
+            if rule
+                .max_count
+                .is_some_and(|max_count| rule.min_count > max_count)
+            {
+                return Err(Error::InvalidPolicy(format!(
+                    "rule `{}` has `min_count` greater than `max_count`",
+                    rule.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates guardrail profiles.
+    fn validate_profiles(&self) -> Result<(), Error> {
+        for (name, profile) in &self.profiles {
+            for detectors in [&profile.input, &profile.output] {
+                for detector_id in detectors.keys() {
+                    // Detector exists, its type is validated by the endpoint the profile is applied on
+                    if !self.detectors.contains_key(detector_id) {
+                        return Err(Error::InvalidProfile(format!(
+                            "profile `{name}` references detector `{detector_id}` which is not configured"
+                        )));
+                    }
+                }
+                // Detector params are valid
+                validate_detector_params(detectors)
+                    .map_err(|error| Error::InvalidProfile(format!("profile `{name}`: {error}")))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates auth config.
+    fn validate_auth_config(&self) -> Result<(), Error> {
+        if let Some(auth) = &self.auth {
+            // Credentials are configured
+            if auth.api_keys.is_empty() && auth.jwt.is_none() {
+                return Err(Error::InvalidAuthConfig(
+                    "at least one of `api_keys` or `jwt` must be configured".into(),
+                ));
+            }
+            // Tenant detectors exist
+            for (tenant, config) in &auth.tenants {
+                if let Some(detector_id) = config
+                    .detectors
+                    .iter()
+                    .flatten()
+                    .find(|detector_id| !self.detectors.contains_key(*detector_id))
+                {
+                    return Err(Error::InvalidAuthConfig(format!(
+                        "tenant `{tenant}` references detector `{detector_id}` which is not configured"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates limits config.
+    fn validate_limits_config(&self) -> Result<(), Error> {
+        if let Some(limits) = &self.limits {
+            let configs = [
+                ("global", &limits.global),
+                ("per_tenant", &limits.per_tenant),
+            ]
+            .into_iter()
+            .chain(
+                limits
+                    .tenants
+                    .iter()
+                    .map(|(tenant, config)| (tenant.as_str(), config)),
+            );
+            for (name, config) in configs {
+                if config
+                    .requests_per_second
+                    .is_some_and(|rate| !rate.is_finite() || rate <= 0.0)
+                {
+                    return Err(Error::InvalidLimitsConfig(format!(
+                        "`{name}` requests_per_second must be greater than 0"
+                    )));
+                }
+                if config.burst == Some(0) || config.max_concurrent_streams == Some(0) {
+                    return Err(Error::InvalidLimitsConfig(format!(
+                        "`{name}` burst and max_concurrent_streams must be greater than 0"
+                    )));
+                }
+                if config.burst.is_some() && config.requests_per_second.is_none() {
+                    return Err(Error::InvalidLimitsConfig(format!(
+                        "`{name}` burst requires requests_per_second"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates jobs config.
+    fn validate_jobs_config(&self) -> Result<(), Error> {
+        if self.jobs.max_jobs == 0 || self.jobs.ttl_seconds == 0 {
+            return Err(Error::InvalidJobsConfig(
+                "max_jobs and ttl_seconds must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validates audit config.
+    fn validate_audit_config(&self) -> Result<(), Error> {
+        if let Some(audit) = &self.audit {
+            // Detected text is not hashed without a secret key
+            if audit.content == AuditContent::Hash && audit.hash_key_path.is_none() {
+                return Err(Error::InvalidAuditConfig(
+                    "`hash_key_path` is required if `content` is `hash`".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates retry and circuit breaker configs of all services.
+    fn validate_client_policies(&self) -> Result<(), Error> {
+        let mut services = vec![];
+        if let Some(generation) = &self.generation {
+            services.push(("generation".to_string(), &generation.service));
+        }
+        if let Some(chat_completions) = &self.chat_completions {
+            services.push(("chat_completions".to_string(), &chat_completions.service));
+        }
+        if let Some(completions) = &self.completions {
+            services.push(("completions".to_string(), &completions.service));
+        }
+        if let Some(chunkers) = &self.chunkers {
+            for (chunker_id, chunker) in chunkers {
+                services.push((format!("chunker `{chunker_id}`"), &chunker.service));
+            }
+        }
+        for (detector_id, detector) in &self.detectors {
+            services.push((format!("detector `{detector_id}`"), &detector.service));
+        }
+        for (name, service) in services {
+            let invalid = service.circuit_breaker.as_ref().is_some_and(|config| {
+                config.failure_threshold == 0
+                    || config.open_seconds == 0
+                    || config.half_open_max_requests == 0
+            });
+            if invalid {
+                return Err(Error::InvalidCircuitBreakerConfig(format!(
+                    "{name}: failure_threshold, open_seconds and half_open_max_requests must be greater than 0"
+                )));
+            }
+            if let Some(retry) = &service.retry {
+                validate_retry_config(&name, retry)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets a guardrail profile.
+    pub fn profile(&self, name: &str) -> Option<&GuardrailProfile> {
+        self.profiles.get(name)
+    }
+
     /// Get ID of chunker associated with a particular detector
     pub fn get_chunker_id(&self, detector_id: &str) -> Option<String> {
         self.detectors
@@ -423,6 +1096,12 @@ impl Default for OrchestratorConfig {
             passthrough_headers: HashSet::default(),
             detector_concurrent_requests: default_detector_concurrent_requests(),
             chunker_concurrent_requests: default_chunker_concurrent_requests(),
+            policies: Vec::default(),
+            profiles: HashMap::default(),
+            auth: None,
+            limits: None,
+            jobs: JobsConfig::default(),
+            audit: None,
         }
     }
 }
@@ -453,6 +1132,44 @@ impl Default for Tls {
     }
 }
 
+/// Validates a retry config.
+fn validate_retry_config(name: &str, retry: &RetryConfig) -> Result<(), Error> {
+    if retry.max_attempts == 0 {
+        return Err(Error::InvalidRetryConfig(format!(
+            "{name}: max_attempts must be greater than 0"
+        )));
+    }
+    if retry.initial_backoff_ms > retry.max_backoff_ms {
+        return Err(Error::InvalidRetryConfig(format!(
+            "{name}: initial_backoff_ms must not be greater than max_backoff_ms"
+        )));
+    }
+    if !(0.0..=1.0).contains(&retry.budget.ratio) {
+        return Err(Error::InvalidRetryConfig(format!(
+            "{name}: budget ratio must be between 0 and 1"
+        )));
+    }
+    if let Some(code) = retry
+        .retryable_status_codes
+        .iter()
+        .find(|&&code| StatusCode::from_u16(code).is_err())
+    {
+        return Err(Error::InvalidRetryConfig(format!(
+            "{name}: invalid status code `{code}`"
+        )));
+    }
+    if let Some(code) = retry
+        .retryable_grpc_codes
+        .iter()
+        .find(|code| grpc_code_from_name(code).is_none())
+    {
+        return Err(Error::InvalidRetryConfig(format!(
+            "{name}: invalid gRPC code `{code}`"
+        )));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -792,4 +1509,510 @@ passthrough_headers:
         );
         Ok(())
     }
+
+    #[test]
+    fn test_deserialize_config_detector_action() -> Result<(), Error> {
+        let s = r#"
+detectors:
+    pii:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+        action: redact
+        redaction_placeholder: "[PII]"
+    hap:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+        "#;
+        let config: OrchestratorConfig = serde_yml::from_str(s).unwrap();
+        let pii = config.detector("pii").unwrap();
+        assert_eq!(pii.action, DetectorAction::Redact);
+        assert_eq!(pii.redaction_placeholder.as_deref(), Some("[PII]"));
+        let hap = config.detector("hap").unwrap();
+        assert_eq!(hap.action, DetectorAction::Flag);
+        assert!(hap.redaction_placeholder.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_config_policies() -> Result<(), Error> {
+        let s = r#"
+detectors:
+    hap:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+policies:
+    - name: block-hap
+      detector_id: hap
+      min_score: 0.9
+      outcome: block
+    - name: warn-hap-output
+      applies_to: output
+      detection: hap
+      min_count: 2
+      outcome: warn
+        "#;
+        let config: OrchestratorConfig = serde_yml::from_str(s).unwrap();
+        config.validate()?;
+        assert_eq!(config.policies.len(), 2);
+        assert_eq!(config.policies[0].outcome, PolicyOutcome::Block);
+        assert_eq!(config.policies[0].min_count, 1);
+        assert!(config.policies[0].applies_to.is_none());
+        assert_eq!(config.policies[1].applies_to, Some(PolicyDirection::Output));
+        assert_eq!(config.policies[1].min_count, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_config_invalid_policies() {
+        let detectors = r#"
+detectors:
+    hap:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+"#;
+        let policies = [
+            // Detector not found
+            r#"
+policies:
+    - name: block-pii
+      detector_id: pii
+      outcome: block
+"#,
+            // Duplicate name
+            r#"
+policies:
+    - name: block-hap
+      outcome: block
+    - name: block-hap
+      outcome: warn
+"#,
+            // Invalid score range
+            r#"
+policies:
+    - name: block-hap
+      min_score: 0.9
+      max_score: 0.5
+      outcome: block
+"#,
+            // Invalid count range
+            r#"
+policies:
+    - name: block-hap
+      min_count: 3
+      max_count: 2
+      outcome: block
+"#,
+        ];
+        for policy in policies {
+            let config: OrchestratorConfig =
+                serde_yml::from_str(&format!("{detectors}{policy}")).unwrap();
+            let error = config
+                .validate()
+                .expect_err("Config should not have been validated");
+            assert!(matches!(error, Error::InvalidPolicy(_)))
+        }
+    }
+
+    #[test]
+    fn test_deserialize_config_profiles() -> Result<(), Error> {
+        let s = r#"
+detectors:
+    hap:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+    pii:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9001
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+profiles:
+    strict:
+        input:
+            hap:
+                threshold: 0.3
+            pii: {}
+        output:
+            hap: {}
+        "#;
+        let config: OrchestratorConfig = serde_yml::from_str(s).unwrap();
+        config.validate()?;
+        let profile = config.profile("strict").unwrap();
+        // Request detectors override params of profile detectors
+        let mut params = DetectorParams::new();
+        params.insert("threshold".into(), 0.8.into());
+        let input_detectors = profile.input_detectors(HashMap::from([("hap".into(), params)]));
+        assert_eq!(input_detectors.len(), 2);
+        assert_eq!(input_detectors["hap"].get("threshold"), Some(&0.8.into()));
+        assert!(input_detectors["pii"].is_empty());
+        let output_detectors = profile.output_detectors(HashMap::new());
+        assert_eq!(output_detectors.len(), 1);
+        assert!(config.profile("lenient").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_config_invalid_profiles() {
+        let detectors = r#"
+detectors:
+    hap:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+    relevance:
+        type: text_generation
+        service:
+            hostname: localhost
+            port: 9001
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+"#;
+        let profiles = [
+            // Detector not found
+            r#"
+profiles:
+    strict:
+        input:
+            pii: {}
+"#,
+            // Invalid detector params
+            r#"
+profiles:
+    strict:
+        input:
+            hap:
+                threshold: high
+"#,
+        ];
+        for profile in profiles {
+            let config: OrchestratorConfig =
+                serde_yml::from_str(&format!("{detectors}{profile}")).unwrap();
+            let error = config
+                .validate()
+                .expect_err("Config should not have been validated");
+            assert!(matches!(error, Error::InvalidProfile(_)))
+        }
+        // Detector types are validated by endpoints
+        let profile = r#"
+profiles:
+    strict:
+        output:
+            relevance: {}
+"#;
+        let config: OrchestratorConfig =
+            serde_yml::from_str(&format!("{detectors}{profile}")).unwrap();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_deserialize_config_auth() -> Result<(), Error> {
+        let s = r#"
+detectors:
+    hap:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+auth:
+    api_keys:
+        - tenant: team-a
+          key_path: /path/to/team-a.key
+    jwt:
+        algorithm: RS256
+        key_path: /path/to/public.pem
+        issuer: https://issuer.example.com
+    tenants:
+        team-a:
+            detectors: [hap]
+            models: [granite]
+        "#;
+        let config: OrchestratorConfig = serde_yml::from_str(s).unwrap();
+        config.validate()?;
+        let auth = config.auth.as_ref().unwrap();
+        assert_eq!(auth.api_keys[0].tenant, "team-a");
+        let jwt = auth.jwt.as_ref().unwrap();
+        assert_eq!(jwt.algorithm, JwtAlgorithm::RS256);
+        assert_eq!(jwt.tenant_claim, "sub");
+        assert!(jwt.audience.is_none());
+        assert!(
+            auth.tenants["team-a"]
+                .models
+                .as_ref()
+                .unwrap()
+                .contains("granite")
+        );
+
+        // Tenant references unknown detector
+        let s = s.replace("detectors: [hap]", "detectors: [pii]");
+        let config: OrchestratorConfig = serde_yml::from_str(&s).unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvalidAuthConfig(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_config_limits() -> Result<(), Error> {
+        let s = r#"
+detectors:
+    hap:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+limits:
+    global:
+        requests_per_second: 100
+        max_concurrent_streams: 50
+    per_tenant:
+        requests_per_second: 10
+        burst: 20
+    tenants:
+        team-a:
+            max_concurrent_streams: 2
+        "#;
+        let config: OrchestratorConfig = serde_yml::from_str(s).unwrap();
+        config.validate()?;
+        let limits = config.limits.as_ref().unwrap();
+        assert_eq!(limits.global.requests_per_second, Some(100.0));
+        assert_eq!(limits.global.max_concurrent_streams, Some(50));
+        assert_eq!(limits.per_tenant.burst, Some(20));
+        assert_eq!(
+            limits.tenants["team-a"],
+            LimitConfig {
+                max_concurrent_streams: Some(2),
+                ..Default::default()
+            }
+        );
+
+        // Invalid request rate
+        let s = s.replace("requests_per_second: 10", "requests_per_second: 0");
+        let config: OrchestratorConfig = serde_yml::from_str(&s).unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvalidLimitsConfig(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_config_jobs() -> Result<(), Error> {
+        let s = r#"
+detectors:
+    hap:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+        "#;
+        let config: OrchestratorConfig = serde_yml::from_str(s).unwrap();
+        config.validate()?;
+        assert_eq!(config.jobs, JobsConfig::default());
+
+        let s = format!("{s}\njobs:\n    max_jobs: 10\n    store_path: /tmp/jobs\n");
+        let config: OrchestratorConfig = serde_yml::from_str(&s).unwrap();
+        config.validate()?;
+        assert_eq!(config.jobs.max_jobs, 10);
+        assert_eq!(config.jobs.ttl_seconds, 3600);
+        assert_eq!(config.jobs.store_path, Some(PathBuf::from("/tmp/jobs")));
+
+        // Invalid max jobs
+        let s = s.replace("max_jobs: 10", "max_jobs: 0");
+        let config: OrchestratorConfig = serde_yml::from_str(&s).unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvalidJobsConfig(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_config_audit() -> Result<(), Error> {
+        let s = r#"
+detectors:
+    hap:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+        "#;
+        let config: OrchestratorConfig = serde_yml::from_str(s).unwrap();
+        assert_eq!(config.audit, None);
+
+        let file =
+            format!("{s}\naudit:\n    sink:\n        type: file\n        path: /tmp/audit.jsonl\n");
+        let config: OrchestratorConfig = serde_yml::from_str(&file).unwrap();
+        config.validate()?;
+        assert_eq!(
+            config.audit,
+            Some(AuditConfig {
+                sink: AuditSinkConfig::File {
+                    path: PathBuf::from("/tmp/audit.jsonl"),
+                    max_size_mb: 100,
+                    max_files: 5,
+                },
+                content: AuditContent::Omit,
+                hash_key_path: None,
+            })
+        );
+
+        let webhook = format!(
+            "{s}\naudit:\n    content: hash\n    sink:\n        type: webhook\n        url: http://localhost:8080/audit\n        headers:\n            authorization: Bearer token\n"
+        );
+        let config: OrchestratorConfig = serde_yml::from_str(&webhook).unwrap();
+        // Hash key is required to hash detected text
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvalidAuditConfig(_))
+        ));
+        let webhook = webhook.replace(
+            "content: hash\n",
+            "content: hash\n    hash_key_path: /secrets/audit.key\n",
+        );
+        let config: OrchestratorConfig = serde_yml::from_str(&webhook).unwrap();
+        config.validate()?;
+        let audit = config.audit.unwrap();
+        assert_eq!(audit.content, AuditContent::Hash);
+        assert_eq!(
+            audit.hash_key_path,
+            Some(PathBuf::from("/secrets/audit.key"))
+        );
+        assert_eq!(
+            audit.sink,
+            AuditSinkConfig::Webhook {
+                url: "http://localhost:8080/audit".into(),
+                headers: HashMap::from([("authorization".into(), "Bearer token".into())]),
+            }
+        );
+
+        // Unknown sink type
+        let s = file.replace("type: file", "type: kafka");
+        assert!(serde_yml::from_str::<OrchestratorConfig>(&s).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_config_detector_cache() -> Result<(), Error> {
+        let s = r#"
+detectors:
+    hap:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+        cache:
+            ttl_seconds: 60
+        "#;
+        let config: OrchestratorConfig = serde_yml::from_str(s).unwrap();
+        config.validate()?;
+        assert_eq!(
+            config.detectors["hap"].cache,
+            Some(DetectorCacheConfig {
+                max_entries: 1024,
+                ttl_seconds: Some(60),
+            })
+        );
+
+        // Invalid max entries
+        let s = s.replace("ttl_seconds: 60", "max_entries: 0");
+        let config: OrchestratorConfig = serde_yml::from_str(&s).unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvalidDetectorCacheConfig(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_config_detector_on_error() -> Result<(), Error> {
+        let s = r#"
+detectors:
+    hap:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+        "#;
+        let config: OrchestratorConfig = serde_yml::from_str(s).unwrap();
+        config.validate()?;
+        assert_eq!(config.detectors["hap"].on_error, DetectorOnError::Fail);
+
+        let s = s.replace(
+            "default_threshold: 0.5",
+            "default_threshold: 0.5\n        on_error: skip",
+        );
+        let config: OrchestratorConfig = serde_yml::from_str(&s).unwrap();
+        config.validate()?;
+        assert_eq!(config.detectors["hap"].on_error, DetectorOnError::Skip);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_config_retry() -> Result<(), Error> {
+        let s = r#"
+detectors:
+    hap:
+        type: text_contents
+        service:
+            hostname: localhost
+            port: 9000
+            retry:
+                max_attempts: 5
+                retryable_grpc_codes: [UNAVAILABLE, DEADLINE_EXCEEDED]
+        chunker_id: whole_doc_chunker
+        default_threshold: 0.5
+        "#;
+        let config: OrchestratorConfig = serde_yml::from_str(s).unwrap();
+        config.validate()?;
+        let retry = config.detectors["hap"].service.retry.as_ref().unwrap();
+        assert_eq!(retry.max_attempts, 5);
+        assert_eq!(retry.retryable_status_codes, vec![502, 503, 504]);
+        assert_eq!(retry.budget, RetryBudgetConfig::default());
+
+        // Invalid gRPC code
+        let s = s.replace("DEADLINE_EXCEEDED", "TIMEOUT");
+        let config: OrchestratorConfig = serde_yml::from_str(&s).unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvalidRetryConfig(_))
+        ));
+        Ok(())
+    }
 }
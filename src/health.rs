@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::HashMap, fmt::Display, time::Duration};
 
 use axum::http::StatusCode;
 use serde::{Deserialize, Serialize};
@@ -147,6 +147,30 @@ impl From<Result<tonic::Response<HealthCheckResponse>, tonic::Status>> for Healt
     }
 }
 
+/// History of the health checks of a client service.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct HealthCheckHistory {
+    /// Latency of the latest health check request in milliseconds.
+    pub latency_ms: u64,
+    /// Unix timestamp (in seconds) of the latest health check request.
+    pub last_checked: u64,
+    /// Number of consecutive health checks without a healthy status.
+    pub consecutive_failures: u32,
+}
+
+impl HealthCheckHistory {
+    /// Records the result of a health check request.
+    pub fn record(&mut self, result: &HealthCheckResult, latency: Duration, checked_at: u64) {
+        self.latency_ms = latency.as_millis() as u64;
+        self.last_checked = checked_at;
+        if result.status == HealthStatus::Healthy {
+            self.consecutive_failures = 0;
+        } else {
+            self.consecutive_failures += 1;
+        }
+    }
+}
+
 /// An optional response body that can be interpreted from an HTTP health check response.
 /// This is a minimal contract that allows HTTP health requests to opt in to more detailed health check responses than just the status code.
 /// If the body omitted, the health check response is considered successful if the status code is `HTTP 200 OK`.
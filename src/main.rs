@@ -15,7 +15,10 @@
 
 */
 
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
 
 use clap::Parser;
 use fms_guardrails_orchestr8::{
@@ -40,6 +43,9 @@ fn main() -> Result<(), anyhow::Error> {
         SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), args.http_port);
     let health_http_addr: SocketAddr =
         SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), args.health_http_port);
+    let grpc_addr: Option<SocketAddr> = args
+        .grpc_port
+        .map(|port| SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), port));
 
     // Launch Tokio runtime
     tokio::runtime::Builder::new_multi_thread()
@@ -48,22 +54,37 @@ fn main() -> Result<(), anyhow::Error> {
         .unwrap()
         .block_on(async {
             let trace_shutdown = utils::trace::init_tracing(args.clone().into())?;
-            let config = OrchestratorConfig::load(args.config_path).await?;
+            let config = OrchestratorConfig::load(&args.config_path).await?;
             let orchestrator = Orchestrator::new(config, args.start_up_health_check).await?;
+            // Probe client health in the background
+            if let Some(interval) = args.health_probe_interval.filter(|interval| *interval > 0) {
+                orchestrator.watch_client_health(Duration::from_secs(interval));
+            }
 
-            let (health_handle, guardrails_handle) = server::run(
+            let (health_handle, guardrails_handle, grpc_handle) = server::run(
                 http_addr,
                 health_http_addr,
+                grpc_addr,
                 args.tls_cert_path,
                 args.tls_key_path,
                 args.tls_client_ca_cert_path,
                 orchestrator,
+                // Reload config on SIGHUP or config file changes
+                Some(server::ConfigWatch {
+                    path: args.config_path.clone(),
+                    interval: args.config_watch_interval.map(Duration::from_secs),
+                }),
             )
             .await
             .unwrap_or_else(|e| panic!("failed to run server: {e}"));
 
             // Await server shutdown
-            let _ = tokio::join!(health_handle, guardrails_handle);
+            let grpc_handle = async {
+                if let Some(grpc_handle) = grpc_handle {
+                    let _ = grpc_handle.await;
+                }
+            };
+            let _ = tokio::join!(health_handle, guardrails_handle, grpc_handle);
             info!("shutdown complete");
 
             trace_shutdown()
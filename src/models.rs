@@ -24,18 +24,32 @@ use serde::{Deserialize, Serialize};
 use crate::{
     clients::{
         self,
+        circuit_breaker::CircuitBreakerStatus,
         detector::{ContentAnalysisResponse, ContextType},
         openai::{Content, ContentType},
     },
-    health::HealthCheckCache,
+    config::{
+        ChunkerType, DetectorAction, DetectorOnError, DetectorType, GuardrailProfile,
+        OrchestratorConfig, PolicyOutcome,
+    },
+    health::{HealthCheckCache, HealthCheckHistory, HealthCheckResult},
     pb,
 };
 
 pub const THRESHOLD_PARAM: &str = "threshold";
+pub const ACTION_PARAM: &str = "action";
+pub const REDACTION_PLACEHOLDER_PARAM: &str = "redaction_placeholder";
+pub const ON_ERROR_PARAM: &str = "on_error";
 
 #[derive(Clone, Debug, Serialize)]
 pub struct InfoResponse {
     pub services: HealthCheckCache,
+    /// Health check history of client services, by client
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub history: BTreeMap<String, HealthCheckHistory>,
+    /// Circuit breaker status of client services with a circuit breaker configured
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub circuit_breakers: BTreeMap<String, CircuitBreakerStatus>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -45,6 +59,69 @@ pub struct InfoParams {
     pub probe: bool,
 }
 
+/// Response of readiness endpoint.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReadyResponse {
+    pub ready: bool,
+    /// Reason the orchestrator is not ready
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Required client services without a healthy status
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unhealthy_services: Vec<String>,
+}
+
+/// Response of detector discovery endpoint.
+#[derive(Clone, Debug, Serialize)]
+pub struct DetectorsResponse {
+    pub detectors: Vec<DetectorInfo>,
+}
+
+/// Configured detector and the endpoints accepting it.
+#[derive(Clone, Debug, Serialize)]
+pub struct DetectorInfo {
+    /// Detector ID
+    pub id: String,
+    /// Detector type
+    pub r#type: DetectorType,
+    /// Chunker ID
+    pub chunker_id: String,
+    /// Default score threshold
+    pub default_threshold: f64,
+    /// Latest health check result
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<HealthCheckResult>,
+    /// Endpoints accepting the detector
+    pub endpoints: Vec<DetectorEndpoint>,
+}
+
+/// Endpoint accepting a detector.
+#[derive(Clone, Debug, Serialize)]
+pub struct DetectorEndpoint {
+    /// Endpoint path
+    pub path: String,
+    /// Request field the detector can be provided in, e.g. `input`, `output` or `detectors`
+    pub field: String,
+}
+
+/// Response of chunker discovery endpoint.
+#[derive(Clone, Debug, Serialize)]
+pub struct ChunkersResponse {
+    pub chunkers: Vec<ChunkerInfo>,
+}
+
+/// Configured chunker.
+#[derive(Clone, Debug, Serialize)]
+pub struct ChunkerInfo {
+    /// Chunker ID
+    pub id: String,
+    /// Chunker type
+    pub r#type: ChunkerType,
+    /// Latest health check result
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<HealthCheckResult>,
+}
+
 /// Parameters relevant to each detector
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct DetectorParams(BTreeMap<String, serde_json::Value>);
@@ -61,6 +138,27 @@ impl DetectorParams {
     pub fn pop_threshold(&mut self) -> Option<f64> {
         self.0.remove(THRESHOLD_PARAM).and_then(|v| v.as_f64())
     }
+
+    /// Action to take on detections, overriding the detector config.
+    pub fn pop_action(&mut self) -> Option<DetectorAction> {
+        self.0
+            .remove(ACTION_PARAM)
+            .and_then(|v| serde_json::from_value(v).ok())
+    }
+
+    /// Placeholder for redacted spans, overriding the detector config.
+    pub fn pop_redaction_placeholder(&mut self) -> Option<String> {
+        self.0
+            .remove(REDACTION_PLACEHOLDER_PARAM)
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+    }
+
+    /// Behavior when the detector fails, overriding the detector config.
+    pub fn pop_on_error(&mut self) -> Option<DetectorOnError> {
+        self.0
+            .remove(ON_ERROR_PARAM)
+            .and_then(|v| serde_json::from_value(v).ok())
+    }
 }
 
 impl std::ops::Deref for DetectorParams {
@@ -95,6 +193,10 @@ pub struct GuardrailsHttpRequest {
     /// Parameters for text generation
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_gen_parameters: Option<GuardrailsTextGenerationParameters>,
+
+    /// Name of a guardrail profile providing detectors, overridden by `guardrail_config`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guardrail_profile: Option<String>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -106,6 +208,32 @@ pub enum ValidationError {
 }
 
 impl GuardrailsHttpRequest {
+    /// Applies the guardrail profile, if specified, to `guardrail_config`.
+    pub fn apply_guardrail_profile(
+        &mut self,
+        config: &OrchestratorConfig,
+    ) -> Result<(), ValidationError> {
+        if let Some(name) = &self.guardrail_profile {
+            let profile = get_guardrail_profile(config, name)?;
+            let guardrail_config = self.guardrail_config.get_or_insert_default();
+            let input_detectors = profile.input_detectors(guardrail_config.input_detectors());
+            if !input_detectors.is_empty() {
+                let masks = guardrail_config.input.take().and_then(|input| input.masks);
+                guardrail_config.input = Some(GuardrailsConfigInput {
+                    models: input_detectors,
+                    masks,
+                });
+            }
+            let output_detectors = profile.output_detectors(guardrail_config.output_detectors());
+            if !output_detectors.is_empty() {
+                guardrail_config.output = Some(GuardrailsConfigOutput {
+                    models: output_detectors,
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Upfront validation of user request
     pub fn validate(&self) -> Result<(), ValidationError> {
         // Validate required parameters
@@ -360,10 +488,28 @@ pub struct TextContentDetectionHttpRequest {
     pub content: String,
 
     /// The map of detectors to be used, along with their respective parameters, e.g. thresholds.
+    #[serde(default)]
     pub detectors: HashMap<String, DetectorParams>,
+
+    /// Name of a guardrail profile providing detectors, overridden by `detectors`.
+    /// Input detectors of the profile are used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guardrail_profile: Option<String>,
 }
 
 impl TextContentDetectionHttpRequest {
+    /// Applies the guardrail profile, if specified, to `detectors`.
+    pub fn apply_guardrail_profile(
+        &mut self,
+        config: &OrchestratorConfig,
+    ) -> Result<(), ValidationError> {
+        if let Some(name) = &self.guardrail_profile {
+            let profile = get_guardrail_profile(config, name)?;
+            self.detectors = profile.input_detectors(std::mem::take(&mut self.detectors));
+        }
+        Ok(())
+    }
+
     /// Upfront validation of user request
     pub fn validate(&self) -> Result<(), ValidationError> {
         // Validate required parameters
@@ -386,6 +532,222 @@ impl TextContentDetectionHttpRequest {
 pub struct TextContentDetectionResult {
     /// Detection results
     pub detections: Vec<ContentAnalysisResponse>,
+
+    /// Vector of warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DetectionWarning>,
+}
+
+/// Default number of records of a batch processed concurrently.
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 8;
+/// Maximum number of records of a batch processed concurrently.
+pub const MAX_BATCH_CONCURRENCY: usize = 64;
+
+/// Order of results returned by the /api/v2/text/detection/content/batch endpoint.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchOrder {
+    /// Results are returned in the order of input records
+    #[default]
+    Input,
+    /// Results are returned as records complete
+    Completion,
+}
+
+/// Query params of the /api/v2/text/detection/content/batch endpoint.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BatchDetectionParams {
+    /// Order of results, defaults to input order
+    #[serde(default)]
+    pub order: BatchOrder,
+    /// Number of records processed concurrently, defaults to [`DEFAULT_BATCH_CONCURRENCY`]
+    pub concurrency: Option<usize>,
+}
+
+impl BatchDetectionParams {
+    /// Upfront validation of user request
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self
+            .concurrency
+            .is_some_and(|concurrency| !(1..=MAX_BATCH_CONCURRENCY).contains(&concurrency))
+        {
+            return Err(ValidationError::Invalid(format!(
+                "`concurrency` must be between 1 and {MAX_BATCH_CONCURRENCY}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A record of the JSONL body of the /api/v2/text/detection/content/batch endpoint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchDetectionRecord {
+    /// Record ID, returned with its result
+    pub id: String,
+
+    /// The content to run detectors on
+    pub content: String,
+
+    /// The map of detectors to be used, along with their respective parameters, e.g. thresholds.
+    #[serde(default)]
+    pub detectors: HashMap<String, DetectorParams>,
+
+    /// Name of a guardrail profile providing detectors, overridden by `detectors`.
+    /// Input detectors of the profile are used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guardrail_profile: Option<String>,
+}
+
+impl From<BatchDetectionRecord> for TextContentDetectionHttpRequest {
+    fn from(value: BatchDetectionRecord) -> Self {
+        Self {
+            content: value.content,
+            detectors: value.detectors,
+            guardrail_profile: value.guardrail_profile,
+        }
+    }
+}
+
+/// A result line of the /api/v2/text/detection/content/batch endpoint, holding
+/// either the detections or the error of a record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchDetectionResult {
+    /// Index of the record in the batch
+    pub index: usize,
+    /// Record ID, missing if the record could not be parsed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Detection results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detections: Option<Vec<ContentAnalysisResponse>>,
+    /// Error processing the record
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<BatchDetectionError>,
+
+    /// Vector of warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DetectionWarning>,
+}
+
+/// Error processing a record of a batch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchDetectionError {
+    pub code: u16,
+    pub details: String,
+}
+
+/// The request format expected in the /api/v2/jobs endpoint, holding the task to run
+/// and its request in the format of the task's endpoint.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(
+    tag = "task",
+    content = "request",
+    rename_all = "snake_case",
+    deny_unknown_fields
+)]
+pub enum JobRequest {
+    /// Request of the /api/v1/task/classification-with-text-generation endpoint
+    ClassificationWithTextGeneration(GuardrailsHttpRequest),
+    /// Request of the /api/v2/text/generation-detection endpoint
+    GenerationDetection(GenerationWithDetectionHttpRequest),
+    /// Request of the /api/v2/text/detection/content endpoint
+    TextContentDetection(TextContentDetectionHttpRequest),
+    /// Records of the /api/v2/text/detection/content/batch endpoint
+    BatchTextContentDetection(BatchDetectionJobRequest),
+    /// Request of the /api/v2/text/detection/chat endpoint
+    ChatDetection(ChatDetectionHttpRequest),
+    /// Request of the /api/v2/text/detection/context endpoint
+    ContextDocsDetection(ContextDocsHttpRequest),
+    /// Request of the /api/v2/text/detection/generated endpoint
+    GeneratedTextDetection(DetectionOnGeneratedHttpRequest),
+    /// Unary request of the /api/v2/chat/completions-detection endpoint
+    ChatCompletionsDetection(clients::openai::ChatCompletionsRequest),
+    /// Unary request of the /api/v2/completions-detection endpoint
+    CompletionsDetection(clients::openai::CompletionsRequest),
+}
+
+impl JobRequest {
+    /// Returns the name of the task.
+    pub fn task(&self) -> &'static str {
+        match self {
+            JobRequest::ClassificationWithTextGeneration(_) => {
+                "classification_with_text_generation"
+            }
+            JobRequest::GenerationDetection(_) => "generation_detection",
+            JobRequest::TextContentDetection(_) => "text_content_detection",
+            JobRequest::BatchTextContentDetection(_) => "batch_text_content_detection",
+            JobRequest::ChatDetection(_) => "chat_detection",
+            JobRequest::ContextDocsDetection(_) => "context_docs_detection",
+            JobRequest::GeneratedTextDetection(_) => "generated_text_detection",
+            JobRequest::ChatCompletionsDetection(_) => "chat_completions_detection",
+            JobRequest::CompletionsDetection(_) => "completions_detection",
+        }
+    }
+
+    /// Applies guardrail profiles and validates the request, as done by the task's endpoint.
+    pub fn validate(&mut self, config: &OrchestratorConfig) -> Result<(), ValidationError> {
+        match self {
+            JobRequest::ClassificationWithTextGeneration(request) => {
+                request.apply_guardrail_profile(config)?;
+                request.validate()
+            }
+            JobRequest::GenerationDetection(request) => request.validate(),
+            JobRequest::TextContentDetection(request) => {
+                request.apply_guardrail_profile(config)?;
+                request.validate()
+            }
+            JobRequest::BatchTextContentDetection(request) => request.validate(),
+            JobRequest::ChatDetection(request) => request.validate_for_text(),
+            JobRequest::ContextDocsDetection(request) => request.validate(),
+            JobRequest::GeneratedTextDetection(request) => request.validate(),
+            JobRequest::ChatCompletionsDetection(request) => {
+                if request.stream == Some(true) {
+                    return Err(ValidationError::Invalid(
+                        "`stream` is not supported for jobs".into(),
+                    ));
+                }
+                request.apply_guardrail_profile(config)?;
+                request.validate()
+            }
+            JobRequest::CompletionsDetection(request) => {
+                if request.stream == Some(true) {
+                    return Err(ValidationError::Invalid(
+                        "`stream` is not supported for jobs".into(),
+                    ));
+                }
+                request.apply_guardrail_profile(config)?;
+                request.validate()
+            }
+        }
+    }
+}
+
+/// Batch text content detection job request. Records are validated as they are processed,
+/// invalid records produce error results.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchDetectionJobRequest {
+    /// Records to run detections on
+    pub records: Vec<BatchDetectionRecord>,
+    /// Number of records processed concurrently, defaults to [`DEFAULT_BATCH_CONCURRENCY`]
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+}
+
+impl BatchDetectionJobRequest {
+    /// Upfront validation of user request
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.records.is_empty() {
+            return Err(ValidationError::Required("records".into()));
+        }
+        BatchDetectionParams {
+            order: BatchOrder::Input,
+            concurrency: self.concurrency,
+        }
+        .validate()
+    }
 }
 /// Streaming classification result on text produced by a text generation model, containing
 /// information from the original text generation output as well as the result of
@@ -522,6 +884,10 @@ pub struct DetectionWarning {
     /// Warning message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
+
+    /// Policy rule that fired, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<PolicyMatch>,
 }
 
 impl DetectionWarning {
@@ -529,6 +895,7 @@ impl DetectionWarning {
         DetectionWarning {
             id: Some(DetectionWarningReason::UnsuitableInput),
             message: Some(UNSUITABLE_INPUT_MESSAGE.to_string()),
+            policy: None,
         }
     }
 
@@ -536,8 +903,32 @@ impl DetectionWarning {
         DetectionWarning {
             id: Some(DetectionWarningReason::UnsuitableOutput),
             message: Some(UNSUITABLE_OUTPUT_MESSAGE.to_string()),
+            policy: None,
         }
     }
+
+    pub fn detector_failed(message: String) -> Self {
+        DetectionWarning {
+            id: Some(DetectionWarningReason::DetectorFailed),
+            message: Some(message),
+            policy: None,
+        }
+    }
+
+    /// Sets the policy rule that fired.
+    pub fn with_policy(mut self, policy: Option<PolicyMatch>) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+/// Policy rule that fired and its outcome.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyMatch {
+    /// Rule name
+    pub rule: String,
+    /// Rule outcome
+    pub outcome: PolicyOutcome,
 }
 
 /// Enumeration of warning reasons on input detection
@@ -557,6 +948,10 @@ pub enum DetectionWarningReason {
     /// Unsuitable text detected on output
     #[serde(rename = "EMPTY_OUTPUT")]
     EmptyOutput,
+
+    /// Optional detector failed and was skipped
+    #[serde(rename = "DETECTOR_FAILED")]
+    DetectorFailed,
 }
 
 /// Generated token information
@@ -926,6 +1321,10 @@ pub struct GenerationWithDetectionResult {
 
     /// Input length
     pub input_token_count: u32,
+
+    /// Vector of warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DetectionWarning>,
 }
 
 /// Detection format received from detectors
@@ -1004,6 +1403,10 @@ impl ContextDocsHttpRequest {
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ContextDocsResult {
     pub detections: Vec<DetectionResult>,
+
+    /// Vector of warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DetectionWarning>,
 }
 
 /// The request format expected in the /api/v2/text/detect/chat endpoint.
@@ -1086,6 +1489,10 @@ impl ChatDetectionHttpRequest {
 pub struct ChatDetectionResult {
     /// Detection results
     pub detections: Vec<DetectionResult>,
+
+    /// Vector of warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DetectionWarning>,
 }
 
 /// The request format expected in the /api/v2/text/detect/generated endpoint.
@@ -1128,10 +1535,24 @@ impl DetectionOnGeneratedHttpRequest {
 pub struct DetectionOnGenerationResult {
     /// Detection results
     pub detections: Vec<DetectionResult>,
+
+    /// Vector of warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DetectionWarning>,
+}
+
+/// Gets a guardrail profile by name.
+pub fn get_guardrail_profile<'a>(
+    config: &'a OrchestratorConfig,
+    name: &str,
+) -> Result<&'a GuardrailProfile, ValidationError> {
+    config
+        .profile(name)
+        .ok_or_else(|| ValidationError::Invalid(format!("guardrail profile `{name}` not found")))
 }
 
 /// Validates detector params.
-fn validate_detector_params(
+pub fn validate_detector_params(
     models: &HashMap<String, DetectorParams>,
 ) -> Result<(), ValidationError> {
     for (model_id, detector_params) in models {
@@ -1143,6 +1564,30 @@ fn validate_detector_params(
                 )));
             }
         }
+        // Validate action is a known action, if specified
+        if let Some(action) = detector_params.get(ACTION_PARAM) {
+            if serde_json::from_value::<DetectorAction>(action.clone()).is_err() {
+                return Err(ValidationError::Invalid(format!(
+                    "`action` parameter specified for model `{model_id}` must be one of `flag`, `redact` or `anonymize`"
+                )));
+            }
+        }
+        // Validate redaction placeholder is a string, if specified
+        if let Some(placeholder) = detector_params.get(REDACTION_PLACEHOLDER_PARAM) {
+            if !placeholder.is_string() {
+                return Err(ValidationError::Invalid(format!(
+                    "`redaction_placeholder` parameter specified for model `{model_id}` must be a string"
+                )));
+            }
+        }
+        // Validate on_error is a known behavior, if specified
+        if let Some(on_error) = detector_params.get(ON_ERROR_PARAM) {
+            if serde_json::from_value::<DetectorOnError>(on_error.clone()).is_err() {
+                return Err(ValidationError::Invalid(format!(
+                    "`on_error` parameter specified for model `{model_id}` must be one of `fail` or `skip`"
+                )));
+            }
+        }
     }
     Ok(())
 }
@@ -1203,6 +1648,10 @@ pub struct StreamingContentDetectionResponse {
     pub detections: Vec<ContentAnalysisResponse>,
     pub processed_index: u32,
     pub start_index: u32,
+
+    /// Vector of warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DetectionWarning>,
 }
 
 #[cfg(test)]
@@ -1225,6 +1674,7 @@ mod tests {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         };
         assert!(request.validate().is_ok());
 
@@ -1242,6 +1692,7 @@ mod tests {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         };
         assert!(request.validate().is_ok());
 
@@ -1259,6 +1710,7 @@ mod tests {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         };
         let result = request.validate();
         assert!(result.is_err());
@@ -1307,6 +1759,7 @@ mod tests {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         };
         let result = request.validate();
         assert!(result.is_err());
@@ -1327,6 +1780,7 @@ mod tests {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         };
         let result = request.validate();
         assert!(result.is_err());
@@ -1347,6 +1801,7 @@ mod tests {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         };
         let result = request.validate();
         assert!(result.is_err());
@@ -1371,6 +1826,7 @@ mod tests {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         };
         assert!(request.validate().is_ok());
 
@@ -1390,6 +1846,7 @@ mod tests {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         };
         assert!(
             request
@@ -1410,6 +1867,15 @@ mod tests {
         let mut value = DetectorParams::new();
         assert!(!value.contains_key("threshold"));
         assert_eq!(value.pop_threshold(), None);
+        let value_json = r#"
+        {
+            "action": "redact",
+            "redaction_placeholder": "[EMAIL]"
+        }"#;
+        let mut value: DetectorParams = serde_json::from_str(value_json)?;
+        assert_eq!(value.pop_action(), Some(DetectorAction::Redact));
+        assert_eq!(value.pop_redaction_placeholder(), Some("[EMAIL]".into()));
+        assert!(value.is_empty());
         Ok(())
     }
 }
@@ -15,48 +15,144 @@
 
 */
 pub mod errors;
-pub use errors::Error;
+pub use errors::{Error, Stage};
 pub mod common;
 pub mod handlers;
 pub mod types;
 
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use tokio::{sync::RwLock, time::Instant};
-use tracing::{debug, info};
+use futures::future::join_all;
+use http::StatusCode;
+use opentelemetry::trace::TraceId;
+use tokio::{
+    sync::RwLock,
+    time::{Instant, MissedTickBehavior},
+};
+use tracing::{debug, info, warn};
 
 use crate::{
+    audit::{Audit, Auditor},
     clients::{
-        ClientMap, GenerationClient, NlpClient, TextContentsDetectorClient, TgisClient,
+        Client, ClientMap, GenerationClient, NlpClient, TextContentsDetectorClient, TgisClient,
         chunker::ChunkerClient,
+        circuit_breaker::{CircuitBreakerStatus, CircuitState},
         detector::{
             TextChatDetectorClient, TextContextDocDetectorClient, TextGenerationDetectorClient,
         },
         openai::OpenAiClient,
     },
-    config::{DetectorType, GenerationProvider, OrchestratorConfig},
-    health::HealthCheckCache,
+    config::{
+        DetectorType, GenerationConfig, GenerationProvider, OpenAiConfig, OrchestratorConfig,
+        RetryConfig, ServiceConfig,
+    },
+    health::{HealthCheckCache, HealthCheckHistory, HealthCheckResult, HealthStatus},
+    orchestrator::{common::DetectorCache, types::Tenant},
 };
 
 const DEFAULT_MAX_RETRIES: usize = 3;
 
 #[cfg_attr(test, derive(Default))]
 pub struct Context {
-    config: OrchestratorConfig,
+    config: Arc<OrchestratorConfig>,
     clients: ClientMap,
+    detector_caches: HashMap<String, Arc<DetectorCache>>,
+    required_clients: Vec<String>,
 }
 
 impl Context {
     pub fn new(config: OrchestratorConfig, clients: ClientMap) -> Self {
-        Self { config, clients }
+        let detector_caches = config
+            .detectors
+            .iter()
+            .filter_map(|(detector_id, detector)| {
+                detector.cache.as_ref().map(|cache| {
+                    (
+                        detector_id.clone(),
+                        Arc::new(DetectorCache::new(detector_id.clone(), cache)),
+                    )
+                })
+            })
+            .collect();
+        let mut required_clients = client_configs(&config)
+            .into_iter()
+            .filter_map(|(key, client_config)| client_config.service().required.then_some(key))
+            .collect::<Vec<_>>();
+        required_clients.sort();
+        Self {
+            config: Arc::new(config),
+            clients,
+            detector_caches,
+            required_clients,
+        }
+    }
+
+    /// Marks clients with an open circuit breaker as unhealthy.
+    fn apply_circuit_breakers(&self, health: &mut HealthCheckCache) {
+        for (key, client) in self.clients.iter() {
+            if client
+                .circuit_breaker()
+                .is_some_and(|circuit_breaker| circuit_breaker.state() == CircuitState::Open)
+            {
+                health.insert(
+                    key.clone(),
+                    HealthCheckResult {
+                        status: HealthStatus::Unhealthy,
+                        code: StatusCode::SERVICE_UNAVAILABLE,
+                        reason: Some("circuit breaker open".into()),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Reuses result caches of `current` for detectors whose client and cache config are unchanged.
+    fn reuse_detector_caches(&mut self, current: &Context) {
+        for (detector_id, cache) in self.detector_caches.iter_mut() {
+            let cache_unchanged = current
+                .config
+                .detector(detector_id)
+                .is_some_and(|detector| detector.cache == self.config.detectors[detector_id].cache);
+            let client_reused = match (
+                current.clients.get_shared(detector_id),
+                self.clients.get_shared(detector_id),
+            ) {
+                (Some(current_client), Some(client)) => Arc::ptr_eq(&current_client, &client),
+                _ => false,
+            };
+            if cache_unchanged && client_reused {
+                if let Some(current_cache) = current.detector_caches.get(detector_id) {
+                    *cache = current_cache.clone();
+                }
+            }
+        }
+    }
+
+    /// Returns the result cache of a detector, if configured.
+    pub fn detector_cache(&self, detector_id: &str) -> Option<Arc<DetectorCache>> {
+        self.detector_caches.get(detector_id).cloned()
     }
 }
 
 /// Handles orchestrator tasks.
+///
+/// Clones share the same context, which is swapped atomically when the
+/// config is reloaded. In-flight tasks complete with the context they started with.
+#[derive(Clone)]
 #[cfg_attr(test, derive(Default))]
 pub struct Orchestrator {
-    ctx: Arc<Context>,
+    ctx: Arc<std::sync::RwLock<Arc<Context>>>,
     client_health: Arc<RwLock<HealthCheckCache>>,
+    health_history: Arc<RwLock<BTreeMap<String, HealthCheckHistory>>>,
+    warmed_up: Arc<AtomicBool>,
+    auditor: Arc<std::sync::RwLock<Option<Arc<Auditor>>>>,
 }
 
 impl Orchestrator {
@@ -64,11 +160,15 @@ impl Orchestrator {
         config: OrchestratorConfig,
         start_up_health_check: bool,
     ) -> Result<Self, Error> {
-        let clients = create_clients(&config).await?;
-        let ctx = Arc::new(Context { config, clients });
+        let clients = create_clients(&config, None).await?;
+        let auditor = create_auditor(&config)?;
+        let ctx = Arc::new(Context::new(config, clients));
         let orchestrator = Self {
-            ctx,
+            ctx: Arc::new(std::sync::RwLock::new(ctx)),
             client_health: Arc::new(RwLock::new(HealthCheckCache::default())),
+            health_history: Arc::new(RwLock::new(BTreeMap::new())),
+            warmed_up: Arc::new(AtomicBool::new(!start_up_health_check)),
+            auditor: Arc::new(std::sync::RwLock::new(auditor)),
         };
         debug!("running start up checks");
         orchestrator.on_start_up(start_up_health_check).await?;
@@ -76,132 +176,484 @@ impl Orchestrator {
         Ok(orchestrator)
     }
 
-    pub fn config(&self) -> &OrchestratorConfig {
-        &self.ctx.config
+    /// Returns the current context.
+    pub fn ctx(&self) -> Arc<Context> {
+        self.ctx.read().unwrap().clone()
+    }
+
+    /// Returns the current config.
+    pub fn config(&self) -> Arc<OrchestratorConfig> {
+        self.ctx().config.clone()
+    }
+
+    /// Starts an audit of a task, recording nothing if auditing is disabled.
+    pub fn audit(
+        &self,
+        endpoint: &'static str,
+        trace_id: TraceId,
+        tenant: Option<&Tenant>,
+    ) -> Audit {
+        match self.auditor.read().unwrap().as_ref() {
+            Some(auditor) => auditor.start(endpoint, trace_id, tenant),
+            None => Audit::disabled(),
+        }
+    }
+
+    /// Reloads the orchestrator with a new config, swapping the context atomically.
+    /// Clients and detector result caches are reused unless the config they are created
+    /// from changed, and the auditor is recreated if the audit config changed.
+    /// On error, the current context is kept.
+    pub async fn reload(&self, config: OrchestratorConfig) -> Result<(), Error> {
+        let current = self.ctx();
+        let clients = create_clients(&config, Some(&current)).await?;
+        let auditor = (config.audit != current.config.audit)
+            .then(|| create_auditor(&config))
+            .transpose()?;
+        let mut ctx = Context::new(config, clients);
+        ctx.reuse_detector_caches(&current);
+        *self.ctx.write().unwrap() = Arc::new(ctx);
+        if let Some(auditor) = auditor {
+            // Records of in-flight tasks are written by the previous auditor
+            *self.auditor.write().unwrap() = auditor;
+        }
+        if !self.ctx().required_clients.is_empty() {
+            // Readiness depends on health of required clients, probe them without waiting
+            // for the next background probe. Until it completes, readiness is evaluated
+            // against the previous results.
+            let orchestrator = self.clone();
+            tokio::spawn(async move {
+                orchestrator.client_health(true).await;
+            });
+        }
+        info!("orchestrator config reloaded");
+        Ok(())
+    }
+
+    /// Spawns a task probing client health every `interval`.
+    pub fn watch_client_health(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let orchestrator = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                orchestrator.client_health(true).await;
+            }
+        })
     }
 
     /// Perform any start-up actions required by the orchestrator.
     /// This should only error when the orchestrator is unable to start up.
-    /// Currently only performs client health probing to have results loaded into the cache.
+    /// Currently only spawns a warm-up probe of client health to have results loaded into the cache,
+    /// the orchestrator is not ready until it completes.
     pub async fn on_start_up(&self, health_check: bool) -> Result<(), Error> {
         info!("Performing start-up actions for orchestrator...");
-        if health_check {
+        // Readiness depends on health of required clients, so they are always probed
+        if health_check || !self.ctx().required_clients.is_empty() {
             info!("Probing client health...");
-            let client_health = self.client_health(true).await;
-            // Results of probe do not affect orchestrator start-up.
-            info!("Client health:\n{client_health}");
+            let orchestrator = self.clone();
+            tokio::spawn(async move {
+                let client_health = orchestrator.client_health(true).await;
+                // Results of probe do not affect orchestrator start-up.
+                info!("Client health:\n{client_health}");
+                orchestrator.warmed_up.store(true, Ordering::Release);
+            });
         }
         Ok(())
     }
 
+    /// Returns `true` if the start-up warm-up probe completed or was not requested.
+    pub fn warmed_up(&self) -> bool {
+        self.warmed_up.load(Ordering::Acquire)
+    }
+
+    /// Returns the required clients without a healthy status, as of the latest health probe.
+    /// Returns `None` if clients have not been probed since start-up.
+    pub async fn unhealthy_required_clients(&self) -> Option<Vec<String>> {
+        let ctx = self.ctx();
+        if ctx.required_clients.is_empty() {
+            return Some(Vec::new());
+        }
+        let health = self.latest_client_health().await;
+        if health.is_empty() {
+            return None;
+        }
+        let unhealthy = ctx
+            .required_clients
+            .iter()
+            .filter(|key| {
+                health
+                    .get(*key)
+                    .is_none_or(|result| result.status != HealthStatus::Healthy)
+            })
+            .cloned()
+            .collect();
+        Some(unhealthy)
+    }
+
+    /// Returns the latest client health results, without probing.
+    pub async fn latest_client_health(&self) -> HealthCheckCache {
+        let mut health = self.client_health.read().await.clone();
+        self.ctx().apply_circuit_breakers(&mut health);
+        health
+    }
+
+    /// Returns the circuit breaker status of clients with a circuit breaker configured.
+    pub fn circuit_breakers(&self) -> BTreeMap<String, CircuitBreakerStatus> {
+        self.ctx()
+            .clients
+            .iter()
+            .filter_map(|(key, client)| {
+                client
+                    .circuit_breaker()
+                    .map(|circuit_breaker| (key.clone(), circuit_breaker.status()))
+            })
+            .collect()
+    }
+
     /// Returns client health state.
     pub async fn client_health(&self, probe: bool) -> HealthCheckCache {
         let initialized = !self.client_health.read().await.is_empty();
         if probe || !initialized {
             debug!("refreshing health cache");
             let now = Instant::now();
-            let mut health = HealthCheckCache::with_capacity(self.ctx.clients.len());
-            // TODO: perform health checks concurrently?
-            for (key, client) in self.ctx.clients.iter() {
+            let ctx = self.ctx();
+            let checked_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let results = join_all(ctx.clients.iter().map(|(key, client)| async move {
+                let now = Instant::now();
                 let result = client.health().await;
-                health.insert(key.into(), result);
-            }
+                (key.clone(), result, now.elapsed())
+            }))
+            .await;
             let mut client_health = self.client_health.write().await;
+            let mut health_history = self.health_history.write().await;
+            let mut health = HealthCheckCache::with_capacity(results.len());
+            for (key, result, latency) in results {
+                if let Some(previous) = client_health.get(&key) {
+                    log_status_change(&key, &previous.status, &result.status);
+                }
+                health_history
+                    .entry(key.clone())
+                    .or_default()
+                    .record(&result, latency, checked_at);
+                health.insert(key, result);
+            }
+            // Drop history of removed clients
+            health_history.retain(|key, _| health.contains_key(key));
             *client_health = health;
             debug!(
                 "refreshing health cache completed in {:.2?}ms",
                 now.elapsed().as_millis()
             );
         }
-        self.client_health.read().await.clone()
+        self.latest_client_health().await
+    }
+
+    /// Returns the health check history of clients.
+    pub async fn client_health_history(&self) -> BTreeMap<String, HealthCheckHistory> {
+        self.health_history.read().await.clone()
     }
 }
 
-async fn create_clients(config: &OrchestratorConfig) -> Result<ClientMap, Error> {
-    let mut clients = ClientMap::new();
+/// Creates the auditor, if auditing is configured.
+fn create_auditor(config: &OrchestratorConfig) -> Result<Option<Arc<Auditor>>, Error> {
+    config
+        .audit
+        .as_ref()
+        .map(|audit| {
+            Auditor::new(audit)
+                .map(Arc::new)
+                .map_err(|error| Error::Other(format!("failed to create auditor: {error}")))
+        })
+        .transpose()
+}
 
-    // Create generation client
-    if let Some(generation) = &config.generation {
-        let retries = generation
-            .service
-            .max_retries
-            .unwrap_or(DEFAULT_MAX_RETRIES);
-        match generation.provider {
-            GenerationProvider::Tgis => {
-                let tgis_client = TgisClient::new(&generation.service).await;
-                let generation_client = GenerationClient::tgis(tgis_client, retries);
-                clients.insert("generation".to_string(), generation_client);
-            }
-            GenerationProvider::Nlp => {
-                let nlp_client = NlpClient::new(&generation.service).await;
-                let generation_client = GenerationClient::nlp(nlp_client, retries);
-                clients.insert("generation".to_string(), generation_client);
-            }
+/// Logs a change of the health status of a client.
+fn log_status_change(key: &str, previous: &HealthStatus, status: &HealthStatus) {
+    if previous == status {
+        return;
+    }
+    if *status == HealthStatus::Unhealthy {
+        warn!(client = %key, %previous, %status, "client health status changed");
+    } else {
+        info!(client = %key, %previous, %status, "client health status changed");
+    }
+}
+
+/// Config a client is created from.
+#[derive(Debug, Clone, PartialEq)]
+enum ClientConfig {
+    Generation(GenerationConfig),
+    OpenAi(OpenAiConfig),
+    Chunker(ServiceConfig),
+    Detector {
+        r#type: DetectorType,
+        service: ServiceConfig,
+        health_service: Option<ServiceConfig>,
+    },
+}
+
+impl ClientConfig {
+    /// Returns the config of the service the client calls.
+    fn service(&self) -> &ServiceConfig {
+        match self {
+            ClientConfig::Generation(config) => &config.service,
+            ClientConfig::OpenAi(config) => &config.service,
+            ClientConfig::Chunker(service) => service,
+            ClientConfig::Detector { service, .. } => service,
         }
     }
+}
 
-    // Create chat completions client
+/// Returns the configs of clients to create, by client key.
+fn client_configs(config: &OrchestratorConfig) -> HashMap<String, ClientConfig> {
+    let mut client_configs = HashMap::new();
+    if let Some(generation) = &config.generation {
+        client_configs.insert(
+            "generation".to_string(),
+            ClientConfig::Generation(generation.clone()),
+        );
+    }
     if let Some(chat_completions) = &config.chat_completions {
-        let openai_client = OpenAiClient::new(
-            &chat_completions.service,
-            chat_completions.health_service.as_ref(),
-        )
-        .await?;
-        clients.insert("chat_completions".to_string(), openai_client);
+        client_configs.insert(
+            "chat_completions".to_string(),
+            ClientConfig::OpenAi(chat_completions.clone()),
+        );
+    }
+    if let Some(completions) = &config.completions {
+        client_configs.insert(
+            "completions".to_string(),
+            ClientConfig::OpenAi(completions.clone()),
+        );
     }
-
-    // Create chunker clients
     if let Some(chunkers) = &config.chunkers {
         for (chunker_id, chunker) in chunkers {
-            let chunker_client = ChunkerClient::new(&chunker.service).await;
-            clients.insert(chunker_id.to_string(), chunker_client);
+            client_configs.insert(
+                chunker_id.to_string(),
+                ClientConfig::Chunker(chunker.service.clone()),
+            );
         }
     }
-
-    // Create detector clients
     for (detector_id, detector) in &config.detectors {
-        match detector.r#type {
+        client_configs.insert(
+            detector_id.to_string(),
+            ClientConfig::Detector {
+                r#type: detector.r#type.clone(),
+                service: detector.service.clone(),
+                health_service: detector.health_service.clone(),
+            },
+        );
+    }
+    client_configs
+}
+
+/// Creates clients for a config. When reloading, clients of the `current` context
+/// are reused if the config they were created from is unchanged.
+async fn create_clients(
+    config: &OrchestratorConfig,
+    current: Option<&Context>,
+) -> Result<ClientMap, Error> {
+    let current_configs = current
+        .map(|ctx| client_configs(&ctx.config))
+        .unwrap_or_default();
+    let mut clients = ClientMap::new();
+    for (key, client_config) in client_configs(config) {
+        let current_client = current
+            .filter(|_| current_configs.get(&key) == Some(&client_config))
+            .and_then(|ctx| ctx.clients.get_shared(&key));
+        let client = match current_client {
+            Some(client) => {
+                debug!(%key, "reusing client");
+                client
+            }
+            None => {
+                debug!(%key, "creating client");
+                create_client(&client_config).await?
+            }
+        };
+        clients.insert_shared(key, client);
+    }
+    Ok(clients)
+}
+
+async fn create_client(client_config: &ClientConfig) -> Result<Arc<dyn Client>, Error> {
+    let client: Arc<dyn Client> = match client_config {
+        ClientConfig::Generation(generation) => {
+            // Generation calls are retried by default, `max_retries` is kept for compatibility
+            let mut service = generation.service.clone();
+            if service.retry.is_none() {
+                service.retry = Some(RetryConfig {
+                    max_attempts: service.max_retries.unwrap_or(DEFAULT_MAX_RETRIES) + 1,
+                    ..Default::default()
+                });
+            }
+            match generation.provider {
+                GenerationProvider::Tgis => {
+                    let tgis_client = TgisClient::new(&service).await;
+                    Arc::new(GenerationClient::tgis(tgis_client))
+                }
+                GenerationProvider::Nlp => {
+                    let nlp_client = NlpClient::new(&service).await;
+                    Arc::new(GenerationClient::nlp(nlp_client))
+                }
+            }
+        }
+        ClientConfig::OpenAi(openai) => {
+            Arc::new(OpenAiClient::new(&openai.service, openai.health_service.as_ref()).await?)
+        }
+        ClientConfig::Chunker(service) => Arc::new(ChunkerClient::new(service).await),
+        ClientConfig::Detector {
+            r#type,
+            service,
+            health_service,
+        } => match r#type {
             DetectorType::TextContents => {
-                clients.insert(
-                    detector_id.into(),
-                    TextContentsDetectorClient::new(
-                        &detector.service,
-                        detector.health_service.as_ref(),
-                    )
-                    .await?,
-                );
+                Arc::new(TextContentsDetectorClient::new(service, health_service.as_ref()).await?)
             }
             DetectorType::TextGeneration => {
-                clients.insert(
-                    detector_id.into(),
-                    TextGenerationDetectorClient::new(
-                        &detector.service,
-                        detector.health_service.as_ref(),
-                    )
-                    .await?,
-                );
+                Arc::new(TextGenerationDetectorClient::new(service, health_service.as_ref()).await?)
             }
             DetectorType::TextChat => {
-                clients.insert(
-                    detector_id.into(),
-                    TextChatDetectorClient::new(
-                        &detector.service,
-                        detector.health_service.as_ref(),
-                    )
-                    .await?,
-                );
+                Arc::new(TextChatDetectorClient::new(service, health_service.as_ref()).await?)
             }
             DetectorType::TextContextDoc => {
-                clients.insert(
-                    detector_id.into(),
-                    TextContextDocDetectorClient::new(
-                        &detector.service,
-                        detector.health_service.as_ref(),
-                    )
-                    .await?,
-                );
+                Arc::new(TextContextDocDetectorClient::new(service, health_service.as_ref()).await?)
             }
+        },
+    };
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{clients::chunker::DEFAULT_CHUNKER_ID, config::DetectorConfig};
+
+    fn detector_config(port: u16) -> DetectorConfig {
+        DetectorConfig {
+            service: ServiceConfig::new("localhost".into(), port),
+            chunker_id: DEFAULT_CHUNKER_ID.into(),
+            ..Default::default()
         }
     }
-    Ok(clients)
+
+    #[tokio::test]
+    async fn test_reload() -> Result<(), Error> {
+        let cached = |port| DetectorConfig {
+            cache: Some(Default::default()),
+            ..detector_config(port)
+        };
+        let config = OrchestratorConfig {
+            detectors: HashMap::from([("hap".into(), cached(9000)), ("pii".into(), cached(9001))]),
+            ..Default::default()
+        };
+        let orchestrator = Orchestrator::new(config.clone(), false).await?;
+        let ctx = orchestrator.ctx();
+
+        // Change `pii` detector service and add `email` detector
+        let mut new_config = config;
+        new_config.detectors.insert("pii".into(), cached(9002));
+        new_config
+            .detectors
+            .insert("email".into(), detector_config(9003));
+        orchestrator.reload(new_config).await?;
+        let new_ctx = orchestrator.ctx();
+
+        // Unchanged client is reused, changed client is rebuilt
+        assert!(Arc::ptr_eq(
+            &ctx.clients.get_shared("hap").unwrap(),
+            &new_ctx.clients.get_shared("hap").unwrap()
+        ));
+        assert!(!Arc::ptr_eq(
+            &ctx.clients.get_shared("pii").unwrap(),
+            &new_ctx.clients.get_shared("pii").unwrap()
+        ));
+        assert!(new_ctx.clients.get("email").is_some());
+        // Result cache of unchanged detector is reused, cache of changed detector is cleared
+        assert!(Arc::ptr_eq(
+            &ctx.detector_cache("hap").unwrap(),
+            &new_ctx.detector_cache("hap").unwrap()
+        ));
+        assert!(!Arc::ptr_eq(
+            &ctx.detector_cache("pii").unwrap(),
+            &new_ctx.detector_cache("pii").unwrap()
+        ));
+        // Previous context is unchanged for in-flight tasks
+        assert!(ctx.clients.get("email").is_none());
+        assert_eq!(orchestrator.config().detectors.len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_health_history() -> Result<(), Error> {
+        let config = OrchestratorConfig {
+            detectors: HashMap::from([("hap".into(), detector_config(9000))]),
+            ..Default::default()
+        };
+        let orchestrator = Orchestrator::new(config, false).await?;
+        assert!(orchestrator.client_health_history().await.is_empty());
+
+        // Detector service is not running, consecutive failures are counted
+        orchestrator.client_health(true).await;
+        orchestrator.client_health(true).await;
+        let history = orchestrator.client_health_history().await;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history["hap"].consecutive_failures, 2);
+        assert!(history["hap"].last_checked > 0);
+
+        // History of removed clients is dropped on the next probe after reload
+        orchestrator.reload(OrchestratorConfig::default()).await?;
+        orchestrator.client_health(true).await;
+        assert!(orchestrator.client_health_history().await.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_required_clients() -> Result<(), Error> {
+        let mut required = detector_config(9000);
+        required.service.required = true;
+        let config = OrchestratorConfig {
+            detectors: HashMap::from([
+                ("hap".into(), required),
+                ("pii".into(), detector_config(9001)),
+            ]),
+            ..Default::default()
+        };
+        let clients = create_clients(&config, None).await?;
+        let orchestrator = Orchestrator {
+            ctx: Arc::new(std::sync::RwLock::new(Arc::new(Context::new(
+                config.clone(),
+                clients,
+            )))),
+            ..Default::default()
+        };
+        // Clients have not been probed yet
+        assert_eq!(orchestrator.unhealthy_required_clients().await, None);
+        // Detector services are not running, only the required one is reported
+        orchestrator.client_health(true).await;
+        assert_eq!(
+            orchestrator.unhealthy_required_clients().await,
+            Some(vec!["hap".into()])
+        );
+        // Results are kept across reloads
+        orchestrator.reload(config.clone()).await?;
+        assert_eq!(
+            orchestrator.unhealthy_required_clients().await,
+            Some(vec!["hap".into()])
+        );
+
+        // Required clients are probed on start-up without a start-up health check
+        let orchestrator = Orchestrator::new(config, false).await?;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while orchestrator.unhealthy_required_clients().await.is_none() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("required clients should have been probed");
+        Ok(())
+    }
 }
@@ -20,3 +20,11 @@ pub mod tasks;
 pub use tasks::*;
 pub mod client;
 pub use client::*;
+pub mod redaction;
+pub use redaction::*;
+pub mod anonymization;
+pub use anonymization::*;
+pub mod policy;
+pub use policy::*;
+pub mod cache;
+pub use cache::*;
@@ -0,0 +1,358 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+//! Reversible input anonymization
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use futures::StreamExt;
+
+use crate::{
+    config::DetectorAction,
+    models::DetectorParams,
+    orchestrator::{
+        Context,
+        types::{ChatCompletionStream, Detection, Detections, DetectorId, GenerationStream},
+    },
+};
+
+/// A set of detectors with the `anonymize` action.
+pub type Anonymizations = HashSet<DetectorId>;
+
+/// Returns detectors with the `anonymize` action.
+/// Action params take precedence over the detector config.
+pub fn get_anonymizations(
+    ctx: &Arc<Context>,
+    detectors: &HashMap<String, DetectorParams>,
+) -> Anonymizations {
+    detectors
+        .iter()
+        .filter_map(|(detector_id, params)| {
+            let config = ctx.config.detector(detector_id)?;
+            let action = params.clone().pop_action().unwrap_or(config.action);
+            (action == DetectorAction::Anonymize).then(|| detector_id.clone())
+        })
+        .collect()
+}
+
+/// Splits detections into detections of detectors with the `anonymize` action and other detections.
+pub fn split_anonymized(
+    detections: Detections,
+    anonymizations: &Anonymizations,
+) -> (Detections, Detections) {
+    let (anonymized, detections): (Vec<_>, Vec<_>) =
+        detections.into_iter().partition(|detection| {
+            detection
+                .detector_id
+                .as_ref()
+                .is_some_and(|detector_id| anonymizations.contains(detector_id))
+        });
+    (anonymized.into(), detections.into())
+}
+
+/// Replaces detected entities in inputs with stable placeholders, e.g. `<PERSON_1>`,
+/// and restores original values in generated text.
+///
+/// The mapping is scoped to a single request.
+#[derive(Debug, Default)]
+pub struct Anonymizer {
+    /// Map of placeholders to original values
+    values: HashMap<String, String>,
+    /// Map of (entity, original value) to placeholders
+    placeholders: HashMap<(String, String), String>,
+    /// Number of placeholders created for each entity
+    counts: HashMap<String, usize>,
+}
+
+impl Anonymizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if no entities have been anonymized.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Replaces spans of detections with placeholders, returning the anonymized text.
+    ///
+    /// Detection offsets are codepoint indices into `text`. The same value of an
+    /// entity is always replaced with the same placeholder. Overlapping spans are
+    /// merged, keeping the entity of the earliest (longest) span.
+    pub fn anonymize(&mut self, text: &str, detections: &[Detection]) -> String {
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut spans = detections
+            .iter()
+            .filter_map(|detection| {
+                // Detections without a span are not anonymized
+                let (start, end) = detection.span()?;
+                let end = end.min(chars.len());
+                let start = start.min(end);
+                (start < end).then(|| (start, end, entity(&detection.detection)))
+            })
+            .collect::<Vec<_>>();
+        spans.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+        let mut merged: Vec<(usize, usize, String)> = Vec::with_capacity(spans.len());
+        for (start, end, entity) in spans {
+            match merged.last_mut() {
+                Some(last) if start < last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end, entity)),
+            }
+        }
+        let mut anonymized = String::with_capacity(text.len());
+        let mut cursor = 0;
+        for (start, end, entity) in merged {
+            anonymized.extend(&chars[cursor..start]);
+            let value = chars[start..end].iter().collect::<String>();
+            anonymized.push_str(&self.placeholder(entity, value));
+            cursor = end;
+        }
+        anonymized.extend(&chars[cursor..]);
+        anonymized
+    }
+
+    /// Replaces placeholders in text with their original values.
+    pub fn deanonymize(&self, text: &str) -> String {
+        if self.is_empty() {
+            return text.to_string();
+        }
+        let mut deanonymized = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('<') {
+            deanonymized.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let value = rest
+                .find('>')
+                .and_then(|end| self.values.get_key_value(&rest[..=end]));
+            match value {
+                Some((placeholder, value)) => {
+                    deanonymized.push_str(value);
+                    rest = &rest[placeholder.len()..];
+                }
+                None => {
+                    deanonymized.push('<');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        deanonymized.push_str(rest);
+        deanonymized
+    }
+
+    /// Returns `true` if `text` is the beginning of a placeholder.
+    fn is_placeholder_prefix(&self, text: &str) -> bool {
+        self.values
+            .keys()
+            .any(|placeholder| placeholder.starts_with(text))
+    }
+
+    fn placeholder(&mut self, entity: String, value: String) -> String {
+        let key = (entity, value);
+        if let Some(placeholder) = self.placeholders.get(&key) {
+            return placeholder.clone();
+        }
+        let count = self.counts.entry(key.0.clone()).or_default();
+        *count += 1;
+        let placeholder = format!("<{}_{}>", key.0, count);
+        self.values.insert(placeholder.clone(), key.1.clone());
+        self.placeholders.insert(key, placeholder.clone());
+        placeholder
+    }
+}
+
+/// Restores original values in streamed text, where placeholders may be
+/// split across chunks.
+#[derive(Debug)]
+pub struct StreamingDeanonymizer {
+    anonymizer: Arc<Anonymizer>,
+    /// Text held back as it may be the beginning of a placeholder
+    buffer: String,
+}
+
+impl StreamingDeanonymizer {
+    pub fn new(anonymizer: Arc<Anonymizer>) -> Self {
+        Self {
+            anonymizer,
+            buffer: String::new(),
+        }
+    }
+
+    /// Appends text, returning deanonymized text that is ready to be sent.
+    pub fn push(&mut self, text: &str) -> String {
+        self.buffer.push_str(text);
+        // Hold back a trailing partial placeholder until it is completed
+        let split = match self.buffer.rfind('<') {
+            Some(index) if self.anonymizer.is_placeholder_prefix(&self.buffer[index..]) => {
+                if self.anonymizer.values.contains_key(&self.buffer[index..]) {
+                    self.buffer.len()
+                } else {
+                    index
+                }
+            }
+            _ => self.buffer.len(),
+        };
+        let rest = self.buffer.split_off(split);
+        let text = std::mem::replace(&mut self.buffer, rest);
+        self.anonymizer.deanonymize(&text)
+    }
+
+    /// Returns remaining held back text.
+    pub fn flush(&mut self) -> String {
+        let text = std::mem::take(&mut self.buffer);
+        self.anonymizer.deanonymize(&text)
+    }
+}
+
+/// Restores original values in generated text of a generation stream.
+pub fn deanonymize_generation_stream(
+    generation_stream: GenerationStream,
+    anonymizer: Arc<Anonymizer>,
+) -> GenerationStream {
+    let mut deanonymizer = StreamingDeanonymizer::new(anonymizer);
+    generation_stream
+        .map(move |(index, result)| {
+            let result = result.map(|mut generation| {
+                let mut text =
+                    deanonymizer.push(generation.generated_text.as_deref().unwrap_or_default());
+                if generation.finish_reason.is_some() {
+                    // Last message, send remaining held back text
+                    text.push_str(&deanonymizer.flush());
+                }
+                if generation.generated_text.is_some() || !text.is_empty() {
+                    generation.generated_text = Some(text);
+                }
+                generation
+            });
+            (index, result)
+        })
+        .boxed()
+}
+
+/// Restores original values in choice content of a chat completion stream.
+pub fn deanonymize_chat_completion_stream(
+    chat_completion_stream: ChatCompletionStream,
+    anonymizer: Arc<Anonymizer>,
+) -> ChatCompletionStream {
+    // Choices are streamed independently so each choice has its own deanonymizer
+    let mut deanonymizers: HashMap<u32, StreamingDeanonymizer> = HashMap::new();
+    chat_completion_stream
+        .map(move |(index, result)| {
+            let result = result.map(|chat_completion| {
+                chat_completion.map(|mut chat_completion| {
+                    for choice in chat_completion.choices.iter_mut() {
+                        let deanonymizer = deanonymizers
+                            .entry(choice.index)
+                            .or_insert_with(|| StreamingDeanonymizer::new(anonymizer.clone()));
+                        let mut text =
+                            deanonymizer.push(choice.delta.content.as_deref().unwrap_or_default());
+                        if choice.finish_reason.is_some() {
+                            // Last message for this choice, send remaining held back text
+                            text.push_str(&deanonymizer.flush());
+                        }
+                        if choice.delta.content.is_some() || !text.is_empty() {
+                            choice.delta.content = Some(text);
+                        }
+                    }
+                    chat_completion
+                })
+            });
+            (index, result)
+        })
+        .boxed()
+}
+
+/// Returns the placeholder entity name for a detection class, e.g. `email_address` -> `EMAIL_ADDRESS`.
+fn entity(detection: &str) -> String {
+    let entity = detection
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if entity.is_empty() {
+        "ENTITY".into()
+    } else {
+        entity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detection(detection: &str, start: usize, end: usize) -> Detection {
+        Detection {
+            start: Some(start),
+            end: Some(end),
+            detection: detection.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_anonymize() {
+        let mut anonymizer = Anonymizer::new();
+        let text = "Jane Doe emailed John Smith, then Jane Doe called.";
+        let detections = vec![
+            detection("person", 0, 8),
+            detection("person", 17, 27),
+            detection("person", 34, 42),
+        ];
+        let anonymized = anonymizer.anonymize(text, &detections);
+        assert_eq!(
+            anonymized,
+            "<PERSON_1> emailed <PERSON_2>, then <PERSON_1> called."
+        );
+        assert_eq!(
+            anonymizer.deanonymize("Hello <PERSON_2>, this is <PERSON_1>. <PERSON_3> <b>"),
+            "Hello John Smith, this is Jane Doe. <PERSON_3> <b>"
+        );
+    }
+
+    #[test]
+    fn test_anonymize_overlapping() {
+        let mut anonymizer = Anonymizer::new();
+        let text = "Call Jane at jane@example.com";
+        let detections = vec![
+            detection("person", 5, 9),
+            detection("email_address", 13, 29),
+            detection("person", 13, 17),
+        ];
+        let anonymized = anonymizer.anonymize(text, &detections);
+        assert_eq!(anonymized, "Call <PERSON_1> at <EMAIL_ADDRESS_1>");
+        assert_eq!(anonymizer.deanonymize(&anonymized), text);
+    }
+
+    #[test]
+    fn test_streaming_deanonymizer() {
+        let mut anonymizer = Anonymizer::new();
+        anonymizer.anonymize("Jane Doe", &[detection("person", 0, 8)]);
+        let mut deanonymizer = StreamingDeanonymizer::new(Arc::new(anonymizer));
+        let chunks = ["Hi <", "PER", "SON_1>", ", a < b", " <PERSON_1>!", " <PER"];
+        let text = chunks
+            .iter()
+            .map(|chunk| deanonymizer.push(chunk))
+            .collect::<Vec<_>>();
+        assert_eq!(text, ["Hi ", "", "Jane Doe", ", a < b", " Jane Doe!", " "]);
+        assert_eq!(deanonymizer.flush(), "<PER");
+    }
+}
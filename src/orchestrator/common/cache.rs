@@ -0,0 +1,297 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+//! Detector result cache
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use ring::digest::{SHA256, digest};
+use serde::Serialize;
+
+use crate::{
+    config::DetectorCacheConfig,
+    models::DetectorParams,
+    orchestrator::types::{Detections, DetectorId},
+    utils::trace::on_detector_cache_lookup,
+};
+
+/// Metadata key set on detections served from the cache.
+pub const CACHE_HIT_METADATA_KEY: &str = "cache_hit";
+
+/// Content-addressed key of a cached detector result, scoped to the tenant of the request.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    detector_id: DetectorId,
+    /// Tenant scope, see [`tenant_scope`](crate::orchestrator::types::tenant_scope)
+    tenant: Option<String>,
+    /// Canonical JSON of the detector params
+    params: String,
+    /// SHA-256 hash of the detector input, e.g. the chunk text
+    input_hash: [u8; 32],
+}
+
+struct CacheEntry {
+    detections: Detections,
+    inserted_at: Instant,
+    tick: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    /// Keys by last use, least recently used first
+    lru: BTreeMap<u64, CacheKey>,
+    tick: u64,
+}
+
+/// LRU cache of detector results with an optional TTL.
+pub struct DetectorCache {
+    detector_id: DetectorId,
+    max_entries: usize,
+    ttl: Option<Duration>,
+    state: Mutex<CacheState>,
+}
+
+impl DetectorCache {
+    pub fn new(detector_id: DetectorId, config: &DetectorCacheConfig) -> Self {
+        Self {
+            detector_id,
+            max_entries: config.max_entries,
+            ttl: config.ttl_seconds.map(Duration::from_secs),
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Returns the cache key of a detector request of a tenant.
+    pub fn key(
+        &self,
+        tenant: Option<&str>,
+        params: &DetectorParams,
+        input: &impl Serialize,
+    ) -> CacheKey {
+        let params = serde_json::to_string(&canonicalize(&serde_json::to_value(params).unwrap()))
+            .unwrap_or_default();
+        let input = serde_json::to_vec(input).unwrap_or_default();
+        let input_hash = digest(&SHA256, &input).as_ref().try_into().unwrap();
+        CacheKey {
+            detector_id: self.detector_id.clone(),
+            tenant: tenant.map(Into::into),
+            params,
+            input_hash,
+        }
+    }
+
+    /// Returns cached detections, marked as cache hits.
+    pub fn get(&self, key: &CacheKey) -> Option<Detections> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let CacheState { entries, lru, .. } = &mut *state;
+        let detections = match entries.get_mut(key) {
+            Some(entry)
+                if self
+                    .ttl
+                    .is_some_and(|ttl| entry.inserted_at.elapsed() >= ttl) =>
+            {
+                lru.remove(&entry.tick);
+                entries.remove(key);
+                None
+            }
+            Some(entry) => {
+                lru.remove(&entry.tick);
+                lru.insert(tick, key.clone());
+                entry.tick = tick;
+                Some(entry.detections.clone())
+            }
+            None => None,
+        };
+        drop(state);
+        on_detector_cache_lookup(&self.detector_id, detections.is_some());
+        detections.map(|detections| {
+            detections
+                .into_iter()
+                .map(|mut detection| {
+                    detection
+                        .metadata
+                        .insert(CACHE_HIT_METADATA_KEY.into(), true.into());
+                    detection
+                })
+                .collect()
+        })
+    }
+
+    /// Inserts detections, evicting the least recently used entry if the cache is full.
+    pub fn insert(&self, key: CacheKey, detections: Detections) {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let CacheState { entries, lru, .. } = &mut *state;
+        if let Some(entry) = entries.remove(&key) {
+            lru.remove(&entry.tick);
+        }
+        while entries.len() >= self.max_entries {
+            let Some((_, key)) = lru.pop_first() else {
+                break;
+            };
+            entries.remove(&key);
+        }
+        lru.insert(tick, key.clone());
+        entries.insert(
+            key,
+            CacheEntry {
+                detections,
+                inserted_at: Instant::now(),
+                tick,
+            },
+        );
+    }
+
+    /// Returns the number of entries in the cache.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Returns `true` if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl std::fmt::Debug for DetectorCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DetectorCache")
+            .field("detector_id", &self.detector_id)
+            .field("max_entries", &self.max_entries)
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+/// Returns a copy of a JSON value with object keys sorted recursively.
+fn canonicalize(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let sorted = map
+                .iter()
+                .map(|(key, value)| (key, canonicalize(value)))
+                .collect::<BTreeMap<_, _>>();
+            serde_json::Value::Object(
+                sorted
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), value))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.iter().map(canonicalize).collect())
+        }
+        value => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::orchestrator::types::Detection;
+
+    fn detections(score: f64) -> Detections {
+        vec![Detection {
+            detection: "has_HAP".into(),
+            score,
+            ..Default::default()
+        }]
+        .into()
+    }
+
+    #[test]
+    fn test_key() {
+        let cache = DetectorCache::new("hap".into(), &DetectorCacheConfig::default());
+        let mut params = DetectorParams::new();
+        params.insert("options".into(), json!({"b": 1, "a": 2}));
+        let mut reordered = DetectorParams::new();
+        reordered.insert("options".into(), json!({"a": 2, "b": 1}));
+        assert_eq!(
+            cache.key(None, &params, &"hello"),
+            cache.key(None, &reordered, &"hello")
+        );
+        assert_ne!(
+            cache.key(None, &params, &"hello"),
+            cache.key(None, &params, &"world")
+        );
+        assert_ne!(
+            cache.key(None, &params, &"hello"),
+            cache.key(None, &DetectorParams::new(), &"hello")
+        );
+        // Keys are scoped to the tenant
+        assert_ne!(
+            cache.key(Some("tenant-1"), &params, &"hello"),
+            cache.key(Some("tenant-2"), &params, &"hello")
+        );
+        assert_ne!(
+            cache.key(Some("tenant-1"), &params, &"hello"),
+            cache.key(None, &params, &"hello")
+        );
+    }
+
+    #[test]
+    fn test_get_and_insert() {
+        let cache = DetectorCache::new(
+            "hap".into(),
+            &DetectorCacheConfig {
+                max_entries: 2,
+                ttl_seconds: None,
+            },
+        );
+        let params = DetectorParams::new();
+        let (a, b, c) = (
+            cache.key(None, &params, &"a"),
+            cache.key(None, &params, &"b"),
+            cache.key(None, &params, &"c"),
+        );
+        assert!(cache.get(&a).is_none());
+        cache.insert(a.clone(), detections(0.1));
+        cache.insert(b.clone(), detections(0.2));
+        let hit = cache.get(&a).unwrap();
+        assert_eq!(hit[0].score, 0.1);
+        assert_eq!(hit[0].metadata[CACHE_HIT_METADATA_KEY], json!(true));
+        // `b` is least recently used and evicted
+        cache.insert(c.clone(), detections(0.3));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&b).is_none());
+        assert!(cache.get(&a).is_some());
+        assert!(cache.get(&c).is_some());
+    }
+
+    #[test]
+    fn test_ttl() {
+        let cache = DetectorCache {
+            detector_id: "hap".into(),
+            max_entries: 2,
+            ttl: Some(Duration::ZERO),
+            state: Mutex::new(CacheState::default()),
+        };
+        let key = cache.key(None, &DetectorParams::new(), &"a");
+        cache.insert(key.clone(), detections(0.1));
+        assert!(cache.get(&key).is_none());
+        assert!(cache.is_empty());
+    }
+}
@@ -15,12 +15,14 @@
 
 */
 //! Client helpers
-use futures::{StreamExt, TryStreamExt};
+use futures::{Future, StreamExt, TryStreamExt};
 use http::{HeaderMap, header::CONTENT_TYPE};
-use tokio::sync::broadcast;
+use tokio::{sync::broadcast, time::Instant};
 use tokio_stream::wrappers::{BroadcastStream, ReceiverStream};
 use tracing::{debug, instrument};
 
+use super::DetectorCache;
+
 use crate::{
     clients::{
         GenerationClient, TextContentsDetectorClient,
@@ -37,28 +39,57 @@ use crate::{
         ClassifiedGeneratedTextResult as GenerateResponse, DetectorParams,
         GuardrailsTextGenerationParameters as GenerateParams,
     },
-    orchestrator::{Error, types::*},
+    orchestrator::{Error, Stage, types::*},
     pb::caikit::runtime::chunkers::{
         BidiStreamingChunkerTokenizationTaskRequest, ChunkerTokenizationTaskRequest,
     },
+    utils::{deadline::Deadline, metrics, trace},
 };
 
+/// Awaits a client call bounded by the request deadline, if set.
+/// Returns a deadline exceeded error naming the stage if the deadline is exceeded.
+async fn with_deadline<T>(
+    stage: Stage,
+    deadline: Option<Deadline>,
+    call: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    let Some(deadline) = deadline else {
+        return call.await;
+    };
+    match tokio::time::timeout(deadline.remaining(), call).await {
+        Ok(Ok(value)) => Ok(value),
+        // Downstream services may fail first as the deadline is propagated to them
+        Ok(Err(error)) if !deadline.is_exceeded() => Err(error),
+        _ => {
+            trace::on_deadline_exceeded(stage);
+            Err(Error::DeadlineExceeded { stage })
+        }
+    }
+}
+
 /// Sends request to chunker client.
 #[instrument(skip_all, fields(chunker_id))]
 pub async fn chunk(
     client: &ChunkerClient,
     chunker_id: ChunkerId,
     text: String,
+    deadline: Option<Deadline>,
 ) -> Result<Chunks, Error> {
     let request = ChunkerTokenizationTaskRequest { text };
     debug!(%chunker_id, ?request, "sending chunker request");
-    let response = client
-        .tokenization_task_predict(&chunker_id, request)
-        .await
-        .map_err(|error| Error::ChunkerRequestFailed {
-            id: chunker_id.clone(),
-            error,
-        })?;
+    let now = Instant::now();
+    let response = with_deadline(Stage::Chunking, deadline, async {
+        client
+            .tokenization_task_predict(&chunker_id, request, deadline)
+            .await
+            .map_err(|error| Error::ChunkerRequestFailed {
+                id: chunker_id.clone(),
+                error,
+            })
+    })
+    .await;
+    metrics::on_chunker_request(&chunker_id, now.elapsed());
+    let response = response?;
     debug!(%chunker_id, ?response, "received chunker response");
     Ok(response.into())
 }
@@ -69,6 +100,7 @@ pub async fn chunk_stream(
     client: &ChunkerClient,
     chunker_id: ChunkerId,
     input_rx: broadcast::Receiver<Result<(usize, String), Error>>, // (message_index, text)
+    deadline: Option<Deadline>,
 ) -> Result<ChunkStream, Error> {
     let input_stream = BroadcastStream::new(input_rx)
         .map(|result| {
@@ -81,7 +113,7 @@ pub async fn chunk_stream(
         .boxed();
     debug!(%chunker_id, "sending chunk stream request");
     let output_stream = client
-        .bidi_streaming_tokenization_task_predict(&chunker_id, input_stream)
+        .bidi_streaming_tokenization_task_predict(&chunker_id, input_stream, deadline)
         .await
         .map_err(|error| Error::ChunkerRequestFailed {
             id: chunker_id.clone(),
@@ -97,6 +129,7 @@ pub async fn chunk_stream(
 }
 
 /// Sends request to text contents detector client.
+/// If a cache is provided, only chunks without cached results are sent.
 #[instrument(skip_all, fields(detector_id))]
 pub async fn detect_text_contents(
     client: &TextContentsDetectorClient,
@@ -105,34 +138,76 @@ pub async fn detect_text_contents(
     params: DetectorParams,
     chunks: Chunks,
     apply_chunk_offset: bool,
+    cache: Option<&DetectorCache>,
 ) -> Result<Detections, Error> {
     let detector_id = detector_id.clone();
+    if chunks.is_empty() {
+        return Ok(Detections::default());
+    }
+    let keys = cache.map(|cache| {
+        chunks
+            .iter()
+            .map(|chunk| cache.key(tenant_scope(&headers), &params, &chunk.text))
+            .collect::<Vec<_>>()
+    });
+    let mut results = match cache.zip(keys.as_ref()) {
+        Some((cache, keys)) => keys.iter().map(|key| cache.get(key)).collect(),
+        None => vec![None; chunks.len()],
+    };
     let contents = chunks
         .iter()
-        .map(|chunk| chunk.text.clone())
+        .zip(&results)
+        .filter(|(_, result)| result.is_none())
+        .map(|(chunk, _)| chunk.text.clone())
         .collect::<Vec<_>>();
-    if contents.is_empty() {
-        return Ok(Detections::default());
+    if !contents.is_empty() {
+        let request = ContentAnalysisRequest::new(contents, params);
+        debug!(%detector_id, ?request, "sending detector request");
+        let deadline = Deadline::from_headers(&headers);
+        let now = Instant::now();
+        let response = with_deadline(Stage::Detection, deadline, async {
+            client
+                .text_contents(&detector_id, request, headers)
+                .await
+                .map_err(|error| Error::DetectorRequestFailed {
+                    id: detector_id.clone(),
+                    error,
+                })
+        })
+        .await;
+        metrics::on_detector_request(&detector_id, now.elapsed());
+        let response = response?;
+        debug!(%detector_id, ?response, "received detector response");
+        let misses = results
+            .iter()
+            .enumerate()
+            .filter(|(_, result)| result.is_none())
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        for (index, detections) in misses.into_iter().zip(response) {
+            let detections = detections
+                .into_iter()
+                .map(|detection| {
+                    let mut detection: Detection = detection.into();
+                    detection.detector_id = Some(detector_id.clone());
+                    detection
+                })
+                .collect::<Detections>();
+            metrics::on_detections(&detector_id, &detections);
+            if let Some((cache, keys)) = cache.zip(keys.as_ref()) {
+                cache.insert(keys[index].clone(), detections.clone());
+            }
+            results[index] = Some(detections);
+        }
     }
-    let request = ContentAnalysisRequest::new(contents, params);
-    debug!(%detector_id, ?request, "sending detector request");
-    let response = client
-        .text_contents(&detector_id, request, headers)
-        .await
-        .map_err(|error| Error::DetectorRequestFailed {
-            id: detector_id.clone(),
-            error,
-        })?;
-    debug!(%detector_id, ?response, "received detector response");
     let detections = chunks
         .into_iter()
-        .zip(response)
+        .zip(results)
         .flat_map(|(chunk, detections)| {
             detections
+                .unwrap_or_default()
                 .into_iter()
-                .map(|detection| {
-                    let mut detection: Detection = detection.into();
-                    detection.detector_id = Some(detector_id.clone());
+                .map(|mut detection| {
                     if apply_chunk_offset {
                         let offset = chunk.start;
                         detection.start = detection.start.map(|start| start + offset);
@@ -155,17 +230,33 @@ pub async fn detect_text_generation(
     params: DetectorParams,
     prompt: String,
     generated_text: String,
+    cache: Option<&DetectorCache>,
 ) -> Result<Detections, Error> {
     let detector_id = detector_id.clone();
+    let key =
+        cache.map(|cache| cache.key(tenant_scope(&headers), &params, &(&prompt, &generated_text)));
+    if let Some(detections) = cache
+        .zip(key.as_ref())
+        .and_then(|(cache, key)| cache.get(key))
+    {
+        return Ok(detections);
+    }
     let request = GenerationDetectionRequest::new(prompt, generated_text, params);
     debug!(%detector_id, ?request, "sending detector request");
-    let response = client
-        .text_generation(&detector_id, request, headers)
-        .await
-        .map_err(|error| Error::DetectorRequestFailed {
-            id: detector_id.clone(),
-            error,
-        })?;
+    let deadline = Deadline::from_headers(&headers);
+    let now = Instant::now();
+    let response = with_deadline(Stage::Detection, deadline, async {
+        client
+            .text_generation(&detector_id, request, headers)
+            .await
+            .map_err(|error| Error::DetectorRequestFailed {
+                id: detector_id.clone(),
+                error,
+            })
+    })
+    .await;
+    metrics::on_detector_request(&detector_id, now.elapsed());
+    let response = response?;
     debug!(%detector_id, ?response, "received detector response");
     let detections = response
         .into_iter()
@@ -175,6 +266,10 @@ pub async fn detect_text_generation(
             detection
         })
         .collect::<Detections>();
+    metrics::on_detections(&detector_id, &detections);
+    if let Some((cache, key)) = cache.zip(key) {
+        cache.insert(key, detections.clone());
+    }
     Ok(detections)
 }
 
@@ -187,17 +282,32 @@ pub async fn detect_text_chat(
     params: DetectorParams,
     messages: Vec<openai::Message>,
     tools: Vec<openai::Tool>,
+    cache: Option<&DetectorCache>,
 ) -> Result<Detections, Error> {
     let detector_id = detector_id.clone();
+    let key = cache.map(|cache| cache.key(tenant_scope(&headers), &params, &(&messages, &tools)));
+    if let Some(detections) = cache
+        .zip(key.as_ref())
+        .and_then(|(cache, key)| cache.get(key))
+    {
+        return Ok(detections);
+    }
     let request = ChatDetectionRequest::new(messages, tools, params);
     debug!(%detector_id, ?request, "sending detector request");
-    let response = client
-        .text_chat(&detector_id, request, headers)
-        .await
-        .map_err(|error| Error::DetectorRequestFailed {
-            id: detector_id.clone(),
-            error,
-        })?;
+    let deadline = Deadline::from_headers(&headers);
+    let now = Instant::now();
+    let response = with_deadline(Stage::Detection, deadline, async {
+        client
+            .text_chat(&detector_id, request, headers)
+            .await
+            .map_err(|error| Error::DetectorRequestFailed {
+                id: detector_id.clone(),
+                error,
+            })
+    })
+    .await;
+    metrics::on_detector_request(&detector_id, now.elapsed());
+    let response = response?;
     debug!(%detector_id, ?response, "received detector response");
     let detections = response
         .into_iter()
@@ -207,6 +317,10 @@ pub async fn detect_text_chat(
             detection
         })
         .collect::<Detections>();
+    metrics::on_detections(&detector_id, &detections);
+    if let Some((cache, key)) = cache.zip(key) {
+        cache.insert(key, detections.clone());
+    }
     Ok(detections)
 }
 
@@ -220,17 +334,38 @@ pub async fn detect_text_context(
     content: String,
     context_type: ContextType,
     context: Vec<String>,
+    cache: Option<&DetectorCache>,
 ) -> Result<Detections, Error> {
     let detector_id = detector_id.clone();
+    let key = cache.map(|cache| {
+        cache.key(
+            tenant_scope(&headers),
+            &params,
+            &(&content, &context_type, &context),
+        )
+    });
+    if let Some(detections) = cache
+        .zip(key.as_ref())
+        .and_then(|(cache, key)| cache.get(key))
+    {
+        return Ok(detections);
+    }
     let request = ContextDocsDetectionRequest::new(content, context_type, context, params.clone());
     debug!(%detector_id, ?request, "sending detector request");
-    let response = client
-        .text_context_doc(&detector_id, request, headers)
-        .await
-        .map_err(|error| Error::DetectorRequestFailed {
-            id: detector_id.clone(),
-            error,
-        })?;
+    let deadline = Deadline::from_headers(&headers);
+    let now = Instant::now();
+    let response = with_deadline(Stage::Detection, deadline, async {
+        client
+            .text_context_doc(&detector_id, request, headers)
+            .await
+            .map_err(|error| Error::DetectorRequestFailed {
+                id: detector_id.clone(),
+                error,
+            })
+    })
+    .await;
+    metrics::on_detector_request(&detector_id, now.elapsed());
+    let response = response?;
     debug!(%detector_id, ?response, "received detector response");
     let detections = response
         .into_iter()
@@ -240,6 +375,10 @@ pub async fn detect_text_context(
             detection
         })
         .collect::<Detections>();
+    metrics::on_detections(&detector_id, &detections);
+    if let Some((cache, key)) = cache.zip(key) {
+        cache.insert(key, detections.clone());
+    }
     Ok(detections)
 }
 
@@ -253,13 +392,17 @@ pub async fn chat_completion(
     let model_id = request.model.clone();
     debug!(%model_id, ?request, "sending chat completions request");
     headers.append(CONTENT_TYPE, JSON_CONTENT_TYPE);
-    let response = client
-        .chat_completions(request, headers)
-        .await
-        .map_err(|error| Error::ChatCompletionRequestFailed {
-            id: model_id.clone(),
-            error,
-        })?;
+    let deadline = Deadline::from_headers(&headers);
+    let response = with_deadline(Stage::Generation, deadline, async {
+        client
+            .chat_completions(request, headers)
+            .await
+            .map_err(|error| Error::ChatCompletionRequestFailed {
+                id: model_id.clone(),
+                error,
+            })
+    })
+    .await?;
     debug!(%model_id, ?response, "received chat completions response");
     Ok(response)
 }
@@ -274,13 +417,17 @@ pub async fn chat_completion_stream(
     let model_id = request.model.clone();
     debug!(%model_id, ?request, "sending chat completions stream request");
     headers.append(CONTENT_TYPE, JSON_CONTENT_TYPE);
-    let response = client
-        .chat_completions(request, headers)
-        .await
-        .map_err(|error| Error::ChatCompletionRequestFailed {
-            id: model_id.clone(),
-            error,
-        })?;
+    let deadline = Deadline::from_headers(&headers);
+    let response = with_deadline(Stage::Generation, deadline, async {
+        client
+            .chat_completions(request, headers)
+            .await
+            .map_err(|error| Error::ChatCompletionRequestFailed {
+                id: model_id.clone(),
+                error,
+            })
+    })
+    .await?;
     let stream = match response {
         openai::ChatCompletionsResponse::Streaming(rx) => ReceiverStream::new(rx),
         openai::ChatCompletionsResponse::Unary(_) => unimplemented!(),
@@ -300,13 +447,17 @@ pub async fn completion(
     let model_id = request.model.clone();
     debug!(%model_id, ?request, "sending completions request");
     headers.append(CONTENT_TYPE, JSON_CONTENT_TYPE);
-    let response = client
-        .completions(request, headers)
-        .await
-        .map_err(|error| Error::CompletionRequestFailed {
-            id: model_id.clone(),
-            error,
-        })?;
+    let deadline = Deadline::from_headers(&headers);
+    let response = with_deadline(Stage::Generation, deadline, async {
+        client
+            .completions(request, headers)
+            .await
+            .map_err(|error| Error::CompletionRequestFailed {
+                id: model_id.clone(),
+                error,
+            })
+    })
+    .await?;
     debug!(%model_id, ?response, "received completions response");
     Ok(response)
 }
@@ -321,13 +472,17 @@ pub async fn completion_stream(
     let model_id = request.model.clone();
     debug!(%model_id, ?request, "sending completions stream request");
     headers.append(CONTENT_TYPE, JSON_CONTENT_TYPE);
-    let response = client
-        .completions(request, headers)
-        .await
-        .map_err(|error| Error::CompletionRequestFailed {
-            id: model_id.clone(),
-            error,
-        })?;
+    let deadline = Deadline::from_headers(&headers);
+    let response = with_deadline(Stage::Generation, deadline, async {
+        client
+            .completions(request, headers)
+            .await
+            .map_err(|error| Error::CompletionRequestFailed {
+                id: model_id.clone(),
+                error,
+            })
+    })
+    .await?;
     let stream = match response {
         openai::CompletionsResponse::Streaming(rx) => ReceiverStream::new(rx),
         openai::CompletionsResponse::Unary(_) => unimplemented!(),
@@ -347,13 +502,17 @@ pub async fn tokenize(
 ) -> Result<(u32, Vec<String>), Error> {
     // (token_count, tokens)
     debug!(%model_id, "sending tokenize request");
-    let response = client
-        .tokenize(model_id.clone(), text, headers)
-        .await
-        .map_err(|error| Error::TokenizeRequestFailed {
-            id: model_id.clone(),
-            error,
-        })?;
+    let deadline = Deadline::from_headers(&headers);
+    let response = with_deadline(Stage::Generation, deadline, async {
+        client
+            .tokenize(model_id.clone(), text, headers)
+            .await
+            .map_err(|error| Error::TokenizeRequestFailed {
+                id: model_id.clone(),
+                error,
+            })
+    })
+    .await?;
     debug!(%model_id, ?response, "received tokenize response");
     Ok(response)
 }
@@ -368,13 +527,17 @@ pub async fn generate(
     params: Option<GenerateParams>,
 ) -> Result<GenerateResponse, Error> {
     debug!(%model_id, "sending generate request");
-    let response = client
-        .generate(model_id.clone(), text, params, headers)
-        .await
-        .map_err(|error| Error::GenerateRequestFailed {
-            id: model_id.clone(),
-            error,
-        })?;
+    let deadline = Deadline::from_headers(&headers);
+    let response = with_deadline(Stage::Generation, deadline, async {
+        client
+            .generate(model_id.clone(), text, params, headers)
+            .await
+            .map_err(|error| Error::GenerateRequestFailed {
+                id: model_id.clone(),
+                error,
+            })
+    })
+    .await?;
     debug!(%model_id, ?response, "received generate response");
     Ok(response)
 }
@@ -389,18 +552,22 @@ pub async fn generate_stream(
     params: Option<GenerateParams>,
 ) -> Result<GenerationStream, Error> {
     debug!(%model_id, "sending generate stream request");
-    let stream = client
-        .generate_stream(model_id.clone(), text, params, headers)
-        .await
-        .map_err(|error| Error::GenerateRequestFailed {
-            id: model_id.clone(),
-            error,
-        })? // maps method call errors
-        .map_err(move |error| Error::GenerateRequestFailed {
-            id: model_id.clone(),
-            error,
-        }) // maps stream errors
-        .enumerate()
-        .boxed();
+    let deadline = Deadline::from_headers(&headers);
+    let stream = with_deadline(Stage::Generation, deadline, async {
+        client
+            .generate_stream(model_id.clone(), text, params, headers)
+            .await
+            .map_err(|error| Error::GenerateRequestFailed {
+                id: model_id.clone(),
+                error,
+            })
+    })
+    .await? // maps method call errors
+    .map_err(move |error| Error::GenerateRequestFailed {
+        id: model_id.clone(),
+        error,
+    }) // maps stream errors
+    .enumerate()
+    .boxed();
     Ok(stream)
 }
@@ -0,0 +1,316 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+//! Policy evaluation
+use std::sync::Arc;
+
+use super::{Redactions, redact_matching};
+use crate::{
+    config::{PolicyDirection, PolicyOutcome, PolicyRule},
+    models::PolicyMatch,
+    orchestrator::{
+        Context,
+        types::{Detection, Detections},
+    },
+};
+
+/// Outcome of evaluating policy rules against detections.
+#[derive(Debug, Clone)]
+pub struct PolicyDecision {
+    /// Outcome to apply
+    pub outcome: PolicyOutcome,
+    /// Rule that fired, if any
+    pub rule: Option<PolicyRule>,
+}
+
+impl PolicyDecision {
+    /// Returns the rule that fired and its outcome, to be reported in warnings.
+    pub fn policy(&self) -> Option<PolicyMatch> {
+        self.rule.as_ref().map(|rule| PolicyMatch {
+            rule: rule.name.clone(),
+            outcome: self.outcome,
+        })
+    }
+
+    /// Returns `true` if the decision is to block.
+    pub fn is_block(&self) -> bool {
+        self.outcome == PolicyOutcome::Block
+    }
+
+    /// Returns `true` if the decision is to allow, i.e. detections are ignored.
+    pub fn is_allow(&self) -> bool {
+        self.outcome == PolicyOutcome::Allow
+    }
+
+    /// Returns `true` if text should be redacted, by the rule that fired or
+    /// by detectors with the `redact` action.
+    pub fn should_redact(&self, redactions: &Redactions) -> bool {
+        !self.is_allow() && (self.outcome == PolicyOutcome::Redact || !redactions.is_empty())
+    }
+
+    /// Redacts spans of detections matched by the rule that fired, if its outcome is `redact`,
+    /// and of detectors with the `redact` action, returning the redacted text.
+    pub fn redact(
+        &self,
+        text: &str,
+        detections: &mut [Detection],
+        redactions: &Redactions,
+    ) -> String {
+        let rule = self
+            .rule
+            .as_ref()
+            .filter(|_| self.outcome == PolicyOutcome::Redact);
+        redact_matching(text, detections, |detection| {
+            // Detections matched by the rule are redacted even without a detector ID
+            let placeholder = detection
+                .detector_id
+                .as_ref()
+                .and_then(|detector_id| redactions.get(detector_id).cloned());
+            if rule.is_some_and(|rule| rule_matches(rule, detection)) {
+                Some(placeholder.flatten())
+            } else {
+                placeholder
+            }
+        })
+    }
+
+    /// Redacts input text, see [`PolicyDecision::redact`]. Spans of `anonymized`
+    /// detections are updated to refer to the redacted text.
+    pub fn redact_input(
+        &self,
+        text: &str,
+        detections: &mut Detections,
+        anonymized: &mut Detections,
+    ) -> String {
+        let n = anonymized.len();
+        let mut all = anonymized
+            .drain(..)
+            .chain(detections.drain(..))
+            .collect::<Vec<_>>();
+        let text = self.redact(text, &mut all, &Redactions::new());
+        detections.extend(all.drain(n..));
+        anonymized.extend(all);
+        text
+    }
+}
+
+/// Evaluates policy rules against detections.
+pub fn evaluate_policy(
+    ctx: &Arc<Context>,
+    direction: PolicyDirection,
+    detections: &[Detection],
+) -> PolicyDecision {
+    evaluate(&ctx.config.policies, direction, detections)
+}
+
+/// Evaluates rules in order, returning the outcome of the first rule to fire.
+///
+/// If no detections are provided, detections are allowed. If no rule fires, the
+/// default outcome is applied, which is to block input and warn on output.
+fn evaluate(
+    rules: &[PolicyRule],
+    direction: PolicyDirection,
+    detections: &[Detection],
+) -> PolicyDecision {
+    if detections.is_empty() {
+        return PolicyDecision {
+            outcome: PolicyOutcome::Allow,
+            rule: None,
+        };
+    }
+    let rule = rules.iter().find(|rule| {
+        if rule
+            .applies_to
+            .is_some_and(|applies_to| applies_to != direction)
+        {
+            return false;
+        }
+        let count = detections
+            .iter()
+            .filter(|detection| rule_matches(rule, detection))
+            .count();
+        count >= rule.min_count && rule.max_count.is_none_or(|max_count| count <= max_count)
+    });
+    match rule {
+        Some(rule) => PolicyDecision {
+            outcome: rule.outcome,
+            rule: Some(rule.clone()),
+        },
+        None => PolicyDecision {
+            outcome: match direction {
+                PolicyDirection::Input => PolicyOutcome::Block,
+                PolicyDirection::Output => PolicyOutcome::Warn,
+            },
+            rule: None,
+        },
+    }
+}
+
+/// Returns `true` if a detection matches all conditions of a rule.
+fn rule_matches(rule: &PolicyRule, detection: &Detection) -> bool {
+    rule.detector_id
+        .as_ref()
+        .is_none_or(|detector_id| detection.detector_id.as_ref() == Some(detector_id))
+        && rule
+            .detection
+            .as_ref()
+            .is_none_or(|class| &detection.detection == class)
+        && rule
+            .detection_type
+            .as_ref()
+            .is_none_or(|detection_type| &detection.detection_type == detection_type)
+        && rule
+            .min_score
+            .is_none_or(|min_score| detection.score >= min_score)
+        && rule
+            .max_score
+            .is_none_or(|max_score| detection.score <= max_score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, outcome: PolicyOutcome) -> PolicyRule {
+        PolicyRule {
+            name: name.into(),
+            applies_to: None,
+            detector_id: None,
+            detection: None,
+            detection_type: None,
+            min_score: None,
+            max_score: None,
+            min_count: 1,
+            max_count: None,
+            outcome,
+        }
+    }
+
+    fn detection(detector_id: &str, detection: &str, score: f64) -> Detection {
+        Detection {
+            start: Some(0),
+            end: Some(4),
+            detector_id: Some(detector_id.into()),
+            detection: detection.into(),
+            score,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_evaluate_default() {
+        let detections = vec![detection("hap", "hap", 0.9)];
+        let decision = evaluate(&[], PolicyDirection::Input, &detections);
+        assert_eq!(decision.outcome, PolicyOutcome::Block);
+        assert!(decision.policy().is_none());
+        let decision = evaluate(&[], PolicyDirection::Output, &detections);
+        assert_eq!(decision.outcome, PolicyOutcome::Warn);
+        let decision = evaluate(&[], PolicyDirection::Output, &[]);
+        assert_eq!(decision.outcome, PolicyOutcome::Allow);
+    }
+
+    #[test]
+    fn test_evaluate_rules() {
+        let rules = vec![
+            PolicyRule {
+                detector_id: Some("hap".into()),
+                min_score: Some(0.8),
+                ..rule("block-hap", PolicyOutcome::Block)
+            },
+            PolicyRule {
+                applies_to: Some(PolicyDirection::Output),
+                detection: Some("email".into()),
+                ..rule("redact-email", PolicyOutcome::Redact)
+            },
+            PolicyRule {
+                min_count: 2,
+                ..rule("warn-many", PolicyOutcome::Warn)
+            },
+            rule("allow-rest", PolicyOutcome::Allow),
+        ];
+        // Score range
+        let decision = evaluate(
+            &rules,
+            PolicyDirection::Input,
+            &[detection("hap", "hap", 0.9)],
+        );
+        assert_eq!(
+            decision.policy(),
+            Some(PolicyMatch {
+                rule: "block-hap".into(),
+                outcome: PolicyOutcome::Block
+            })
+        );
+        // Direction
+        let detections = vec![detection("pii", "email", 0.9)];
+        let decision = evaluate(&rules, PolicyDirection::Output, &detections);
+        assert_eq!(decision.rule.unwrap().name, "redact-email");
+        let decision = evaluate(&rules, PolicyDirection::Input, &detections);
+        assert_eq!(decision.rule.unwrap().name, "allow-rest");
+        // Count
+        let detections = vec![detection("hap", "hap", 0.5), detection("pii", "ssn", 0.9)];
+        let decision = evaluate(&rules, PolicyDirection::Input, &detections);
+        assert_eq!(decision.rule.unwrap().name, "warn-many");
+    }
+
+    #[test]
+    fn test_redact() {
+        let decision = PolicyDecision {
+            outcome: PolicyOutcome::Redact,
+            rule: Some(PolicyRule {
+                detection: Some("email".into()),
+                ..rule("redact-email", PolicyOutcome::Redact)
+            }),
+        };
+        let text = "Mail jane@example.com, call 555-0100";
+        let mut detections = vec![
+            Detection {
+                start: Some(5),
+                end: Some(21),
+                ..detection("pii", "email", 0.9)
+            },
+            Detection {
+                start: Some(28),
+                end: Some(36),
+                ..detection("pii", "phone", 0.9)
+            },
+        ];
+        let redactions = Redactions::new();
+        assert!(decision.should_redact(&redactions));
+        let redacted = decision.redact(text, &mut detections, &redactions);
+        assert_eq!(redacted, "Mail [EMAIL], call 555-0100");
+    }
+
+    #[test]
+    fn test_redact_no_detector_id() {
+        let decision = PolicyDecision {
+            outcome: PolicyOutcome::Redact,
+            rule: Some(PolicyRule {
+                detection: Some("email".into()),
+                ..rule("redact-email", PolicyOutcome::Redact)
+            }),
+        };
+        let text = "Mail jane@example.com";
+        let mut detections = vec![Detection {
+            start: Some(5),
+            end: Some(21),
+            detector_id: None,
+            ..detection("pii", "email", 0.9)
+        }];
+        let redacted = decision.redact(text, &mut detections, &Redactions::new());
+        assert_eq!(redacted, "Mail [EMAIL]");
+    }
+}
@@ -0,0 +1,259 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+//! Output redaction
+use std::{collections::HashMap, sync::Arc};
+
+use super::slice_codepoints;
+use crate::{
+    config::DetectorAction,
+    models::DetectorParams,
+    orchestrator::{
+        Context,
+        types::{Detection, DetectorId},
+    },
+};
+
+/// A map of detectors with the `redact` action to their placeholder, if set.
+pub type Redactions = HashMap<DetectorId, Option<String>>;
+
+/// Returns detectors with the `redact` action.
+/// Action params take precedence over the detector config.
+pub fn get_redactions(
+    ctx: &Arc<Context>,
+    detectors: &HashMap<String, DetectorParams>,
+) -> Redactions {
+    detectors
+        .iter()
+        .filter_map(|(detector_id, params)| {
+            let config = ctx.config.detector(detector_id)?;
+            let mut params = params.clone();
+            let action = params.pop_action().unwrap_or(config.action);
+            (action == DetectorAction::Redact).then(|| {
+                let placeholder = params
+                    .pop_redaction_placeholder()
+                    .or_else(|| config.redaction_placeholder.clone());
+                (detector_id.clone(), placeholder)
+            })
+        })
+        .collect()
+}
+
+/// Replaces spans of detections from redacting detectors with placeholders,
+/// returning the redacted text.
+///
+/// Detection offsets are codepoint indices into `text`. Overlapping spans are
+/// merged into a single placeholder. Spans and text of all detections are
+/// updated to refer to the redacted text.
+pub fn redact(text: &str, detections: &mut [Detection], redactions: &Redactions) -> String {
+    redact_matching(text, detections, |detection| {
+        redactions.get(detection.detector_id.as_ref()?).cloned()
+    })
+}
+
+/// Replaces spans of detections for which `placeholder` returns `Some` with placeholders,
+/// returning the redacted text. See [`redact`].
+pub fn redact_matching(
+    text: &str,
+    detections: &mut [Detection],
+    placeholder: impl Fn(&Detection) -> Option<Option<String>>,
+) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    // Collect spans to redact
+    let mut spans = detections
+        .iter()
+        .filter_map(|detection| {
+            // Detections without a span are not redacted
+            let (start, end) = detection.span()?;
+            let placeholder = placeholder(detection)?;
+            let end = end.min(chars.len());
+            let start = start.min(end);
+            (start < end).then(|| {
+                let placeholder = placeholder
+                    .unwrap_or_else(|| format!("[{}]", detection.detection.to_uppercase()));
+                (start, end, placeholder)
+            })
+        })
+        .collect::<Vec<_>>();
+    if spans.is_empty() {
+        return text.to_string();
+    }
+    // Merge overlapping spans, keeping the placeholder of the earliest (longest) span
+    spans.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    let mut merged: Vec<(usize, usize, String)> = Vec::with_capacity(spans.len());
+    for (start, end, placeholder) in spans {
+        match merged.last_mut() {
+            Some(last) if start < last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end, placeholder)),
+        }
+    }
+    // Build redacted text, tracking (start, end, redacted_start, redacted_end) of each span
+    let mut redacted = String::with_capacity(text.len());
+    let mut spans = Vec::with_capacity(merged.len());
+    let mut cursor = 0;
+    let mut len = 0;
+    for (start, end, placeholder) in merged {
+        redacted.extend(&chars[cursor..start]);
+        len += start - cursor;
+        let redacted_start = len;
+        redacted.push_str(&placeholder);
+        len += placeholder.chars().count();
+        spans.push((start, end, redacted_start, len));
+        cursor = end;
+    }
+    redacted.extend(&chars[cursor..]);
+    // Update detections to refer to the redacted text
+    let map_index = |index: usize, is_end: bool| {
+        let mut shift = 0isize;
+        for &(start, end, redacted_start, redacted_end) in &spans {
+            if index <= start {
+                break;
+            }
+            if index < end {
+                // Index falls within a redacted span
+                return if is_end { redacted_end } else { redacted_start };
+            }
+            shift += (redacted_end - redacted_start) as isize - (end - start) as isize;
+        }
+        index.saturating_add_signed(shift)
+    };
+    for detection in detections.iter_mut() {
+        if let Some((start, end)) = detection.span() {
+            let start = map_index(start, false);
+            let end = map_index(end, true).max(start);
+            detection.start = Some(start);
+            detection.end = Some(end);
+            if detection.text.is_some() {
+                detection.text = Some(slice_codepoints(&redacted, start, end));
+            }
+        }
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detection(detector_id: &str, detection: &str, start: usize, end: usize) -> Detection {
+        Detection {
+            start: Some(start),
+            end: Some(end),
+            text: Some(String::new()),
+            detector_id: Some(detector_id.into()),
+            detection: detection.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_redact() {
+        let text = "Email jane@example.com or call 555-0100 now.";
+        let mut detections = vec![
+            detection("pii", "email", 6, 22),
+            detection("pii", "phone", 31, 39),
+        ];
+        let redactions = Redactions::from([("pii".into(), None)]);
+        let redacted = redact(text, &mut detections, &redactions);
+        assert_eq!(redacted, "Email [EMAIL] or call [PHONE] now.");
+        assert_eq!(
+            (detections[0].start, detections[0].end),
+            (Some(6), Some(13))
+        );
+        assert_eq!(detections[0].text.as_deref(), Some("[EMAIL]"));
+        assert_eq!(
+            (detections[1].start, detections[1].end),
+            (Some(22), Some(29))
+        );
+        assert_eq!(detections[1].text.as_deref(), Some("[PHONE]"));
+    }
+
+    #[test]
+    fn test_redact_overlapping() {
+        let text = "Contact Jane Doe at home.";
+        let mut detections = vec![
+            detection("names", "person", 8, 16),
+            detection("pii", "first_name", 8, 12),
+            detection("hap", "hap", 13, 24),
+        ];
+        let redactions = Redactions::from([
+            ("names".into(), Some("<NAME>".into())),
+            ("pii".into(), None),
+        ]);
+        let redacted = redact(text, &mut detections, &redactions);
+        assert_eq!(redacted, "Contact <NAME> at home.");
+        // Redacted detections refer to the merged placeholder
+        assert_eq!(
+            (detections[0].start, detections[0].end),
+            (Some(8), Some(14))
+        );
+        assert_eq!(
+            (detections[1].start, detections[1].end),
+            (Some(8), Some(14))
+        );
+        // Flagged detection starting within a redacted span is clamped to it
+        assert_eq!(
+            (detections[2].start, detections[2].end),
+            (Some(8), Some(22))
+        );
+        assert_eq!(detections[2].text.as_deref(), Some("<NAME> at home"));
+    }
+
+    #[test]
+    fn test_redact_codepoints() {
+        let text = "哈囉 jane@example.com 世界";
+        let mut detections = vec![detection("pii", "email", 3, 19)];
+        let redactions = Redactions::from([("pii".into(), Some("[EMAIL]".into()))]);
+        let redacted = redact(text, &mut detections, &redactions);
+        assert_eq!(redacted, "哈囉 [EMAIL] 世界");
+        assert_eq!(
+            (detections[0].start, detections[0].end),
+            (Some(3), Some(10))
+        );
+    }
+
+    #[test]
+    fn test_redact_no_redactions() {
+        let text = "Email jane@example.com";
+        let mut detections = vec![detection("pii", "email", 6, 22)];
+        let redacted = redact(text, &mut detections, &Redactions::new());
+        assert_eq!(redacted, text);
+        assert_eq!(
+            (detections[0].start, detections[0].end),
+            (Some(6), Some(22))
+        );
+    }
+
+    #[test]
+    fn test_redact_no_span() {
+        let text = "Email jane@example.com";
+        // Detections on whole chat histories have no span
+        let mut detections = vec![
+            Detection {
+                detector_id: Some("pii".into()),
+                detection: "email".into(),
+                ..Default::default()
+            },
+            detection("pii", "email", 6, 22),
+        ];
+        let redactions = Redactions::from([("pii".into(), None)]);
+        let redacted = redact(text, &mut detections, &redactions);
+        assert_eq!(redacted, "Email [EMAIL]");
+        assert_eq!(detections[0].span(), None);
+        assert_eq!(detections[0].text, None);
+        assert_eq!(detections[1].span(), Some((6, 13)));
+    }
+}
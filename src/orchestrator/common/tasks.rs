@@ -21,7 +21,7 @@ use futures::{StreamExt, TryStreamExt, future::try_join_all, stream};
 use http::HeaderMap;
 use tokio::sync::{broadcast, mpsc};
 use tokio_stream::wrappers::ReceiverStream;
-use tracing::{Instrument, debug, instrument};
+use tracing::{Instrument, debug, instrument, warn};
 
 use super::{client::*, utils::*};
 use crate::{
@@ -34,8 +34,10 @@ use crate::{
         },
         openai,
     },
+    config::DetectorOnError,
     models::DetectorParams,
     orchestrator::{Context, Error, types::*},
+    utils::{deadline::Deadline, task_group},
 };
 
 /// Spawns chunk tasks. Returns a map of chunks.
@@ -43,6 +45,7 @@ pub async fn chunks(
     ctx: Arc<Context>,
     chunkers: Vec<ChunkerId>,
     inputs: Vec<(usize, String)>, // (offset, text)
+    deadline: Option<Deadline>,
 ) -> Result<HashMap<ChunkerId, Chunks>, Error> {
     if inputs.is_empty() {
         return Ok(HashMap::default());
@@ -54,7 +57,7 @@ pub async fn chunks(
             let inputs = inputs.clone();
             // Spawn task for chunker
             // Chunkers are processed in-parallel
-            tokio::spawn(
+            task_group::spawn(
                 async move {
                     // Send concurrent requests for inputs
                     let chunks = stream::iter(inputs)
@@ -71,7 +74,7 @@ pub async fn chunks(
                                     .clients
                                     .get_as::<ChunkerClient>(&chunker_id)
                                     .ok_or_else(|| Error::ChunkerNotFound(chunker_id.clone()))?;
-                                let chunks = chunk(client, chunker_id.clone(), text)
+                                let chunks = chunk(client, chunker_id.clone(), text, deadline)
                                     .await?
                                     .into_iter()
                                     .map(|mut chunk| {
@@ -109,6 +112,7 @@ pub async fn chunk_streams(
     ctx: Arc<Context>,
     chunkers: Vec<ChunkerId>,
     input_rx: mpsc::Receiver<Result<(usize, String), Error>>, // (message_index, text)
+    deadline: Option<Deadline>,
 ) -> Result<HashMap<ChunkerId, broadcast::Sender<Result<Chunk, Error>>>, Error> {
     // Create input broadcast channel
     let input_stream = ReceiverStream::new(input_rx).boxed();
@@ -129,7 +133,7 @@ pub async fn chunk_streams(
                 .clients
                 .get_as::<ChunkerClient>(&chunker_id)
                 .ok_or_else(|| Error::ChunkerNotFound(chunker_id.clone()))?;
-            chunk_stream(client, chunker_id.clone(), input_broadcast_rx).await
+            chunk_stream(client, chunker_id.clone(), input_broadcast_rx, deadline).await
         }?;
         // Create chunk broadcast channel
         let chunk_broadcast_tx = broadcast_stream(chunk_stream);
@@ -156,7 +160,7 @@ fn whole_doc_chunk_stream(
     // Create output channel
     let (output_tx, output_rx) = mpsc::channel(1);
     // Spawn task to collect input channel
-    tokio::spawn(
+    task_group::spawn(
         async move {
             // Collect input channel
             // Alternatively, wrap receiver in BroadcastStream and collect() via StreamExt
@@ -194,7 +198,8 @@ pub async fn text_contents_detections(
     inputs: Vec<(usize, String)>,
 ) -> Result<(u32, Detections), Error> {
     let chunkers = get_chunker_ids(&ctx, &detectors)?;
-    let chunk_map = chunks(ctx.clone(), chunkers, inputs).await?;
+    let deadline = Deadline::from_headers(&headers);
+    let chunk_map = chunks(ctx.clone(), chunkers, inputs, deadline).await?;
     let inputs = detectors
         .iter()
         .map(|(detector_id, params)| {
@@ -213,11 +218,14 @@ pub async fn text_contents_detections(
             let headers = headers.clone();
             let default_threshold = ctx.config.detector(&detector_id).unwrap().default_threshold;
             let threshold = params.pop_threshold().unwrap_or(default_threshold);
+            let on_error = resolve_on_error(&ctx, &detector_id, &mut params);
+            pop_action_params(&mut params);
             async move {
                 let client = ctx
                     .clients
                     .get_as::<TextContentsDetectorClient>(&detector_id)
                     .unwrap();
+                let cache = ctx.detector_cache(&detector_id);
                 let detections = detect_text_contents(
                     client,
                     headers,
@@ -225,19 +233,18 @@ pub async fn text_contents_detections(
                     params,
                     chunks.clone(),
                     true,
+                    cache.as_deref(),
                 )
-                .await?
-                .into_iter()
-                .filter(|detection| detection.score >= threshold)
-                .collect::<Detections>();
-                Ok::<_, Error>(detections)
+                .await
+                .map(|detections| detections.filter(|detection| detection.score >= threshold));
+                skip_on_error(on_error, &detector_id, detections)
             }
             .in_current_span()
         })
         .buffer_unordered(ctx.config.detector_concurrent_requests)
         .try_collect::<Vec<_>>()
         .await?;
-    let mut detections = results.into_iter().flatten().collect::<Detections>();
+    let mut detections = results.into_iter().collect::<Detections>();
     detections.sort_by_key(|detection| detection.start);
     Ok((input_id, detections))
 }
@@ -254,7 +261,8 @@ pub async fn text_contents_detection_streams(
 ) -> Result<Vec<DetectionStream>, Error> {
     // Create chunk streams
     let chunkers = get_chunker_ids(&ctx, &detectors)?;
-    let chunk_stream_map = chunk_streams(ctx.clone(), chunkers, input_rx).await?;
+    let deadline = Deadline::from_headers(&headers);
+    let chunk_stream_map = chunk_streams(ctx.clone(), chunkers, input_rx, deadline).await?;
     // Create detection streams
     let mut streams = Vec::with_capacity(detectors.len());
     for (detector_id, mut params) in detectors {
@@ -262,13 +270,16 @@ pub async fn text_contents_detection_streams(
         let headers = headers.clone();
         let default_threshold = ctx.config.detector(&detector_id).unwrap().default_threshold;
         let threshold = params.pop_threshold().unwrap_or(default_threshold);
+        let on_error = resolve_on_error(&ctx, &detector_id, &mut params);
+        pop_action_params(&mut params);
         let chunker_id = ctx.config.get_chunker_id(&detector_id).unwrap();
+        let cache = ctx.detector_cache(&detector_id);
         // Subscribe to chunk broadcast channel
         let mut chunk_rx = chunk_stream_map.get(&chunker_id).unwrap().subscribe();
         // Create detection channel
         let (detection_tx, detection_rx) = mpsc::channel(128);
         // Spawn detection task
-        tokio::spawn(
+        task_group::spawn(
             async move {
                 while let Ok(result) = chunk_rx.recv().await {
                     match result {
@@ -284,6 +295,7 @@ pub async fn text_contents_detection_streams(
                                 params.clone(),
                                 vec![chunk.clone()].into(),
                                 false,
+                                cache.as_deref(),
                             )
                             .await
                             {
@@ -297,6 +309,13 @@ pub async fn text_contents_detection_streams(
                                     let _ =
                                         detection_tx.send(Ok((input_id, chunk, detections))).await;
                                 }
+                                Err(error) if is_skippable(on_error, &error) => {
+                                    // Send skipped detections so the chunk can still be batched
+                                    warn!(%detector_id, %error, "optional detector failed, skipping");
+                                    let detections = Detections::skipped(&detector_id, error);
+                                    let _ =
+                                        detection_tx.send(Ok((input_id, chunk, detections))).await;
+                                }
                                 Err(error) => {
                                     // Send error to detection channel
                                     let _ = detection_tx.send(Err(error)).await;
@@ -346,11 +365,14 @@ pub async fn text_generation_detections(
             let headers = headers.clone();
             let default_threshold = ctx.config.detector(&detector_id).unwrap().default_threshold;
             let threshold = params.pop_threshold().unwrap_or(default_threshold);
+            let on_error = resolve_on_error(&ctx, &detector_id, &mut params);
+            pop_action_params(&mut params);
             async move {
                 let client = ctx
                     .clients
                     .get_as::<TextGenerationDetectorClient>(&detector_id)
                     .unwrap();
+                let cache = ctx.detector_cache(&detector_id);
                 let detections = detect_text_generation(
                     client,
                     headers,
@@ -358,19 +380,18 @@ pub async fn text_generation_detections(
                     params,
                     prompt,
                     generated_text,
+                    cache.as_deref(),
                 )
-                .await?
-                .into_iter()
-                .filter(|detection| detection.score >= threshold)
-                .collect::<Detections>();
-                Ok::<_, Error>(detections)
+                .await
+                .map(|detections| detections.filter(|detection| detection.score >= threshold));
+                skip_on_error(on_error, &detector_id, detections)
             }
             .in_current_span()
         })
         .buffer_unordered(ctx.config.detector_concurrent_requests)
         .try_collect::<Vec<_>>()
         .await?;
-    let detections = results.into_iter().flatten().collect::<Detections>();
+    let detections = results.into_iter().collect::<Detections>();
     Ok(detections)
 }
 
@@ -402,11 +423,14 @@ pub async fn text_chat_detections(
             let headers = headers.clone();
             let default_threshold = ctx.config.detector(&detector_id).unwrap().default_threshold;
             let threshold = params.pop_threshold().unwrap_or(default_threshold);
+            let on_error = resolve_on_error(&ctx, &detector_id, &mut params);
+            pop_action_params(&mut params);
             async move {
                 let client = ctx
                     .clients
                     .get_as::<TextChatDetectorClient>(&detector_id)
                     .unwrap();
+                let cache = ctx.detector_cache(&detector_id);
                 let detections = detect_text_chat(
                     client,
                     headers,
@@ -414,19 +438,18 @@ pub async fn text_chat_detections(
                     params,
                     messages,
                     tools,
+                    cache.as_deref(),
                 )
-                .await?
-                .into_iter()
-                .filter(|detection| detection.score >= threshold)
-                .collect::<Detections>();
-                Ok::<_, Error>(detections)
+                .await
+                .map(|detections| detections.filter(|detection| detection.score >= threshold));
+                skip_on_error(on_error, &detector_id, detections)
             }
             .in_current_span()
         })
         .buffer_unordered(ctx.config.detector_concurrent_requests)
         .try_collect::<Vec<_>>()
         .await?;
-    let detections = results.into_iter().flatten().collect::<Detections>();
+    let detections = results.into_iter().collect::<Detections>();
     Ok(detections)
 }
 
@@ -462,11 +485,14 @@ pub async fn text_context_detections(
                 let default_threshold =
                     ctx.config.detector(&detector_id).unwrap().default_threshold;
                 let threshold = params.pop_threshold().unwrap_or(default_threshold);
+                let on_error = resolve_on_error(&ctx, &detector_id, &mut params);
+                pop_action_params(&mut params);
                 async move {
                     let client = ctx
                         .clients
                         .get_as::<TextContextDocDetectorClient>(&detector_id)
                         .unwrap();
+                    let cache = ctx.detector_cache(&detector_id);
                     let detections = detect_text_context(
                         client,
                         headers,
@@ -475,12 +501,11 @@ pub async fn text_context_detections(
                         content,
                         context_type,
                         context,
+                        cache.as_deref(),
                     )
-                    .await?
-                    .into_iter()
-                    .filter(|detection| detection.score >= threshold)
-                    .collect::<Detections>();
-                    Ok::<_, Error>(detections)
+                    .await
+                    .map(|detections| detections.filter(|detection| detection.score >= threshold));
+                    skip_on_error(on_error, &detector_id, detections)
                 }
                 .in_current_span()
             },
@@ -488,17 +513,64 @@ pub async fn text_context_detections(
         .buffer_unordered(ctx.config.detector_concurrent_requests)
         .try_collect::<Vec<_>>()
         .await?;
-    let detections = results.into_iter().flatten().collect::<Detections>();
+    let detections = results.into_iter().collect::<Detections>();
     Ok(detections)
 }
 
+/// Removes action params, which are applied by the orchestrator and not sent to detectors.
+fn pop_action_params(params: &mut DetectorParams) {
+    params.pop_action();
+    params.pop_redaction_placeholder();
+}
+
+/// Resolves the behavior when a detector fails,
+/// the request param overrides the detector config.
+fn resolve_on_error(
+    ctx: &Context,
+    detector_id: &str,
+    params: &mut DetectorParams,
+) -> DetectorOnError {
+    let default_on_error = ctx
+        .config
+        .detector(detector_id)
+        .map(|config| config.on_error)
+        .unwrap_or_default();
+    params.pop_on_error().unwrap_or(default_on_error)
+}
+
+/// Returns skipped detections in place of an error for optional detectors.
+fn skip_on_error(
+    on_error: DetectorOnError,
+    detector_id: &str,
+    result: Result<Detections, Error>,
+) -> Result<Detections, Error> {
+    match result {
+        Err(error) if is_skippable(on_error, &error) => {
+            warn!(%detector_id, %error, "optional detector failed, skipping");
+            Ok(Detections::skipped(detector_id, error))
+        }
+        result => result,
+    }
+}
+
+/// Returns `true` if an error of an optional detector can be skipped.
+/// Only detector request failures are skipped, errors failing the whole request,
+/// e.g. an exceeded deadline or cancellation, are propagated.
+fn is_skippable(on_error: DetectorOnError, error: &Error) -> bool {
+    on_error == DetectorOnError::Skip
+        && matches!(
+            error,
+            Error::DetectorRequestFailed { .. } | Error::Client(_)
+        )
+}
+
 /// Fans-out a stream to a broadcast channel.
 pub fn broadcast_stream<T>(mut stream: BoxStream<T>) -> broadcast::Sender<T>
 where
     T: Clone + Send + 'static,
 {
     let (broadcast_tx, _) = broadcast::channel(128);
-    tokio::spawn({
+    task_group::spawn({
         let broadcast_tx = broadcast_tx.clone();
         async move {
             while let Some(msg) = stream.next().await {
@@ -511,6 +583,7 @@ where
 
 #[cfg(test)]
 mod test {
+    use std::time::Duration;
 
     use mocktail::prelude::*;
     use tokio::sync::OnceCell;
@@ -520,7 +593,7 @@ mod test {
         clients::detector::{ContentAnalysisRequest, ContentAnalysisResponse},
         config::OrchestratorConfig,
         models::Metadata,
-        orchestrator::create_clients,
+        orchestrator::{Stage, create_clients},
         pb::{
             caikit::runtime::chunkers::{
                 BidiStreamingChunkerTokenizationTaskRequest, ChunkerTokenizationTaskRequest,
@@ -715,6 +788,35 @@ mod test {
         Arc::new(Context::new(config, clients))
     }
 
+    #[test]
+    fn test_skip_on_error() {
+        let detector_error = Error::DetectorRequestFailed {
+            id: "hap".into(),
+            error: crate::clients::Error::Http {
+                code: http::StatusCode::INTERNAL_SERVER_ERROR,
+                message: "internal server error".into(),
+            },
+        };
+        // Detector request failures of optional detectors are skipped
+        let detections =
+            skip_on_error(DetectorOnError::Skip, "hap", Err(detector_error.clone())).unwrap();
+        assert!(detections.is_empty());
+        assert_eq!(detections.skipped_detectors().len(), 1);
+        assert!(skip_on_error(DetectorOnError::Fail, "hap", Err(detector_error)).is_err());
+        // Deadline and cancellation errors are propagated
+        let deadline_error = Error::DeadlineExceeded {
+            stage: Stage::Detection,
+        };
+        assert_eq!(
+            skip_on_error(DetectorOnError::Skip, "hap", Err(deadline_error.clone())).unwrap_err(),
+            deadline_error
+        );
+        assert_eq!(
+            skip_on_error(DetectorOnError::Skip, "hap", Err(Error::Cancelled)).unwrap_err(),
+            Error::Cancelled
+        );
+    }
+
     #[test_log::test(tokio::test)]
     async fn tests() -> Result<(), Error> {
         test_chunks().await?;
@@ -733,6 +835,7 @@ mod test {
             ctx.clone(),
             vec!["sentence_chunker".into(), "whole_doc_chunker".into()],
             vec![(0, TEXT1.to_string())],
+            None,
         )
         .await?;
 
@@ -755,6 +858,7 @@ mod test {
             ctx.clone(),
             vec!["sentence_chunker".into()],
             vec![(0, TEXT1.to_string()), (0, TEXT2.to_string())],
+            None,
         )
         .await?;
         assert_eq!(chunk_map.len(), 1, "chunk map length should be 1");
@@ -770,6 +874,7 @@ mod test {
             ctx.clone(),
             vec!["does_not_exist".into()],
             vec![(0, TEXT1.to_string())],
+            None,
         )
         .await;
         assert!(
@@ -782,6 +887,7 @@ mod test {
             ctx.clone(),
             vec!["error_chunker".into()],
             vec![(0, TEXT1.to_string())],
+            None,
         )
         .await;
         assert!(
@@ -796,8 +902,26 @@ mod test {
             "should return chunker request failed error"
         );
 
+        // Deadline exceeded
+        let result = chunks(
+            ctx.clone(),
+            vec!["sentence_chunker".into()],
+            vec![(0, TEXT1.to_string())],
+            Some(Deadline::after(Duration::ZERO)),
+        )
+        .await;
+        assert!(
+            result.is_err_and(|e| matches!(
+                e,
+                Error::DeadlineExceeded {
+                    stage: Stage::Chunking
+                }
+            )),
+            "should return deadline exceeded error"
+        );
+
         // Empty inputs
-        let chunk_map = chunks(ctx.clone(), vec!["sentence_chunker".into()], vec![]).await?;
+        let chunk_map = chunks(ctx.clone(), vec!["sentence_chunker".into()], vec![], None).await?;
         assert!(chunk_map.is_empty(), "chunk map should be empty");
 
         // With mask offsets
@@ -805,6 +929,7 @@ mod test {
             ctx.clone(),
             vec!["sentence_chunker".into()],
             vec![(5, TEXT1.to_string())],
+            None,
         )
         .await?;
         assert_eq!(chunk_map.len(), 1, "chunk map length should be 1");
@@ -869,6 +994,42 @@ mod test {
             "should return detector not found error"
         );
 
+        // Detector error, unmatched params are not mocked
+        let mut detector_params = DetectorParams::new();
+        detector_params.insert("unmatched".to_string(), true.into());
+        let detectors = HashMap::from([("fake_detector".to_string(), detector_params.clone())]);
+        let result = text_contents_detections(
+            ctx.clone(),
+            HeaderMap::default(),
+            detectors,
+            0,
+            vec![(0, TEXT1.to_string())],
+        )
+        .await;
+        assert!(result.is_err(), "should return detector error");
+
+        // Detector error, skipped with on_error override
+        detector_params.insert("on_error".to_string(), "skip".into());
+        let detectors = HashMap::from([("fake_detector".to_string(), detector_params)]);
+        let (_, detections) = text_contents_detections(
+            ctx.clone(),
+            HeaderMap::default(),
+            detectors,
+            0,
+            vec![(0, TEXT1.to_string())],
+        )
+        .await?;
+        assert!(detections.is_empty(), "should have no detections");
+        assert_eq!(
+            detections
+                .skipped_detectors()
+                .iter()
+                .map(|skipped| skipped.detector_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["fake_detector"],
+            "should have skipped fake_detector"
+        );
+
         // TODO: add more cases
 
         Ok(())
@@ -882,7 +1043,7 @@ mod test {
         drop(broadcast_tx);
 
         // Spawn task to send values
-        tokio::spawn(async move {
+        task_group::spawn(async move {
             for value in 0..10 {
                 let _ = tx.send(value).await;
             }
@@ -911,7 +1072,7 @@ mod test {
         ];
 
         let chunk_stream_map =
-            chunk_streams(ctx.clone(), vec!["sentence_chunker".into()], input_rx).await?;
+            chunk_streams(ctx.clone(), vec!["sentence_chunker".into()], input_rx, None).await?;
 
         let mut chunk_broadcast_rx = chunk_stream_map
             .get("sentence_chunker")
@@ -920,7 +1081,7 @@ mod test {
         drop(chunk_stream_map);
 
         // Spawn task to send inputs to input channel
-        tokio::spawn(async move {
+        task_group::spawn(async move {
             for input in inputs {
                 let _ = input_tx.send(Ok(input)).await;
             }
@@ -971,7 +1132,7 @@ mod test {
         .await?;
 
         // Spawn task to send inputs to input channel
-        tokio::spawn(async move {
+        task_group::spawn(async move {
             for input in inputs {
                 let _ = input_tx.send(Ok(input)).await;
             }
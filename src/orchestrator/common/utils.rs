@@ -22,7 +22,7 @@ use crate::{
     clients::chunker::DEFAULT_CHUNKER_ID,
     config::{DetectorConfig, DetectorType},
     models::DetectorParams,
-    orchestrator::{Context, Error},
+    orchestrator::{Context, Error, types::Tenant},
 };
 
 /// Slices chars between start and end indices.
@@ -116,26 +116,93 @@ pub fn configure_mock_servers(
     };
 }
 
+/// Detectors accepted by a request field of an endpoint.
+///
+/// Shared by [`validate_detectors`] and the detector discovery endpoint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AcceptedDetectors {
+    /// Accepted detector types
+    pub detector_types: &'static [DetectorType],
+    /// Whether detectors using `whole_doc_chunker` are accepted
+    pub allows_whole_doc_chunker: bool,
+}
+
+impl AcceptedDetectors {
+    /// Returns `true` if a detector is accepted.
+    pub fn accepts(&self, detector: &DetectorConfig) -> bool {
+        self.detector_types.contains(&detector.r#type)
+            && (self.allows_whole_doc_chunker || detector.chunker_id != DEFAULT_CHUNKER_ID)
+    }
+}
+
+/// `text_contents` detectors.
+pub const TEXT_CONTENTS_DETECTORS: AcceptedDetectors = AcceptedDetectors {
+    detector_types: &[DetectorType::TextContents],
+    allows_whole_doc_chunker: true,
+};
+
+/// `text_contents` detectors applied to streams, which can't use `whole_doc_chunker`.
+pub const STREAMING_TEXT_CONTENTS_DETECTORS: AcceptedDetectors = AcceptedDetectors {
+    detector_types: &[DetectorType::TextContents],
+    allows_whole_doc_chunker: false,
+};
+
+/// `text_generation` detectors.
+pub const TEXT_GENERATION_DETECTORS: AcceptedDetectors = AcceptedDetectors {
+    detector_types: &[DetectorType::TextGeneration],
+    allows_whole_doc_chunker: true,
+};
+
+/// `text_chat` detectors.
+pub const TEXT_CHAT_DETECTORS: AcceptedDetectors = AcceptedDetectors {
+    detector_types: &[DetectorType::TextChat],
+    allows_whole_doc_chunker: true,
+};
+
+/// `text_context_doc` detectors.
+pub const TEXT_CONTEXT_DOC_DETECTORS: AcceptedDetectors = AcceptedDetectors {
+    detector_types: &[DetectorType::TextContextDoc],
+    allows_whole_doc_chunker: true,
+};
+
+/// Input detectors of chat completions.
+pub const CHAT_COMPLETIONS_INPUT_DETECTORS: AcceptedDetectors = AcceptedDetectors {
+    detector_types: &[DetectorType::TextContents, DetectorType::TextChat],
+    allows_whole_doc_chunker: true,
+};
+
+/// Output detectors of chat completions.
+pub const CHAT_COMPLETIONS_OUTPUT_DETECTORS: AcceptedDetectors = AcceptedDetectors {
+    detector_types: &[
+        DetectorType::TextContents,
+        DetectorType::TextChat,
+        DetectorType::TextGeneration,
+        DetectorType::TextContextDoc,
+    ],
+    allows_whole_doc_chunker: true,
+};
+
 /// Validates guardrails on request.
 pub fn validate_detectors(
     detectors: &HashMap<String, DetectorParams>,
     orchestrator_detectors: &HashMap<String, DetectorConfig>,
-    allowed_detector_types: &[DetectorType],
-    allows_whole_doc_chunker: bool,
+    accepted: AcceptedDetectors,
 ) -> Result<(), Error> {
     let whole_doc_chunker_id = DEFAULT_CHUNKER_ID;
     for detector_id in detectors.keys() {
         // validate detectors
         match orchestrator_detectors.get(detector_id) {
             Some(detector_config) => {
-                if !allowed_detector_types.contains(&detector_config.r#type) {
+                if !accepted.detector_types.contains(&detector_config.r#type) {
                     let error = Error::Validation(format!(
                         "detector `{detector_id}` is not supported by this endpoint"
                     ));
                     error!("{error}");
                     return Err(error);
                 }
-                if !allows_whole_doc_chunker && detector_config.chunker_id == whole_doc_chunker_id {
+                if !accepted.allows_whole_doc_chunker
+                    && detector_config.chunker_id == whole_doc_chunker_id
+                {
                     let error = Error::Validation(format!(
                         "detector `{detector_id}` uses chunker `whole_doc_chunker`, which is not supported by this endpoint"
                     ));
@@ -153,6 +220,39 @@ pub fn validate_detectors(
     Ok(())
 }
 
+/// Splits detectors into detectors of `detector_type` and other detectors.
+pub fn split_detectors(
+    detectors: HashMap<String, DetectorParams>,
+    orchestrator_detectors: &HashMap<String, DetectorConfig>,
+    detector_type: DetectorType,
+) -> (
+    HashMap<String, DetectorParams>,
+    HashMap<String, DetectorParams>,
+) {
+    detectors.into_iter().partition(|(detector_id, _)| {
+        orchestrator_detectors
+            .get(detector_id)
+            .is_some_and(|detector| detector.r#type == detector_type)
+    })
+}
+
+/// Validates the tenant, if any, is allowed to use detectors and generation model.
+pub fn validate_tenant(
+    tenant: Option<&Tenant>,
+    detectors: &[&HashMap<String, DetectorParams>],
+    model_id: Option<&str>,
+) -> Result<(), Error> {
+    if let Some(tenant) = tenant {
+        for detectors in detectors {
+            tenant.validate_detectors(detectors)?;
+        }
+        if let Some(model_id) = model_id {
+            tenant.validate_model(model_id)?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -176,4 +276,42 @@ mod tests {
         let s = "哈囉世界";
         assert_eq!(slice_codepoints(s, 3, 4), "界");
     }
+
+    #[test]
+    fn test_accepted_detectors() {
+        let accepted = [
+            TEXT_CONTENTS_DETECTORS,
+            STREAMING_TEXT_CONTENTS_DETECTORS,
+            TEXT_GENERATION_DETECTORS,
+            TEXT_CHAT_DETECTORS,
+            TEXT_CONTEXT_DOC_DETECTORS,
+            CHAT_COMPLETIONS_INPUT_DETECTORS,
+            CHAT_COMPLETIONS_OUTPUT_DETECTORS,
+        ];
+        let detector_types = [
+            DetectorType::TextContents,
+            DetectorType::TextGeneration,
+            DetectorType::TextChat,
+            DetectorType::TextContextDoc,
+        ];
+        // Detectors listed by discovery are exactly those accepted by validation
+        for accepted in accepted {
+            for detector_type in &detector_types {
+                for chunker_id in [DEFAULT_CHUNKER_ID, "sentence_chunker"] {
+                    let detector = DetectorConfig {
+                        r#type: detector_type.clone(),
+                        chunker_id: chunker_id.into(),
+                        ..Default::default()
+                    };
+                    let orchestrator_detectors = HashMap::from([("detector".into(), detector)]);
+                    let detectors = HashMap::from([("detector".into(), DetectorParams::new())]);
+                    assert_eq!(
+                        validate_detectors(&detectors, &orchestrator_detectors, accepted).is_ok(),
+                        accepted.accepts(&orchestrator_detectors["detector"]),
+                        "{accepted:?} disagrees on {detector_type:?} detector using {chunker_id}"
+                    );
+                }
+            }
+        }
+    }
 }
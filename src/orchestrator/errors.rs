@@ -39,12 +39,57 @@ pub enum Error {
     TokenizeRequestFailed { id: String, error: clients::Error },
     #[error("validation error: {0}")]
     Validation(String),
+    #[error("forbidden: {0}")]
+    Forbidden(String),
     #[error("{0}")]
     Other(String),
     #[error("cancelled")]
     Cancelled,
     #[error("json deserialization error: {0}")]
     JsonError(String),
+    #[error("request deadline exceeded during {stage}")]
+    DeadlineExceeded { stage: Stage },
+}
+
+impl Error {
+    /// Returns the name of the error variant.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Client(_) => "client",
+            Error::DetectorNotFound(_) => "detector_not_found",
+            Error::ChunkerNotFound(_) => "chunker_not_found",
+            Error::DetectorRequestFailed { .. } => "detector_request_failed",
+            Error::ChunkerRequestFailed { .. } => "chunker_request_failed",
+            Error::GenerateRequestFailed { .. } => "generate_request_failed",
+            Error::ChatCompletionRequestFailed { .. } => "chat_completion_request_failed",
+            Error::CompletionRequestFailed { .. } => "completion_request_failed",
+            Error::TokenizeRequestFailed { .. } => "tokenize_request_failed",
+            Error::Validation(_) => "validation",
+            Error::Forbidden(_) => "forbidden",
+            Error::Other(_) => "other",
+            Error::Cancelled => "cancelled",
+            Error::JsonError(_) => "json_error",
+            Error::DeadlineExceeded { .. } => "deadline_exceeded",
+        }
+    }
+}
+
+/// Processing stage of a request, reported when its deadline is exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Chunking,
+    Detection,
+    Generation,
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stage::Chunking => write!(f, "chunking"),
+            Stage::Detection => write!(f, "detection"),
+            Stage::Generation => write!(f, "generation"),
+        }
+    }
 }
 
 impl From<tokio::task::JoinError> for Error {
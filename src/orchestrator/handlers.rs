@@ -22,6 +22,7 @@ pub use classification_with_gen::ClassificationWithGenTask;
 pub mod streaming_classification_with_gen;
 pub use streaming_classification_with_gen::StreamingClassificationWithGenTask;
 pub mod chat_completions_detection;
+pub mod completions_detection;
 pub mod streaming_content_detection;
 pub use streaming_content_detection::StreamingContentDetectionTask;
 pub mod generation_with_detection;
@@ -34,6 +35,8 @@ pub mod detection_on_generation;
 pub use detection_on_generation::DetectionOnGenerationTask;
 pub mod text_content_detection;
 pub use text_content_detection::TextContentDetectionTask;
+pub mod batch_text_content_detection;
+pub use batch_text_content_detection::BatchTextContentDetectionTask;
 
 use super::Error;
 
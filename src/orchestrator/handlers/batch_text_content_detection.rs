@@ -0,0 +1,199 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+use std::sync::Arc;
+
+use futures::StreamExt;
+use http::HeaderMap;
+use opentelemetry::trace::TraceId;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{Instrument, info, instrument};
+
+use super::Handle;
+use crate::{
+    models::{
+        BatchDetectionRecord, BatchOrder, TextContentDetectionHttpRequest,
+        TextContentDetectionResult,
+    },
+    orchestrator::{
+        Context, Error, Orchestrator,
+        common::{self, validate_detectors, validate_tenant},
+        types::{BoxStream, Tenant, with_tenant_header},
+    },
+    utils::task_group,
+};
+
+impl Handle<BatchTextContentDetectionTask> for Orchestrator {
+    type Response = ReceiverStream<BatchRecordResult>;
+
+    #[instrument(
+        name = "batch_text_content_detection",
+        skip_all,
+        fields(
+            trace_id = ?task.trace_id,
+            headers = ?task.headers,
+            tenant = task.tenant.as_ref().map(|tenant| tenant.id.as_str())
+        )
+    )]
+    async fn handle(&self, task: BatchTextContentDetectionTask) -> Result<Self::Response, Error> {
+        let audit = self.audit(
+            "/api/v2/text/detection/content/batch",
+            task.trace_id,
+            task.tenant.as_ref(),
+        );
+        audit.finish(self.handle_batch_text_content_detection(task).await)
+    }
+}
+
+impl Orchestrator {
+    async fn handle_batch_text_content_detection(
+        &self,
+        task: BatchTextContentDetectionTask,
+    ) -> Result<ReceiverStream<BatchRecordResult>, Error> {
+        let ctx = self.ctx();
+        let trace_id = task.trace_id;
+        info!(%trace_id, order = ?task.order, concurrency = task.concurrency, "task started");
+
+        // Create response channel
+        let (response_tx, response_rx) = mpsc::channel::<BatchRecordResult>(128);
+
+        task_group::spawn(
+            async move {
+                let headers = task.headers;
+                let tenant = task.tenant;
+                // Records are read from the input stream as they are processed,
+                // so at most `concurrency` records are in flight
+                let results = task.input_stream.map(move |(index, record)| {
+                    let ctx = ctx.clone();
+                    let headers = headers.clone();
+                    let tenant = tenant.clone();
+                    async move {
+                        match record {
+                            Ok(record) => {
+                                let id = record.id.clone();
+                                let result = detect_record(ctx, headers, tenant, record).await;
+                                BatchRecordResult {
+                                    index,
+                                    id: Some(id),
+                                    result,
+                                }
+                            }
+                            Err(error) => BatchRecordResult {
+                                index,
+                                id: None,
+                                result: Err(error),
+                            },
+                        }
+                    }
+                });
+                let mut results = match task.order {
+                    BatchOrder::Input => results.buffered(task.concurrency).boxed(),
+                    BatchOrder::Completion => results.buffer_unordered(task.concurrency).boxed(),
+                };
+                while let Some(result) = results.next().await {
+                    if response_tx.send(result).await.is_err() {
+                        info!("response channel closed, stopping batch");
+                        return;
+                    }
+                }
+                info!("task completed");
+            }
+            .in_current_span(),
+        );
+
+        Ok(ReceiverStream::new(response_rx))
+    }
+}
+
+/// Runs detections on a record, failing only the record on errors.
+async fn detect_record(
+    ctx: Arc<Context>,
+    headers: HeaderMap,
+    tenant: Option<Tenant>,
+    record: BatchDetectionRecord,
+) -> Result<TextContentDetectionResult, Error> {
+    let mut request: TextContentDetectionHttpRequest = record.into();
+    request.apply_guardrail_profile(&ctx.config)?;
+    request.validate()?;
+    validate_detectors(
+        &request.detectors,
+        &ctx.config.detectors,
+        common::TEXT_CONTENTS_DETECTORS,
+    )?;
+    validate_tenant(tenant.as_ref(), &[&request.detectors], None)?;
+
+    let (_, detections) = common::text_contents_detections(
+        ctx,
+        headers,
+        request.detectors,
+        0,
+        vec![(0, request.content)],
+    )
+    .await?;
+
+    Ok(TextContentDetectionResult {
+        warnings: detections.warnings(),
+        detections: detections.into(),
+    })
+}
+
+/// Result of a record of a batch.
+#[derive(Debug)]
+pub struct BatchRecordResult {
+    /// Index of the record in the batch
+    pub index: usize,
+    /// Record ID, missing if the record could not be parsed
+    pub id: Option<String>,
+    /// Detections or error
+    pub result: Result<TextContentDetectionResult, Error>,
+}
+
+pub struct BatchTextContentDetectionTask {
+    /// Trace ID
+    pub trace_id: TraceId,
+    /// Headers
+    pub headers: HeaderMap,
+    /// Order of results
+    pub order: BatchOrder,
+    /// Number of records processed concurrently
+    pub concurrency: usize,
+    /// Input stream of records
+    pub input_stream: BoxStream<(usize, Result<BatchDetectionRecord, Error>)>,
+    /// Tenant
+    pub tenant: Option<Tenant>,
+}
+
+impl BatchTextContentDetectionTask {
+    pub fn new(
+        trace_id: TraceId,
+        headers: HeaderMap,
+        order: BatchOrder,
+        concurrency: usize,
+        input_stream: BoxStream<(usize, Result<BatchDetectionRecord, Error>)>,
+        tenant: Option<Tenant>,
+    ) -> Self {
+        Self {
+            trace_id,
+            headers: with_tenant_header(headers, tenant.as_ref()),
+            order,
+            concurrency,
+            input_stream,
+            tenant,
+        }
+    }
+}
@@ -14,14 +14,25 @@
  limitations under the License.
 
 */
+use std::{collections::HashMap, sync::Arc};
+
 use http::HeaderMap;
 use opentelemetry::trace::TraceId;
 use tracing::instrument;
 
 use super::Handle;
 use crate::{
-    clients::openai::{ChatCompletionsRequest, ChatCompletionsResponse},
-    orchestrator::{Error, Orchestrator},
+    audit::AnonymizedFlag,
+    clients::{
+        detector::ContextType,
+        openai::{ChatCompletionsRequest, ChatCompletionsResponse, Content, Message},
+    },
+    config::{DetectorType, PolicyDirection},
+    models::DetectorParams,
+    orchestrator::{
+        Context, Error, Orchestrator, common,
+        types::{Detections, Tenant, with_tenant_header},
+    },
 };
 
 pub mod streaming;
@@ -33,10 +44,43 @@ impl Handle<ChatCompletionsDetectionTask> for Orchestrator {
     #[instrument(
         name = "chat_completions_detection",
         skip_all,
-        fields(trace_id = ?task.trace_id, headers = ?task.headers)
+        fields(
+            trace_id = ?task.trace_id,
+            headers = ?task.headers,
+            tenant = task.tenant.as_ref().map(|tenant| tenant.id.as_str())
+        )
     )]
-    async fn handle(&self, task: ChatCompletionsDetectionTask) -> Result<Self::Response, Error> {
-        let ctx = self.ctx.clone();
+    async fn handle(
+        &self,
+        mut task: ChatCompletionsDetectionTask,
+    ) -> Result<Self::Response, Error> {
+        let ctx = self.ctx();
+        let audit = self
+            .audit(
+                "/api/v2/chat/completions-detection",
+                task.trace_id,
+                task.tenant.as_ref(),
+            )
+            .detectors(Some(PolicyDirection::Input), &task.request.detectors.input)
+            .detectors(
+                Some(PolicyDirection::Output),
+                &task.request.detectors.output,
+            )
+            .redactions(
+                Some(PolicyDirection::Output),
+                common::get_redactions(&ctx, &task.request.detectors.output).into_keys(),
+            );
+        task.anonymized = audit.anonymized_flag();
+        audit.finish(self.handle_chat_completions_detection(task).await)
+    }
+}
+
+impl Orchestrator {
+    async fn handle_chat_completions_detection(
+        &self,
+        task: ChatCompletionsDetectionTask,
+    ) -> Result<ChatCompletionsResponse, Error> {
+        let ctx = self.ctx();
         match task.request.stream {
             Some(true) => streaming::handle_streaming(ctx, task).await,
             _ => unary::handle_unary(ctx, task).await,
@@ -52,14 +96,81 @@ pub struct ChatCompletionsDetectionTask {
     pub request: ChatCompletionsRequest,
     /// Headers
     pub headers: HeaderMap,
+    /// Tenant
+    pub tenant: Option<Tenant>,
+    /// Set if entities of the input are anonymized, recorded by the audit
+    pub anonymized: AnonymizedFlag,
 }
 
 impl ChatCompletionsDetectionTask {
-    pub fn new(trace_id: TraceId, request: ChatCompletionsRequest, headers: HeaderMap) -> Self {
+    pub fn new(
+        trace_id: TraceId,
+        request: ChatCompletionsRequest,
+        headers: HeaderMap,
+        tenant: Option<Tenant>,
+    ) -> Self {
         Self {
             trace_id,
             request,
-            headers,
+            headers: with_tenant_header(headers, tenant.as_ref()),
+            tenant,
+            anonymized: AnonymizedFlag::default(),
         }
     }
 }
+
+/// Runs detections of output detectors other than `text_contents` on a choice,
+/// following the detector type rules of ADR 009:
+/// - `text_chat` detectors are applied to the messages of the request followed by the choice.
+/// - `text_generation` detectors are applied to the last user message as prompt and the
+///   choice as generated text.
+/// - `text_context_doc` detectors are applied to the choice with the context documents of
+///   the request, see [`ChatCompletionsRequest::context`].
+pub async fn choice_detections(
+    ctx: Arc<Context>,
+    headers: HeaderMap,
+    detectors: HashMap<String, DetectorParams>,
+    request: Arc<ChatCompletionsRequest>,
+    choice: Message,
+) -> Result<Detections, Error> {
+    let choice_text = match &choice.content {
+        Some(Content::Text(text)) => text.clone(),
+        _ => String::new(),
+    };
+    let (chat_detectors, detectors) =
+        common::split_detectors(detectors, &ctx.config.detectors, DetectorType::TextChat);
+    let (generation_detectors, context_detectors) = common::split_detectors(
+        detectors,
+        &ctx.config.detectors,
+        DetectorType::TextGeneration,
+    );
+    let mut messages = request.messages.clone();
+    messages.push(choice);
+    let (chat_detections, generation_detections, context_detections) = tokio::try_join!(
+        common::text_chat_detections(
+            ctx.clone(),
+            headers.clone(),
+            chat_detectors,
+            messages,
+            request.tools(),
+        ),
+        common::text_generation_detections(
+            ctx.clone(),
+            headers.clone(),
+            generation_detectors,
+            request.prompt(),
+            choice_text.clone(),
+        ),
+        common::text_context_detections(
+            ctx,
+            headers,
+            context_detectors,
+            choice_text,
+            ContextType::Document,
+            request.context(),
+        ),
+    )?;
+    Ok([chat_detections, generation_detections, context_detections]
+        .into_iter()
+        .collect())
+}
@@ -15,37 +15,43 @@
 
 */
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::{Arc, Mutex},
 };
 
 use dashmap::DashMap;
-use futures::{StreamExt, TryStreamExt, stream};
+use futures::{StreamExt, TryStreamExt, future, stream};
+use http::HeaderMap;
 use opentelemetry::trace::TraceId;
 use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::{Instrument, debug, error, info, instrument, warn};
 use uuid::Uuid;
 
 use super::ChatCompletionsDetectionTask;
 use crate::{
-    clients::openai::*,
-    config::DetectorType,
+    clients::{chunker::DEFAULT_CHUNKER_ID, openai::*},
+    config::{DetectorType, PolicyDirection, PolicyOutcome},
     models::{
         DetectionWarningReason, DetectorParams, UNSUITABLE_INPUT_MESSAGE, UNSUITABLE_OUTPUT_MESSAGE,
     },
     orchestrator::{
         Context, Error,
-        common::{self, text_contents_detections, validate_detectors},
+        common::{
+            self, Anonymizer, Redactions, text_contents_detections, validate_detectors,
+            validate_tenant,
+        },
         types::{
             ChatCompletionBatcher, ChatCompletionStream, ChatMessageIterator, ChoiceIndex, Chunk,
             DetectionBatchStream, Detections,
         },
     },
+    utils::task_group,
 };
 
 pub async fn handle_streaming(
     ctx: Arc<Context>,
-    task: ChatCompletionsDetectionTask,
+    mut task: ChatCompletionsDetectionTask,
 ) -> Result<ChatCompletionsResponse, Error> {
     let trace_id = task.trace_id;
     let detectors = task.request.detectors.clone();
@@ -55,7 +61,7 @@ pub async fn handle_streaming(
     let (response_tx, response_rx) =
         mpsc::channel::<Result<Option<ChatCompletionChunk>, Error>>(128);
 
-    tokio::spawn(
+    task_group::spawn(
         async move {
             let input_detectors = detectors.input;
             let output_detectors = detectors.output;
@@ -64,8 +70,7 @@ pub async fn handle_streaming(
             if let Err(error) = validate_detectors(
                 &input_detectors,
                 &ctx.config.detectors,
-                &[DetectorType::TextContents],
-                true,
+                common::CHAT_COMPLETIONS_INPUT_DETECTORS,
             ) {
                 let _ = response_tx.send(Err(error)).await;
                 return;
@@ -74,17 +79,45 @@ pub async fn handle_streaming(
             if let Err(error) = validate_detectors(
                 &output_detectors,
                 &ctx.config.detectors,
-                &[DetectorType::TextContents],
-                true,
+                common::CHAT_COMPLETIONS_OUTPUT_DETECTORS,
             ) {
                 let _ = response_tx.send(Err(error)).await;
                 return;
             }
+            // Validate tenant restrictions
+            if let Err(error) = validate_tenant(
+                task.tenant.as_ref(),
+                &[&input_detectors, &output_detectors],
+                Some(&task.request.model),
+            ) {
+                let _ = response_tx.send(Err(error)).await;
+                return;
+            }
+            // Disallow the `redact` action on `whole_doc_chunker` output detectors
+            // as chunks have already been sent when their detections are available
+            let redactions = common::get_redactions(&ctx, &output_detectors);
+            if let Some(detector_id) = redactions.keys().find(|detector_id| {
+                ctx.config.get_chunker_id(detector_id).unwrap() == DEFAULT_CHUNKER_ID
+            }) {
+                let error = Error::Validation(format!(
+                    "detector `{detector_id}` uses chunker `whole_doc_chunker`, which does not support the `redact` action for streaming output detection"
+                ));
+                let _ = response_tx.send(Err(error)).await;
+                return;
+            }
 
             // Handle input detection (unary)
+            let mut anonymizer = Anonymizer::new();
             if !input_detectors.is_empty() {
-                match handle_input_detection(ctx.clone(), &task, input_detectors).await {
-                    Ok(Some(chunk)) => {
+                match handle_input_detection(
+                    ctx.clone(),
+                    &mut task,
+                    input_detectors,
+                    &mut anonymizer,
+                )
+                .await
+                {
+                    Ok(Some((PolicyOutcome::Block, chunk))) => {
                         info!(%trace_id, "task completed: returning response with input detections");
                         // Send message with input detections to response channel and terminate
                         let _ = response_tx.send(Ok(Some(chunk))).await;
@@ -92,6 +125,13 @@ pub async fn handle_streaming(
                         let _ = response_tx.send(Ok(None)).await;
                         return;
                     }
+                    Ok(Some((_, chunk))) => {
+                        // Send message with input detections to response channel and proceed
+                        if response_tx.send(Ok(Some(chunk))).await.is_err() {
+                            info!(%trace_id, "task completed: client disconnected");
+                            return;
+                        }
+                    }
                     Ok(None) => (), // No input detections
                     Err(error) => {
                         // Input detections failed
@@ -107,7 +147,7 @@ pub async fn handle_streaming(
                 .clients
                 .get_as::<OpenAiClient>("chat_completions")
                 .unwrap();
-            let chat_completion_stream = match common::chat_completion_stream(client, task.headers.clone(), task.request.clone()).await {
+            let mut chat_completion_stream = match common::chat_completion_stream(client, task.headers.clone(), task.request.clone()).await {
                 Ok(stream) => stream,
                 Err(error) => {
                     error!(%trace_id, %error, "task failed: error creating chat completions stream");
@@ -116,10 +156,17 @@ pub async fn handle_streaming(
                     return;
                 }
             };
+            if !anonymizer.is_empty() {
+                // Restore anonymized entities in choice content
+                chat_completion_stream = common::deanonymize_chat_completion_stream(
+                    chat_completion_stream,
+                    Arc::new(anonymizer),
+                );
+            }
 
             if output_detectors.is_empty() {
                 // No output detectors, forward chat completion chunks to response channel
-                process_chat_completion_stream(trace_id, chat_completion_stream, None, None, Some(response_tx.clone())).await;
+                process_chat_completion_stream(trace_id, chat_completion_stream, None, None, None, Some(response_tx.clone())).await;
                 info!(%trace_id, "task completed: chat completion stream closed");
             } else {
                 // Handle output detection
@@ -145,9 +192,10 @@ pub async fn handle_streaming(
 #[instrument(skip_all)]
 async fn handle_input_detection(
     ctx: Arc<Context>,
-    task: &ChatCompletionsDetectionTask,
+    task: &mut ChatCompletionsDetectionTask,
     detectors: HashMap<String, DetectorParams>,
-) -> Result<Option<ChatCompletionChunk>, Error> {
+    anonymizer: &mut Anonymizer,
+) -> Result<Option<(PolicyOutcome, ChatCompletionChunk)>, Error> {
     let trace_id = task.trace_id;
     let model_id = task.request.model.clone();
 
@@ -170,41 +218,87 @@ async fn handle_input_detection(
     }
     let input_id = message.index;
     let input_text = message.text.map(|s| s.to_string()).unwrap_or_default();
-    let detections = match common::text_contents_detections(
-        ctx.clone(),
-        task.headers.clone(),
-        detectors.clone(),
-        input_id,
-        vec![(0, input_text)],
-    )
-    .await
-    {
-        Ok((_, detections)) => detections,
+    // Chat detectors are applied to all messages and tools of the request
+    let (chat_detectors, detectors) =
+        common::split_detectors(detectors, &ctx.config.detectors, DetectorType::TextChat);
+    let detections = match tokio::try_join!(
+        common::text_contents_detections(
+            ctx.clone(),
+            task.headers.clone(),
+            detectors.clone(),
+            input_id,
+            vec![(0, input_text.clone())],
+        ),
+        common::text_chat_detections(
+            ctx.clone(),
+            task.headers.clone(),
+            chat_detectors,
+            task.request.messages.clone(),
+            task.request.tools(),
+        ),
+    ) {
+        Ok(((_, detections), chat_detections)) => [detections, chat_detections]
+            .into_iter()
+            .collect::<Detections>(),
         Err(error) => {
             error!(%trace_id, %error, "task failed: error processing input detections");
             return Err(error);
         }
     };
-    if !detections.is_empty() {
+    let mut warnings: Vec<OrchestratorWarning> = detections.warnings();
+    // Detections of detectors with the `anonymize` action are not subject to policy
+    let anonymizations = common::get_anonymizations(&ctx, &detectors);
+    let (mut anonymized, mut detections) = common::split_anonymized(detections, &anonymizations);
+    let decision = common::evaluate_policy(&ctx, PolicyDirection::Input, &detections);
+    if decision.is_block() {
+        detections.extend(anonymized);
+    } else if decision.should_redact(&Redactions::new()) || !anonymized.is_empty() {
+        // Mask detected spans and replace anonymized entities with placeholders
+        // before chat completion
+        let mut text = input_text;
+        if decision.should_redact(&Redactions::new()) {
+            text = decision.redact_input(&text, &mut detections, &mut anonymized);
+        }
+        text = anonymizer.anonymize(&text, &anonymized);
+        if !anonymized.is_empty() {
+            task.anonymized.set();
+        }
+        task.request.messages[input_id as usize].content = Some(Content::Text(text));
+    }
+    if !decision.is_allow() {
         // Build chat completion chunk with input detections
+        warnings.push(
+            OrchestratorWarning::new(
+                DetectionWarningReason::UnsuitableInput,
+                UNSUITABLE_INPUT_MESSAGE,
+            )
+            .with_policy(decision.policy()),
+        );
         let chunk = ChatCompletionChunk {
             id: Uuid::new_v4().simple().to_string(),
             model: model_id,
             created: common::current_timestamp().as_secs() as i64,
             detections: Some(ChatDetections {
                 input: vec![InputDetectionResult {
-                    message_index: message.index,
+                    message_index: input_id,
                     results: detections.into(),
                 }],
                 ..Default::default()
             }),
-            warnings: vec![OrchestratorWarning::new(
-                DetectionWarningReason::UnsuitableInput,
-                UNSUITABLE_INPUT_MESSAGE,
-            )],
+            warnings,
+            ..Default::default()
+        };
+        Ok(Some((decision.outcome, chunk)))
+    } else if !warnings.is_empty() {
+        // No input detections, send warnings ahead of the chat completion
+        let chunk = ChatCompletionChunk {
+            id: Uuid::new_v4().simple().to_string(),
+            model: model_id,
+            created: common::current_timestamp().as_secs() as i64,
+            warnings,
             ..Default::default()
         };
-        Ok(Some(chunk))
+        Ok(Some((decision.outcome, chunk)))
     } else {
         // No input detections
         Ok(None)
@@ -221,16 +315,39 @@ async fn handle_output_detection(
 ) {
     let trace_id = task.trace_id;
     let request = task.request.clone();
+    let redactions = common::get_redactions(&ctx, &detectors);
     // Split output detectors into 2 groups:
     // 1) Output Detectors: Applied to chunks. Detections are returned in batches.
-    // 2) Whole Doc Output Detectors: Applied to concatenated chunks (whole doc) after the chat completion stream has been consumed.
-    // Currently, this is any detector that uses "whole_doc_chunker".
+    // 2) Whole Doc Output Detectors: Applied to concatenated chunks (whole doc) of each choice once it has finished.
+    // Currently, this is any detector that uses `whole_doc_chunker` and detectors of types other than
+    // `text_contents`, which are applied to each choice, see [`super::choice_detections`].
     let (whole_doc_detectors, detectors): (HashMap<_, _>, HashMap<_, _>) =
         detectors.into_iter().partition(|(detector_id, _)| {
-            ctx.config.get_chunker_id(detector_id).unwrap() == "whole_doc_chunker"
+            let detector = ctx.config.detector(detector_id).unwrap();
+            detector.r#type != DetectorType::TextContents
+                || detector.chunker_id == DEFAULT_CHUNKER_ID
         });
     let chat_completion_state = Arc::new(ChatCompletionState::new());
 
+    // Spawn task to run whole doc output detections on each choice as soon as it has finished
+    let (finished_tx, whole_doc_detection_handle) = if !whole_doc_detectors.is_empty() {
+        let (finished_tx, finished_rx) = mpsc::channel::<ChoiceIndex>(32);
+        let handle = task_group::spawn(
+            handle_whole_doc_output_detection(
+                ctx.clone(),
+                task.headers.clone(),
+                Arc::new(task.request.clone()),
+                whole_doc_detectors,
+                chat_completion_state.clone(),
+                finished_rx,
+            )
+            .in_current_span(),
+        );
+        (Some(finished_tx), Some(handle))
+    } else {
+        (None, None)
+    };
+
     if !detectors.is_empty() {
         // Set up streaming detection pipeline
         // n represents how many choices to generate for each input message
@@ -263,16 +380,18 @@ async fn handle_output_detection(
                     error!(%trace_id, %error, "task failed: error creating detection streams");
                     // Send error to response channel and terminate
                     let _ = response_tx.send(Err(error)).await;
+                    return;
                 }
             }
         }
 
         // Spawn task to consume chat completions stream and send choice text to detection pipeline
-        tokio::spawn(process_chat_completion_stream(
+        task_group::spawn(process_chat_completion_stream(
             trace_id,
             chat_completion_stream,
             Some(chat_completion_state.clone()),
             Some(input_txs),
+            finished_tx,
             None,
         ));
         // Process detection streams and await completion
@@ -282,7 +401,9 @@ async fn handle_output_detection(
         );
         process_detection_batch_stream(
             trace_id,
+            ctx.clone(),
             chat_completion_state.clone(),
+            redactions,
             detection_batch_stream,
             response_tx.clone(),
         )
@@ -295,14 +416,24 @@ async fn handle_output_detection(
             chat_completion_stream,
             Some(chat_completion_state.clone()),
             None,
+            finished_tx,
             Some(response_tx.clone()),
         )
         .await;
     }
+    // Abort whole doc output detections if the client has disconnected
+    if response_tx.is_closed() {
+        if let Some(handle) = whole_doc_detection_handle {
+            handle.abort();
+        }
+        info!(%trace_id, "task completed: client disconnected");
+        return;
+    }
+
     // NOTE: at this point, the chat completions stream has been fully consumed and chat completion state is final
 
     // If whole doc output detections or usage is requested, a final message is sent with these items
-    if !whole_doc_detectors.is_empty() || chat_completion_state.usage().is_some() {
+    if whole_doc_detection_handle.is_some() || chat_completion_state.usage().is_some() {
         let mut chat_completion = ChatCompletionChunk {
             id: chat_completion_state.id(),
             created: chat_completion_state.created(),
@@ -310,16 +441,9 @@ async fn handle_output_detection(
             usage: chat_completion_state.usage(),
             ..Default::default()
         };
-        if !whole_doc_detectors.is_empty() {
-            // Handle whole doc output detection
-            match handle_whole_doc_output_detection(
-                ctx.clone(),
-                task,
-                whole_doc_detectors,
-                chat_completion_state,
-            )
-            .await
-            {
+        if let Some(handle) = whole_doc_detection_handle {
+            // Await whole doc output detections
+            match handle.await.map_err(Error::from).and_then(|result| result) {
                 Ok((detections, warnings)) => {
                     chat_completion.detections = Some(detections);
                     chat_completion.warnings = warnings;
@@ -344,6 +468,7 @@ async fn process_chat_completion_stream(
     mut chat_completion_stream: ChatCompletionStream,
     chat_completion_state: Option<Arc<ChatCompletionState>>,
     input_txs: Option<HashMap<u32, mpsc::Sender<Result<(usize, String), Error>>>>,
+    finished_tx: Option<mpsc::Sender<ChoiceIndex>>,
     response_tx: Option<mpsc::Sender<Result<Option<ChatCompletionChunk>, Error>>>,
 ) {
     while let Some((message_index, result)) = chat_completion_stream.next().await {
@@ -405,6 +530,12 @@ async fn process_chat_completion_stream(
                         {
                             let _ = input_tx.send(Ok((message_index, choice_text))).await;
                         }
+                        // Notify whole doc output detection that this choice has finished
+                        if choice.finish_reason.is_some() {
+                            if let Some(finished_tx) = &finished_tx {
+                                let _ = finished_tx.send(choice.index).await;
+                            }
+                        }
                     } else {
                         debug!(%trace_id, %message_index, ?chat_completion, "chat completion chunk contains no choice");
                         warn!(%trace_id, %message_index, "chat completion chunk contains no choice");
@@ -429,64 +560,83 @@ async fn process_chat_completion_stream(
     }
 }
 
+/// Handles whole doc output detection.
+///
+/// Detections are run on the full text of each choice as soon as its finish reason is received.
+/// Choices without a finish reason are processed once the chat completion stream has been consumed.
+/// Up to `detector_concurrent_requests` choices are processed concurrently.
 #[instrument(skip_all)]
 async fn handle_whole_doc_output_detection(
     ctx: Arc<Context>,
-    task: &ChatCompletionsDetectionTask,
+    headers: HeaderMap,
+    request: Arc<ChatCompletionsRequest>,
     detectors: HashMap<String, DetectorParams>,
     chat_completion_state: Arc<ChatCompletionState>,
+    finished_rx: mpsc::Receiver<ChoiceIndex>,
 ) -> Result<(ChatDetections, Vec<OrchestratorWarning>), Error> {
-    // Create vec of choice_index->inputs, where inputs contains the concatenated text for the choice
-    let choice_inputs = chat_completion_state
-        .chat_completions
-        .iter()
-        .map(|entry| {
-            let choice_index = *entry.key();
-            let text = entry
-                .values()
-                .map(|chunk| {
-                    chunk
-                        .choices
-                        .first()
-                        .and_then(|choice| choice.delta.content.clone())
-                        .unwrap_or_default()
-                })
-                .collect::<String>();
-            let inputs = vec![(0usize, text)];
-            (choice_index, inputs)
-        })
-        .collect::<Vec<_>>();
-    // Process detections concurrently for choices
-    let choice_detections = stream::iter(choice_inputs)
-        .map(|(choice_index, inputs)| {
-            text_contents_detections(
+    // Choices as they finish, followed by remaining choices without a finish reason
+    // NOTE: the channel closes once the chat completion stream has been consumed
+    let state = chat_completion_state.clone();
+    let remaining = stream::once(async move {
+        state
+            .chat_completions
+            .iter()
+            .map(|entry| *entry.key())
+            .collect::<Vec<_>>()
+    })
+    .flat_map(stream::iter);
+    let mut finished = HashSet::new();
+    // Run detections on each choice once, failing fast if any choice fails
+    let mut choice_detections = ReceiverStream::new(finished_rx)
+        .chain(remaining)
+        .filter(move |choice_index| future::ready(finished.insert(*choice_index)))
+        .map(|choice_index| {
+            whole_doc_choice_detections(
                 ctx.clone(),
-                task.headers.clone(),
+                headers.clone(),
+                request.clone(),
                 detectors.clone(),
+                chat_completion_state.clone(),
                 choice_index,
-                inputs,
             )
         })
         .buffer_unordered(ctx.config.detector_concurrent_requests)
         .try_collect::<Vec<_>>()
         .await?;
-    // Build output detections
+    choice_detections.sort_by_key(|(choice_index, _)| *choice_index);
+    // Build output detections and warnings
+    // NOTE: choice content has already been sent, so policy outcomes are reported only
+    let mut warnings = Vec::new();
+    for (_, detections) in &choice_detections {
+        for warning in detections.warnings::<OrchestratorWarning>() {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+    }
     let output = choice_detections
         .into_iter()
-        .map(|(choice_index, detections)| OutputDetectionResult {
-            choice_index,
-            results: detections.into(),
+        .map(|(choice_index, detections)| {
+            let decision = common::evaluate_policy(&ctx, PolicyDirection::Output, &detections);
+            let detections = if decision.is_allow() {
+                Detections::default()
+            } else {
+                let warning = OrchestratorWarning::new(
+                    DetectionWarningReason::UnsuitableOutput,
+                    UNSUITABLE_OUTPUT_MESSAGE,
+                )
+                .with_policy(decision.policy());
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+                detections
+            };
+            OutputDetectionResult {
+                choice_index,
+                results: detections.into(),
+            }
         })
         .collect::<Vec<_>>();
-    // Build warnings
-    let warnings = if output.iter().any(|d| !d.results.is_empty()) {
-        vec![OrchestratorWarning::new(
-            DetectionWarningReason::UnsuitableOutput,
-            UNSUITABLE_OUTPUT_MESSAGE,
-        )]
-    } else {
-        Vec::new()
-    };
     let detections = ChatDetections {
         output,
         ..Default::default()
@@ -494,12 +644,64 @@ async fn handle_whole_doc_output_detection(
     Ok((detections, warnings))
 }
 
+/// Runs whole doc output detections on the concatenated text of a choice.
+async fn whole_doc_choice_detections(
+    ctx: Arc<Context>,
+    headers: HeaderMap,
+    request: Arc<ChatCompletionsRequest>,
+    detectors: HashMap<String, DetectorParams>,
+    chat_completion_state: Arc<ChatCompletionState>,
+    choice_index: ChoiceIndex,
+) -> Result<(ChoiceIndex, Detections), Error> {
+    // Concatenate text of chat completion chunks for this choice
+    let text = chat_completion_state
+        .chat_completions
+        .get(&choice_index)
+        .map(|chat_completions| {
+            chat_completions
+                .values()
+                .map(|chunk| {
+                    chunk
+                        .choices
+                        .first()
+                        .and_then(|choice| choice.delta.content.clone())
+                        .unwrap_or_default()
+                })
+                .collect::<String>()
+        })
+        .unwrap_or_default();
+    let (detectors, choice_detectors) =
+        common::split_detectors(detectors, &ctx.config.detectors, DetectorType::TextContents);
+    let choice = Message {
+        role: Role::Assistant,
+        content: Some(Content::Text(text.clone())),
+        ..Default::default()
+    };
+    let contents_detections = text_contents_detections(
+        ctx.clone(),
+        headers.clone(),
+        detectors,
+        choice_index,
+        vec![(0, text)],
+    );
+    let other_detections =
+        super::choice_detections(ctx, headers, choice_detectors, request, choice);
+    let ((_, detections), other_detections) =
+        tokio::try_join!(contents_detections, other_detections)?;
+    let detections = [detections, other_detections]
+        .into_iter()
+        .collect::<Detections>();
+    Ok((choice_index, detections))
+}
+
 /// Builds a response with output detections.
 fn output_detection_response(
+    ctx: &Arc<Context>,
     chat_completion_state: &Arc<ChatCompletionState>,
+    redactions: &Redactions,
     choice_index: u32,
     chunk: Chunk,
-    detections: Detections,
+    mut detections: Detections,
 ) -> Result<ChatCompletionChunk, Error> {
     // Get chat completions for this choice index
     let chat_completions = chat_completion_state
@@ -511,7 +713,20 @@ fn output_detection_response(
         .range(chunk.input_start_index..=chunk.input_end_index)
         .map(|(_index, chat_completion)| chat_completion.clone())
         .collect::<Vec<_>>();
-    let content = Some(chunk.text);
+    let mut warnings: Vec<OrchestratorWarning> = detections.warnings();
+    let decision = common::evaluate_policy(ctx, PolicyDirection::Output, &detections);
+    let content = if decision.is_block() {
+        // Withhold chunk text
+        None
+    } else if decision.should_redact(redactions) {
+        // Mask detected spans in chunk text
+        Some(decision.redact(&chunk.text, &mut detections, redactions))
+    } else {
+        Some(chunk.text)
+    };
+    if decision.is_allow() {
+        detections = Detections::default();
+    }
     let logprobs = merge_logprobs(&chat_completions);
     // Build response using the last chat completion received for this chunk
     if let Some(chat_completion) = chat_completions.last() {
@@ -523,10 +738,16 @@ fn output_detection_response(
         chat_completion.choices[0].logprobs = logprobs;
         // Set warnings
         if !detections.is_empty() {
-            chat_completion.warnings = vec![OrchestratorWarning::new(
-                DetectionWarningReason::UnsuitableOutput,
-                UNSUITABLE_OUTPUT_MESSAGE,
-            )];
+            warnings.push(
+                OrchestratorWarning::new(
+                    DetectionWarningReason::UnsuitableOutput,
+                    UNSUITABLE_OUTPUT_MESSAGE,
+                )
+                .with_policy(decision.policy()),
+            );
+        }
+        if !warnings.is_empty() {
+            chat_completion.warnings = warnings;
         }
         // Set detections
         chat_completion.detections = Some(ChatDetections {
@@ -567,7 +788,9 @@ fn merge_logprobs(chat_completions: &[ChatCompletionChunk]) -> Option<ChatComple
 /// Consumes a detection batch stream, builds responses, and sends them to a response channel.
 async fn process_detection_batch_stream(
     trace_id: TraceId,
+    ctx: Arc<Context>,
     chat_completion_state: Arc<ChatCompletionState>,
+    redactions: Redactions,
     mut detection_batch_stream: DetectionBatchStream,
     response_tx: mpsc::Sender<Result<Option<ChatCompletionChunk>, Error>>,
 ) {
@@ -575,7 +798,9 @@ async fn process_detection_batch_stream(
         match result {
             Ok((choice_index, chunk, detections)) => {
                 match output_detection_response(
+                    &ctx,
                     &chat_completion_state,
+                    &redactions,
                     choice_index,
                     chunk,
                     detections,
@@ -20,23 +20,24 @@ use futures::future::try_join_all;
 use tracing::{Instrument, error, info, instrument};
 use uuid::Uuid;
 
-use super::ChatCompletionsDetectionTask;
+use super::{ChatCompletionsDetectionTask, choice_detections};
 use crate::{
     clients::openai::*,
-    config::DetectorType,
+    config::{DetectorType, PolicyDirection, PolicyOutcome},
     models::{
         DetectionWarningReason, DetectorParams, UNSUITABLE_INPUT_MESSAGE, UNSUITABLE_OUTPUT_MESSAGE,
     },
     orchestrator::{
         Context, Error,
-        common::{self, validate_detectors},
-        types::ChatMessageIterator,
+        common::{self, Anonymizer, Redactions, validate_detectors, validate_tenant},
+        types::{ChatMessageIterator, Detections},
     },
+    utils::task_group,
 };
 
 pub async fn handle_unary(
     ctx: Arc<Context>,
-    task: ChatCompletionsDetectionTask,
+    mut task: ChatCompletionsDetectionTask,
 ) -> Result<ChatCompletionsResponse, Error> {
     let trace_id = task.trace_id;
     let detectors = task.request.detectors.clone();
@@ -47,26 +48,36 @@ pub async fn handle_unary(
     validate_detectors(
         &input_detectors,
         &ctx.config.detectors,
-        &[DetectorType::TextContents],
-        true,
+        common::CHAT_COMPLETIONS_INPUT_DETECTORS,
     )?;
 
     validate_detectors(
         &output_detectors,
         &ctx.config.detectors,
-        &[DetectorType::TextContents],
-        true,
+        common::CHAT_COMPLETIONS_OUTPUT_DETECTORS,
+    )?;
+    validate_tenant(
+        task.tenant.as_ref(),
+        &[&input_detectors, &output_detectors],
+        Some(&task.request.model),
     )?;
 
+    let mut anonymizer = Anonymizer::new();
+    let mut input_response = None;
     if !input_detectors.is_empty() {
         // Handle input detection
-        match handle_input_detection(ctx.clone(), &task, input_detectors).await {
-            Ok(Some(completion)) => {
+        match handle_input_detection(ctx.clone(), &mut task, input_detectors, &mut anonymizer).await
+        {
+            Ok(Some((PolicyOutcome::Block, completion))) => {
                 info!(%trace_id, "task completed: returning response with input detections");
                 // Return response with input detections and terminate
                 let response = completion.into();
                 return Ok(response);
             }
+            Ok(Some((_, completion))) => {
+                // Input detections are returned alongside the chat completion
+                input_response = Some(completion);
+            }
             Ok(None) => (), // No input detections
             Err(error) => {
                 // Input detections failed
@@ -80,30 +91,47 @@ pub async fn handle_unary(
         .clients
         .get_as::<OpenAiClient>("chat_completions")
         .unwrap();
-    let chat_completion =
+    let mut chat_completion =
         match common::chat_completion(client, task.headers.clone(), task.request.clone()).await {
             Ok(ChatCompletionsResponse::Unary(chat_completion)) => *chat_completion,
             Ok(ChatCompletionsResponse::Streaming(_)) => unimplemented!(),
             Err(error) => return Err(error),
         };
+    if !anonymizer.is_empty() {
+        // Restore anonymized entities in choice content
+        for choice in chat_completion.choices.iter_mut() {
+            choice.message.content = choice
+                .message
+                .content
+                .take()
+                .map(|content| anonymizer.deanonymize(&content));
+        }
+    }
 
     if !output_detectors.is_empty() {
         // Handle output detection
-        let chat_completion =
+        chat_completion =
             handle_output_detection(ctx.clone(), task, output_detectors, chat_completion).await?;
-        Ok(chat_completion.into())
-    } else {
-        // No output detectors, send chat completion response
-        Ok(chat_completion.into())
     }
+    if let Some(input_response) = input_response {
+        // Add input detections and warnings to chat completion
+        if let Some(detections) = input_response.detections {
+            chat_completion.detections.get_or_insert_default().input = detections.input;
+        }
+        let mut warnings = input_response.warnings;
+        warnings.append(&mut chat_completion.warnings);
+        chat_completion.warnings = warnings;
+    }
+    Ok(chat_completion.into())
 }
 
 #[instrument(skip_all)]
 async fn handle_input_detection(
     ctx: Arc<Context>,
-    task: &ChatCompletionsDetectionTask,
+    task: &mut ChatCompletionsDetectionTask,
     detectors: HashMap<String, DetectorParams>,
-) -> Result<Option<ChatCompletion>, Error> {
+    anonymizer: &mut Anonymizer,
+) -> Result<Option<(PolicyOutcome, ChatCompletion)>, Error> {
     let trace_id = task.trace_id;
     let model_id = task.request.model.clone();
 
@@ -128,41 +156,84 @@ async fn handle_input_detection(
     }
     let input_id = message.index;
     let input_text = message.text.map(|s| s.to_string()).unwrap_or_default();
-    let detections = match common::text_contents_detections(
-        ctx.clone(),
-        task.headers.clone(),
-        detectors.clone(),
-        input_id,
-        vec![(0, input_text)],
-    )
-    .await
-    {
-        Ok((_, detections)) => detections,
+    // Chat detectors are applied to all messages and tools of the request
+    let (chat_detectors, detectors) =
+        common::split_detectors(detectors, &ctx.config.detectors, DetectorType::TextChat);
+    let detections = match tokio::try_join!(
+        common::text_contents_detections(
+            ctx.clone(),
+            task.headers.clone(),
+            detectors.clone(),
+            input_id,
+            vec![(0, input_text.clone())],
+        ),
+        common::text_chat_detections(
+            ctx.clone(),
+            task.headers.clone(),
+            chat_detectors,
+            task.request.messages.clone(),
+            task.request.tools(),
+        ),
+    ) {
+        Ok(((_, detections), chat_detections)) => [detections, chat_detections]
+            .into_iter()
+            .collect::<Detections>(),
         Err(error) => {
             error!(%trace_id, %error, "task failed: error processing input detections");
             return Err(error);
         }
     };
-    if !detections.is_empty() {
+    let mut warnings: Vec<OrchestratorWarning> = detections.warnings();
+    // Detections of detectors with the `anonymize` action are not subject to policy
+    let anonymizations = common::get_anonymizations(&ctx, &detectors);
+    let (mut anonymized, mut detections) = common::split_anonymized(detections, &anonymizations);
+    let decision = common::evaluate_policy(&ctx, PolicyDirection::Input, &detections);
+    if decision.is_block() {
+        detections.extend(anonymized);
+    } else if decision.should_redact(&Redactions::new()) || !anonymized.is_empty() {
+        // Mask detected spans and replace anonymized entities with placeholders
+        // before chat completion
+        let mut text = input_text;
+        if decision.should_redact(&Redactions::new()) {
+            text = decision.redact_input(&text, &mut detections, &mut anonymized);
+        }
+        text = anonymizer.anonymize(&text, &anonymized);
+        if !anonymized.is_empty() {
+            task.anonymized.set();
+        }
+        task.request.messages[input_id as usize].content = Some(Content::Text(text));
+    }
+    if !decision.is_allow() {
         // Build chat completion with input detections
+        warnings.push(
+            OrchestratorWarning::new(
+                DetectionWarningReason::UnsuitableInput,
+                UNSUITABLE_INPUT_MESSAGE,
+            )
+            .with_policy(decision.policy()),
+        );
         let chat_completion = ChatCompletion {
             id: Uuid::new_v4().simple().to_string(),
             model: model_id,
             created: common::current_timestamp().as_secs() as i64,
             detections: Some(ChatDetections {
                 input: vec![InputDetectionResult {
-                    message_index: message.index,
+                    message_index: input_id,
                     results: detections.into(),
                 }],
                 ..Default::default()
             }),
-            warnings: vec![OrchestratorWarning::new(
-                DetectionWarningReason::UnsuitableInput,
-                UNSUITABLE_INPUT_MESSAGE,
-            )],
+            warnings,
+            ..Default::default()
+        };
+        Ok(Some((decision.outcome, chat_completion)))
+    } else if !warnings.is_empty() {
+        // No input detections, return warnings alongside the chat completion
+        let chat_completion = ChatCompletion {
+            warnings,
             ..Default::default()
         };
-        Ok(Some(chat_completion))
+        Ok(Some((decision.outcome, chat_completion)))
     } else {
         // No input detections
         Ok(None)
@@ -176,6 +247,11 @@ async fn handle_output_detection(
     detectors: HashMap<String, DetectorParams>,
     mut chat_completion: ChatCompletion,
 ) -> Result<ChatCompletion, Error> {
+    let redactions = common::get_redactions(&ctx, &detectors);
+    // Detectors of other types are applied to each choice, see [`choice_detections`]
+    let (detectors, choice_detectors) =
+        common::split_detectors(detectors, &ctx.config.detectors, DetectorType::TextContents);
+    let request = Arc::new(task.request);
     let mut tasks = Vec::with_capacity(chat_completion.choices.len());
     for choice in &chat_completion.choices {
         if choice
@@ -195,14 +271,29 @@ async fn handle_output_detection(
         }
         let input_id = choice.index;
         let input_text = choice.message.content.clone().unwrap_or_default();
-        tasks.push(tokio::spawn(
-            common::text_contents_detections(
-                ctx.clone(),
-                task.headers.clone(),
-                detectors.clone(),
-                input_id,
-                vec![(0, input_text)],
-            )
+        let contents_detections = common::text_contents_detections(
+            ctx.clone(),
+            task.headers.clone(),
+            detectors.clone(),
+            input_id,
+            vec![(0, input_text)],
+        );
+        let other_detections = choice_detections(
+            ctx.clone(),
+            task.headers.clone(),
+            choice_detectors.clone(),
+            request.clone(),
+            choice.message.clone().into(),
+        );
+        tasks.push(task_group::spawn(
+            async move {
+                let ((_, detections), other_detections) =
+                    tokio::try_join!(contents_detections, other_detections)?;
+                let detections = [detections, other_detections]
+                    .into_iter()
+                    .collect::<Detections>();
+                Ok::<_, Error>((input_id, detections))
+            }
             .in_current_span(),
         ));
     }
@@ -212,12 +303,51 @@ async fn handle_output_detection(
         .collect::<Result<Vec<_>, Error>>()?;
     if !detections.is_empty() {
         // Update chat completion with detections
+        let mut warnings = Vec::new();
+        for (_, detections) in &detections {
+            for warning in detections.warnings::<OrchestratorWarning>() {
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+            }
+        }
         let output = detections
             .into_iter()
-            .filter(|(_, detections)| !detections.is_empty())
-            .map(|(input_id, detections)| OutputDetectionResult {
-                choice_index: input_id,
-                results: detections.into(),
+            .filter_map(|(input_id, mut detections)| {
+                let decision = common::evaluate_policy(&ctx, PolicyDirection::Output, &detections);
+                if decision.is_allow() {
+                    return None;
+                }
+                if let Some(choice) = chat_completion
+                    .choices
+                    .iter_mut()
+                    .find(|choice| choice.index == input_id)
+                {
+                    if decision.is_block() {
+                        // Withhold choice content
+                        choice.message.content = None;
+                    } else if decision.should_redact(&redactions) {
+                        // Mask detected spans in choice content
+                        let text = decision.redact(
+                            choice.message.content.as_deref().unwrap_or_default(),
+                            &mut detections,
+                            &redactions,
+                        );
+                        choice.message.content = Some(text);
+                    }
+                }
+                let warning = OrchestratorWarning::new(
+                    DetectionWarningReason::UnsuitableOutput,
+                    UNSUITABLE_OUTPUT_MESSAGE,
+                )
+                .with_policy(decision.policy());
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+                Some(OutputDetectionResult {
+                    choice_index: input_id,
+                    results: detections.into(),
+                })
             })
             .collect::<Vec<_>>();
         if !output.is_empty() {
@@ -225,10 +355,9 @@ async fn handle_output_detection(
                 output,
                 ..Default::default()
             });
-            chat_completion.warnings = vec![OrchestratorWarning::new(
-                DetectionWarningReason::UnsuitableOutput,
-                UNSUITABLE_OUTPUT_MESSAGE,
-            )];
+            chat_completion.warnings = warnings;
+        } else {
+            chat_completion.warnings.extend(warnings);
         }
     }
     Ok(chat_completion)
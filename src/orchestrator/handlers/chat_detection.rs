@@ -23,11 +23,11 @@ use tracing::{info, instrument};
 use super::Handle;
 use crate::{
     clients::openai,
-    config::DetectorType,
     models::{ChatDetectionHttpRequest, ChatDetectionResult, DetectorParams},
     orchestrator::{
         Error, Orchestrator,
-        common::{self, validate_detectors},
+        common::{self, validate_detectors, validate_tenant},
+        types::{Tenant, with_tenant_header},
     },
 };
 
@@ -37,19 +37,39 @@ impl Handle<ChatDetectionTask> for Orchestrator {
     #[instrument(
         name = "chat_detection",
         skip_all,
-        fields(trace_id = ?task.trace_id, headers = ?task.headers)
+        fields(
+            trace_id = ?task.trace_id,
+            headers = ?task.headers,
+            tenant = task.tenant.as_ref().map(|tenant| tenant.id.as_str())
+        )
     )]
     async fn handle(&self, task: ChatDetectionTask) -> Result<Self::Response, Error> {
-        let ctx = self.ctx.clone();
+        let audit = self
+            .audit(
+                "/api/v2/text/detection/chat",
+                task.trace_id,
+                task.tenant.as_ref(),
+            )
+            .detectors(None, &task.detectors);
+        audit.finish(self.handle_chat_detection(task).await)
+    }
+}
+
+impl Orchestrator {
+    async fn handle_chat_detection(
+        &self,
+        task: ChatDetectionTask,
+    ) -> Result<ChatDetectionResult, Error> {
+        let ctx = self.ctx();
         let trace_id = task.trace_id;
         info!(%trace_id, config = ?task.detectors, "task started");
 
         validate_detectors(
             &task.detectors,
             &ctx.config.detectors,
-            &[DetectorType::TextChat],
-            true,
+            common::TEXT_CHAT_DETECTORS,
         )?;
+        validate_tenant(task.tenant.as_ref(), &[&task.detectors], None)?;
 
         // Handle detection
         let detections = common::text_chat_detections(
@@ -62,6 +82,7 @@ impl Handle<ChatDetectionTask> for Orchestrator {
         .await?;
 
         Ok(ChatDetectionResult {
+            warnings: detections.warnings(),
             detections: detections.into(),
         })
     }
@@ -79,16 +100,24 @@ pub struct ChatDetectionTask {
     pub tools: Vec<openai::Tool>,
     /// Headers
     pub headers: HeaderMap,
+    /// Tenant
+    pub tenant: Option<Tenant>,
 }
 
 impl ChatDetectionTask {
-    pub fn new(trace_id: TraceId, request: ChatDetectionHttpRequest, headers: HeaderMap) -> Self {
+    pub fn new(
+        trace_id: TraceId,
+        request: ChatDetectionHttpRequest,
+        headers: HeaderMap,
+        tenant: Option<Tenant>,
+    ) -> Self {
         Self {
             trace_id,
             detectors: request.detectors,
             messages: request.messages,
             tools: request.tools,
-            headers,
+            headers: with_tenant_header(headers, tenant.as_ref()),
+            tenant,
         }
     }
 }
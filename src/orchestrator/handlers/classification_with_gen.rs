@@ -23,8 +23,9 @@ use tracing::{error, info, instrument};
 
 use super::Handle;
 use crate::{
+    audit::AnonymizedFlag,
     clients::GenerationClient,
-    config::DetectorType,
+    config::{PolicyDirection, PolicyOutcome},
     models::{
         ClassifiedGeneratedTextResult, DetectionWarning, DetectorParams, GuardrailsConfig,
         GuardrailsHttpRequest, GuardrailsTextGenerationParameters,
@@ -32,7 +33,8 @@ use crate::{
     },
     orchestrator::{
         Context, Error, Orchestrator,
-        common::{self, validate_detectors},
+        common::{self, Anonymizer, Redactions, validate_detectors, validate_tenant},
+        types::{Tenant, with_tenant_header},
     },
 };
 
@@ -42,10 +44,45 @@ impl Handle<ClassificationWithGenTask> for Orchestrator {
     #[instrument(
         name = "classification_with_gen",
         skip_all,
-        fields(trace_id = ?task.trace_id, model_id = task.model_id, headers = ?task.headers)
+        fields(
+            trace_id = ?task.trace_id,
+            model_id = task.model_id,
+            headers = ?task.headers,
+            tenant = task.tenant.as_ref().map(|tenant| tenant.id.as_str())
+        )
     )]
-    async fn handle(&self, task: ClassificationWithGenTask) -> Result<Self::Response, Error> {
-        let ctx = self.ctx.clone();
+    async fn handle(&self, mut task: ClassificationWithGenTask) -> Result<Self::Response, Error> {
+        let ctx = self.ctx();
+        let audit = self
+            .audit(
+                "/api/v1/task/classification-with-text-generation",
+                task.trace_id,
+                task.tenant.as_ref(),
+            )
+            .detectors(
+                Some(PolicyDirection::Input),
+                &task.guardrails_config.input_detectors(),
+            )
+            .detectors(
+                Some(PolicyDirection::Output),
+                &task.guardrails_config.output_detectors(),
+            )
+            .redactions(
+                Some(PolicyDirection::Output),
+                common::get_redactions(&ctx, &task.guardrails_config.output_detectors())
+                    .into_keys(),
+            );
+        task.anonymized = audit.anonymized_flag();
+        audit.finish(self.handle_classification_with_gen(task).await)
+    }
+}
+
+impl Orchestrator {
+    async fn handle_classification_with_gen(
+        &self,
+        mut task: ClassificationWithGenTask,
+    ) -> Result<ClassifiedGeneratedTextResult, Error> {
+        let ctx = self.ctx();
         let trace_id = task.trace_id;
         info!(%trace_id, config = ?task.guardrails_config, "task started");
         let input_detectors = task.guardrails_config.input_detectors();
@@ -55,25 +92,36 @@ impl Handle<ClassificationWithGenTask> for Orchestrator {
         validate_detectors(
             &input_detectors,
             &ctx.config.detectors,
-            &[DetectorType::TextContents],
-            true,
+            common::TEXT_CONTENTS_DETECTORS,
         )?;
         // output detectors validation
         validate_detectors(
             &output_detectors,
             &ctx.config.detectors,
-            &[DetectorType::TextContents],
-            true,
+            common::TEXT_CONTENTS_DETECTORS,
+        )?;
+        validate_tenant(
+            task.tenant.as_ref(),
+            &[&input_detectors, &output_detectors],
+            Some(&task.model_id),
         )?;
 
+        let mut anonymizer = Anonymizer::new();
+        let mut input_response = None;
         if !input_detectors.is_empty() {
             // Handle input detection
-            match handle_input_detection(ctx.clone(), &task, input_detectors).await {
-                Ok(Some(response)) => {
+            match handle_input_detection(ctx.clone(), &mut task, input_detectors, &mut anonymizer)
+                .await
+            {
+                Ok(Some((PolicyOutcome::Block, response))) => {
                     info!(%trace_id, "task completed: returning response with input detections");
                     // Return response with input detections and terminate
                     return Ok(response);
                 }
+                Ok(Some((_, response))) => {
+                    // Input detections are returned alongside generation
+                    input_response = Some(response);
+                }
                 Ok(None) => (), // No input detections
                 Err(error) => {
                     // Input detections failed
@@ -87,7 +135,7 @@ impl Handle<ClassificationWithGenTask> for Orchestrator {
             .clients
             .get_as::<GenerationClient>("generation")
             .unwrap();
-        let generation = common::generate(
+        let mut generation = common::generate(
             client,
             task.headers.clone(),
             task.model_id.clone(),
@@ -95,24 +143,40 @@ impl Handle<ClassificationWithGenTask> for Orchestrator {
             task.text_gen_parameters.clone(),
         )
         .await?;
+        if !anonymizer.is_empty() {
+            // Restore anonymized entities in generated text
+            generation.generated_text = generation
+                .generated_text
+                .map(|text| anonymizer.deanonymize(&text));
+        }
 
-        if !output_detectors.is_empty() {
+        let mut response = if !output_detectors.is_empty() {
             // Handle output detection
-            handle_output_detection(ctx.clone(), task, output_detectors, generation).await
+            handle_output_detection(ctx.clone(), task, output_detectors, generation).await?
         } else {
             // No output detectors, return generation
             info!(%trace_id, "task completed: returning generation response");
-            Ok(generation)
+            generation
+        };
+        if let Some(input_response) = input_response {
+            // Add input detections and warnings to response
+            response.token_classification_results.input =
+                input_response.token_classification_results.input;
+            let mut warnings = input_response.warnings.unwrap_or_default();
+            warnings.extend(response.warnings.take().unwrap_or_default());
+            response.warnings = Some(warnings);
         }
+        Ok(response)
     }
 }
 
 #[instrument(skip_all)]
 async fn handle_input_detection(
     ctx: Arc<Context>,
-    task: &ClassificationWithGenTask,
+    task: &mut ClassificationWithGenTask,
     detectors: HashMap<String, DetectorParams>,
-) -> Result<Option<ClassifiedGeneratedTextResult>, Error> {
+    anonymizer: &mut Anonymizer,
+) -> Result<Option<(PolicyOutcome, ClassifiedGeneratedTextResult)>, Error> {
     let trace_id = task.trace_id;
     let inputs = common::apply_masks(task.inputs.clone(), task.guardrails_config.input_masks());
     let detections = match common::text_contents_detections(
@@ -130,7 +194,13 @@ async fn handle_input_detection(
             return Err(error);
         }
     };
-    if !detections.is_empty() {
+    let mut warnings: Vec<DetectionWarning> = detections.warnings();
+    // Detections of detectors with the `anonymize` action are not subject to policy
+    let anonymizations = common::get_anonymizations(&ctx, &detectors);
+    let (mut anonymized, mut detections) = common::split_anonymized(detections, &anonymizations);
+    let decision = common::evaluate_policy(&ctx, PolicyDirection::Input, &detections);
+    if decision.is_block() {
+        detections.extend(anonymized);
         // Get token count
         let client = ctx
             .clients
@@ -151,16 +221,46 @@ async fn handle_input_detection(
             }
         };
         // Build response with input detections
+        warnings.push(DetectionWarning::unsuitable_input().with_policy(decision.policy()));
         let response = ClassifiedGeneratedTextResult {
             input_token_count,
             token_classification_results: TextGenTokenClassificationResults {
                 input: Some(detections.into()),
                 output: None,
             },
-            warnings: Some(vec![DetectionWarning::unsuitable_input()]),
+            warnings: Some(warnings),
+            ..Default::default()
+        };
+        return Ok(Some((decision.outcome, response)));
+    }
+    if decision.should_redact(&Redactions::new()) {
+        // Mask detected spans before generation
+        task.inputs = decision.redact_input(&task.inputs, &mut detections, &mut anonymized);
+    }
+    if !anonymized.is_empty() {
+        // Replace detected entities with placeholders before generation
+        task.inputs = anonymizer.anonymize(&task.inputs, &anonymized);
+        task.anonymized.set();
+    }
+    if !decision.is_allow() {
+        // Build response with input detections to be returned alongside generation
+        warnings.push(DetectionWarning::unsuitable_input().with_policy(decision.policy()));
+        let response = ClassifiedGeneratedTextResult {
+            token_classification_results: TextGenTokenClassificationResults {
+                input: Some(detections.into()),
+                output: None,
+            },
+            warnings: Some(warnings),
             ..Default::default()
         };
-        Ok(Some(response))
+        Ok(Some((decision.outcome, response)))
+    } else if !warnings.is_empty() {
+        // No input detections, return warnings alongside generation
+        let response = ClassifiedGeneratedTextResult {
+            warnings: Some(warnings),
+            ..Default::default()
+        };
+        Ok(Some((decision.outcome, response)))
     } else {
         // No input detections
         Ok(None)
@@ -175,13 +275,14 @@ async fn handle_output_detection(
     generation: ClassifiedGeneratedTextResult,
 ) -> Result<ClassifiedGeneratedTextResult, Error> {
     let trace_id = task.trace_id;
+    let redactions = common::get_redactions(&ctx, &detectors);
     let generated_text = generation.generated_text.clone().unwrap_or_default();
-    let detections = match common::text_contents_detections(
-        ctx,
+    let mut detections = match common::text_contents_detections(
+        ctx.clone(),
         task.headers,
         detectors,
         0,
-        vec![(0, generated_text)],
+        vec![(0, generated_text.clone())],
     )
     .await
     {
@@ -191,10 +292,24 @@ async fn handle_output_detection(
             return Err(error);
         }
     };
+    let mut warnings: Vec<DetectionWarning> = detections.warnings();
+    let decision = common::evaluate_policy(&ctx, PolicyDirection::Output, &detections);
     let mut response = generation;
-    if !detections.is_empty() {
+    if !decision.is_allow() {
+        if decision.should_redact(&redactions) {
+            // Mask detected spans in generated text
+            response.generated_text =
+                Some(decision.redact(&generated_text, &mut detections, &redactions));
+        }
+        if decision.is_block() {
+            // Withhold generated text
+            response.generated_text = None;
+        }
         response.token_classification_results.output = Some(detections.into());
-        response.warnings = Some(vec![DetectionWarning::unsuitable_output()]);
+        warnings.push(DetectionWarning::unsuitable_output().with_policy(decision.policy()));
+    }
+    if !warnings.is_empty() {
+        response.warnings = Some(warnings);
     }
     info!(%trace_id, "task completed: returning response with output detections");
     Ok(response)
@@ -214,17 +329,28 @@ pub struct ClassificationWithGenTask {
     pub text_gen_parameters: Option<GuardrailsTextGenerationParameters>,
     /// Headers
     pub headers: HeaderMap,
+    /// Tenant
+    pub tenant: Option<Tenant>,
+    /// Set if entities of the input are anonymized, recorded by the audit
+    pub anonymized: AnonymizedFlag,
 }
 
 impl ClassificationWithGenTask {
-    pub fn new(trace_id: TraceId, request: GuardrailsHttpRequest, headers: HeaderMap) -> Self {
+    pub fn new(
+        trace_id: TraceId,
+        request: GuardrailsHttpRequest,
+        headers: HeaderMap,
+        tenant: Option<Tenant>,
+    ) -> Self {
         Self {
             trace_id,
             model_id: request.model_id,
             inputs: request.inputs,
             guardrails_config: request.guardrail_config.unwrap_or_default(),
             text_gen_parameters: request.text_gen_parameters,
-            headers,
+            headers: with_tenant_header(headers, tenant.as_ref()),
+            tenant,
+            anonymized: AnonymizedFlag::default(),
         }
     }
 }
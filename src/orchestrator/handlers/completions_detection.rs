@@ -0,0 +1,106 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+use http::HeaderMap;
+use opentelemetry::trace::TraceId;
+use tracing::instrument;
+
+use super::Handle;
+use crate::{
+    clients::openai::{CompletionsRequest, CompletionsResponse},
+    config::PolicyDirection,
+    orchestrator::{
+        Error, Orchestrator, common,
+        types::{Tenant, with_tenant_header},
+    },
+};
+
+pub mod streaming;
+pub mod unary;
+
+impl Handle<CompletionsDetectionTask> for Orchestrator {
+    type Response = CompletionsResponse;
+
+    #[instrument(
+        name = "completions_detection",
+        skip_all,
+        fields(
+            trace_id = ?task.trace_id,
+            headers = ?task.headers,
+            tenant = task.tenant.as_ref().map(|tenant| tenant.id.as_str())
+        )
+    )]
+    async fn handle(&self, task: CompletionsDetectionTask) -> Result<Self::Response, Error> {
+        let ctx = self.ctx();
+        let audit = self
+            .audit(
+                "/api/v2/completions-detection",
+                task.trace_id,
+                task.tenant.as_ref(),
+            )
+            .detectors(Some(PolicyDirection::Input), &task.request.detectors.input)
+            .detectors(
+                Some(PolicyDirection::Output),
+                &task.request.detectors.output,
+            )
+            .redactions(
+                Some(PolicyDirection::Output),
+                common::get_redactions(&ctx, &task.request.detectors.output).into_keys(),
+            );
+        audit.finish(self.handle_completions_detection(task).await)
+    }
+}
+
+impl Orchestrator {
+    async fn handle_completions_detection(
+        &self,
+        task: CompletionsDetectionTask,
+    ) -> Result<CompletionsResponse, Error> {
+        let ctx = self.ctx();
+        match task.request.stream {
+            Some(true) => streaming::handle_streaming(ctx, task).await,
+            _ => unary::handle_unary(ctx, task).await,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CompletionsDetectionTask {
+    /// Trace ID
+    pub trace_id: TraceId,
+    /// Request
+    pub request: CompletionsRequest,
+    /// Headers
+    pub headers: HeaderMap,
+    /// Tenant
+    pub tenant: Option<Tenant>,
+}
+
+impl CompletionsDetectionTask {
+    pub fn new(
+        trace_id: TraceId,
+        request: CompletionsRequest,
+        headers: HeaderMap,
+        tenant: Option<Tenant>,
+    ) -> Self {
+        Self {
+            trace_id,
+            request,
+            headers: with_tenant_header(headers, tenant.as_ref()),
+            tenant,
+        }
+    }
+}
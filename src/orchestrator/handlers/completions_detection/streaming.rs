@@ -0,0 +1,780 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use dashmap::DashMap;
+use futures::{StreamExt, TryStreamExt, future, stream};
+use http::HeaderMap;
+use opentelemetry::trace::TraceId;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{Instrument, debug, error, info, instrument, warn};
+use uuid::Uuid;
+
+use super::CompletionsDetectionTask;
+use crate::{
+    clients::{chunker::DEFAULT_CHUNKER_ID, openai::*},
+    config::{PolicyDirection, PolicyOutcome},
+    models::{
+        DetectionWarningReason, DetectorParams, UNSUITABLE_INPUT_MESSAGE, UNSUITABLE_OUTPUT_MESSAGE,
+    },
+    orchestrator::{
+        Context, Error,
+        common::{self, Redactions, text_contents_detections, validate_detectors, validate_tenant},
+        types::{
+            ChatCompletionBatcher, ChoiceIndex, Chunk, CompletionStream, DetectionBatchStream,
+            Detections,
+        },
+    },
+    utils::task_group,
+};
+
+pub async fn handle_streaming(
+    ctx: Arc<Context>,
+    mut task: CompletionsDetectionTask,
+) -> Result<CompletionsResponse, Error> {
+    let trace_id = task.trace_id;
+    let detectors = task.request.detectors.clone();
+    info!(%trace_id, config = ?detectors, "task started");
+
+    // Create response channel
+    let (response_tx, response_rx) = mpsc::channel::<Result<Option<Completion>, Error>>(128);
+
+    task_group::spawn(
+        async move {
+            let input_detectors = detectors.input;
+            let output_detectors = detectors.output;
+
+            // Validate input detectors
+            if let Err(error) = validate_detectors(
+                &input_detectors,
+                &ctx.config.detectors,
+                common::TEXT_CONTENTS_DETECTORS,
+            ) {
+                let _ = response_tx.send(Err(error)).await;
+                return;
+            }
+            // Validate output detectors
+            if let Err(error) = validate_detectors(
+                &output_detectors,
+                &ctx.config.detectors,
+                common::TEXT_CONTENTS_DETECTORS,
+            ) {
+                let _ = response_tx.send(Err(error)).await;
+                return;
+            }
+            // Validate tenant restrictions
+            if let Err(error) = validate_tenant(
+                task.tenant.as_ref(),
+                &[&input_detectors, &output_detectors],
+                Some(&task.request.model),
+            ) {
+                let _ = response_tx.send(Err(error)).await;
+                return;
+            }
+            // Disallow the `redact` action on `whole_doc_chunker` output detectors
+            // as chunks have already been sent when their detections are available
+            let redactions = common::get_redactions(&ctx, &output_detectors);
+            if let Some(detector_id) = redactions.keys().find(|detector_id| {
+                ctx.config.get_chunker_id(detector_id).unwrap() == DEFAULT_CHUNKER_ID
+            }) {
+                let error = Error::Validation(format!(
+                    "detector `{detector_id}` uses chunker `whole_doc_chunker`, which does not support the `redact` action for streaming output detection"
+                ));
+                let _ = response_tx.send(Err(error)).await;
+                return;
+            }
+
+            // Handle input detection (unary)
+            if !input_detectors.is_empty() {
+                match handle_input_detection(ctx.clone(), &mut task, input_detectors).await {
+                    Ok(Some((PolicyOutcome::Block, completion))) => {
+                        info!(%trace_id, "task completed: returning response with input detections");
+                        // Send message with input detections to response channel and terminate
+                        let _ = response_tx.send(Ok(Some(completion))).await;
+                        // Send None to signal completion
+                        let _ = response_tx.send(Ok(None)).await;
+                        return;
+                    }
+                    Ok(Some((_, completion))) => {
+                        // Send message with input detections to response channel and proceed
+                        if response_tx.send(Ok(Some(completion))).await.is_err() {
+                            info!(%trace_id, "task completed: client disconnected");
+                            return;
+                        }
+                    }
+                    Ok(None) => (), // No input detections
+                    Err(error) => {
+                        // Input detections failed
+                        // Send error to response channel and terminate
+                        let _ = response_tx.send(Err(error)).await;
+                        return;
+                    }
+                }
+            }
+
+            // Create completions stream
+            let client = ctx.clients.get_as::<OpenAiClient>("completions").unwrap();
+            let completion_stream = match common::completion_stream(client, task.headers.clone(), task.request.clone()).await {
+                Ok(stream) => stream,
+                Err(error) => {
+                    error!(%trace_id, %error, "task failed: error creating completions stream");
+                    // Send error to response channel and terminate
+                    let _ = response_tx.send(Err(error)).await;
+                    return;
+                }
+            };
+
+            if output_detectors.is_empty() {
+                // No output detectors, forward completion chunks to response channel
+                process_completion_stream(trace_id, completion_stream, None, None, Some(response_tx.clone())).await;
+                info!(%trace_id, "task completed: completion stream closed");
+            } else {
+                // Handle output detection
+                handle_output_detection(
+                    ctx.clone(),
+                    &task,
+                    output_detectors,
+                    completion_stream,
+                    response_tx.clone(),
+                )
+                .await;
+            }
+
+            // Send None to signal completion
+            let _ = response_tx.send(Ok(None)).await;
+        }
+        .in_current_span(),
+    );
+
+    Ok(CompletionsResponse::Streaming(response_rx))
+}
+
+#[instrument(skip_all)]
+async fn handle_input_detection(
+    ctx: Arc<Context>,
+    task: &mut CompletionsDetectionTask,
+    detectors: HashMap<String, DetectorParams>,
+) -> Result<Option<(PolicyOutcome, Completion)>, Error> {
+    let trace_id = task.trace_id;
+    let model_id = task.request.model.clone();
+
+    // Input detectors are applied to the prompt
+    let input_id = 0;
+    let input_text = task.request.prompt.clone();
+    let mut detections = match common::text_contents_detections(
+        ctx.clone(),
+        task.headers.clone(),
+        detectors.clone(),
+        input_id,
+        vec![(0, input_text.clone())],
+    )
+    .await
+    {
+        Ok((_, detections)) => detections,
+        Err(error) => {
+            error!(%trace_id, %error, "task failed: error processing input detections");
+            return Err(error);
+        }
+    };
+    let mut warnings: Vec<OrchestratorWarning> = detections.warnings();
+    let decision = common::evaluate_policy(&ctx, PolicyDirection::Input, &detections);
+    if !decision.is_block() && decision.should_redact(&Redactions::new()) {
+        // Mask detected spans in prompt before completion
+        task.request.prompt = decision.redact(&input_text, &mut detections, &Redactions::new());
+    }
+    if !decision.is_allow() {
+        // Build completion chunk with input detections
+        warnings.push(
+            OrchestratorWarning::new(
+                DetectionWarningReason::UnsuitableInput,
+                UNSUITABLE_INPUT_MESSAGE,
+            )
+            .with_policy(decision.policy()),
+        );
+        let completion = Completion {
+            id: Uuid::new_v4().simple().to_string(),
+            object: "text_completion".into(),
+            model: model_id,
+            created: common::current_timestamp().as_secs() as i64,
+            detections: Some(ChatDetections {
+                input: vec![InputDetectionResult {
+                    message_index: input_id,
+                    results: detections.into(),
+                }],
+                ..Default::default()
+            }),
+            warnings,
+            ..Default::default()
+        };
+        Ok(Some((decision.outcome, completion)))
+    } else if !warnings.is_empty() {
+        // No input detections, send warnings ahead of the completion
+        let completion = Completion {
+            id: Uuid::new_v4().simple().to_string(),
+            object: "text_completion".into(),
+            model: model_id,
+            created: common::current_timestamp().as_secs() as i64,
+            warnings,
+            ..Default::default()
+        };
+        Ok(Some((decision.outcome, completion)))
+    } else {
+        // No input detections
+        Ok(None)
+    }
+}
+
+#[instrument(skip_all)]
+async fn handle_output_detection(
+    ctx: Arc<Context>,
+    task: &CompletionsDetectionTask,
+    detectors: HashMap<String, DetectorParams>,
+    completion_stream: CompletionStream,
+    response_tx: mpsc::Sender<Result<Option<Completion>, Error>>,
+) {
+    let trace_id = task.trace_id;
+    let request = task.request.clone();
+    let redactions = common::get_redactions(&ctx, &detectors);
+    // Split output detectors into 2 groups:
+    // 1) Output Detectors: Applied to chunks. Detections are returned in batches.
+    // 2) Whole Doc Output Detectors: Applied to concatenated chunks (whole doc) of each choice once it has finished.
+    // Currently, this is any detector that uses `whole_doc_chunker`.
+    let (whole_doc_detectors, detectors): (HashMap<_, _>, HashMap<_, _>) =
+        detectors.into_iter().partition(|(detector_id, _)| {
+            ctx.config.get_chunker_id(detector_id).unwrap() == DEFAULT_CHUNKER_ID
+        });
+    let completion_state = Arc::new(CompletionState::new());
+
+    // Spawn task to run whole doc output detections on each choice as soon as it has finished
+    let (finished_tx, whole_doc_detection_handle) = if !whole_doc_detectors.is_empty() {
+        let (finished_tx, finished_rx) = mpsc::channel::<ChoiceIndex>(32);
+        let handle = task_group::spawn(
+            handle_whole_doc_output_detection(
+                ctx.clone(),
+                task.headers.clone(),
+                whole_doc_detectors,
+                completion_state.clone(),
+                finished_rx,
+            )
+            .in_current_span(),
+        );
+        (Some(finished_tx), Some(handle))
+    } else {
+        (None, None)
+    };
+
+    if !detectors.is_empty() {
+        // Set up streaming detection pipeline
+        // n represents how many choices to generate for the prompt
+        // Choices are processed independently so each choice has its own input channels and detection streams.
+        let n = request.extra.get("n").and_then(|v| v.as_i64()).unwrap_or(1) as usize;
+        // Create input channels
+        let mut input_txs = HashMap::with_capacity(n);
+        let mut input_rxs = HashMap::with_capacity(n);
+        (0..n).for_each(|choice_index| {
+            let (input_tx, input_rx) = mpsc::channel::<Result<(usize, String), Error>>(32);
+            input_txs.insert(choice_index as u32, input_tx);
+            input_rxs.insert(choice_index as u32, input_rx);
+        });
+        // Create detection streams
+        let mut detection_streams = Vec::with_capacity(n * detectors.len());
+        for (choice_index, input_rx) in input_rxs {
+            match common::text_contents_detection_streams(
+                ctx.clone(),
+                task.headers.clone(),
+                detectors.clone(),
+                choice_index,
+                input_rx,
+            )
+            .await
+            {
+                Ok(streams) => {
+                    detection_streams.extend(streams);
+                }
+                Err(error) => {
+                    error!(%trace_id, %error, "task failed: error creating detection streams");
+                    // Send error to response channel and terminate
+                    let _ = response_tx.send(Err(error)).await;
+                    return;
+                }
+            }
+        }
+
+        // Spawn task to consume completions stream and send choice text to detection pipeline
+        task_group::spawn(process_completion_stream(
+            trace_id,
+            completion_stream,
+            Some(completion_state.clone()),
+            Some(input_txs),
+            finished_tx,
+            None,
+        ));
+        // Process detection streams and await completion
+        // NOTE: completion chunks are choice-indexed like chat completion chunks,
+        // so the chat completion batcher is reused here.
+        let detection_batch_stream = DetectionBatchStream::new(
+            ChatCompletionBatcher::new(detectors.len()),
+            detection_streams,
+        );
+        process_detection_batch_stream(
+            trace_id,
+            ctx.clone(),
+            completion_state.clone(),
+            redactions,
+            detection_batch_stream,
+            response_tx.clone(),
+        )
+        .await;
+    } else {
+        // We only have whole doc detectors, so the streaming detection pipeline is disabled
+        // Consume completions stream and await completion
+        process_completion_stream(
+            trace_id,
+            completion_stream,
+            Some(completion_state.clone()),
+            None,
+            finished_tx,
+            Some(response_tx.clone()),
+        )
+        .await;
+    }
+    // Abort whole doc output detections if the client has disconnected
+    if response_tx.is_closed() {
+        if let Some(handle) = whole_doc_detection_handle {
+            handle.abort();
+        }
+        info!(%trace_id, "task completed: client disconnected");
+        return;
+    }
+
+    // NOTE: at this point, the completions stream has been fully consumed and completion state is final
+
+    // If whole doc output detections or usage is requested, a final message is sent with these items
+    if whole_doc_detection_handle.is_some() || completion_state.usage().is_some() {
+        let mut completion = Completion {
+            id: completion_state.id(),
+            object: "text_completion".into(),
+            created: completion_state.created(),
+            model: completion_state.model(),
+            usage: completion_state.usage(),
+            ..Default::default()
+        };
+        if let Some(handle) = whole_doc_detection_handle {
+            // Await whole doc output detections
+            match handle.await.map_err(Error::from).and_then(|result| result) {
+                Ok((detections, warnings)) => {
+                    completion.detections = Some(detections);
+                    completion.warnings = warnings;
+                }
+                Err(error) => {
+                    error!(%error, "task failed: error processing whole doc output detections");
+                    // Send error to response channel
+                    let _ = response_tx.send(Err(error)).await;
+                    return;
+                }
+            }
+        }
+        // Send completion with whole doc output detections and/or usage to response channel
+        let _ = response_tx.send(Ok(Some(completion))).await;
+    }
+}
+
+/// Processes completion stream.
+#[allow(clippy::type_complexity)]
+async fn process_completion_stream(
+    trace_id: TraceId,
+    mut completion_stream: CompletionStream,
+    completion_state: Option<Arc<CompletionState>>,
+    input_txs: Option<HashMap<u32, mpsc::Sender<Result<(usize, String), Error>>>>,
+    finished_tx: Option<mpsc::Sender<ChoiceIndex>>,
+    response_tx: Option<mpsc::Sender<Result<Option<Completion>, Error>>>,
+) {
+    while let Some((message_index, result)) = completion_stream.next().await {
+        match result {
+            Ok(Some(completion)) => {
+                // Send completion chunk to response channel
+                // NOTE: this forwards completion chunks without detections and is only
+                // done here for 2 cases: a) no output detectors b) only whole doc output detectors
+                if let Some(response_tx) = &response_tx {
+                    if response_tx
+                        .send(Ok(Some(completion.clone())))
+                        .await
+                        .is_err()
+                    {
+                        info!(%trace_id, "task completed: client disconnected");
+                        return;
+                    }
+                }
+                if completion.choices.is_empty() && completion.usage.is_some() {
+                    // Set usage state from the usage message
+                    // NOTE: this message has no choices and is not sent to detection input channel
+                    if let Some(state) = &completion_state {
+                        state.metadata.lock().unwrap().usage = completion.usage.clone();
+                    }
+                } else {
+                    if message_index == 0 {
+                        // Set metadata state from the first message
+                        // NOTE: these values are the same for all completion chunks
+                        if let Some(state) = &completion_state {
+                            let mut metadata = state.metadata.lock().unwrap();
+                            metadata.id = completion.id.clone();
+                            metadata.created = completion.created;
+                            metadata.model = completion.model.clone();
+                        }
+                    }
+                    // NOTE: completion chunks should contain only 1 choice
+                    if let Some(choice) = completion.choices.first() {
+                        // Extract choice text
+                        let choice_text = choice.text.clone();
+                        // Update state for this choice index
+                        if let Some(state) = &completion_state {
+                            match state.completions.entry(choice.index) {
+                                dashmap::Entry::Occupied(mut entry) => {
+                                    entry.get_mut().insert(message_index, completion.clone());
+                                }
+                                dashmap::Entry::Vacant(entry) => {
+                                    entry.insert(BTreeMap::from([(
+                                        message_index,
+                                        completion.clone(),
+                                    )]));
+                                }
+                            }
+                        }
+                        // Send choice text to detection input channel
+                        if let Some(input_tx) =
+                            input_txs.as_ref().and_then(|txs| txs.get(&choice.index))
+                        {
+                            let _ = input_tx.send(Ok((message_index, choice_text))).await;
+                        }
+                        // Notify whole doc output detection that this choice has finished
+                        if choice.finish_reason.is_some() {
+                            if let Some(finished_tx) = &finished_tx {
+                                let _ = finished_tx.send(choice.index).await;
+                            }
+                        }
+                    } else {
+                        debug!(%trace_id, %message_index, ?completion, "completion chunk contains no choice");
+                        warn!(%trace_id, %message_index, "completion chunk contains no choice");
+                    }
+                }
+            }
+            Ok(None) => (), // Complete, stream has closed
+            Err(error) => {
+                error!(%trace_id, %error, "task failed: error received from completion stream");
+                // Send error to response channel
+                if let Some(response_tx) = &response_tx {
+                    let _ = response_tx.send(Err(error.clone())).await;
+                }
+                // Send error to detection input channels
+                if let Some(input_txs) = &input_txs {
+                    for input_tx in input_txs.values() {
+                        let _ = input_tx.send(Err(error.clone())).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Handles whole doc output detection.
+///
+/// Detections are run on the full text of each choice as soon as its finish reason is received.
+/// Choices without a finish reason are processed once the completion stream has been consumed.
+/// Up to `detector_concurrent_requests` choices are processed concurrently.
+#[instrument(skip_all)]
+async fn handle_whole_doc_output_detection(
+    ctx: Arc<Context>,
+    headers: HeaderMap,
+    detectors: HashMap<String, DetectorParams>,
+    completion_state: Arc<CompletionState>,
+    finished_rx: mpsc::Receiver<ChoiceIndex>,
+) -> Result<(ChatDetections, Vec<OrchestratorWarning>), Error> {
+    // Choices as they finish, followed by remaining choices without a finish reason
+    // NOTE: the channel closes once the completion stream has been consumed
+    let state = completion_state.clone();
+    let remaining = stream::once(async move {
+        state
+            .completions
+            .iter()
+            .map(|entry| *entry.key())
+            .collect::<Vec<_>>()
+    })
+    .flat_map(stream::iter);
+    let mut finished = HashSet::new();
+    // Run detections on each choice once, failing fast if any choice fails
+    let mut choice_detections = ReceiverStream::new(finished_rx)
+        .chain(remaining)
+        .filter(move |choice_index| future::ready(finished.insert(*choice_index)))
+        .map(|choice_index| {
+            // Concatenate text of completion chunks for this choice
+            let text = completion_state
+                .completions
+                .get(&choice_index)
+                .map(|completions| {
+                    completions
+                        .values()
+                        .map(|chunk| {
+                            chunk
+                                .choices
+                                .first()
+                                .map(|choice| choice.text.clone())
+                                .unwrap_or_default()
+                        })
+                        .collect::<String>()
+                })
+                .unwrap_or_default();
+            text_contents_detections(
+                ctx.clone(),
+                headers.clone(),
+                detectors.clone(),
+                choice_index,
+                vec![(0, text)],
+            )
+        })
+        .buffer_unordered(ctx.config.detector_concurrent_requests)
+        .try_collect::<Vec<_>>()
+        .await?;
+    choice_detections.sort_by_key(|(choice_index, _)| *choice_index);
+    // Build output detections and warnings
+    // NOTE: choice text has already been sent, so policy outcomes are reported only
+    let mut warnings = Vec::new();
+    for (_, detections) in &choice_detections {
+        for warning in detections.warnings::<OrchestratorWarning>() {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+    }
+    let output = choice_detections
+        .into_iter()
+        .map(|(choice_index, detections)| {
+            let decision = common::evaluate_policy(&ctx, PolicyDirection::Output, &detections);
+            let detections = if decision.is_allow() {
+                Detections::default()
+            } else {
+                let warning = OrchestratorWarning::new(
+                    DetectionWarningReason::UnsuitableOutput,
+                    UNSUITABLE_OUTPUT_MESSAGE,
+                )
+                .with_policy(decision.policy());
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+                detections
+            };
+            OutputDetectionResult {
+                choice_index,
+                results: detections.into(),
+            }
+        })
+        .collect::<Vec<_>>();
+    let detections = ChatDetections {
+        output,
+        ..Default::default()
+    };
+    Ok((detections, warnings))
+}
+
+/// Builds a response with output detections.
+fn output_detection_response(
+    ctx: &Arc<Context>,
+    completion_state: &Arc<CompletionState>,
+    redactions: &Redactions,
+    choice_index: u32,
+    chunk: Chunk,
+    mut detections: Detections,
+) -> Result<Completion, Error> {
+    // Get completions for this choice index
+    let completions = completion_state.completions.get(&choice_index).unwrap();
+    // Get range of completions for this chunk
+    let completions = completions
+        .range(chunk.input_start_index..=chunk.input_end_index)
+        .map(|(_index, completion)| completion.clone())
+        .collect::<Vec<_>>();
+    let mut warnings: Vec<OrchestratorWarning> = detections.warnings();
+    let decision = common::evaluate_policy(ctx, PolicyDirection::Output, &detections);
+    let text = if decision.is_block() {
+        // Withhold chunk text
+        String::new()
+    } else if decision.should_redact(redactions) {
+        // Mask detected spans in chunk text
+        decision.redact(&chunk.text, &mut detections, redactions)
+    } else {
+        chunk.text
+    };
+    if decision.is_allow() {
+        detections = Detections::default();
+    }
+    let logprobs = merge_logprobs(&completions);
+    // Build response using the last completion received for this chunk
+    if let Some(completion) = completions.last() {
+        let mut completion = completion.clone();
+        // Set text
+        completion.choices[0].text = text;
+        // Set logprobs
+        completion.choices[0].logprobs = logprobs;
+        // Set warnings
+        if !detections.is_empty() {
+            warnings.push(
+                OrchestratorWarning::new(
+                    DetectionWarningReason::UnsuitableOutput,
+                    UNSUITABLE_OUTPUT_MESSAGE,
+                )
+                .with_policy(decision.policy()),
+            );
+        }
+        if !warnings.is_empty() {
+            completion.warnings = warnings;
+        }
+        // Set detections
+        completion.detections = Some(ChatDetections {
+            output: vec![OutputDetectionResult {
+                choice_index,
+                results: detections.into(),
+            }],
+            ..Default::default()
+        });
+        Ok(completion)
+    } else {
+        error!(
+            %choice_index,
+            %chunk.input_start_index,
+            %chunk.input_end_index,
+            "no completions found for chunk"
+        );
+        Err(Error::Other("no completions found for chunk".into()))
+    }
+}
+
+/// Combines logprobs from completion chunks to a single [`CompletionLogprobs`].
+fn merge_logprobs(completions: &[Completion]) -> Option<CompletionLogprobs> {
+    let mut merged: Option<CompletionLogprobs> = None;
+    for completion in completions {
+        if let Some(logprobs) = completion
+            .choices
+            .first()
+            .and_then(|choice| choice.logprobs.as_ref())
+        {
+            match &mut merged {
+                Some(merged) => {
+                    merged.tokens.extend_from_slice(&logprobs.tokens);
+                    merged
+                        .token_logprobs
+                        .extend_from_slice(&logprobs.token_logprobs);
+                    merged
+                        .top_logprobs
+                        .extend_from_slice(&logprobs.top_logprobs);
+                    merged.text_offset.extend_from_slice(&logprobs.text_offset);
+                }
+                None => merged = Some(logprobs.clone()),
+            }
+        }
+    }
+    merged
+}
+
+/// Consumes a detection batch stream, builds responses, and sends them to a response channel.
+async fn process_detection_batch_stream(
+    trace_id: TraceId,
+    ctx: Arc<Context>,
+    completion_state: Arc<CompletionState>,
+    redactions: Redactions,
+    mut detection_batch_stream: DetectionBatchStream,
+    response_tx: mpsc::Sender<Result<Option<Completion>, Error>>,
+) {
+    while let Some(result) = detection_batch_stream.next().await {
+        match result {
+            Ok((choice_index, chunk, detections)) => {
+                match output_detection_response(
+                    &ctx,
+                    &completion_state,
+                    &redactions,
+                    choice_index,
+                    chunk,
+                    detections,
+                ) {
+                    Ok(completion) => {
+                        // Send completion to response channel
+                        if response_tx.send(Ok(Some(completion))).await.is_err() {
+                            info!(%trace_id, "task completed: client disconnected");
+                            return;
+                        }
+                    }
+                    Err(error) => {
+                        error!(%trace_id, %error, "task failed: error building output detection response");
+                        // Send error to response channel and terminate
+                        let _ = response_tx.send(Err(error)).await;
+                        return;
+                    }
+                }
+            }
+            Err(error) => {
+                error!(%trace_id, %error, "task failed: error received from detection batch stream");
+                // Send error to response channel and terminate
+                let _ = response_tx.send(Err(error)).await;
+                return;
+            }
+        }
+    }
+    info!(%trace_id, "task completed: detection batch stream closed");
+}
+
+#[derive(Debug, Default)]
+struct CompletionMetadata {
+    /// A unique identifier for the completion. Each chunk has the same ID.
+    pub id: String,
+    /// The Unix timestamp (in seconds) of when the completion was created. Each chunk has the same timestamp.
+    pub created: i64,
+    /// The model to generate the completion.
+    pub model: String,
+    /// Completion usage statistics.
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Default)]
+struct CompletionState {
+    /// Completion metadata.
+    pub metadata: Mutex<CompletionMetadata>,
+    /// A map of completion chunks received for each choice.
+    pub completions: DashMap<ChoiceIndex, BTreeMap<usize, Completion>>,
+}
+
+impl CompletionState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn id(&self) -> String {
+        self.metadata.lock().unwrap().id.clone()
+    }
+
+    pub fn created(&self) -> i64 {
+        self.metadata.lock().unwrap().created
+    }
+
+    pub fn model(&self) -> String {
+        self.metadata.lock().unwrap().model.clone()
+    }
+
+    pub fn usage(&self) -> Option<Usage> {
+        self.metadata.lock().unwrap().usage.clone()
+    }
+}
@@ -0,0 +1,280 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+use std::{collections::HashMap, sync::Arc};
+
+use futures::future::try_join_all;
+use tracing::{Instrument, error, info, instrument};
+use uuid::Uuid;
+
+use super::CompletionsDetectionTask;
+use crate::{
+    clients::openai::*,
+    config::{PolicyDirection, PolicyOutcome},
+    models::{
+        DetectionWarningReason, DetectorParams, UNSUITABLE_INPUT_MESSAGE, UNSUITABLE_OUTPUT_MESSAGE,
+    },
+    orchestrator::{
+        Context, Error,
+        common::{self, Redactions, validate_detectors, validate_tenant},
+    },
+    utils::task_group,
+};
+
+pub async fn handle_unary(
+    ctx: Arc<Context>,
+    mut task: CompletionsDetectionTask,
+) -> Result<CompletionsResponse, Error> {
+    let trace_id = task.trace_id;
+    let detectors = task.request.detectors.clone();
+    info!(%trace_id, config = ?detectors, "task started");
+    let input_detectors = detectors.input;
+    let output_detectors = detectors.output;
+
+    validate_detectors(
+        &input_detectors,
+        &ctx.config.detectors,
+        common::TEXT_CONTENTS_DETECTORS,
+    )?;
+
+    validate_detectors(
+        &output_detectors,
+        &ctx.config.detectors,
+        common::TEXT_CONTENTS_DETECTORS,
+    )?;
+    validate_tenant(
+        task.tenant.as_ref(),
+        &[&input_detectors, &output_detectors],
+        Some(&task.request.model),
+    )?;
+
+    let mut input_response = None;
+    if !input_detectors.is_empty() {
+        // Handle input detection
+        match handle_input_detection(ctx.clone(), &mut task, input_detectors).await {
+            Ok(Some((PolicyOutcome::Block, completion))) => {
+                info!(%trace_id, "task completed: returning response with input detections");
+                // Return response with input detections and terminate
+                let response = completion.into();
+                return Ok(response);
+            }
+            Ok(Some((_, completion))) => {
+                // Input detections are returned alongside the completion
+                input_response = Some(completion);
+            }
+            Ok(None) => (), // No input detections
+            Err(error) => {
+                // Input detections failed
+                return Err(error);
+            }
+        }
+    }
+
+    // Handle completion
+    let client = ctx.clients.get_as::<OpenAiClient>("completions").unwrap();
+    let mut completion =
+        match common::completion(client, task.headers.clone(), task.request.clone()).await {
+            Ok(CompletionsResponse::Unary(completion)) => *completion,
+            Ok(CompletionsResponse::Streaming(_)) => {
+                return Err(Error::Other(
+                    "unexpected streaming response for unary completion request".into(),
+                ));
+            }
+            Err(error) => return Err(error),
+        };
+
+    if !output_detectors.is_empty() {
+        // Handle output detection
+        completion =
+            handle_output_detection(ctx.clone(), task, output_detectors, completion).await?;
+    }
+    if let Some(input_response) = input_response {
+        // Add input detections and warnings to completion
+        if let Some(detections) = input_response.detections {
+            completion.detections.get_or_insert_default().input = detections.input;
+        }
+        let mut warnings = input_response.warnings;
+        warnings.append(&mut completion.warnings);
+        completion.warnings = warnings;
+    }
+    Ok(completion.into())
+}
+
+#[instrument(skip_all)]
+async fn handle_input_detection(
+    ctx: Arc<Context>,
+    task: &mut CompletionsDetectionTask,
+    detectors: HashMap<String, DetectorParams>,
+) -> Result<Option<(PolicyOutcome, Completion)>, Error> {
+    let trace_id = task.trace_id;
+    let model_id = task.request.model.clone();
+
+    // Input detectors are applied to the prompt
+    let input_id = 0;
+    let input_text = task.request.prompt.clone();
+    let mut detections = match common::text_contents_detections(
+        ctx.clone(),
+        task.headers.clone(),
+        detectors.clone(),
+        input_id,
+        vec![(0, input_text.clone())],
+    )
+    .await
+    {
+        Ok((_, detections)) => detections,
+        Err(error) => {
+            error!(%trace_id, %error, "task failed: error processing input detections");
+            return Err(error);
+        }
+    };
+    let mut warnings: Vec<OrchestratorWarning> = detections.warnings();
+    let decision = common::evaluate_policy(&ctx, PolicyDirection::Input, &detections);
+    if !decision.is_block() && decision.should_redact(&Redactions::new()) {
+        // Mask detected spans in prompt before completion
+        task.request.prompt = decision.redact(&input_text, &mut detections, &Redactions::new());
+    }
+    if !decision.is_allow() {
+        // Build completion with input detections
+        warnings.push(
+            OrchestratorWarning::new(
+                DetectionWarningReason::UnsuitableInput,
+                UNSUITABLE_INPUT_MESSAGE,
+            )
+            .with_policy(decision.policy()),
+        );
+        let completion = Completion {
+            id: Uuid::new_v4().simple().to_string(),
+            object: "text_completion".into(),
+            model: model_id,
+            created: common::current_timestamp().as_secs() as i64,
+            detections: Some(ChatDetections {
+                input: vec![InputDetectionResult {
+                    message_index: input_id,
+                    results: detections.into(),
+                }],
+                ..Default::default()
+            }),
+            warnings,
+            ..Default::default()
+        };
+        Ok(Some((decision.outcome, completion)))
+    } else if !warnings.is_empty() {
+        // No input detections, return warnings alongside the completion
+        let completion = Completion {
+            warnings,
+            ..Default::default()
+        };
+        Ok(Some((decision.outcome, completion)))
+    } else {
+        // No input detections
+        Ok(None)
+    }
+}
+
+#[instrument(skip_all)]
+async fn handle_output_detection(
+    ctx: Arc<Context>,
+    task: CompletionsDetectionTask,
+    detectors: HashMap<String, DetectorParams>,
+    mut completion: Completion,
+) -> Result<Completion, Error> {
+    let redactions = common::get_redactions(&ctx, &detectors);
+    let mut tasks = Vec::with_capacity(completion.choices.len());
+    for choice in &completion.choices {
+        if choice.text.is_empty() {
+            completion.warnings.push(OrchestratorWarning::new(
+                DetectionWarningReason::EmptyOutput,
+                &format!(
+                    "Choice of index {} has no content. Output detection was not executed",
+                    choice.index
+                ),
+            ));
+            continue;
+        }
+        let input_id = choice.index;
+        let input_text = choice.text.clone();
+        tasks.push(task_group::spawn(
+            common::text_contents_detections(
+                ctx.clone(),
+                task.headers.clone(),
+                detectors.clone(),
+                input_id,
+                vec![(0, input_text)],
+            )
+            .in_current_span(),
+        ));
+    }
+    let detections = try_join_all(tasks)
+        .await?
+        .into_iter()
+        .collect::<Result<Vec<_>, Error>>()?;
+    if !detections.is_empty() {
+        // Update completion with detections
+        let mut warnings = Vec::new();
+        for (_, detections) in &detections {
+            for warning in detections.warnings::<OrchestratorWarning>() {
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+            }
+        }
+        let output = detections
+            .into_iter()
+            .filter_map(|(input_id, mut detections)| {
+                let decision = common::evaluate_policy(&ctx, PolicyDirection::Output, &detections);
+                if decision.is_allow() {
+                    return None;
+                }
+                if let Some(choice) = completion
+                    .choices
+                    .iter_mut()
+                    .find(|choice| choice.index == input_id)
+                {
+                    if decision.is_block() {
+                        // Withhold choice text
+                        choice.text = String::new();
+                    } else if decision.should_redact(&redactions) {
+                        // Mask detected spans in choice text
+                        let text = decision.redact(&choice.text, &mut detections, &redactions);
+                        choice.text = text;
+                    }
+                }
+                let warning = OrchestratorWarning::new(
+                    DetectionWarningReason::UnsuitableOutput,
+                    UNSUITABLE_OUTPUT_MESSAGE,
+                )
+                .with_policy(decision.policy());
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+                Some(OutputDetectionResult {
+                    choice_index: input_id,
+                    results: detections.into(),
+                })
+            })
+            .collect::<Vec<_>>();
+        if !output.is_empty() {
+            completion.detections = Some(ChatDetections {
+                output,
+                ..Default::default()
+            });
+            completion.warnings = warnings;
+        } else {
+            completion.warnings.extend(warnings);
+        }
+    }
+    Ok(completion)
+}
@@ -23,11 +23,11 @@ use tracing::{info, instrument};
 use super::Handle;
 use crate::{
     clients::detector::ContextType,
-    config::DetectorType,
     models::{ContextDocsHttpRequest, ContextDocsResult, DetectorParams},
     orchestrator::{
         Error, Orchestrator,
-        common::{self, validate_detectors},
+        common::{self, validate_detectors, validate_tenant},
+        types::{Tenant, with_tenant_header},
     },
 };
 
@@ -37,19 +37,39 @@ impl Handle<ContextDocsDetectionTask> for Orchestrator {
     #[instrument(
         name = "context_docs_detection",
         skip_all,
-        fields(trace_id = ?task.trace_id, headers = ?task.headers)
+        fields(
+            trace_id = ?task.trace_id,
+            headers = ?task.headers,
+            tenant = task.tenant.as_ref().map(|tenant| tenant.id.as_str())
+        )
     )]
     async fn handle(&self, task: ContextDocsDetectionTask) -> Result<Self::Response, Error> {
-        let ctx = self.ctx.clone();
+        let audit = self
+            .audit(
+                "/api/v2/text/detection/context",
+                task.trace_id,
+                task.tenant.as_ref(),
+            )
+            .detectors(None, &task.detectors);
+        audit.finish(self.handle_context_docs_detection(task).await)
+    }
+}
+
+impl Orchestrator {
+    async fn handle_context_docs_detection(
+        &self,
+        task: ContextDocsDetectionTask,
+    ) -> Result<ContextDocsResult, Error> {
+        let ctx = self.ctx();
         let trace_id = task.trace_id;
         info!(%trace_id, config = ?task.detectors, "task started");
 
         validate_detectors(
             &task.detectors,
             &ctx.config.detectors,
-            &[DetectorType::TextContextDoc],
-            true,
+            common::TEXT_CONTEXT_DOC_DETECTORS,
         )?;
+        validate_tenant(task.tenant.as_ref(), &[&task.detectors], None)?;
 
         // Handle detection
         let detections = common::text_context_detections(
@@ -63,6 +83,7 @@ impl Handle<ContextDocsDetectionTask> for Orchestrator {
         .await?;
 
         Ok(ContextDocsResult {
+            warnings: detections.warnings(),
             detections: detections.into(),
         })
     }
@@ -82,17 +103,25 @@ pub struct ContextDocsDetectionTask {
     pub detectors: HashMap<String, DetectorParams>,
     /// Headers
     pub headers: HeaderMap,
+    /// Tenant
+    pub tenant: Option<Tenant>,
 }
 
 impl ContextDocsDetectionTask {
-    pub fn new(trace_id: TraceId, request: ContextDocsHttpRequest, headers: HeaderMap) -> Self {
+    pub fn new(
+        trace_id: TraceId,
+        request: ContextDocsHttpRequest,
+        headers: HeaderMap,
+        tenant: Option<Tenant>,
+    ) -> Self {
         Self {
             trace_id,
             content: request.content,
             context_type: request.context_type,
             context: request.context,
             detectors: request.detectors,
-            headers,
+            headers: with_tenant_header(headers, tenant.as_ref()),
+            tenant,
         }
     }
 }
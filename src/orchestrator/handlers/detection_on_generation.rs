@@ -22,11 +22,11 @@ use tracing::{info, instrument};
 
 use super::Handle;
 use crate::{
-    config::DetectorType,
     models::{DetectionOnGeneratedHttpRequest, DetectionOnGenerationResult, DetectorParams},
     orchestrator::{
         Error, Orchestrator,
-        common::{self, validate_detectors},
+        common::{self, validate_detectors, validate_tenant},
+        types::{Tenant, with_tenant_header},
     },
 };
 
@@ -36,19 +36,39 @@ impl Handle<DetectionOnGenerationTask> for Orchestrator {
     #[instrument(
         name = "detection_on_generation",
         skip_all,
-        fields(trace_id = ?task.trace_id, headers = ?task.headers)
+        fields(
+            trace_id = ?task.trace_id,
+            headers = ?task.headers,
+            tenant = task.tenant.as_ref().map(|tenant| tenant.id.as_str())
+        )
     )]
     async fn handle(&self, task: DetectionOnGenerationTask) -> Result<Self::Response, Error> {
-        let ctx = self.ctx.clone();
+        let audit = self
+            .audit(
+                "/api/v2/text/detection/generated",
+                task.trace_id,
+                task.tenant.as_ref(),
+            )
+            .detectors(None, &task.detectors);
+        audit.finish(self.handle_detection_on_generation(task).await)
+    }
+}
+
+impl Orchestrator {
+    async fn handle_detection_on_generation(
+        &self,
+        task: DetectionOnGenerationTask,
+    ) -> Result<DetectionOnGenerationResult, Error> {
+        let ctx = self.ctx();
         let trace_id = task.trace_id;
         info!(%trace_id, config = ?task.detectors, "task started");
 
         validate_detectors(
             &task.detectors,
             &ctx.config.detectors,
-            &[DetectorType::TextGeneration],
-            true,
+            common::TEXT_GENERATION_DETECTORS,
         )?;
+        validate_tenant(task.tenant.as_ref(), &[&task.detectors], None)?;
 
         // Handle detection
         let detections = common::text_generation_detections(
@@ -61,6 +81,7 @@ impl Handle<DetectionOnGenerationTask> for Orchestrator {
         .await?;
 
         Ok(DetectionOnGenerationResult {
+            warnings: detections.warnings(),
             detections: detections.into(),
         })
     }
@@ -78,6 +99,8 @@ pub struct DetectionOnGenerationTask {
     pub detectors: HashMap<String, DetectorParams>,
     /// Headers
     pub headers: HeaderMap,
+    /// Tenant
+    pub tenant: Option<Tenant>,
 }
 
 impl DetectionOnGenerationTask {
@@ -85,13 +108,15 @@ impl DetectionOnGenerationTask {
         trace_id: TraceId,
         request: DetectionOnGeneratedHttpRequest,
         headers: HeaderMap,
+        tenant: Option<Tenant>,
     ) -> Self {
         Self {
             trace_id,
             prompt: request.prompt,
             generated_text: request.generated_text,
             detectors: request.detectors,
-            headers,
+            headers: with_tenant_header(headers, tenant.as_ref()),
+            tenant,
         }
     }
 }
@@ -23,14 +23,15 @@ use tracing::{info, instrument};
 use super::Handle;
 use crate::{
     clients::GenerationClient,
-    config::DetectorType,
+    config::PolicyDirection,
     models::{
         DetectorParams, GenerationWithDetectionHttpRequest, GenerationWithDetectionResult,
         GuardrailsTextGenerationParameters,
     },
     orchestrator::{
         Error, Orchestrator,
-        common::{self, validate_detectors},
+        common::{self, validate_detectors, validate_tenant},
+        types::{Tenant, with_tenant_header},
     },
 };
 
@@ -40,18 +41,42 @@ impl Handle<GenerationWithDetectionTask> for Orchestrator {
     #[instrument(
         name = "generation_with_detection",
         skip_all,
-        fields(trace_id = ?task.trace_id, headers = ?task.headers)
+        fields(
+            trace_id = ?task.trace_id,
+            headers = ?task.headers,
+            tenant = task.tenant.as_ref().map(|tenant| tenant.id.as_str())
+        )
     )]
     async fn handle(&self, task: GenerationWithDetectionTask) -> Result<Self::Response, Error> {
-        let ctx = self.ctx.clone();
+        let audit = self
+            .audit(
+                "/api/v2/text/generation-detection",
+                task.trace_id,
+                task.tenant.as_ref(),
+            )
+            .detectors(Some(PolicyDirection::Output), &task.detectors);
+        audit.finish(self.handle_generation_with_detection(task).await)
+    }
+}
+
+impl Orchestrator {
+    async fn handle_generation_with_detection(
+        &self,
+        task: GenerationWithDetectionTask,
+    ) -> Result<GenerationWithDetectionResult, Error> {
+        let ctx = self.ctx();
         let trace_id = task.trace_id;
         info!(%trace_id, config = ?task.detectors, "task started");
 
         validate_detectors(
             &task.detectors,
             &ctx.config.detectors,
-            &[DetectorType::TextGeneration],
-            true,
+            common::TEXT_GENERATION_DETECTORS,
+        )?;
+        validate_tenant(
+            task.tenant.as_ref(),
+            &[&task.detectors],
+            Some(&task.model_id),
         )?;
 
         // Handle generation
@@ -82,6 +107,7 @@ impl Handle<GenerationWithDetectionTask> for Orchestrator {
         Ok(GenerationWithDetectionResult {
             generated_text,
             input_token_count: generation.input_token_count,
+            warnings: detections.warnings(),
             detections: detections.into(),
         })
     }
@@ -101,6 +127,8 @@ pub struct GenerationWithDetectionTask {
     pub text_gen_parameters: Option<GuardrailsTextGenerationParameters>,
     /// Headers
     pub headers: HeaderMap,
+    /// Tenant
+    pub tenant: Option<Tenant>,
 }
 
 impl GenerationWithDetectionTask {
@@ -108,6 +136,7 @@ impl GenerationWithDetectionTask {
         trace_id: TraceId,
         request: GenerationWithDetectionHttpRequest,
         headers: HeaderMap,
+        tenant: Option<Tenant>,
     ) -> Self {
         Self {
             trace_id,
@@ -115,7 +144,8 @@ impl GenerationWithDetectionTask {
             prompt: request.prompt,
             detectors: request.detectors,
             text_gen_parameters: request.text_gen_parameters,
-            headers,
+            headers: with_tenant_header(headers, tenant.as_ref()),
+            tenant,
         }
     }
 }
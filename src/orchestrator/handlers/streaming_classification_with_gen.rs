@@ -29,8 +29,9 @@ use tracing::{Instrument, error, info, instrument};
 
 use super::Handle;
 use crate::{
+    audit::AnonymizedFlag,
     clients::GenerationClient,
-    config::DetectorType,
+    config::{PolicyDirection, PolicyOutcome},
     models::{
         ClassifiedGeneratedTextStreamResult, DetectionWarning, DetectorParams, GuardrailsConfig,
         GuardrailsHttpRequest, GuardrailsTextGenerationParameters,
@@ -38,11 +39,13 @@ use crate::{
     },
     orchestrator::{
         Context, Error, Orchestrator,
-        common::{self, validate_detectors},
+        common::{self, Anonymizer, Redactions, validate_detectors, validate_tenant},
         types::{
             Chunk, DetectionBatchStream, Detections, GenerationStream, MaxProcessedIndexBatcher,
+            Tenant, with_tenant_header,
         },
     },
+    utils::task_group,
 };
 
 impl Handle<StreamingClassificationWithGenTask> for Orchestrator {
@@ -54,20 +57,51 @@ impl Handle<StreamingClassificationWithGenTask> for Orchestrator {
         fields(
             trace_id = task.trace_id.to_string(),
             model_id = task.model_id,
-            headers = ?task.headers
+            headers = ?task.headers,
+            tenant = task.tenant.as_ref().map(|tenant| tenant.id.as_str())
         )
     )]
     async fn handle(
         &self,
-        task: StreamingClassificationWithGenTask,
+        mut task: StreamingClassificationWithGenTask,
     ) -> Result<Self::Response, Error> {
-        let ctx = self.ctx.clone();
+        let ctx = self.ctx();
+        let audit = self
+            .audit(
+                "/api/v1/task/server-streaming-classification-with-text-generation",
+                task.trace_id,
+                task.tenant.as_ref(),
+            )
+            .detectors(
+                Some(PolicyDirection::Input),
+                &task.guardrails_config.input_detectors(),
+            )
+            .detectors(
+                Some(PolicyDirection::Output),
+                &task.guardrails_config.output_detectors(),
+            )
+            .redactions(
+                Some(PolicyDirection::Output),
+                common::get_redactions(&ctx, &task.guardrails_config.output_detectors())
+                    .into_keys(),
+            );
+        task.anonymized = audit.anonymized_flag();
+        audit.finish(self.handle_streaming_classification_with_gen(task).await)
+    }
+}
+
+impl Orchestrator {
+    async fn handle_streaming_classification_with_gen(
+        &self,
+        mut task: StreamingClassificationWithGenTask,
+    ) -> Result<ReceiverStream<Result<ClassifiedGeneratedTextStreamResult, Error>>, Error> {
+        let ctx = self.ctx();
 
         // Create response channel
         let (response_tx, response_rx) =
             mpsc::channel::<Result<ClassifiedGeneratedTextStreamResult, Error>>(128);
 
-        tokio::spawn(async move {
+        task_group::spawn(async move {
             let trace_id = task.trace_id;
             info!(%trace_id, config = ?task.guardrails_config, "task started");
             let input_detectors = task.guardrails_config.input_detectors();
@@ -79,8 +113,7 @@ impl Handle<StreamingClassificationWithGenTask> for Orchestrator {
             if let Err(error) = validate_detectors(
                 &input_detectors,
                 &ctx.config.detectors,
-                &[DetectorType::TextContents],
-                true,
+                common::TEXT_CONTENTS_DETECTORS,
             ) {
                 let _ = response_tx.send(Err(error)).await;
                 return;
@@ -95,22 +128,45 @@ impl Handle<StreamingClassificationWithGenTask> for Orchestrator {
             if let Err(error) = validate_detectors(
                 &output_detectors,
                 &ctx.config.detectors,
-                &[DetectorType::TextContents],
-                false,
+                common::STREAMING_TEXT_CONTENTS_DETECTORS,
+            ) {
+                let _ = response_tx.send(Err(error)).await;
+                return;
+            }
+            // Validate tenant restrictions
+            if let Err(error) = validate_tenant(
+                task.tenant.as_ref(),
+                &[&input_detectors, &output_detectors],
+                Some(&task.model_id),
             ) {
                 let _ = response_tx.send(Err(error)).await;
                 return;
             }
 
+            let mut anonymizer = Anonymizer::new();
             if !input_detectors.is_empty() {
                 // Handle input detection
-                match handle_input_detection(ctx.clone(), &task, input_detectors).await {
-                    Ok(Some(response)) => {
+                match handle_input_detection(
+                    ctx.clone(),
+                    &mut task,
+                    input_detectors,
+                    &mut anonymizer,
+                )
+                .await
+                {
+                    Ok(Some((PolicyOutcome::Block, response))) => {
                         info!(%trace_id, "task completed: returning response with input detections");
                         // Send message with input detections to response channel and terminate
                         let _ = response_tx.send(Ok(response)).await;
                         return;
                     }
+                    Ok(Some((_, response))) => {
+                        // Send message with input detections to response channel and continue
+                        if response_tx.send(Ok(response)).await.is_err() {
+                            info!(%trace_id, "task completed: client disconnected");
+                            return;
+                        }
+                    }
                     Ok(None) => (), // No input detections
                     Err(error) => {
                         // Input detections failed
@@ -126,7 +182,7 @@ impl Handle<StreamingClassificationWithGenTask> for Orchestrator {
                 .clients
                 .get_as::<GenerationClient>("generation")
                 .unwrap();
-            let generation_stream = match common::generate_stream(
+            let mut generation_stream = match common::generate_stream(
                 client,
                 task.headers.clone(),
                 task.model_id.clone(),
@@ -143,6 +199,11 @@ impl Handle<StreamingClassificationWithGenTask> for Orchestrator {
                     return;
                 }
             };
+            if !anonymizer.is_empty() {
+                // Restore anonymized entities in generated text
+                generation_stream =
+                    common::deanonymize_generation_stream(generation_stream, Arc::new(anonymizer));
+            }
 
             if !output_detectors.is_empty() {
                 // Handle output detection
@@ -167,9 +228,10 @@ impl Handle<StreamingClassificationWithGenTask> for Orchestrator {
 #[instrument(skip_all)]
 async fn handle_input_detection(
     ctx: Arc<Context>,
-    task: &StreamingClassificationWithGenTask,
+    task: &mut StreamingClassificationWithGenTask,
     detectors: HashMap<String, DetectorParams>,
-) -> Result<Option<ClassifiedGeneratedTextStreamResult>, Error> {
+    anonymizer: &mut Anonymizer,
+) -> Result<Option<(PolicyOutcome, ClassifiedGeneratedTextStreamResult)>, Error> {
     let trace_id = task.trace_id;
     let inputs = common::apply_masks(task.inputs.clone(), task.guardrails_config.input_masks());
     let detections = match common::text_contents_detections(
@@ -187,7 +249,13 @@ async fn handle_input_detection(
             return Err(error);
         }
     };
-    if !detections.is_empty() {
+    let mut warnings: Vec<DetectionWarning> = detections.warnings();
+    // Detections of detectors with the `anonymize` action are not subject to policy
+    let anonymizations = common::get_anonymizations(&ctx, &detectors);
+    let (mut anonymized, mut detections) = common::split_anonymized(detections, &anonymizations);
+    let decision = common::evaluate_policy(&ctx, PolicyDirection::Input, &detections);
+    if decision.is_block() {
+        detections.extend(anonymized);
         // Get token count
         let client = ctx
             .clients
@@ -208,16 +276,46 @@ async fn handle_input_detection(
             }
         };
         // Build response with input detections
+        warnings.push(DetectionWarning::unsuitable_input().with_policy(decision.policy()));
         let response = ClassifiedGeneratedTextStreamResult {
             input_token_count,
             token_classification_results: TextGenTokenClassificationResults {
                 input: Some(detections.into()),
                 output: None,
             },
-            warnings: Some(vec![DetectionWarning::unsuitable_input()]),
+            warnings: Some(warnings),
             ..Default::default()
         };
-        Ok(Some(response))
+        return Ok(Some((decision.outcome, response)));
+    }
+    if decision.should_redact(&Redactions::new()) {
+        // Mask detected spans before generation
+        task.inputs = decision.redact_input(&task.inputs, &mut detections, &mut anonymized);
+    }
+    if !anonymized.is_empty() {
+        // Replace detected entities with placeholders before generation
+        task.inputs = anonymizer.anonymize(&task.inputs, &anonymized);
+        task.anonymized.set();
+    }
+    if !decision.is_allow() {
+        // Build message with input detections to be sent ahead of generation
+        warnings.push(DetectionWarning::unsuitable_input().with_policy(decision.policy()));
+        let response = ClassifiedGeneratedTextStreamResult {
+            token_classification_results: TextGenTokenClassificationResults {
+                input: Some(detections.into()),
+                output: None,
+            },
+            warnings: Some(warnings),
+            ..Default::default()
+        };
+        Ok(Some((decision.outcome, response)))
+    } else if !warnings.is_empty() {
+        // No input detections, send warnings ahead of generation
+        let response = ClassifiedGeneratedTextStreamResult {
+            warnings: Some(warnings),
+            ..Default::default()
+        };
+        Ok(Some((decision.outcome, response)))
     } else {
         // No input detections
         Ok(None)
@@ -233,6 +331,7 @@ async fn handle_output_detection(
     response_tx: mpsc::Sender<Result<ClassifiedGeneratedTextStreamResult, Error>>,
 ) {
     let trace_id = task.trace_id;
+    let redactions = common::get_redactions(&ctx, &detectors);
     // Create input channel for detection pipeline
     let (input_tx, input_rx) = mpsc::channel(128);
     // Create shared generations
@@ -240,7 +339,7 @@ async fn handle_output_detection(
         Arc::new(RwLock::new(Vec::new()));
     // Create detection streams
     let detection_streams = common::text_contents_detection_streams(
-        ctx,
+        ctx.clone(),
         task.headers.clone(),
         detectors.clone(),
         0,
@@ -249,7 +348,7 @@ async fn handle_output_detection(
     .await;
 
     // Spawn task to process detection streams
-    tokio::spawn({
+    task_group::spawn({
         let generations = generations.clone();
         async move {
             match detection_streams {
@@ -261,7 +360,9 @@ async fn handle_output_detection(
                     );
                     process_detection_batch_stream(
                         trace_id,
+                        ctx,
                         generations,
+                        redactions,
                         detection_batch_stream,
                         response_tx,
                     )
@@ -278,7 +379,7 @@ async fn handle_output_detection(
     });
 
     // Spawn task to consume generations
-    tokio::spawn(
+    task_group::spawn(
         async move {
             while let Some((index, result)) = generation_stream.next().await {
                 match result {
@@ -332,7 +433,9 @@ async fn forward_generation_stream(
 #[instrument(skip_all)]
 async fn process_detection_batch_stream(
     trace_id: TraceId,
+    ctx: Arc<Context>,
     generations: Arc<RwLock<Vec<ClassifiedGeneratedTextStreamResult>>>,
+    redactions: Redactions,
     mut detection_batch_stream: DetectionBatchStream,
     response_tx: mpsc::Sender<Result<ClassifiedGeneratedTextStreamResult, Error>>,
 ) {
@@ -340,7 +443,9 @@ async fn process_detection_batch_stream(
         match result {
             Ok((_, chunk, detections)) => {
                 // Create response for this batch with output detections
-                let response = output_detection_response(&generations, chunk, detections).unwrap();
+                let response =
+                    output_detection_response(&ctx, &generations, &redactions, chunk, detections)
+                        .unwrap();
                 // Send message to response channel
                 if response_tx.send(Ok(response)).await.is_err() {
                     info!(%trace_id, "task completed: client disconnected");
@@ -360,9 +465,11 @@ async fn process_detection_batch_stream(
 
 /// Builds a response with output detections.
 fn output_detection_response(
+    ctx: &Arc<Context>,
     generations: &Arc<RwLock<Vec<ClassifiedGeneratedTextStreamResult>>>,
+    redactions: &Redactions,
     chunk: Chunk,
-    detections: Detections,
+    mut detections: Detections,
 ) -> Result<ClassifiedGeneratedTextStreamResult, Error> {
     // Get subset of generations relevant for this chunk
     let generations_slice = generations
@@ -376,14 +483,34 @@ fn output_detection_response(
         .iter()
         .flat_map(|generation| generation.tokens.clone().unwrap_or_default())
         .collect::<Vec<_>>();
+    let mut warnings: Vec<DetectionWarning> = detections.warnings();
+    let decision = common::evaluate_policy(ctx, PolicyDirection::Output, &detections);
+    let text = if decision.is_block() {
+        // Withhold chunk text
+        None
+    } else if decision.should_redact(redactions) {
+        // Mask detected spans in chunk text
+        Some(decision.redact(&chunk.text, &mut detections, redactions))
+    } else {
+        Some(chunk.text)
+    };
+    if decision.is_allow() {
+        detections = Detections::default();
+    }
     let mut response = ClassifiedGeneratedTextStreamResult {
-        generated_text: Some(chunk.text),
+        generated_text: text,
         start_index: Some(chunk.start as u32),
         processed_index: Some(chunk.end as u32),
         tokens: Some(tokens),
         ..last
     };
     response.token_classification_results.output = Some(detections.into());
+    if let Some(policy) = decision.policy() {
+        warnings.push(DetectionWarning::unsuitable_output().with_policy(Some(policy)));
+    }
+    if !warnings.is_empty() {
+        response.warnings = Some(warnings);
+    }
     if chunk.input_start_index == 0 {
         // Get input_token_count and seed from first generation message
         let first = generations_slice.first().unwrap();
@@ -413,17 +540,28 @@ pub struct StreamingClassificationWithGenTask {
     pub text_gen_parameters: Option<GuardrailsTextGenerationParameters>,
     /// Headers
     pub headers: HeaderMap,
+    /// Tenant
+    pub tenant: Option<Tenant>,
+    /// Set if entities of the input are anonymized, recorded by the audit
+    pub anonymized: AnonymizedFlag,
 }
 
 impl StreamingClassificationWithGenTask {
-    pub fn new(trace_id: TraceId, request: GuardrailsHttpRequest, headers: HeaderMap) -> Self {
+    pub fn new(
+        trace_id: TraceId,
+        request: GuardrailsHttpRequest,
+        headers: HeaderMap,
+        tenant: Option<Tenant>,
+    ) -> Self {
         Self {
             trace_id,
             model_id: request.model_id,
             inputs: request.inputs,
             guardrails_config: request.guardrail_config.unwrap_or_default(),
             text_gen_parameters: request.text_gen_parameters,
-            headers,
+            headers: with_tenant_header(headers, tenant.as_ref()),
+            tenant,
+            anonymized: AnonymizedFlag::default(),
         }
     }
 }
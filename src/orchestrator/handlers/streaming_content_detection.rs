@@ -25,13 +25,15 @@ use tracing::{Instrument, error, info, instrument};
 
 use super::Handle;
 use crate::{
-    config::DetectorType,
     models::{DetectorParams, StreamingContentDetectionRequest, StreamingContentDetectionResponse},
     orchestrator::{
         Context, Error, Orchestrator,
-        common::{self, validate_detectors},
-        types::{BoxStream, DetectionBatchStream, MaxProcessedIndexBatcher},
+        common::{self, validate_detectors, validate_tenant},
+        types::{
+            BoxStream, DetectionBatchStream, MaxProcessedIndexBatcher, Tenant, with_tenant_header,
+        },
     },
+    utils::task_group,
 };
 
 type InputStream =
@@ -43,19 +45,38 @@ impl Handle<StreamingContentDetectionTask> for Orchestrator {
     #[instrument(
         name = "streaming_content_detection",
         skip_all,
-        fields(trace_id = task.trace_id.to_string(), headers = ?task.headers)
+        fields(
+            trace_id = task.trace_id.to_string(),
+            headers = ?task.headers,
+            tenant = task.tenant.as_ref().map(|tenant| tenant.id.as_str())
+        )
     )]
     async fn handle(&self, task: StreamingContentDetectionTask) -> Result<Self::Response, Error> {
-        let ctx = self.ctx.clone();
+        let audit = self.audit(
+            "/api/v2/text/detection/stream-content",
+            task.trace_id,
+            task.tenant.as_ref(),
+        );
+        audit.finish(self.handle_streaming_content_detection(task).await)
+    }
+}
+
+impl Orchestrator {
+    async fn handle_streaming_content_detection(
+        &self,
+        task: StreamingContentDetectionTask,
+    ) -> Result<ReceiverStream<Result<StreamingContentDetectionResponse, Error>>, Error> {
+        let ctx = self.ctx();
 
         // Create response channel
         let (response_tx, response_rx) =
             mpsc::channel::<Result<StreamingContentDetectionResponse, Error>>(128);
 
-        tokio::spawn(
+        task_group::spawn(
             async move {
                 let trace_id = task.trace_id;
                 let headers = task.headers;
+                let tenant = task.tenant;
                 let mut input_stream = Box::pin(task.input_stream.peekable());
                 let detectors = match extract_detectors(&mut input_stream).await {
                     Ok(detectors) => detectors,
@@ -70,12 +91,15 @@ impl Handle<StreamingContentDetectionTask> for Orchestrator {
                 if let Err(error) = validate_detectors(
                     &detectors,
                     &ctx.config.detectors,
-                    &[DetectorType::TextContents],
-                    false,
+                    common::STREAMING_TEXT_CONTENTS_DETECTORS,
                 ) {
                     let _ = response_tx.send(Err(error)).await;
                     return;
                 }
+                if let Err(error) = validate_tenant(tenant.as_ref(), &[&detectors], None) {
+                    let _ = response_tx.send(Err(error)).await;
+                    return;
+                }
 
                 handle_detection(ctx, trace_id, headers, detectors, input_stream, response_tx)
                     .await;
@@ -130,7 +154,7 @@ async fn handle_detection(
         common::text_contents_detection_streams(ctx, headers, detectors.clone(), 0, input_rx).await;
 
     // Spawn task to process detection streams
-    tokio::spawn(
+    task_group::spawn(
         async move {
             match detection_streams {
                 Ok(detection_streams) => {
@@ -153,7 +177,7 @@ async fn handle_detection(
     );
 
     // Spawn task to consume input stream
-    tokio::spawn(
+    task_group::spawn(
         async move {
             while let Some((index, result)) = input_stream.next().await {
                 match result {
@@ -185,6 +209,7 @@ async fn process_detection_batch_stream(
                 let response = StreamingContentDetectionResponse {
                     start_index: chunk.start as u32,
                     processed_index: chunk.end as u32,
+                    warnings: detections.warnings(),
                     detections: detections.into(),
                 };
                 // Send message to response channel
@@ -213,6 +238,8 @@ pub struct StreamingContentDetectionTask {
     pub detectors: HashMap<String, DetectorParams>,
     /// Input stream to run detections on
     pub input_stream: BoxStream<(usize, Result<StreamingContentDetectionRequest, Error>)>,
+    /// Tenant
+    pub tenant: Option<Tenant>,
 }
 
 impl StreamingContentDetectionTask {
@@ -220,12 +247,14 @@ impl StreamingContentDetectionTask {
         trace_id: TraceId,
         headers: HeaderMap,
         input_stream: BoxStream<(usize, Result<StreamingContentDetectionRequest, Error>)>,
+        tenant: Option<Tenant>,
     ) -> Self {
         Self {
             trace_id,
-            headers,
+            headers: with_tenant_header(headers, tenant.as_ref()),
             detectors: HashMap::default(),
             input_stream,
+            tenant,
         }
     }
 }
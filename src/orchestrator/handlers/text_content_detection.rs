@@ -22,11 +22,11 @@ use tracing::{info, instrument};
 
 use super::Handle;
 use crate::{
-    config::DetectorType,
     models::{DetectorParams, TextContentDetectionHttpRequest, TextContentDetectionResult},
     orchestrator::{
         Error, Orchestrator,
-        common::{self, validate_detectors},
+        common::{self, validate_detectors, validate_tenant},
+        types::{Tenant, with_tenant_header},
     },
 };
 
@@ -36,19 +36,39 @@ impl Handle<TextContentDetectionTask> for Orchestrator {
     #[instrument(
         name = "text_content_detection",
         skip_all,
-        fields(trace_id = ?task.trace_id, headers = ?task.headers)
+        fields(
+            trace_id = ?task.trace_id,
+            headers = ?task.headers,
+            tenant = task.tenant.as_ref().map(|tenant| tenant.id.as_str())
+        )
     )]
     async fn handle(&self, task: TextContentDetectionTask) -> Result<Self::Response, Error> {
-        let ctx = self.ctx.clone();
+        let audit = self
+            .audit(
+                "/api/v2/text/detection/content",
+                task.trace_id,
+                task.tenant.as_ref(),
+            )
+            .detectors(None, &task.detectors);
+        audit.finish(self.handle_text_content_detection(task).await)
+    }
+}
+
+impl Orchestrator {
+    async fn handle_text_content_detection(
+        &self,
+        task: TextContentDetectionTask,
+    ) -> Result<TextContentDetectionResult, Error> {
+        let ctx = self.ctx();
         let trace_id = task.trace_id;
         info!(%trace_id, config = ?task.detectors, "task started");
 
         validate_detectors(
             &task.detectors,
             &ctx.config.detectors,
-            &[DetectorType::TextContents],
-            true,
+            common::TEXT_CONTENTS_DETECTORS,
         )?;
+        validate_tenant(task.tenant.as_ref(), &[&task.detectors], None)?;
 
         // Handle detection
         let (_, detections) = common::text_contents_detections(
@@ -61,6 +81,7 @@ impl Handle<TextContentDetectionTask> for Orchestrator {
         .await?;
 
         Ok(TextContentDetectionResult {
+            warnings: detections.warnings(),
             detections: detections.into(),
         })
     }
@@ -76,6 +97,8 @@ pub struct TextContentDetectionTask {
     pub detectors: HashMap<String, DetectorParams>,
     /// Headers
     pub headers: HeaderMap,
+    /// Tenant
+    pub tenant: Option<Tenant>,
 }
 
 impl TextContentDetectionTask {
@@ -83,12 +106,14 @@ impl TextContentDetectionTask {
         trace_id: TraceId,
         request: TextContentDetectionHttpRequest,
         headers: HeaderMap,
+        tenant: Option<Tenant>,
     ) -> Self {
         Self {
             trace_id,
             content: request.content,
             detectors: request.detectors,
-            headers,
+            headers: with_tenant_header(headers, tenant.as_ref()),
+            tenant,
         }
     }
 }
@@ -14,7 +14,10 @@
  limitations under the License.
 
 */
-use crate::{clients::detector, models};
+use crate::{
+    clients::{detector, openai},
+    models,
+};
 
 /// A detection.
 #[derive(Default, Debug, Clone, PartialEq)]
@@ -39,6 +42,15 @@ pub struct Detection {
     pub metadata: models::Metadata,
 }
 
+impl Detection {
+    /// Returns the (start, end) span of the detection, if any.
+    ///
+    /// Detections on whole chat histories, e.g. by `text_chat` detectors, have no span.
+    pub fn span(&self) -> Option<(usize, usize)> {
+        self.start.zip(self.end)
+    }
+}
+
 /// Detection evidence.
 #[derive(Default, Clone, Debug, PartialEq)]
 pub struct DetectionEvidence {
@@ -60,14 +72,64 @@ pub struct Evidence {
     pub score: Option<f64>,
 }
 
-/// An array of detections.
+/// A detector that failed and was skipped, as configured with `on_error: skip`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedDetector {
+    /// ID of the detector
+    pub detector_id: String,
+    /// Error message
+    pub error: String,
+}
+
+impl SkippedDetector {
+    /// Returns a warning message naming the skipped detector.
+    pub fn message(&self) -> String {
+        format!(
+            "Detector `{}` failed and was skipped: {}",
+            self.detector_id, self.error
+        )
+    }
+}
+
+/// An array of detections and the optional detectors skipped producing them.
 #[derive(Default, Debug, Clone)]
-pub struct Detections(Vec<Detection>);
+pub struct Detections(Vec<Detection>, Vec<SkippedDetector>);
 
 impl Detections {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Creates empty detections for a detector that failed and was skipped.
+    pub fn skipped(detector_id: impl Into<String>, error: impl ToString) -> Self {
+        Self(
+            Vec::new(),
+            vec![SkippedDetector {
+                detector_id: detector_id.into(),
+                error: error.to_string(),
+            }],
+        )
+    }
+
+    /// Returns the detectors skipped due to errors.
+    pub fn skipped_detectors(&self) -> &[SkippedDetector] {
+        &self.1
+    }
+
+    /// Returns warnings naming the optional detectors that failed and were
+    /// skipped, with reason `DETECTOR_FAILED`. Handlers return these in the
+    /// `warnings` field of their responses, alongside policy warnings.
+    pub fn warnings<T>(&self) -> Vec<T>
+    where
+        T: for<'a> From<&'a SkippedDetector>,
+    {
+        self.1.iter().map(Into::into).collect()
+    }
+
+    /// Filters detections, preserving skipped detectors.
+    pub fn filter(self, f: impl FnMut(&Detection) -> bool) -> Self {
+        Self(self.0.into_iter().filter(f).collect(), self.1)
+    }
 }
 
 impl std::ops::Deref for Detections {
@@ -103,9 +165,20 @@ impl FromIterator<Detection> for Detections {
     }
 }
 
+impl FromIterator<Detections> for Detections {
+    fn from_iter<T: IntoIterator<Item = Detections>>(iter: T) -> Self {
+        let mut detections = Detections::new();
+        for value in iter {
+            detections.0.extend(value.0);
+            detections.1.extend(value.1);
+        }
+        detections
+    }
+}
+
 impl From<Vec<Detection>> for Detections {
     fn from(value: Vec<Detection>) -> Self {
-        Self(value)
+        Self(value, Vec::new())
     }
 }
 
@@ -113,10 +186,13 @@ impl From<Vec<Detection>> for Detections {
 
 impl From<detector::ContentAnalysisResponse> for Detection {
     fn from(value: detector::ContentAnalysisResponse) -> Self {
+        // An empty span without text denotes a detection without a span,
+        // see `From<Detection> for detector::ContentAnalysisResponse`
+        let has_span = value.start != 0 || value.end != 0 || !value.text.is_empty();
         Self {
-            start: Some(value.start),
-            end: Some(value.end),
-            text: Some(value.text),
+            start: has_span.then_some(value.start),
+            end: has_span.then_some(value.end),
+            text: has_span.then_some(value.text),
             detector_id: value.detector_id,
             detection_type: value.detection_type,
             detection: value.detection,
@@ -135,11 +211,26 @@ impl From<Vec<Vec<detector::ContentAnalysisResponse>>> for Detections {
         value
             .into_iter()
             .flatten()
-            .map(|detection| detection.into())
+            .map(Detection::from)
             .collect::<Detections>()
     }
 }
 
+impl From<&SkippedDetector> for models::DetectionWarning {
+    fn from(value: &SkippedDetector) -> Self {
+        models::DetectionWarning::detector_failed(value.message())
+    }
+}
+
+impl From<&SkippedDetector> for openai::OrchestratorWarning {
+    fn from(value: &SkippedDetector) -> Self {
+        openai::OrchestratorWarning::new(
+            models::DetectionWarningReason::DetectorFailed,
+            &value.message(),
+        )
+    }
+}
+
 impl From<DetectionEvidence> for models::EvidenceObj {
     fn from(value: DetectionEvidence) -> Self {
         let evidence = (!value.evidence.is_empty())
@@ -248,6 +339,21 @@ impl From<Detection> for models::TokenClassificationResult {
     }
 }
 
+impl From<models::TokenClassificationResult> for Detection {
+    fn from(value: models::TokenClassificationResult) -> Self {
+        Self {
+            start: Some(value.start as usize),
+            end: Some(value.end as usize),
+            text: Some(value.word),
+            detector_id: value.detector_id,
+            detection_type: value.entity_group,
+            detection: value.entity,
+            score: value.score,
+            ..Default::default()
+        }
+    }
+}
+
 impl From<Detections> for Vec<models::TokenClassificationResult> {
     fn from(value: Detections) -> Self {
         value.into_iter().map(Into::into).collect()
@@ -258,10 +364,16 @@ impl From<Detection> for detector::ContentAnalysisResponse {
     fn from(value: Detection) -> Self {
         let evidence = (!value.evidence.is_empty())
             .then_some(value.evidence.into_iter().map(Into::into).collect());
+        // The response requires a span, so detections without a span
+        // are returned with an empty span at the start and no text
+        let (start, end, text) = match value.span() {
+            Some((start, end)) => (start, end, value.text.unwrap_or_default()),
+            None => (0, 0, String::new()),
+        };
         Self {
-            start: value.start.unwrap(),
-            end: value.end.unwrap(),
-            text: value.text.unwrap(),
+            start,
+            end,
+            text,
             detection: value.detection,
             detection_type: value.detection_type,
             detector_id: value.detector_id,
@@ -19,7 +19,7 @@ use tokio::sync::{mpsc, oneshot};
 use tracing::{debug, error};
 
 use super::{Batch, Chunk, DetectionBatcher, DetectionStream, Detections};
-use crate::orchestrator::Error;
+use crate::{orchestrator::Error, utils::task_group};
 
 /// A stream adapter that wraps detection streams and
 /// produces a stream of batches using a [`DetectionBatcher`]
@@ -34,7 +34,7 @@ impl DetectionBatchStream {
     pub fn new(batcher: impl DetectionBatcher, mut streams: Vec<DetectionStream>) -> Self {
         let (batch_tx, batch_rx) = mpsc::channel(32);
         // Spawn task to receive detections and process batches
-        tokio::spawn(async move {
+        task_group::spawn(async move {
             if streams.len() == 1 {
                 // Skip the batching process for a single detection stream
                 let mut stream = streams.swap_remove(0);
@@ -182,7 +182,7 @@ impl DetectionBatcherManagerHandle {
     pub fn new(batcher: impl DetectionBatcher) -> Self {
         let (tx, rx) = mpsc::channel(32);
         let mut actor = DetectionBatcherManager::new(batcher, rx);
-        tokio::spawn(async move { actor.run().await });
+        task_group::spawn(async move { actor.run().await });
         Self { tx }
     }
 
@@ -79,7 +79,7 @@ impl DetectionBatcher for ChatCompletionBatcher {
         {
             // We have all detections for the chunk, remove and return it.
             if let Some(((chunk, choice_index), detections)) = self.state.pop_first() {
-                let detections = detections.into_iter().flatten().collect();
+                let detections = detections.into_iter().collect();
                 return Some((choice_index, chunk, detections));
             }
         }
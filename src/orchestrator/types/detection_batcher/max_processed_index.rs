@@ -71,7 +71,7 @@ impl DetectionBatcher for MaxProcessedIndexBatcher {
         {
             // We have all detections for the chunk, remove and return it.
             if let Some((chunk, detections)) = self.state.pop_first() {
-                let detections = detections.into_iter().flatten().collect();
+                let detections = detections.into_iter().collect();
                 return Some((0, chunk, detections));
             }
         }
@@ -0,0 +1,147 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+use std::collections::{HashMap, HashSet};
+
+use http::{HeaderMap, HeaderValue};
+
+use super::DetectorId;
+use crate::{config::TenantConfig, models::DetectorParams, orchestrator::Error};
+
+/// Internal header carrying the hex encoded tenant ID of a task, scoping detector result caches.
+/// Removed from incoming requests and not sent to downstream services.
+pub const TENANT_HEADER: &str = "x-orchestrator-tenant";
+
+/// Authenticated caller identity and its restrictions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tenant {
+    /// Tenant ID
+    pub id: String,
+    /// Detectors the tenant may use, all if `None`
+    pub detectors: Option<HashSet<DetectorId>>,
+    /// Generation models the tenant may use, all if `None`
+    pub models: Option<HashSet<String>>,
+}
+
+impl Tenant {
+    pub fn new(id: impl Into<String>, config: Option<&TenantConfig>) -> Self {
+        Self {
+            id: id.into(),
+            detectors: config.and_then(|config| config.detectors.clone()),
+            models: config.and_then(|config| config.models.clone()),
+        }
+    }
+
+    /// Returns `true` if the tenant is allowed to use a detector.
+    pub fn allows_detector(&self, detector_id: &str) -> bool {
+        self.detectors
+            .as_ref()
+            .is_none_or(|allowed| allowed.contains(detector_id))
+    }
+
+    /// Validates the tenant is allowed to use detectors.
+    pub fn validate_detectors(
+        &self,
+        detectors: &HashMap<String, DetectorParams>,
+    ) -> Result<(), Error> {
+        if let Some(detector_id) = detectors
+            .keys()
+            .find(|detector_id| !self.allows_detector(detector_id))
+        {
+            return Err(Error::Forbidden(format!(
+                "tenant `{}` is not allowed to use detector `{detector_id}`",
+                self.id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Validates the tenant is allowed to use a generation model.
+    pub fn validate_model(&self, model_id: &str) -> Result<(), Error> {
+        if self
+            .models
+            .as_ref()
+            .is_some_and(|allowed| !allowed.contains(model_id))
+        {
+            return Err(Error::Forbidden(format!(
+                "tenant `{}` is not allowed to use model `{model_id}`",
+                self.id
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Tenant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+/// Returns `headers` with the internal tenant header set for `tenant`, if any.
+pub fn with_tenant_header(mut headers: HeaderMap, tenant: Option<&Tenant>) -> HeaderMap {
+    headers.remove(TENANT_HEADER);
+    if let Some(tenant) = tenant {
+        // Hex encoded, as IDs from token claims may not be valid header values
+        let id = tenant
+            .id
+            .bytes()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        headers.insert(TENANT_HEADER, HeaderValue::from_str(&id).unwrap());
+    }
+    headers
+}
+
+/// Returns the tenant scope carried by the internal tenant header, if any.
+pub fn tenant_scope(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(TENANT_HEADER)
+        .and_then(|value| value.to_str().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let tenant = Tenant::new(
+            "team-a",
+            Some(&TenantConfig {
+                detectors: Some(HashSet::from(["hap".into()])),
+                models: Some(HashSet::from(["granite".into()])),
+            }),
+        );
+        let detectors = HashMap::from([("hap".to_string(), DetectorParams::new())]);
+        assert!(tenant.validate_detectors(&detectors).is_ok());
+        let detectors = HashMap::from([("pii".to_string(), DetectorParams::new())]);
+        assert!(matches!(
+            tenant.validate_detectors(&detectors),
+            Err(Error::Forbidden(_))
+        ));
+        assert!(tenant.validate_model("granite").is_ok());
+        assert!(matches!(
+            tenant.validate_model("llama"),
+            Err(Error::Forbidden(_))
+        ));
+
+        // Tenants without restrictions are allowed to use all detectors and models
+        let tenant = Tenant::new("team-b", None);
+        assert!(tenant.validate_detectors(&detectors).is_ok());
+        assert!(tenant.validate_model("llama").is_ok());
+    }
+}
@@ -14,40 +14,78 @@
  limitations under the License.
 
 */
-use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
 
+use http::StatusCode;
 use tokio::{net::TcpListener, signal};
 use tower_http::trace::TraceLayer;
-use tracing::info;
+use tracing::{error, info};
 
-use crate::orchestrator::Orchestrator;
+use crate::{config::OrchestratorConfig, orchestrator::Orchestrator};
 
+mod auth;
 mod errors;
+mod grpc;
+mod jobs;
+mod limits;
 mod routes;
 mod tls;
+mod ws;
+use auth::Authenticator;
 pub use errors::Error;
+use jobs::JobStore;
+use limits::Limiter;
 use tls::{configure_tls, serve_with_tls};
 
-/// Configures and runs orchestrator servers.
+/// Config file watched for changes, see [`ServerState::watch_config`].
+#[derive(Debug, Clone)]
+pub struct ConfigWatch {
+    /// Config file path
+    pub path: PathBuf,
+    /// Interval between config file modification checks, if unset the config
+    /// is only reloaded on `SIGHUP`
+    pub interval: Option<Duration>,
+}
+
+/// Configures and runs orchestrator servers, returning the health, guardrails
+/// and, if `grpc_addr` is provided, gRPC server handles. If `config_watch` is
+/// provided, the config is reloaded on `SIGHUP` and config file changes.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 pub async fn run(
     guardrails_addr: SocketAddr,
     health_addr: SocketAddr,
+    grpc_addr: Option<SocketAddr>,
     tls_cert_path: Option<PathBuf>,
     tls_key_path: Option<PathBuf>,
     tls_client_ca_cert_path: Option<PathBuf>,
     orchestrator: Orchestrator,
-) -> Result<(tokio::task::JoinHandle<()>, tokio::task::JoinHandle<()>), Error> {
-    let state = Arc::new(ServerState::new(orchestrator));
+    config_watch: Option<ConfigWatch>,
+) -> Result<
+    (
+        tokio::task::JoinHandle<()>,
+        tokio::task::JoinHandle<()>,
+        Option<tokio::task::JoinHandle<()>>,
+    ),
+    Error,
+> {
+    let state = Arc::new(ServerState::new(orchestrator)?);
+    if let Some(config_watch) = config_watch {
+        state.watch_config(config_watch);
+    }
+    let tls_config = configure_tls(tls_cert_path, tls_key_path, tls_client_ca_cert_path);
     let health_handle = run_health_server(health_addr, state.clone()).await?;
-    let guardrails_handle = run_guardrails_server(
-        guardrails_addr,
-        tls_cert_path,
-        tls_key_path,
-        tls_client_ca_cert_path,
-        state,
-    )
-    .await?;
-    Ok((health_handle, guardrails_handle))
+    let guardrails_handle =
+        run_guardrails_server(guardrails_addr, tls_config.clone(), state.clone()).await?;
+    let grpc_handle = match grpc_addr {
+        Some(grpc_addr) => Some(run_grpc_server(grpc_addr, tls_config, state).await?),
+        None => None,
+    };
+    Ok((health_handle, guardrails_handle, grpc_handle))
 }
 
 /// Configures and runs health server.
@@ -68,9 +106,7 @@ async fn run_health_server(
 /// Configures and runs guardrails server.
 async fn run_guardrails_server(
     addr: SocketAddr,
-    tls_cert_path: Option<PathBuf>,
-    tls_key_path: Option<PathBuf>,
-    tls_client_ca_cert_path: Option<PathBuf>,
+    tls_config: Option<Arc<rustls::ServerConfig>>,
     state: Arc<ServerState>,
 ) -> Result<tokio::task::JoinHandle<()>, Error> {
     info!("starting guardrails server on {addr}");
@@ -83,7 +119,6 @@ async fn run_guardrails_server(
             .on_eos(crate::utils::trace::on_outgoing_eos),
     );
     let listener = TcpListener::bind(&addr).await?;
-    let tls_config = configure_tls(tls_cert_path, tls_key_path, tls_client_ca_cert_path);
     let shutdown_signal = shutdown_signal();
     if let Some(tls_config) = tls_config {
         Ok(serve_with_tls(app, listener, tls_config, shutdown_signal))
@@ -96,6 +131,42 @@ async fn run_guardrails_server(
     }
 }
 
+/// Configures and runs gRPC server.
+async fn run_grpc_server(
+    addr: SocketAddr,
+    tls_config: Option<Arc<rustls::ServerConfig>>,
+    state: Arc<ServerState>,
+) -> Result<tokio::task::JoinHandle<()>, Error> {
+    info!("starting grpc server on {addr}");
+    let router = tonic::service::Routes::new(grpc::service(state)).into_axum_router();
+    let app = router.layer(
+        TraceLayer::new_for_grpc()
+            .make_span_with(crate::utils::trace::incoming_request_span)
+            .on_request(crate::utils::trace::on_incoming_request)
+            .on_response(crate::utils::trace::on_outgoing_response)
+            .on_eos(crate::utils::trace::on_outgoing_eos),
+    );
+    let listener = TcpListener::bind(&addr).await?;
+    let shutdown_signal = shutdown_signal();
+    if let Some(tls_config) = tls_config {
+        // gRPC requires HTTP/2
+        let mut tls_config = (*tls_config).clone();
+        tls_config.alpn_protocols = vec![b"h2".to_vec()];
+        Ok(serve_with_tls(
+            app,
+            listener,
+            Arc::new(tls_config),
+            shutdown_signal,
+        ))
+    } else {
+        let server =
+            axum::serve(listener, app.into_make_service()).with_graceful_shutdown(shutdown_signal);
+        Ok(tokio::task::spawn(async {
+            server.await.expect("grpc server crashed!")
+        }))
+    }
+}
+
 /// Shutdown signal handler
 async fn shutdown_signal() {
     let ctrl_c = async {
@@ -120,14 +191,152 @@ async fn shutdown_signal() {
 }
 
 /// Server shared state
+///
+/// The authenticator and limiter are swapped when the config is reloaded.
 pub struct ServerState {
     orchestrator: Orchestrator,
+    authenticator: RwLock<Option<Arc<Authenticator>>>,
+    limiter: RwLock<Option<Arc<Limiter>>>,
+    jobs: JobStore,
 }
 
 impl ServerState {
-    pub fn new(orchestrator: Orchestrator) -> Self {
-        Self { orchestrator }
+    pub fn new(orchestrator: Orchestrator) -> Result<Self, Error> {
+        let config = orchestrator.config();
+        let authenticator = create_authenticator(&config)?;
+        let limiter = create_limiter(&config);
+        let jobs = JobStore::new(&config.jobs)?;
+        Ok(Self {
+            orchestrator,
+            authenticator: RwLock::new(authenticator),
+            limiter: RwLock::new(limiter),
+            jobs,
+        })
+    }
+
+    /// Returns the current authenticator, if authentication is enabled.
+    pub fn authenticator(&self) -> Option<Arc<Authenticator>> {
+        self.authenticator.read().unwrap().clone()
     }
+
+    /// Returns the current limiter, if limits are enabled.
+    pub fn limiter(&self) -> Option<Arc<Limiter>> {
+        self.limiter.read().unwrap().clone()
+    }
+
+    /// Reloads the orchestrator with a new config and swaps the authenticator and,
+    /// if limits changed, the limiter. The jobs config and enabled endpoints are fixed
+    /// at startup, so configs changing them are rejected. On error, the current config is kept.
+    pub async fn reload(&self, config: OrchestratorConfig) -> Result<(), Error> {
+        let current = self.orchestrator.config();
+        if config.jobs != current.jobs {
+            return Err(reload_error(
+                "jobs config cannot be changed without a restart",
+            ));
+        }
+        if config.chat_completions.is_some() != current.chat_completions.is_some()
+            || config.completions.is_some() != current.completions.is_some()
+        {
+            return Err(reload_error(
+                "enabled endpoints cannot be changed without a restart",
+            ));
+        }
+        // Authenticator is always recreated to pick up changed API key and JWT key files
+        let authenticator = create_authenticator(&config)?;
+        // Limiter is kept if limits did not change to preserve rate and stream state
+        let limiter = if config.limits == current.limits {
+            self.limiter()
+        } else {
+            create_limiter(&config)
+        };
+        self.orchestrator
+            .reload(config)
+            .await
+            .map_err(|error| reload_error(error.to_string()))?;
+        *self.authenticator.write().unwrap() = authenticator;
+        *self.limiter.write().unwrap() = limiter;
+        info!("server config reloaded");
+        Ok(())
+    }
+
+    /// Spawns a task reloading the config on `SIGHUP` and, if the watch interval
+    /// is set, when the config file is modified. Invalid configs are rejected,
+    /// keeping the current config.
+    pub fn watch_config(
+        self: &Arc<Self>,
+        config_watch: ConfigWatch,
+    ) -> tokio::task::JoinHandle<()> {
+        let state = self.clone();
+        let ConfigWatch { path, interval } = config_watch;
+        tokio::spawn(async move {
+            #[cfg(unix)]
+            let mut sighup = signal::unix::signal(signal::unix::SignalKind::hangup())
+                .expect("failed to install SIGHUP handler");
+            let mut interval = interval.map(tokio::time::interval);
+            let mut modified = config_modified(&path).await;
+            loop {
+                #[cfg(unix)]
+                let hangup = sighup.recv();
+                #[cfg(not(unix))]
+                let hangup = std::future::pending::<Option<()>>();
+                let tick = async {
+                    match interval.as_mut() {
+                        Some(interval) => interval.tick().await,
+                        None => std::future::pending().await,
+                    }
+                };
+                tokio::select! {
+                    _ = hangup => info!("SIGHUP received, reloading config"),
+                    _ = tick => {
+                        let current = config_modified(&path).await;
+                        if current == modified {
+                            continue;
+                        }
+                        modified = current;
+                        info!(path = %path.display(), "config file modified, reloading config");
+                    }
+                }
+                match OrchestratorConfig::load(&path).await {
+                    Ok(config) => {
+                        if let Err(error) = state.reload(config).await {
+                            error!(%error, "config reload failed, keeping current config");
+                        }
+                    }
+                    Err(error) => error!(%error, "invalid config, keeping current config"),
+                }
+            }
+        })
+    }
+}
+
+fn create_authenticator(config: &OrchestratorConfig) -> Result<Option<Arc<Authenticator>>, Error> {
+    config
+        .auth
+        .as_ref()
+        .map(|auth| Authenticator::new(auth).map(Arc::new))
+        .transpose()
+}
+
+fn create_limiter(config: &OrchestratorConfig) -> Option<Arc<Limiter>> {
+    config
+        .limits
+        .as_ref()
+        .map(|limits| Arc::new(Limiter::new(limits)))
+}
+
+fn reload_error(details: impl Into<String>) -> Error {
+    Error {
+        code: StatusCode::INTERNAL_SERVER_ERROR,
+        details: details.into(),
+    }
+}
+
+/// Returns the modified time of the config file, if available.
+async fn config_modified(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok()
 }
 
 #[cfg(test)]
@@ -145,7 +354,9 @@ mod tests {
             None,
             None,
             None,
+            None,
             Orchestrator::default(),
+            None,
         )
         .await;
         assert!(result.is_err_and(|error| {
@@ -165,13 +376,15 @@ mod tests {
             .collect();
         let tls_cert_path = resources.join("localhost.crt");
         let tls_key_path = resources.join("localhost.key");
-        let (_health_handle, guardrails_handle) = run(
+        let (_health_handle, guardrails_handle, _grpc_handle) = run(
             guardrails_addr,
             health_addr,
+            None,
             Some(tls_cert_path),
             Some(tls_key_path),
             None,
             Orchestrator::default(),
+            None,
         )
         .await?;
 
@@ -181,4 +394,88 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_run_grpc_with_tls() -> Result<(), Error> {
+        let guardrails_addr: SocketAddr = "0.0.0.0:50106".parse().unwrap();
+        let health_addr: SocketAddr = "0.0.0.0:50107".parse().unwrap();
+        let grpc_addr: SocketAddr = "0.0.0.0:50108".parse().unwrap();
+        let resources: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "resources"]
+            .iter()
+            .collect();
+        let tls_cert_path = resources.join("localhost.crt");
+        let tls_key_path = resources.join("localhost.key");
+        let (_health_handle, _guardrails_handle, grpc_handle) = run(
+            guardrails_addr,
+            health_addr,
+            Some(grpc_addr),
+            Some(tls_cert_path),
+            Some(tls_key_path),
+            None,
+            Orchestrator::default(),
+            None,
+        )
+        .await?;
+
+        // Ensure grpc server task is still running
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        assert!(grpc_handle.is_some_and(|handle| !handle.is_finished()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reload() -> Result<(), Error> {
+        use crate::config::{ApiKeyConfig, AuthConfig, JobsConfig};
+
+        let key_path = std::env::temp_dir().join("test_reload_api_key");
+        std::fs::write(&key_path, "key-1")?;
+        let config = OrchestratorConfig {
+            auth: Some(AuthConfig {
+                api_keys: vec![ApiKeyConfig {
+                    tenant: "tenant-1".into(),
+                    key_path: key_path.clone(),
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let state = ServerState::new(Orchestrator::new(config.clone(), false).await?)?;
+        let headers = |key: &str| {
+            http::HeaderMap::from_iter([(
+                http::header::AUTHORIZATION,
+                format!("Bearer {key}").parse().unwrap(),
+            )])
+        };
+        assert!(
+            state
+                .authenticator()
+                .unwrap()
+                .authenticate(&headers("key-1"))
+                .is_ok()
+        );
+        assert!(state.limiter().is_none());
+
+        // Revoked API key is rejected after reload and limits are enabled
+        std::fs::write(&key_path, "key-2")?;
+        let mut new_config = config.clone();
+        new_config.limits = Some(Default::default());
+        state.reload(new_config).await?;
+        let authenticator = state.authenticator().unwrap();
+        assert!(authenticator.authenticate(&headers("key-1")).is_err());
+        assert!(authenticator.authenticate(&headers("key-2")).is_ok());
+        assert!(state.limiter().is_some());
+
+        // Jobs config changes are rejected, keeping the current config
+        let mut new_config = config;
+        new_config.jobs = JobsConfig {
+            max_jobs: 1,
+            ..Default::default()
+        };
+        assert!(state.reload(new_config).await.is_err());
+        assert!(state.limiter().is_some());
+
+        std::fs::remove_file(&key_path)?;
+        Ok(())
+    }
 }
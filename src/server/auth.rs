@@ -0,0 +1,294 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+//! Guardrails server authentication
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use http::{HeaderMap, StatusCode, header::AUTHORIZATION};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde_json::{Map, Value};
+use tracing::{debug, warn};
+
+use super::{Error, ServerState};
+use crate::{
+    config::{AuthConfig, JwtAlgorithm, JwtConfig, TenantConfig},
+    orchestrator::types::Tenant,
+};
+
+/// Authenticates callers with API keys or JWT bearer tokens,
+/// resolving the tenant they identify.
+pub struct Authenticator {
+    /// API keys and the tenant they identify
+    api_keys: Vec<(String, String)>,
+    /// JWT verifier, if configured
+    jwt: Option<JwtVerifier>,
+    /// Restrictions per tenant
+    tenants: HashMap<String, TenantConfig>,
+}
+
+struct JwtVerifier {
+    key: DecodingKey,
+    validation: Validation,
+    tenant_claim: String,
+}
+
+impl Authenticator {
+    /// Creates an authenticator, loading keys from files.
+    pub fn new(config: &AuthConfig) -> Result<Self, Error> {
+        let api_keys = config
+            .api_keys
+            .iter()
+            .map(|api_key| {
+                let key = std::fs::read_to_string(&api_key.key_path)?
+                    .trim()
+                    .to_string();
+                if key.is_empty() {
+                    return Err(internal_error(format!(
+                        "api key file `{}` is empty",
+                        api_key.key_path.display()
+                    )));
+                }
+                Ok((key, api_key.tenant.clone()))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let jwt = config.jwt.as_ref().map(JwtVerifier::new).transpose()?;
+        Ok(Self {
+            api_keys,
+            jwt,
+            tenants: config.tenants.clone(),
+        })
+    }
+
+    /// Authenticates a request from its `Authorization: Bearer` header.
+    pub fn authenticate(&self, headers: &HeaderMap) -> Result<Tenant, Error> {
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .ok_or_else(|| unauthorized("missing bearer token"))?;
+        // API keys are checked first, tokens not matching an API key are verified as JWTs
+        let tenant_id = match self
+            .api_keys
+            .iter()
+            .find(|(key, _)| constant_time_eq(key.as_bytes(), token.as_bytes()))
+        {
+            Some((_, tenant_id)) => tenant_id.clone(),
+            None => match &self.jwt {
+                Some(jwt) => jwt.verify(token)?,
+                None => return Err(unauthorized("invalid api key")),
+            },
+        };
+        Ok(Tenant::new(&tenant_id, self.tenants.get(&tenant_id)))
+    }
+}
+
+impl JwtVerifier {
+    fn new(config: &JwtConfig) -> Result<Self, Error> {
+        let key = std::fs::read(&config.key_path)?;
+        let (key, algorithm) = match config.algorithm {
+            JwtAlgorithm::HS256 => (
+                DecodingKey::from_secret(key.trim_ascii_end()),
+                Algorithm::HS256,
+            ),
+            JwtAlgorithm::RS256 => (
+                DecodingKey::from_rsa_pem(&key).map_err(|error| {
+                    internal_error(format!(
+                        "invalid RS256 public key `{}`: {error}",
+                        config.key_path.display()
+                    ))
+                })?,
+                Algorithm::RS256,
+            ),
+        };
+        let mut validation = Validation::new(algorithm);
+        if let Some(issuer) = &config.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &config.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        Ok(Self {
+            key,
+            validation,
+            tenant_claim: config.tenant_claim.clone(),
+        })
+    }
+
+    /// Verifies a token, returning the tenant it identifies.
+    fn verify(&self, token: &str) -> Result<String, Error> {
+        let claims = jsonwebtoken::decode::<Map<String, Value>>(token, &self.key, &self.validation)
+            .map_err(|error| unauthorized(format!("invalid token: {error}")))?
+            .claims;
+        claims
+            .get(&self.tenant_claim)
+            .and_then(|value| value.as_str())
+            .map(|tenant_id| tenant_id.to_string())
+            .ok_or_else(|| {
+                unauthorized(format!(
+                    "token is missing the `{}` claim",
+                    self.tenant_claim
+                ))
+            })
+    }
+}
+
+/// Authentication middleware, adding the authenticated [`Tenant`] to request extensions.
+pub async fn authenticate(
+    State(state): State<Arc<ServerState>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, Error> {
+    if let Some(authenticator) = state.authenticator() {
+        let tenant = authenticator
+            .authenticate(request.headers())
+            .inspect_err(|error| warn!(%error, "authentication failed"))?;
+        debug!(%tenant, "request authenticated");
+        request.extensions_mut().insert(tenant);
+    }
+    Ok(next.run(request).await)
+}
+
+/// Compares bytes in constant time with respect to their contents.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn unauthorized(details: impl Into<String>) -> Error {
+    Error {
+        code: StatusCode::UNAUTHORIZED,
+        details: details.into(),
+    }
+}
+
+fn internal_error(details: impl Into<String>) -> Error {
+    Error {
+        code: StatusCode::INTERNAL_SERVER_ERROR,
+        details: details.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        path::PathBuf,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    use http::HeaderValue;
+    use jsonwebtoken::{EncodingKey, Header};
+    use serde_json::json;
+
+    use super::*;
+    use crate::config::ApiKeyConfig;
+
+    fn write_key(name: &str, key: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        std::fs::write(&path, key).unwrap();
+        path
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_authenticate_api_key() -> Result<(), Error> {
+        let config = AuthConfig {
+            api_keys: vec![ApiKeyConfig {
+                tenant: "team-a".into(),
+                key_path: write_key("team-a.key", "secret-api-key\n"),
+            }],
+            tenants: HashMap::from([(
+                "team-a".into(),
+                TenantConfig {
+                    detectors: Some(HashSet::from(["hap".into()])),
+                    models: None,
+                },
+            )]),
+            ..Default::default()
+        };
+        let authenticator = Authenticator::new(&config)?;
+
+        let tenant = authenticator.authenticate(&bearer("secret-api-key"))?;
+        assert_eq!(tenant.id, "team-a");
+        assert_eq!(tenant.detectors, Some(HashSet::from(["hap".into()])));
+
+        let error = authenticator
+            .authenticate(&bearer("wrong-api-key"))
+            .unwrap_err();
+        assert_eq!(error.code, StatusCode::UNAUTHORIZED);
+        let error = authenticator.authenticate(&HeaderMap::new()).unwrap_err();
+        assert_eq!(error.code, StatusCode::UNAUTHORIZED);
+        Ok(())
+    }
+
+    #[test]
+    fn test_authenticate_jwt() -> Result<(), Error> {
+        let secret = "jwt-secret";
+        let config = AuthConfig {
+            jwt: Some(JwtConfig {
+                algorithm: JwtAlgorithm::HS256,
+                key_path: write_key("jwt.key", secret),
+                issuer: Some("issuer".into()),
+                audience: None,
+                tenant_claim: "tenant".into(),
+            }),
+            ..Default::default()
+        };
+        let authenticator = Authenticator::new(&config)?;
+        let exp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 60;
+        let encode = |claims: Value| {
+            jsonwebtoken::encode(
+                &Header::default(),
+                &claims,
+                &EncodingKey::from_secret(secret.as_bytes()),
+            )
+            .unwrap()
+        };
+
+        let token = encode(json!({"tenant": "team-b", "iss": "issuer", "exp": exp}));
+        let tenant = authenticator.authenticate(&bearer(&token))?;
+        assert_eq!(tenant, Tenant::new("team-b", None));
+
+        // Invalid issuer
+        let token = encode(json!({"tenant": "team-b", "iss": "other", "exp": exp}));
+        let error = authenticator.authenticate(&bearer(&token)).unwrap_err();
+        assert_eq!(error.code, StatusCode::UNAUTHORIZED);
+
+        // Missing tenant claim
+        let token = encode(json!({"sub": "team-b", "iss": "issuer", "exp": exp}));
+        let error = authenticator.authenticate(&bearer(&token)).unwrap_err();
+        assert_eq!(error.code, StatusCode::UNAUTHORIZED);
+        Ok(())
+    }
+}
@@ -24,7 +24,7 @@ use axum::{
 use http::StatusCode;
 use serde::{Deserialize, Serialize};
 
-use crate::{models::ValidationError, orchestrator};
+use crate::{models::ValidationError, orchestrator, utils::metrics};
 
 /// High-level errors to return to clients.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -95,6 +95,7 @@ impl From<ValidationError> for Error {
 impl From<orchestrator::Error> for Error {
     fn from(value: orchestrator::Error) -> Self {
         use orchestrator::Error::*;
+        metrics::on_error(&value);
         match value {
             DetectorNotFound(_) | ChunkerNotFound(_) => Self {
                 code: StatusCode::NOT_FOUND,
@@ -104,6 +105,7 @@ impl From<orchestrator::Error> for Error {
             | ChunkerRequestFailed { ref error, .. }
             | GenerateRequestFailed { ref error, .. }
             | ChatCompletionRequestFailed { ref error, .. }
+            | CompletionRequestFailed { ref error, .. }
             | TokenizeRequestFailed { ref error, .. } => match error.status_code() {
                 // return actual error for subset of errors
                 StatusCode::BAD_REQUEST
@@ -123,6 +125,14 @@ impl From<orchestrator::Error> for Error {
                 code: StatusCode::UNPROCESSABLE_ENTITY,
                 details: message,
             },
+            Forbidden(message) => Self {
+                code: StatusCode::FORBIDDEN,
+                details: message,
+            },
+            DeadlineExceeded { .. } => Self {
+                code: StatusCode::GATEWAY_TIMEOUT,
+                details: value.to_string(),
+            },
             _ => Self {
                 code: StatusCode::INTERNAL_SERVER_ERROR,
                 details: "unexpected error occurred while processing request".into(),
@@ -130,3 +140,23 @@ impl From<orchestrator::Error> for Error {
         }
     }
 }
+
+impl From<Error> for tonic::Status {
+    fn from(value: Error) -> Self {
+        use tonic::Code;
+        let code = match value.code {
+            StatusCode::BAD_REQUEST
+            | StatusCode::UNPROCESSABLE_ENTITY
+            | StatusCode::UNSUPPORTED_MEDIA_TYPE
+            | StatusCode::PAYLOAD_TOO_LARGE => Code::InvalidArgument,
+            StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+            StatusCode::FORBIDDEN => Code::PermissionDenied,
+            StatusCode::NOT_FOUND => Code::NotFound,
+            StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+            StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+            StatusCode::GATEWAY_TIMEOUT => Code::DeadlineExceeded,
+            _ => Code::Internal,
+        };
+        Self::new(code, value.details)
+    }
+}
@@ -0,0 +1,499 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+//! gRPC front-end for the orchestrator API.
+//!
+//! Requests and responses are [`Struct`] messages holding the JSON bodies of the
+//! equivalent HTTP endpoints, see `protos/orchestrator.proto`.
+use std::sync::Arc;
+
+use futures::{StreamExt, TryStreamExt, stream::BoxStream};
+use http::StatusCode;
+use prost_types::{ListValue, Struct, value::Kind};
+use serde::{Serialize, de::DeserializeOwned};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming, metadata::MetadataMap};
+
+use super::{Error, ServerState, limits, routes::filter_headers};
+use crate::{
+    clients::openai::{ChatCompletionsRequest, ChatCompletionsResponse},
+    models::{
+        self, ClassifiedGeneratedTextStreamResult, StreamingContentDetectionRequest,
+        StreamingContentDetectionResponse,
+    },
+    orchestrator::{
+        self,
+        handlers::{chat_completions_detection::ChatCompletionsDetectionTask, *},
+        types::Tenant,
+    },
+    pb::guardrails::orchestrator::v1::guardrails_orchestrator_server::{
+        GuardrailsOrchestrator, GuardrailsOrchestratorServer, SERVICE_NAME,
+    },
+    utils::{metrics, trace::current_trace_id},
+};
+
+type ResponseStream = BoxStream<'static, Result<Struct, Status>>;
+
+/// Creates the gRPC service.
+pub fn service(state: Arc<ServerState>) -> GuardrailsOrchestratorServer<GrpcService> {
+    GuardrailsOrchestratorServer::new(GrpcService { state })
+}
+
+pub struct GrpcService {
+    state: Arc<ServerState>,
+}
+
+impl GrpcService {
+    /// Authenticates a request and checks the request rate limit, returning the tenant.
+    fn admit(&self, metadata: &MetadataMap) -> Result<Option<Tenant>, Error> {
+        let tenant = match self.state.authenticator() {
+            Some(authenticator) => Some(
+                authenticator
+                    .authenticate(&metadata.clone().into_headers())
+                    .inspect_err(|error| tracing::warn!(%error, "authentication failed"))?,
+            ),
+            None => None,
+        };
+        if let Some(limiter) = self.state.limiter() {
+            limiter
+                .check_rate(tenant.as_ref())
+                .inspect_err(limits::on_rejected)
+                .map_err(limit_exceeded)?;
+        }
+        Ok(tenant)
+    }
+
+    /// Acquires a concurrent stream permit and records the stream in flight,
+    /// both held until the returned stream completes.
+    fn track_stream(
+        &self,
+        method: &str,
+        tenant: Option<&Tenant>,
+        stream: ResponseStream,
+    ) -> Result<ResponseStream, Error> {
+        let permit = self
+            .state
+            .limiter()
+            .map(|limiter| {
+                limiter
+                    .acquire_stream(tenant)
+                    .inspect_err(limits::on_rejected)
+                    .map_err(limit_exceeded)
+            })
+            .transpose()?;
+        let stream_guard = metrics::track_stream(&format!("/{SERVICE_NAME}/{method}"));
+        Ok(stream
+            .map(move |message| {
+                let _permit = &permit;
+                let _stream_guard = &stream_guard;
+                message
+            })
+            .boxed())
+    }
+
+    /// Returns passthrough headers from request metadata.
+    fn headers(&self, metadata: &MetadataMap) -> http::HeaderMap {
+        filter_headers(
+            &self.state.orchestrator.config().passthrough_headers,
+            metadata.clone().into_headers(),
+        )
+    }
+}
+
+#[tonic::async_trait]
+impl GuardrailsOrchestrator for GrpcService {
+    type ServerStreamingClassificationWithTextGenerationStream = ResponseStream;
+    type StreamContentDetectionStream = ResponseStream;
+    type StreamingChatCompletionsDetectionStream = ResponseStream;
+
+    async fn classification_with_text_generation(
+        &self,
+        request: Request<Struct>,
+    ) -> Result<Response<Struct>, Status> {
+        let trace_id = current_trace_id();
+        let tenant = self.admit(request.metadata())?;
+        let headers = self.headers(request.metadata());
+        let mut request: models::GuardrailsHttpRequest = from_struct(request.into_inner())?;
+        request
+            .apply_guardrail_profile(&self.state.orchestrator.config())
+            .map_err(Error::from)?;
+        request.validate().map_err(Error::from)?;
+        let task = ClassificationWithGenTask::new(trace_id, request, headers, tenant);
+        let response = self
+            .state
+            .orchestrator
+            .handle(task)
+            .await
+            .map_err(Error::from)?;
+        Ok(Response::new(to_struct(&response)?))
+    }
+
+    async fn server_streaming_classification_with_text_generation(
+        &self,
+        request: Request<Struct>,
+    ) -> Result<Response<ResponseStream>, Status> {
+        let trace_id = current_trace_id();
+        let tenant = self.admit(request.metadata())?;
+        let headers = self.headers(request.metadata());
+        let mut request: models::GuardrailsHttpRequest = from_struct(request.into_inner())?;
+        request
+            .apply_guardrail_profile(&self.state.orchestrator.config())
+            .map_err(Error::from)?;
+        request.validate().map_err(Error::from)?;
+        let task =
+            StreamingClassificationWithGenTask::new(trace_id, request, headers, tenant.clone());
+        let response_stream = self
+            .state
+            .orchestrator
+            .handle(task)
+            .await
+            .map_err(Error::from)?;
+        let response_stream = response_stream
+            .map(to_message::<ClassifiedGeneratedTextStreamResult>)
+            .map_err(Status::from)
+            .boxed();
+        Ok(Response::new(self.track_stream(
+            "ServerStreamingClassificationWithTextGeneration",
+            tenant.as_ref(),
+            response_stream,
+        )?))
+    }
+
+    async fn stream_content_detection(
+        &self,
+        request: Request<Streaming<Struct>>,
+    ) -> Result<Response<ResponseStream>, Status> {
+        let trace_id = current_trace_id();
+        let tenant = self.admit(request.metadata())?;
+        let headers = self.headers(request.metadata());
+        let input_stream = request
+            .into_inner()
+            .map(|result| {
+                let message = result
+                    .map_err(|status| status.message().to_string())
+                    .and_then(struct_to_json)
+                    .and_then(|message| {
+                        serde_json::from_value::<StreamingContentDetectionRequest>(message)
+                            .map_err(|error| error.to_string())
+                    })
+                    .map_err(orchestrator::Error::Validation)?;
+                message.validate()?;
+                Ok(message)
+            })
+            .enumerate()
+            .boxed();
+        let task =
+            StreamingContentDetectionTask::new(trace_id, headers, input_stream, tenant.clone());
+        let response_stream = self
+            .state
+            .orchestrator
+            .handle(task)
+            .await
+            .map_err(Error::from)?;
+        let response_stream = response_stream
+            .map(to_message::<StreamingContentDetectionResponse>)
+            .map_err(Status::from)
+            .boxed();
+        Ok(Response::new(self.track_stream(
+            "StreamContentDetection",
+            tenant.as_ref(),
+            response_stream,
+        )?))
+    }
+
+    async fn text_content_detection(
+        &self,
+        request: Request<Struct>,
+    ) -> Result<Response<Struct>, Status> {
+        let trace_id = current_trace_id();
+        let tenant = self.admit(request.metadata())?;
+        let headers = self.headers(request.metadata());
+        let mut request: models::TextContentDetectionHttpRequest =
+            from_struct(request.into_inner())?;
+        request
+            .apply_guardrail_profile(&self.state.orchestrator.config())
+            .map_err(Error::from)?;
+        request.validate().map_err(Error::from)?;
+        let task = TextContentDetectionTask::new(trace_id, request, headers, tenant);
+        let response = self
+            .state
+            .orchestrator
+            .handle(task)
+            .await
+            .map_err(Error::from)?;
+        Ok(Response::new(to_struct(&response)?))
+    }
+
+    async fn chat_detection(&self, request: Request<Struct>) -> Result<Response<Struct>, Status> {
+        let trace_id = current_trace_id();
+        let tenant = self.admit(request.metadata())?;
+        let headers = self.headers(request.metadata());
+        let request: models::ChatDetectionHttpRequest = from_struct(request.into_inner())?;
+        request.validate_for_text().map_err(Error::from)?;
+        let task = ChatDetectionTask::new(trace_id, request, headers, tenant);
+        let response = self
+            .state
+            .orchestrator
+            .handle(task)
+            .await
+            .map_err(Error::from)?;
+        Ok(Response::new(to_struct(&response)?))
+    }
+
+    async fn context_docs_detection(
+        &self,
+        request: Request<Struct>,
+    ) -> Result<Response<Struct>, Status> {
+        let trace_id = current_trace_id();
+        let tenant = self.admit(request.metadata())?;
+        let headers = self.headers(request.metadata());
+        let request: models::ContextDocsHttpRequest = from_struct(request.into_inner())?;
+        request.validate().map_err(Error::from)?;
+        let task = ContextDocsDetectionTask::new(trace_id, request, headers, tenant);
+        let response = self
+            .state
+            .orchestrator
+            .handle(task)
+            .await
+            .map_err(Error::from)?;
+        Ok(Response::new(to_struct(&response)?))
+    }
+
+    async fn generated_text_detection(
+        &self,
+        request: Request<Struct>,
+    ) -> Result<Response<Struct>, Status> {
+        let trace_id = current_trace_id();
+        let tenant = self.admit(request.metadata())?;
+        let headers = self.headers(request.metadata());
+        let request: models::DetectionOnGeneratedHttpRequest = from_struct(request.into_inner())?;
+        request.validate().map_err(Error::from)?;
+        let task = DetectionOnGenerationTask::new(trace_id, request, headers, tenant);
+        let response = self
+            .state
+            .orchestrator
+            .handle(task)
+            .await
+            .map_err(Error::from)?;
+        Ok(Response::new(to_struct(&response)?))
+    }
+
+    async fn chat_completions_detection(
+        &self,
+        request: Request<Struct>,
+    ) -> Result<Response<Struct>, Status> {
+        let trace_id = current_trace_id();
+        let tenant = self.admit(request.metadata())?;
+        let headers = self.headers(request.metadata());
+        let mut request: ChatCompletionsRequest = from_struct(request.into_inner())?;
+        if request.stream == Some(true) {
+            return Err(Status::invalid_argument(
+                "`stream` is not supported, use StreamingChatCompletionsDetection",
+            ));
+        }
+        request
+            .apply_guardrail_profile(&self.state.orchestrator.config())
+            .map_err(Error::from)?;
+        request.validate().map_err(Error::from)?;
+        let task = ChatCompletionsDetectionTask::new(trace_id, request, headers, tenant);
+        match self.state.orchestrator.handle(task).await {
+            Ok(ChatCompletionsResponse::Unary(response)) => {
+                Ok(Response::new(to_struct(&response)?))
+            }
+            Ok(ChatCompletionsResponse::Streaming(_)) => {
+                Err(Status::internal("unexpected streaming response"))
+            }
+            Err(error) => Err(Error::from(error).into()),
+        }
+    }
+
+    async fn streaming_chat_completions_detection(
+        &self,
+        request: Request<Struct>,
+    ) -> Result<Response<ResponseStream>, Status> {
+        let trace_id = current_trace_id();
+        let tenant = self.admit(request.metadata())?;
+        let headers = self.headers(request.metadata());
+        let mut request: ChatCompletionsRequest = from_struct(request.into_inner())?;
+        request.stream = Some(true);
+        request
+            .apply_guardrail_profile(&self.state.orchestrator.config())
+            .map_err(Error::from)?;
+        request.validate().map_err(Error::from)?;
+        let task = ChatCompletionsDetectionTask::new(trace_id, request, headers, tenant.clone());
+        let response_rx = match self.state.orchestrator.handle(task).await {
+            Ok(ChatCompletionsResponse::Streaming(response_rx)) => response_rx,
+            Ok(ChatCompletionsResponse::Unary(_)) => {
+                return Err(Status::internal("unexpected unary response"));
+            }
+            Err(error) => return Err(Error::from(error).into()),
+        };
+        // The end of the stream replaces the `[DONE]` message
+        let response_stream = ReceiverStream::new(response_rx)
+            .filter_map(|message| async move {
+                match message {
+                    Ok(Some(chunk)) => Some(to_struct(&chunk).map_err(Status::from)),
+                    Ok(None) => None,
+                    Err(error) => Some(Err(Error::from(error).into())),
+                }
+            })
+            .boxed();
+        Ok(Response::new(self.track_stream(
+            "StreamingChatCompletionsDetection",
+            tenant.as_ref(),
+            response_stream,
+        )?))
+    }
+}
+
+/// Returns the error of a rejected request, mapped to `RESOURCE_EXHAUSTED`.
+fn limit_exceeded(error: limits::LimitExceeded) -> Error {
+    Error {
+        code: StatusCode::TOO_MANY_REQUESTS,
+        details: error.to_string(),
+    }
+}
+
+/// Converts a response stream message to a [`Struct`] message.
+fn to_message<T: Serialize>(message: Result<T, orchestrator::Error>) -> Result<Struct, Error> {
+    match message {
+        Ok(message) => to_struct(&message),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Deserializes a request from a [`Struct`].
+fn from_struct<T: DeserializeOwned>(value: Struct) -> Result<T, Error> {
+    struct_to_json(value)
+        .and_then(|value| serde_json::from_value(value).map_err(|error| error.to_string()))
+        .map_err(|details| Error {
+            code: StatusCode::UNPROCESSABLE_ENTITY,
+            details,
+        })
+}
+
+/// Serializes a response to a [`Struct`].
+fn to_struct<T: Serialize>(value: &T) -> Result<Struct, Error> {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::Object(fields)) => Ok(Struct {
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key, json_to_value(value)))
+                .collect(),
+        }),
+        Ok(_) => Err(Error {
+            code: StatusCode::INTERNAL_SERVER_ERROR,
+            details: "response is not a JSON object".into(),
+        }),
+        Err(error) => Err(Error {
+            code: StatusCode::INTERNAL_SERVER_ERROR,
+            details: error.to_string(),
+        }),
+    }
+}
+
+/// Converts a [`Struct`] to a JSON object.
+fn struct_to_json(value: Struct) -> Result<serde_json::Value, String> {
+    value
+        .fields
+        .into_iter()
+        .map(|(key, value)| Ok((key, value_to_json(value)?)))
+        .collect::<Result<serde_json::Map<_, _>, _>>()
+        .map(serde_json::Value::Object)
+}
+
+/// Converts a [`prost_types::Value`] to a JSON value. Protobuf numbers are doubles,
+/// so integral numbers are converted to JSON integers to deserialize as integer fields.
+fn value_to_json(value: prost_types::Value) -> Result<serde_json::Value, String> {
+    use serde_json::Value;
+    Ok(match value.kind {
+        None | Some(Kind::NullValue(_)) => Value::Null,
+        Some(Kind::BoolValue(value)) => Value::Bool(value),
+        Some(Kind::NumberValue(value)) => {
+            if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+                Value::from(value as i64)
+            } else {
+                serde_json::Number::from_f64(value)
+                    .map(Value::Number)
+                    .ok_or_else(|| format!("invalid number: {value}"))?
+            }
+        }
+        Some(Kind::StringValue(value)) => Value::String(value),
+        Some(Kind::StructValue(value)) => struct_to_json(value)?,
+        Some(Kind::ListValue(value)) => Value::Array(
+            value
+                .values
+                .into_iter()
+                .map(value_to_json)
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+/// Converts a JSON value to a [`prost_types::Value`].
+fn json_to_value(value: serde_json::Value) -> prost_types::Value {
+    use serde_json::Value;
+    let kind = match value {
+        Value::Null => Kind::NullValue(prost_types::NullValue::NullValue.into()),
+        Value::Bool(value) => Kind::BoolValue(value),
+        Value::Number(value) => Kind::NumberValue(value.as_f64().unwrap_or_default()),
+        Value::String(value) => Kind::StringValue(value),
+        Value::Array(values) => Kind::ListValue(ListValue {
+            values: values.into_iter().map(json_to_value).collect(),
+        }),
+        Value::Object(fields) => Kind::StructValue(Struct {
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key, json_to_value(value)))
+                .collect(),
+        }),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::TextContentDetectionHttpRequest;
+
+    #[test]
+    fn test_struct_json_roundtrip() {
+        let value = json!({
+            "content": "hello",
+            "detectors": {
+                "hap": { "threshold": 0.5, "max_count": 2, "labels": ["a", "b"], "strict": true },
+            },
+            "missing": null,
+        });
+        let message = to_struct(&value).unwrap();
+        assert_eq!(struct_to_json(message).unwrap(), value);
+    }
+
+    #[test]
+    fn test_from_struct() {
+        let message =
+            to_struct(&json!({ "content": "hello", "detectors": { "hap": {} } })).unwrap();
+        let request: TextContentDetectionHttpRequest = from_struct(message).unwrap();
+        assert_eq!(request.content, "hello");
+
+        let message = to_struct(&json!({ "content": 1 })).unwrap();
+        let error = from_struct::<TextContentDetectionHttpRequest>(message).unwrap_err();
+        assert_eq!(Status::from(error).code(), tonic::Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,587 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+//! Asynchronous jobs running orchestrator tasks
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use futures::{StreamExt, stream};
+use http::{HeaderMap, StatusCode};
+use opentelemetry::trace::TraceId;
+use serde::{Deserialize, Serialize};
+use tracing::{Instrument, info, info_span, warn};
+use uuid::Uuid;
+
+use super::{Error, ServerState, routes::batch_detection_result};
+use crate::{
+    clients::openai::{ChatCompletionsResponse, CompletionsResponse},
+    config::JobsConfig,
+    models::{BatchOrder, DEFAULT_BATCH_CONCURRENCY, JobRequest},
+    orchestrator::{
+        self,
+        handlers::{
+            chat_completions_detection::ChatCompletionsDetectionTask,
+            completions_detection::CompletionsDetectionTask, *,
+        },
+        types::Tenant,
+    },
+    utils::task_group::TaskGroup,
+};
+
+/// Job status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Job progress, in units of work of the task, e.g. records of a batch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobProgress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Asynchronous job running a task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    /// Job ID
+    pub id: String,
+    /// Task run by the job
+    pub task: String,
+    /// Job status
+    pub status: JobStatus,
+    /// Job progress
+    pub progress: JobProgress,
+    /// Unix timestamp (in seconds) of when the job was created
+    pub created_at: u64,
+    /// Unix timestamp (in seconds) of the last status change
+    pub updated_at: u64,
+    /// Task response, in the format of the task's endpoint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    /// Task error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<Error>,
+    /// Tenant that submitted the job
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+}
+
+impl Job {
+    /// Returns `true` if the job can be accessed by `tenant`.
+    fn is_owned_by(&self, tenant: Option<&Tenant>) -> bool {
+        self.tenant_id.as_deref() == tenant.map(|tenant| tenant.id.as_str())
+    }
+}
+
+struct JobEntry {
+    job: Job,
+    tasks: Option<TaskGroup>,
+}
+
+/// Bounded store of jobs. Finished jobs are removed once their TTL expires, or when
+/// the store is full to make room for new jobs. Jobs are optionally persisted to disk.
+pub struct JobStore {
+    jobs: Mutex<HashMap<String, JobEntry>>,
+    max_jobs: usize,
+    ttl_seconds: u64,
+    store_path: Option<PathBuf>,
+}
+
+impl JobStore {
+    /// Creates a job store, loading persisted jobs. Jobs that were running when
+    /// the orchestrator stopped are marked as failed.
+    pub fn new(config: &JobsConfig) -> Result<Self, Error> {
+        let mut jobs = HashMap::new();
+        if let Some(store_path) = &config.store_path {
+            std::fs::create_dir_all(store_path)?;
+            for entry in std::fs::read_dir(store_path)? {
+                let path = entry?.path();
+                if path.extension().is_none_or(|extension| extension != "json") {
+                    continue;
+                }
+                let mut job = match std::fs::read(&path)
+                    .map_err(|error| error.to_string())
+                    .and_then(|bytes| {
+                        serde_json::from_slice::<Job>(&bytes).map_err(|error| error.to_string())
+                    }) {
+                    Ok(job) => job,
+                    Err(error) => {
+                        warn!(?path, %error, "skipping invalid job file");
+                        continue;
+                    }
+                };
+                if job.status == JobStatus::Running {
+                    job.status = JobStatus::Failed;
+                    job.updated_at = now();
+                    job.error = Some(Error {
+                        code: StatusCode::INTERNAL_SERVER_ERROR,
+                        details: "job interrupted by orchestrator restart".into(),
+                    });
+                    std::fs::write(&path, serde_json::to_vec(&job).unwrap())?;
+                }
+                jobs.insert(job.id.clone(), JobEntry { job, tasks: None });
+            }
+            info!(count = jobs.len(), ?store_path, "loaded persisted jobs");
+        }
+        Ok(Self {
+            jobs: Mutex::new(jobs),
+            max_jobs: config.max_jobs,
+            ttl_seconds: config.ttl_seconds,
+            store_path: config.store_path.clone(),
+        })
+    }
+
+    /// Gets a job.
+    pub async fn get(&self, id: &str, tenant: Option<&Tenant>) -> Option<Job> {
+        let (job, removed) = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let removed = remove_expired(&mut jobs, self.ttl_seconds, now());
+            let job = jobs
+                .get(id)
+                .map(|entry| entry.job.clone())
+                .filter(|job| job.is_owned_by(tenant));
+            (job, removed)
+        };
+        self.remove_persisted(removed).await;
+        job
+    }
+
+    /// Cancels a running job, aborting its task and the tasks it spawned. Finished jobs
+    /// are returned unchanged.
+    pub async fn cancel(&self, id: &str, tenant: Option<&Tenant>) -> Option<Job> {
+        let job = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let entry = jobs
+                .get_mut(id)
+                .filter(|entry| entry.job.is_owned_by(tenant))?;
+            if entry.job.status != JobStatus::Running {
+                return Some(entry.job.clone());
+            }
+            if let Some(tasks) = entry.tasks.take() {
+                tasks.abort();
+            }
+            entry.job.status = JobStatus::Cancelled;
+            entry.job.updated_at = now();
+            entry.job.clone()
+        };
+        info!(job_id = id, "job cancelled");
+        self.persist(&job).await;
+        Some(job)
+    }
+
+    /// Inserts a new job, removing expired jobs and, if the store is full, the
+    /// oldest finished job.
+    async fn insert(&self, job: Job) -> Result<(), Error> {
+        let removed = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let mut removed = remove_expired(&mut jobs, self.ttl_seconds, now());
+            if jobs.len() >= self.max_jobs {
+                let oldest = jobs
+                    .values()
+                    .filter(|entry| entry.job.status != JobStatus::Running)
+                    .min_by_key(|entry| entry.job.updated_at)
+                    .map(|entry| entry.job.id.clone())
+                    .ok_or_else(|| Error {
+                        code: StatusCode::TOO_MANY_REQUESTS,
+                        details: "too many running jobs, try again later".into(),
+                    })?;
+                jobs.remove(&oldest);
+                removed.push(oldest);
+            }
+            jobs.insert(
+                job.id.clone(),
+                JobEntry {
+                    job: job.clone(),
+                    tasks: None,
+                },
+            );
+            removed
+        };
+        self.remove_persisted(removed).await;
+        self.persist(&job).await;
+        Ok(())
+    }
+
+    /// Sets the task group of a running job.
+    fn set_tasks(&self, id: &str, tasks: TaskGroup) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(entry) = jobs
+            .get_mut(id)
+            .filter(|entry| entry.job.status == JobStatus::Running)
+        {
+            entry.tasks = Some(tasks);
+        }
+    }
+
+    /// Updates the progress of a running job.
+    fn set_progress(&self, id: &str, completed: usize) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(entry) = jobs.get_mut(id) {
+            entry.job.progress.completed = completed;
+        }
+    }
+
+    /// Sets the result of a running job.
+    async fn finish(&self, id: &str, result: Result<serde_json::Value, Error>) {
+        let job = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let Some(entry) = jobs
+                .get_mut(id)
+                .filter(|entry| entry.job.status == JobStatus::Running)
+            else {
+                return;
+            };
+            entry.tasks = None;
+            match result {
+                Ok(result) => {
+                    entry.job.status = JobStatus::Completed;
+                    entry.job.progress.completed = entry.job.progress.total;
+                    entry.job.result = Some(result);
+                }
+                Err(error) => {
+                    entry.job.status = JobStatus::Failed;
+                    entry.job.error = Some(error);
+                }
+            }
+            entry.job.updated_at = now();
+            entry.job.clone()
+        };
+        info!(job_id = id, status = ?job.status, "job finished");
+        self.persist(&job).await;
+    }
+
+    /// Persists a job, if a store path is configured.
+    async fn persist(&self, job: &Job) {
+        let Some(store_path) = &self.store_path else {
+            return;
+        };
+        let path = job_path(store_path, &job.id);
+        // Write to a temporary file first, so job files are never partially written
+        let tmp_path = path.with_extension("json.tmp");
+        let result = async {
+            tokio::fs::write(&tmp_path, serde_json::to_vec(job)?).await?;
+            tokio::fs::rename(&tmp_path, &path).await
+        }
+        .await;
+        if let Err(error) = result {
+            warn!(job_id = %job.id, %error, "failed to persist job");
+        }
+    }
+
+    /// Removes persisted jobs, if a store path is configured.
+    async fn remove_persisted(&self, ids: Vec<String>) {
+        let Some(store_path) = &self.store_path else {
+            return;
+        };
+        for id in ids {
+            if let Err(error) = tokio::fs::remove_file(job_path(store_path, &id)).await {
+                warn!(job_id = %id, %error, "failed to remove persisted job");
+            }
+        }
+    }
+}
+
+/// Submits a job running the task of `request`, returning the running job.
+pub async fn submit(
+    state: Arc<ServerState>,
+    trace_id: TraceId,
+    headers: HeaderMap,
+    tenant: Option<Tenant>,
+    request: JobRequest,
+) -> Result<Job, Error> {
+    let created_at = now();
+    let total = match &request {
+        JobRequest::BatchTextContentDetection(request) => request.records.len(),
+        _ => 1,
+    };
+    let job = Job {
+        id: Uuid::new_v4().simple().to_string(),
+        task: request.task().into(),
+        status: JobStatus::Running,
+        progress: JobProgress {
+            completed: 0,
+            total,
+        },
+        created_at,
+        updated_at: created_at,
+        result: None,
+        error: None,
+        tenant_id: tenant.as_ref().map(|tenant| tenant.id.clone()),
+    };
+    state.jobs.insert(job.clone()).await?;
+    info!(job_id = %job.id, task = %job.task, "job submitted");
+
+    let span = info_span!("job", job_id = %job.id, task = %job.task);
+    // Tasks spawned to run the job join its task group, so they are aborted on cancel
+    let tasks = TaskGroup::default();
+    tasks.spawn({
+        let state = state.clone();
+        let id = job.id.clone();
+        async move {
+            let result = run(&state, &id, trace_id, headers, tenant, request).await;
+            state.jobs.finish(&id, result).await;
+        }
+        .instrument(span)
+    });
+    state.jobs.set_tasks(&job.id, tasks);
+    Ok(job)
+}
+
+/// Runs the task of a job with the task's handler.
+async fn run(
+    state: &ServerState,
+    id: &str,
+    trace_id: TraceId,
+    headers: HeaderMap,
+    tenant: Option<Tenant>,
+    request: JobRequest,
+) -> Result<serde_json::Value, Error> {
+    let orchestrator = &state.orchestrator;
+    match request {
+        JobRequest::ClassificationWithTextGeneration(request) => {
+            let task = ClassificationWithGenTask::new(trace_id, request, headers, tenant);
+            to_value(orchestrator.handle(task).await?)
+        }
+        JobRequest::GenerationDetection(request) => {
+            let task = GenerationWithDetectionTask::new(trace_id, request, headers, tenant);
+            to_value(orchestrator.handle(task).await?)
+        }
+        JobRequest::TextContentDetection(request) => {
+            let task = TextContentDetectionTask::new(trace_id, request, headers, tenant);
+            to_value(orchestrator.handle(task).await?)
+        }
+        JobRequest::BatchTextContentDetection(request) => {
+            let input_stream = stream::iter(
+                request
+                    .records
+                    .into_iter()
+                    .map(Ok::<_, orchestrator::Error>),
+            )
+            .enumerate()
+            .boxed();
+            let task = BatchTextContentDetectionTask::new(
+                trace_id,
+                headers,
+                BatchOrder::Completion,
+                request.concurrency.unwrap_or(DEFAULT_BATCH_CONCURRENCY),
+                input_stream,
+                tenant,
+            );
+            let mut response_stream = orchestrator.handle(task).await?;
+            let mut results = Vec::new();
+            while let Some(record) = response_stream.next().await {
+                results.push(batch_detection_result(record));
+                state.jobs.set_progress(id, results.len());
+            }
+            results.sort_by_key(|result| result.index);
+            to_value(results)
+        }
+        JobRequest::ChatDetection(request) => {
+            let task = ChatDetectionTask::new(trace_id, request, headers, tenant);
+            to_value(orchestrator.handle(task).await?)
+        }
+        JobRequest::ContextDocsDetection(request) => {
+            let task = ContextDocsDetectionTask::new(trace_id, request, headers, tenant);
+            to_value(orchestrator.handle(task).await?)
+        }
+        JobRequest::GeneratedTextDetection(request) => {
+            let task = DetectionOnGenerationTask::new(trace_id, request, headers, tenant);
+            to_value(orchestrator.handle(task).await?)
+        }
+        JobRequest::ChatCompletionsDetection(request) => {
+            let task = ChatCompletionsDetectionTask::new(trace_id, request, headers, tenant);
+            match orchestrator.handle(task).await? {
+                ChatCompletionsResponse::Unary(response) => to_value(response),
+                ChatCompletionsResponse::Streaming(_) => Err(Error {
+                    code: StatusCode::INTERNAL_SERVER_ERROR,
+                    details: "unexpected streaming response".into(),
+                }),
+            }
+        }
+        JobRequest::CompletionsDetection(request) => {
+            let task = CompletionsDetectionTask::new(trace_id, request, headers, tenant);
+            match orchestrator.handle(task).await? {
+                CompletionsResponse::Unary(response) => to_value(response),
+                CompletionsResponse::Streaming(_) => Err(Error {
+                    code: StatusCode::INTERNAL_SERVER_ERROR,
+                    details: "unexpected streaming response".into(),
+                }),
+            }
+        }
+    }
+}
+
+/// Removes finished jobs older than `ttl_seconds`, returning their IDs.
+fn remove_expired(jobs: &mut HashMap<String, JobEntry>, ttl_seconds: u64, now: u64) -> Vec<String> {
+    let expired = jobs
+        .values()
+        .filter(|entry| {
+            entry.job.status != JobStatus::Running
+                && entry.job.updated_at.saturating_add(ttl_seconds) <= now
+        })
+        .map(|entry| entry.job.id.clone())
+        .collect::<Vec<_>>();
+    for id in &expired {
+        jobs.remove(id);
+    }
+    expired
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<serde_json::Value, Error> {
+    serde_json::to_value(value).map_err(|error| Error {
+        code: StatusCode::INTERNAL_SERVER_ERROR,
+        details: error.to_string(),
+    })
+}
+
+fn job_path(store_path: &Path, id: &str) -> PathBuf {
+    store_path.join(format!("{id}.json"))
+}
+
+/// Returns the current Unix timestamp, in seconds.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, status: JobStatus, updated_at: u64) -> Job {
+        Job {
+            id: id.into(),
+            task: "text_content_detection".into(),
+            status,
+            progress: JobProgress::default(),
+            created_at: updated_at,
+            updated_at,
+            result: None,
+            error: None,
+            tenant_id: None,
+        }
+    }
+
+    fn store(max_jobs: usize, store_path: Option<PathBuf>) -> JobStore {
+        JobStore::new(&JobsConfig {
+            max_jobs,
+            ttl_seconds: 60,
+            store_path,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_remove_expired() {
+        let mut jobs = HashMap::from_iter(
+            [
+                job("running", JobStatus::Running, 0),
+                job("completed", JobStatus::Completed, 100),
+                job("expired", JobStatus::Failed, 0),
+            ]
+            .map(|job| (job.id.clone(), JobEntry { job, tasks: None })),
+        );
+        assert_eq!(remove_expired(&mut jobs, 60, 120), vec!["expired"]);
+        assert!(jobs.contains_key("running"));
+        assert!(jobs.contains_key("completed"));
+    }
+
+    #[tokio::test]
+    async fn test_insert_full() {
+        let store = store(2, None);
+        let now = now();
+        store
+            .insert(job("a", JobStatus::Completed, now - 10))
+            .await
+            .unwrap();
+        store
+            .insert(job("b", JobStatus::Running, now))
+            .await
+            .unwrap();
+        // Oldest finished job is removed
+        store
+            .insert(job("c", JobStatus::Running, now))
+            .await
+            .unwrap();
+        assert!(store.get("a", None).await.is_none());
+        assert!(store.get("b", None).await.is_some());
+        // All jobs are running
+        let error = store
+            .insert(job("d", JobStatus::Running, now))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_access() {
+        let store = store(10, None);
+        let tenant = Tenant::new("team-a", None);
+        let mut tenant_job = job("a", JobStatus::Running, now());
+        tenant_job.tenant_id = Some("team-a".into());
+        store.insert(tenant_job).await.unwrap();
+        assert!(store.get("a", Some(&tenant)).await.is_some());
+        assert!(store.get("a", None).await.is_none());
+        assert!(
+            store
+                .get("a", Some(&Tenant::new("team-b", None)))
+                .await
+                .is_none()
+        );
+        assert!(store.cancel("a", None).await.is_none());
+        let job = store.cancel("a", Some(&tenant)).await.unwrap();
+        assert_eq!(job.status, JobStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_persisted_jobs() {
+        let store_path = std::env::temp_dir().join(format!("jobs-{}", Uuid::new_v4().simple()));
+        let store = store(10, Some(store_path.clone()));
+        store
+            .insert(job("running", JobStatus::Running, now()))
+            .await
+            .unwrap();
+        store
+            .insert(job("completed", JobStatus::Running, now()))
+            .await
+            .unwrap();
+        store
+            .finish("completed", Ok(serde_json::json!({ "detections": [] })))
+            .await;
+
+        // Jobs survive restarts, running jobs are failed
+        let store = self::store(10, Some(store_path.clone()));
+        let job = store.get("completed", None).await.unwrap();
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!(job.result, Some(serde_json::json!({ "detections": [] })));
+        let job = store.get("running", None).await.unwrap();
+        assert_eq!(job.status, JobStatus::Failed);
+
+        std::fs::remove_dir_all(store_path).unwrap();
+    }
+}
@@ -0,0 +1,432 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+//! Guardrails server request rate and concurrency limits
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use futures::StreamExt;
+use http::{StatusCode, header::RETRY_AFTER};
+use serde::Deserialize;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::warn;
+
+use super::{Error, ServerState};
+use crate::{
+    config::{LimitConfig, LimitsConfig},
+    orchestrator::types::Tenant,
+    utils::trace,
+};
+
+/// Endpoints always returning a streaming response.
+const STREAMING_PATHS: [&str; 4] = [
+    "/api/v1/task/server-streaming-classification-with-text-generation",
+    "/api/v2/text/detection/stream-content",
+    "/api/v2/text/detection/stream-content/ws",
+    "/api/v2/text/detection/content/batch",
+];
+/// Endpoints returning a streaming response when requested with `stream`.
+const OPT_IN_STREAMING_PATHS: [&str; 2] = [
+    "/api/v2/chat/completions-detection",
+    "/api/v2/completions-detection",
+];
+/// Maximum body size buffered to check the `stream` param, matching axum's default body limit.
+const MAX_BUFFERED_BODY_SIZE: usize = 2 * 1024 * 1024;
+/// `Retry-After` returned when the concurrent stream limit is exceeded.
+const STREAM_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Enforces request rate and concurrent stream limits, globally and per tenant.
+pub struct Limiter {
+    /// Limits shared by all requests
+    global: Limits,
+    /// Config for tenant limits
+    config: LimitsConfig,
+    /// Limits per tenant, created on first request
+    tenants: DashMap<String, Arc<Limits>>,
+}
+
+/// Limits for a scope.
+#[derive(Debug)]
+struct Limits {
+    scope: Scope,
+    bucket: Option<TokenBucket>,
+    streams: Option<Arc<Semaphore>>,
+}
+
+/// Scope of a limit.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scope {
+    Global,
+    Tenant(String),
+}
+
+/// Token bucket for request rate limiting.
+#[derive(Debug)]
+struct TokenBucket {
+    /// Tokens added per second
+    rate: f64,
+    /// Maximum tokens
+    capacity: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+/// Limit exceeded by a request.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum LimitExceeded {
+    #[error("request rate limit exceeded for {scope}")]
+    RequestRate { scope: Scope, retry_after: Duration },
+    #[error("concurrent stream limit exceeded for {scope}")]
+    ConcurrentStreams { scope: Scope },
+}
+
+/// Concurrent stream permits, released when dropped.
+#[derive(Debug, Default)]
+pub struct StreamPermit(Vec<OwnedSemaphorePermit>);
+
+impl Limiter {
+    pub fn new(config: &LimitsConfig) -> Self {
+        Self {
+            global: Limits::new(Scope::Global, &config.global),
+            config: config.clone(),
+            tenants: DashMap::new(),
+        }
+    }
+
+    /// Admits a request if the request rate limits of the tenant, if any,
+    /// and the server are not exceeded.
+    pub fn check_rate(&self, tenant: Option<&Tenant>) -> Result<(), LimitExceeded> {
+        if let Some(limits) = tenant.map(|tenant| self.tenant_limits(tenant)) {
+            limits.check_rate()?;
+        }
+        self.global.check_rate()
+    }
+
+    /// Acquires a permit for a streaming request if the concurrent stream limits
+    /// of the tenant, if any, and the server are not exceeded.
+    pub fn acquire_stream(&self, tenant: Option<&Tenant>) -> Result<StreamPermit, LimitExceeded> {
+        let mut permit = StreamPermit::default();
+        if let Some(limits) = tenant.map(|tenant| self.tenant_limits(tenant)) {
+            permit.0.extend(limits.acquire_stream()?);
+        }
+        permit.0.extend(self.global.acquire_stream()?);
+        Ok(permit)
+    }
+
+    /// Returns `true` if any concurrent stream limit is configured.
+    fn limits_streams(&self) -> bool {
+        self.global.streams.is_some()
+            || self.config.per_tenant.max_concurrent_streams.is_some()
+            || self
+                .config
+                .tenants
+                .values()
+                .any(|config| config.max_concurrent_streams.is_some())
+    }
+
+    fn tenant_limits(&self, tenant: &Tenant) -> Arc<Limits> {
+        self.tenants
+            .entry(tenant.id.clone())
+            .or_insert_with(|| {
+                let config = self
+                    .config
+                    .tenants
+                    .get(&tenant.id)
+                    .unwrap_or(&self.config.per_tenant);
+                Arc::new(Limits::new(Scope::Tenant(tenant.id.clone()), config))
+            })
+            .clone()
+    }
+}
+
+impl Limits {
+    fn new(scope: Scope, config: &LimitConfig) -> Self {
+        let bucket = config
+            .requests_per_second
+            .map(|rate| TokenBucket::new(rate, config.burst.map(f64::from)));
+        let streams = config
+            .max_concurrent_streams
+            .map(|max| Arc::new(Semaphore::new(max)));
+        Self {
+            scope,
+            bucket,
+            streams,
+        }
+    }
+
+    fn check_rate(&self) -> Result<(), LimitExceeded> {
+        match &self.bucket {
+            Some(bucket) => {
+                bucket
+                    .try_acquire()
+                    .map_err(|retry_after| LimitExceeded::RequestRate {
+                        scope: self.scope.clone(),
+                        retry_after,
+                    })
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn acquire_stream(&self) -> Result<Option<OwnedSemaphorePermit>, LimitExceeded> {
+        self.streams
+            .clone()
+            .map(|streams| {
+                streams
+                    .try_acquire_owned()
+                    .map_err(|_| LimitExceeded::ConcurrentStreams {
+                        scope: self.scope.clone(),
+                    })
+            })
+            .transpose()
+    }
+}
+
+impl TokenBucket {
+    fn new(rate: f64, burst: Option<f64>) -> Self {
+        let capacity = burst.unwrap_or(rate.ceil()).max(1.0);
+        Self {
+            rate,
+            capacity,
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// Takes a token, returning the time until a token is available if the bucket is empty.
+    fn try_acquire(&self) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        let (tokens, updated) = &mut *state;
+        let now = Instant::now();
+        *tokens =
+            (*tokens + now.duration_since(*updated).as_secs_f64() * self.rate).min(self.capacity);
+        *updated = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - *tokens) / self.rate))
+        }
+    }
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scope::Global => write!(f, "server"),
+            Scope::Tenant(tenant_id) => write!(f, "tenant `{tenant_id}`"),
+        }
+    }
+}
+
+impl LimitExceeded {
+    /// Returns the kind of limit exceeded.
+    pub fn limit(&self) -> &'static str {
+        match self {
+            LimitExceeded::RequestRate { .. } => "request_rate",
+            LimitExceeded::ConcurrentStreams { .. } => "concurrent_streams",
+        }
+    }
+
+    pub fn scope(&self) -> &Scope {
+        match self {
+            LimitExceeded::RequestRate { scope, .. }
+            | LimitExceeded::ConcurrentStreams { scope } => scope,
+        }
+    }
+
+    /// Returns how long the client should wait before retrying.
+    pub fn retry_after(&self) -> Duration {
+        match self {
+            LimitExceeded::RequestRate { retry_after, .. } => *retry_after,
+            LimitExceeded::ConcurrentStreams { .. } => STREAM_RETRY_AFTER,
+        }
+    }
+}
+
+impl IntoResponse for LimitExceeded {
+    fn into_response(self) -> Response {
+        // `Retry-After` is in whole seconds, rounded up
+        let retry_after = self.retry_after().as_secs_f64().ceil().max(1.0) as u64;
+        let error = Error {
+            code: StatusCode::TOO_MANY_REQUESTS,
+            details: self.to_string(),
+        };
+        ([(RETRY_AFTER, retry_after.to_string())], error).into_response()
+    }
+}
+
+/// Limits middleware, rejecting requests exceeding request rate or concurrent stream
+/// limits with `429 Too Many Requests`. Stream permits are held until the response
+/// body completes, or by the endpoint for upgraded connections.
+pub async fn limit(
+    State(state): State<Arc<ServerState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(limiter) = state.limiter() else {
+        return next.run(request).await;
+    };
+    let tenant = request.extensions().get::<Tenant>().cloned();
+    if let Err(error) = limiter.check_rate(tenant.as_ref()) {
+        return reject(error);
+    }
+    if !limiter.limits_streams() {
+        return next.run(request).await;
+    }
+    let (request, streaming) = match is_streaming(request).await {
+        Ok(result) => result,
+        Err(error) => return error.into_response(),
+    };
+    if !streaming {
+        return next.run(request).await;
+    }
+    let permit = match limiter.acquire_stream(tenant.as_ref()) {
+        Ok(permit) => Arc::new(permit),
+        Err(error) => return reject(error),
+    };
+    // Upgraded connections outlive the response, the endpoint holds the permit until closed
+    let mut request = request;
+    request.extensions_mut().insert(permit.clone());
+    let (parts, body) = next.run(request).await.into_parts();
+    let body = body.into_data_stream().map(move |chunk| {
+        let _permit = &permit;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+fn reject(error: LimitExceeded) -> Response {
+    on_rejected(&error);
+    error.into_response()
+}
+
+/// Logs and records metrics for a request rejected by a limit.
+pub fn on_rejected(error: &LimitExceeded) {
+    let scope = match error.scope() {
+        Scope::Global => "global",
+        Scope::Tenant(tenant_id) => tenant_id,
+    };
+    warn!(%error, "request rejected");
+    trace::on_limit_exceeded(error.limit(), scope);
+}
+
+/// Returns whether a request produces a streaming response. The body of requests to
+/// endpoints where streaming is opt-in is buffered to check the `stream` param.
+async fn is_streaming(request: Request) -> Result<(Request, bool), Error> {
+    #[derive(Deserialize)]
+    struct StreamParam {
+        #[serde(default)]
+        stream: bool,
+    }
+    let path = request.uri().path();
+    if STREAMING_PATHS.contains(&path) {
+        return Ok((request, true));
+    }
+    if !OPT_IN_STREAMING_PATHS.contains(&path) {
+        return Ok((request, false));
+    }
+    let (parts, body) = request.into_parts();
+    let body = to_bytes(body, MAX_BUFFERED_BODY_SIZE)
+        .await
+        .map_err(|error| Error {
+            code: StatusCode::PAYLOAD_TOO_LARGE,
+            details: error.to_string(),
+        })?;
+    // Invalid requests are rejected by the endpoint
+    let streaming = serde_json::from_slice::<StreamParam>(&body).is_ok_and(|param| param.stream);
+    Ok((Request::from_parts(parts, Body::from(body)), streaming))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_check_rate() {
+        let limiter = Limiter::new(&LimitsConfig {
+            global: LimitConfig {
+                requests_per_second: Some(1.0),
+                burst: Some(3),
+                ..Default::default()
+            },
+            per_tenant: LimitConfig {
+                requests_per_second: Some(1.0),
+                burst: Some(2),
+                ..Default::default()
+            },
+            tenants: HashMap::default(),
+        });
+        let tenant_a = Tenant::new("team-a", None);
+        let tenant_b = Tenant::new("team-b", None);
+        assert!(limiter.check_rate(Some(&tenant_a)).is_ok());
+        assert!(limiter.check_rate(Some(&tenant_a)).is_ok());
+        let error = limiter.check_rate(Some(&tenant_a)).unwrap_err();
+        assert_eq!(error.scope(), &Scope::Tenant("team-a".into()));
+        assert!(error.retry_after() <= Duration::from_secs(1));
+        // Tenant limits are independent, global limit is shared
+        assert!(limiter.check_rate(Some(&tenant_b)).is_ok());
+        let error = limiter.check_rate(Some(&tenant_b)).unwrap_err();
+        assert_eq!(error.scope(), &Scope::Global);
+        assert!(limiter.check_rate(None).is_err());
+    }
+
+    #[test]
+    fn test_acquire_stream() {
+        let limiter = Limiter::new(&LimitsConfig {
+            tenants: HashMap::from([(
+                "team-a".into(),
+                LimitConfig {
+                    max_concurrent_streams: Some(1),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        });
+        let tenant_a = Tenant::new("team-a", None);
+        let permit = limiter.acquire_stream(Some(&tenant_a)).unwrap();
+        assert!(matches!(
+            limiter.acquire_stream(Some(&tenant_a)),
+            Err(LimitExceeded::ConcurrentStreams { .. })
+        ));
+        // Tenants not listed are unlimited
+        let tenant_b = Tenant::new("team-b", None);
+        assert!(limiter.acquire_stream(Some(&tenant_b)).is_ok());
+        // Dropping the permit releases it
+        drop(permit);
+        assert!(limiter.acquire_stream(Some(&tenant_a)).is_ok());
+    }
+
+    #[test]
+    fn test_limit_exceeded_response() {
+        let error = LimitExceeded::RequestRate {
+            scope: Scope::Global,
+            retry_after: Duration::from_millis(1500),
+        };
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "2");
+    }
+}
@@ -21,9 +21,10 @@ use std::{
 };
 
 use axum::{
-    Json, Router,
-    extract::{Query, State},
-    http::HeaderMap,
+    Extension, Json, Router,
+    extract::{MatchedPath, Path, Query, State, WebSocketUpgrade},
+    http::{HeaderMap, StatusCode},
+    middleware,
     response::{
         IntoResponse, Response,
         sse::{Event, KeepAlive, Sse},
@@ -37,27 +38,145 @@ use futures::{
 };
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
-use tracing::info;
+use tracing::{Instrument, Span, info};
 
-use super::{Error, ServerState};
+use super::{
+    Error, ServerState, auth,
+    jobs::{self, Job},
+    limits,
+    limits::StreamPermit,
+    ws,
+};
 use crate::{
-    clients::openai::{ChatCompletionsRequest, ChatCompletionsResponse},
-    models::{self, InfoParams, InfoResponse, StreamingContentDetectionRequest},
+    clients::openai::{
+        ChatCompletionsRequest, ChatCompletionsResponse, CompletionsRequest, CompletionsResponse,
+    },
+    config::{DetectorConfig, OrchestratorConfig},
+    health::HealthCheckCache,
+    models::{
+        self, BatchDetectionError, BatchDetectionParams, BatchDetectionRecord,
+        BatchDetectionResult, ChunkerInfo, ChunkersResponse, DEFAULT_BATCH_CONCURRENCY,
+        DetectorEndpoint, DetectorInfo, DetectorsResponse, InfoParams, InfoResponse, JobRequest,
+        ReadyResponse, StreamingContentDetectionRequest,
+    },
     orchestrator::{
         self,
-        handlers::{chat_completions_detection::ChatCompletionsDetectionTask, *},
+        common::{
+            AcceptedDetectors, CHAT_COMPLETIONS_INPUT_DETECTORS, CHAT_COMPLETIONS_OUTPUT_DETECTORS,
+            STREAMING_TEXT_CONTENTS_DETECTORS, TEXT_CHAT_DETECTORS, TEXT_CONTENTS_DETECTORS,
+            TEXT_CONTEXT_DOC_DETECTORS, TEXT_GENERATION_DETECTORS,
+        },
+        handlers::{
+            batch_text_content_detection::BatchRecordResult,
+            chat_completions_detection::ChatCompletionsDetectionTask,
+            completions_detection::CompletionsDetectionTask, *,
+        },
+        types::{TENANT_HEADER, Tenant},
+    },
+    utils::{
+        self,
+        deadline::{Deadline, REQUEST_DEADLINE_HEADER},
+        metrics,
+        trace::current_trace_id,
     },
-    utils::{self, trace::current_trace_id},
 };
 
 const PACKAGE_VERSION: &str = env!("CARGO_PKG_VERSION");
 const PACKAGE_NAME: &str = env!("CARGO_PKG_NAME");
 
+/// Detectors accepted by request fields of endpoints: (path, request field, accepted detectors).
+/// Handlers validate detectors against the same [`AcceptedDetectors`].
+const DETECTOR_ENDPOINTS: &[(&str, &str, AcceptedDetectors)] = &[
+    (
+        "/api/v1/task/classification-with-text-generation",
+        "input",
+        TEXT_CONTENTS_DETECTORS,
+    ),
+    (
+        "/api/v1/task/classification-with-text-generation",
+        "output",
+        TEXT_CONTENTS_DETECTORS,
+    ),
+    (
+        "/api/v1/task/server-streaming-classification-with-text-generation",
+        "input",
+        TEXT_CONTENTS_DETECTORS,
+    ),
+    (
+        "/api/v1/task/server-streaming-classification-with-text-generation",
+        "output",
+        STREAMING_TEXT_CONTENTS_DETECTORS,
+    ),
+    (
+        "/api/v2/text/detection/stream-content",
+        "detectors",
+        STREAMING_TEXT_CONTENTS_DETECTORS,
+    ),
+    (
+        "/api/v2/text/detection/stream-content/ws",
+        "detectors",
+        STREAMING_TEXT_CONTENTS_DETECTORS,
+    ),
+    (
+        "/api/v2/text/generation-detection",
+        "detectors",
+        TEXT_GENERATION_DETECTORS,
+    ),
+    (
+        "/api/v2/text/detection/content",
+        "detectors",
+        TEXT_CONTENTS_DETECTORS,
+    ),
+    (
+        "/api/v2/text/detection/content/batch",
+        "detectors",
+        TEXT_CONTENTS_DETECTORS,
+    ),
+    (
+        "/api/v2/text/detection/chat",
+        "detectors",
+        TEXT_CHAT_DETECTORS,
+    ),
+    (
+        "/api/v2/text/detection/context",
+        "detectors",
+        TEXT_CONTEXT_DOC_DETECTORS,
+    ),
+    (
+        "/api/v2/text/detection/generated",
+        "detectors",
+        TEXT_GENERATION_DETECTORS,
+    ),
+    (
+        "/api/v2/chat/completions-detection",
+        "input",
+        CHAT_COMPLETIONS_INPUT_DETECTORS,
+    ),
+    (
+        "/api/v2/chat/completions-detection",
+        "output",
+        CHAT_COMPLETIONS_OUTPUT_DETECTORS,
+    ),
+    (
+        "/api/v2/completions-detection",
+        "input",
+        TEXT_CONTENTS_DETECTORS,
+    ),
+    (
+        "/api/v2/completions-detection",
+        "output",
+        TEXT_CONTENTS_DETECTORS,
+    ),
+];
+
 /// Creates health router.
 pub fn health_router(state: Arc<ServerState>) -> Router {
     Router::new()
         .route("/health", get(health))
+        .route("/live", get(health))
+        .route("/ready", get(ready))
         .route("/info", get(info))
+        .route("/metrics", get(prometheus_metrics))
         .with_state(state)
 }
 
@@ -78,17 +197,32 @@ pub fn guardrails_router(state: Arc<ServerState>) -> Router {
             "/api/v2/text/detection/stream-content",
             post(stream_content_detection),
         )
+        .route(
+            "/api/v2/text/detection/stream-content/ws",
+            get(stream_content_detection_ws),
+        )
         .route(
             "/api/v2/text/generation-detection",
             post(generation_with_detection),
         )
         .route("/api/v2/text/detection/content", post(detection_content))
+        .route(
+            "/api/v2/text/detection/content/batch",
+            post(batch_detection_content),
+        )
         .route("/api/v2/text/detection/chat", post(detect_chat))
         .route(
             "/api/v2/text/detection/context",
             post(detect_context_documents),
         )
-        .route("/api/v2/text/detection/generated", post(detect_generated));
+        .route("/api/v2/text/detection/generated", post(detect_generated))
+        // job routes
+        .route("/api/v2/jobs", post(submit_job))
+        .route("/api/v2/jobs/{job_id}", get(get_job).delete(cancel_job))
+        // discovery routes
+        .route("/api/v1/detectors", get(detectors))
+        .route("/api/v1/detectors/{detector_id}", get(detector))
+        .route("/api/v1/chunkers", get(chunkers));
     if state.orchestrator.config().chat_completions.is_some() {
         info!("Enabling chat completions detection endpoint");
         router = router.route(
@@ -96,6 +230,26 @@ pub fn guardrails_router(state: Arc<ServerState>) -> Router {
             post(chat_completions_detection),
         );
     }
+    if state.orchestrator.config().completions.is_some() {
+        info!("Enabling completions detection endpoint");
+        router = router.route("/api/v2/completions-detection", post(completions_detection));
+    }
+    // Limits and authentication are always layered as they may be enabled on config reload,
+    // both are no-ops if not configured
+    if state.limiter().is_some() {
+        info!("Enabling request limits");
+    }
+    router = router.route_layer(middleware::from_fn_with_state(state.clone(), limits::limit));
+    // Added last to authenticate requests before limits are applied
+    if state.authenticator().is_some() {
+        info!("Enabling authentication");
+    }
+    router = router.route_layer(middleware::from_fn_with_state(
+        state.clone(),
+        auth::authenticate,
+    ));
+    // Added last to measure all requests, including rejected ones
+    router = router.route_layer(middleware::from_fn(metrics::track_requests));
     router.with_state(state)
 }
 
@@ -108,23 +262,140 @@ async fn health() -> Result<impl IntoResponse, ()> {
     Ok(Json(info_object).into_response())
 }
 
+/// Returns metrics in the Prometheus text format.
+async fn prometheus_metrics() -> impl IntoResponse {
+    (
+        [(http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(),
+    )
+}
+
+/// Readiness probe, fails until the start-up warm-up probe completes
+/// and while any required client service is not healthy.
+/// Only results of health probes are read, clients are not probed.
+async fn ready(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    if !state.orchestrator.warmed_up() {
+        let response = ReadyResponse {
+            ready: false,
+            reason: Some("client health warm-up probe in progress".into()),
+            unhealthy_services: Vec::new(),
+        };
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(response));
+    }
+    let Some(unhealthy_services) = state.orchestrator.unhealthy_required_clients().await else {
+        let response = ReadyResponse {
+            ready: false,
+            reason: Some("required client services have not been probed".into()),
+            unhealthy_services: Vec::new(),
+        };
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(response));
+    };
+    if !unhealthy_services.is_empty() {
+        let response = ReadyResponse {
+            ready: false,
+            reason: Some("required client services are not healthy".into()),
+            unhealthy_services,
+        };
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(response));
+    }
+    let response = ReadyResponse {
+        ready: true,
+        reason: None,
+        unhealthy_services,
+    };
+    (StatusCode::OK, Json(response))
+}
+
 async fn info(
     State(state): State<Arc<ServerState>>,
     Query(params): Query<InfoParams>,
 ) -> Result<Json<InfoResponse>, Error> {
     let services = state.orchestrator.client_health(params.probe).await;
-    Ok(Json(InfoResponse { services }))
+    let history = state.orchestrator.client_health_history().await;
+    let circuit_breakers = state.orchestrator.circuit_breakers();
+    Ok(Json(InfoResponse {
+        services,
+        history,
+        circuit_breakers,
+    }))
+}
+
+async fn detectors(
+    State(state): State<Arc<ServerState>>,
+    tenant: Option<Extension<Tenant>>,
+) -> Result<Json<DetectorsResponse>, Error> {
+    let config = state.orchestrator.config();
+    let health = state.orchestrator.latest_client_health().await;
+    let mut detectors = config
+        .detectors
+        .iter()
+        .filter(|(detector_id, _)| {
+            tenant
+                .as_ref()
+                .is_none_or(|Extension(tenant)| tenant.allows_detector(detector_id))
+        })
+        .map(|(detector_id, detector)| detector_info(&config, &health, detector_id, detector))
+        .collect::<Vec<_>>();
+    detectors.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(Json(DetectorsResponse { detectors }))
+}
+
+async fn detector(
+    State(state): State<Arc<ServerState>>,
+    tenant: Option<Extension<Tenant>>,
+    Path(detector_id): Path<String>,
+) -> Result<Json<DetectorInfo>, Error> {
+    let config = state.orchestrator.config();
+    let allowed = tenant
+        .as_ref()
+        .is_none_or(|Extension(tenant)| tenant.allows_detector(&detector_id));
+    match config.detector(&detector_id) {
+        Some(detector) if allowed => {
+            let health = state.orchestrator.latest_client_health().await;
+            Ok(Json(detector_info(
+                &config,
+                &health,
+                &detector_id,
+                detector,
+            )))
+        }
+        _ => Err(orchestrator::Error::DetectorNotFound(detector_id).into()),
+    }
+}
+
+async fn chunkers(State(state): State<Arc<ServerState>>) -> Result<Json<ChunkersResponse>, Error> {
+    let config = state.orchestrator.config();
+    let health = state.orchestrator.latest_client_health().await;
+    let mut chunkers = config
+        .chunkers
+        .iter()
+        .flatten()
+        .map(|(chunker_id, chunker)| ChunkerInfo {
+            id: chunker_id.clone(),
+            r#type: chunker.r#type,
+            health: health.get(chunker_id).cloned(),
+        })
+        .collect::<Vec<_>>();
+    chunkers.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(Json(ChunkersResponse { chunkers }))
 }
 
 async fn classification_with_gen(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
-    WithRejection(Json(request), _): WithRejection<Json<models::GuardrailsHttpRequest>, Error>,
+    tenant: Option<Extension<Tenant>>,
+    WithRejection(Json(mut request), _): WithRejection<Json<models::GuardrailsHttpRequest>, Error>,
 ) -> Result<impl IntoResponse, Error> {
     let trace_id = current_trace_id();
+    request.apply_guardrail_profile(&state.orchestrator.config())?;
     request.validate()?;
     let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
-    let task = ClassificationWithGenTask::new(trace_id, request, headers);
+    let task = ClassificationWithGenTask::new(
+        trace_id,
+        request,
+        headers,
+        tenant.map(|Extension(tenant)| tenant),
+    );
     match state.orchestrator.handle(task).await {
         Ok(response) => Ok(Json(response).into_response()),
         Err(error) => Err(error.into()),
@@ -134,6 +405,7 @@ async fn classification_with_gen(
 async fn generation_with_detection(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
+    tenant: Option<Extension<Tenant>>,
     WithRejection(Json(request), _): WithRejection<
         Json<models::GenerationWithDetectionHttpRequest>,
         Error,
@@ -142,7 +414,12 @@ async fn generation_with_detection(
     let trace_id = current_trace_id();
     request.validate()?;
     let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
-    let task = GenerationWithDetectionTask::new(trace_id, request, headers);
+    let task = GenerationWithDetectionTask::new(
+        trace_id,
+        request,
+        headers,
+        tenant.map(|Extension(tenant)| tenant),
+    );
     match state.orchestrator.handle(task).await {
         Ok(response) => Ok(Json(response).into_response()),
         Err(error) => Err(error.into()),
@@ -152,10 +429,14 @@ async fn generation_with_detection(
 async fn stream_classification_with_gen(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
-    WithRejection(Json(request), _): WithRejection<Json<models::GuardrailsHttpRequest>, Error>,
+    tenant: Option<Extension<Tenant>>,
+    WithRejection(Json(mut request), _): WithRejection<Json<models::GuardrailsHttpRequest>, Error>,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
     let trace_id = current_trace_id();
-    if let Err(error) = request.validate() {
+    if let Err(error) = request
+        .apply_guardrail_profile(&state.orchestrator.config())
+        .and_then(|_| request.validate())
+    {
         // Request validation failed, return stream with single error SSE event
         let error: Error = error.into();
         return Sse::new(
@@ -167,7 +448,12 @@ async fn stream_classification_with_gen(
         );
     }
     let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
-    let task = StreamingClassificationWithGenTask::new(trace_id, request, headers);
+    let task = StreamingClassificationWithGenTask::new(
+        trace_id,
+        request,
+        headers,
+        tenant.map(|Extension(tenant)| tenant),
+    );
     let response_stream = state.orchestrator.handle(task).await.unwrap();
     // Convert response stream to a stream of SSE events
     let event_stream = response_stream
@@ -188,23 +474,11 @@ async fn stream_classification_with_gen(
 async fn stream_content_detection(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
+    tenant: Option<Extension<Tenant>>,
     json_lines: JsonLines<StreamingContentDetectionRequest>,
 ) -> Result<impl IntoResponse, Error> {
     let trace_id = current_trace_id();
-    // Validate the content-type from the header and ensure it is application/x-ndjson
-    // If it's not, return a UnsupportedContentType error with the appropriate message
-    let content_type = headers
-        .get(http::header::CONTENT_TYPE)
-        .and_then(|value| value.to_str().ok());
-    match content_type {
-        Some(content_type) if content_type.starts_with("application/x-ndjson") => (),
-        _ => {
-            return Err(Error {
-                code: http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
-                details: "expected application/x-ndjson".into(),
-            });
-        }
-    };
+    validate_ndjson_content_type(&headers)?;
     let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
 
     // Create input stream
@@ -220,7 +494,12 @@ async fn stream_content_detection(
         .boxed();
 
     // Create task and submit to handler
-    let task = StreamingContentDetectionTask::new(trace_id, headers, input_stream);
+    let task = StreamingContentDetectionTask::new(
+        trace_id,
+        headers,
+        input_stream,
+        tenant.map(|Extension(tenant)| tenant),
+    );
     let mut response_stream = state.orchestrator.handle(task).await?;
 
     // Create output stream
@@ -250,33 +529,163 @@ async fn stream_content_detection(
     Ok(Response::new(axum::body::Body::from_stream(output_stream)))
 }
 
+async fn stream_content_detection_ws(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    tenant: Option<Extension<Tenant>>,
+    permit: Option<Extension<Arc<StreamPermit>>>,
+    matched_path: MatchedPath,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let trace_id = current_trace_id();
+    let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
+    let tenant = tenant.map(|Extension(tenant)| tenant);
+    let permit = permit.map(|Extension(permit)| permit);
+    let span = Span::current();
+    let endpoint = matched_path.as_str().to_string();
+    ws.on_upgrade(move |socket| {
+        // The connection is tracked as a stream in flight until it is closed
+        let stream_guard = metrics::track_stream(&endpoint);
+        async move {
+            ws::stream_content_detection(socket, state, trace_id, headers, tenant, permit).await;
+            drop(stream_guard);
+        }
+        .instrument(span)
+    })
+}
+
 async fn detection_content(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
-    WithRejection(Json(request), _): WithRejection<
+    tenant: Option<Extension<Tenant>>,
+    WithRejection(Json(mut request), _): WithRejection<
         Json<models::TextContentDetectionHttpRequest>,
         Error,
     >,
 ) -> Result<impl IntoResponse, Error> {
     let trace_id = current_trace_id();
+    request.apply_guardrail_profile(&state.orchestrator.config())?;
     request.validate()?;
     let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
-    let task = TextContentDetectionTask::new(trace_id, request, headers);
+    let task = TextContentDetectionTask::new(
+        trace_id,
+        request,
+        headers,
+        tenant.map(|Extension(tenant)| tenant),
+    );
     match state.orchestrator.handle(task).await {
         Ok(response) => Ok(Json(response).into_response()),
         Err(error) => Err(error.into()),
     }
 }
 
+async fn batch_detection_content(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    tenant: Option<Extension<Tenant>>,
+    Query(params): Query<BatchDetectionParams>,
+    json_lines: JsonLines<BatchDetectionRecord>,
+) -> Result<impl IntoResponse, Error> {
+    let trace_id = current_trace_id();
+    validate_ndjson_content_type(&headers)?;
+    params.validate()?;
+    let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
+
+    // Create input stream, records failing to parse produce error results
+    let input_stream = json_lines
+        .map(|result| result.map_err(|error| orchestrator::Error::Validation(error.to_string())))
+        .enumerate()
+        .boxed();
+
+    // Create task and submit to handler
+    let task = BatchTextContentDetectionTask::new(
+        trace_id,
+        headers,
+        params.order,
+        params.concurrency.unwrap_or(DEFAULT_BATCH_CONCURRENCY),
+        input_stream,
+        tenant.map(|Extension(tenant)| tenant),
+    );
+    let response_stream = state.orchestrator.handle(task).await?;
+
+    // Create output stream of ND-JSON formatted results
+    let output_stream = response_stream.map(|record| {
+        let result = batch_detection_result(record);
+        Ok::<_, Infallible>(utils::json::to_nd_string(&result).unwrap())
+    });
+
+    Ok((
+        [(http::header::CONTENT_TYPE, "application/x-ndjson")],
+        axum::body::Body::from_stream(output_stream),
+    ))
+}
+
+async fn submit_job(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    tenant: Option<Extension<Tenant>>,
+    WithRejection(Json(mut request), _): WithRejection<Json<JobRequest>, Error>,
+) -> Result<impl IntoResponse, Error> {
+    let trace_id = current_trace_id();
+    request.validate(&state.orchestrator.config())?;
+    let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
+    let job = jobs::submit(
+        state,
+        trace_id,
+        headers,
+        tenant.map(|Extension(tenant)| tenant),
+        request,
+    )
+    .await?;
+    Ok((http::StatusCode::ACCEPTED, Json(job)))
+}
+
+async fn get_job(
+    State(state): State<Arc<ServerState>>,
+    tenant: Option<Extension<Tenant>>,
+    Path(job_id): Path<String>,
+) -> Result<Json<Job>, Error> {
+    let tenant = tenant.as_ref().map(|Extension(tenant)| tenant);
+    match state.jobs.get(&job_id, tenant).await {
+        Some(job) => Ok(Json(job)),
+        None => Err(job_not_found(&job_id)),
+    }
+}
+
+async fn cancel_job(
+    State(state): State<Arc<ServerState>>,
+    tenant: Option<Extension<Tenant>>,
+    Path(job_id): Path<String>,
+) -> Result<Json<Job>, Error> {
+    let tenant = tenant.as_ref().map(|Extension(tenant)| tenant);
+    match state.jobs.cancel(&job_id, tenant).await {
+        Some(job) => Ok(Json(job)),
+        None => Err(job_not_found(&job_id)),
+    }
+}
+
+fn job_not_found(job_id: &str) -> Error {
+    Error {
+        code: http::StatusCode::NOT_FOUND,
+        details: format!("job `{job_id}` not found"),
+    }
+}
+
 async fn detect_context_documents(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
+    tenant: Option<Extension<Tenant>>,
     WithRejection(Json(request), _): WithRejection<Json<models::ContextDocsHttpRequest>, Error>,
 ) -> Result<impl IntoResponse, Error> {
     let trace_id = current_trace_id();
     request.validate()?;
     let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
-    let task = ContextDocsDetectionTask::new(trace_id, request, headers);
+    let task = ContextDocsDetectionTask::new(
+        trace_id,
+        request,
+        headers,
+        tenant.map(|Extension(tenant)| tenant),
+    );
     match state.orchestrator.handle(task).await {
         Ok(response) => Ok(Json(response).into_response()),
         Err(error) => Err(error.into()),
@@ -286,12 +695,18 @@ async fn detect_context_documents(
 async fn detect_chat(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
+    tenant: Option<Extension<Tenant>>,
     WithRejection(Json(request), _): WithRejection<Json<models::ChatDetectionHttpRequest>, Error>,
 ) -> Result<impl IntoResponse, Error> {
     let trace_id = current_trace_id();
     request.validate_for_text()?;
     let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
-    let task = ChatDetectionTask::new(trace_id, request, headers);
+    let task = ChatDetectionTask::new(
+        trace_id,
+        request,
+        headers,
+        tenant.map(|Extension(tenant)| tenant),
+    );
     match state.orchestrator.handle(task).await {
         Ok(response) => Ok(Json(response).into_response()),
         Err(error) => Err(error.into()),
@@ -301,6 +716,7 @@ async fn detect_chat(
 async fn detect_generated(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
+    tenant: Option<Extension<Tenant>>,
     WithRejection(Json(request), _): WithRejection<
         Json<models::DetectionOnGeneratedHttpRequest>,
         Error,
@@ -309,7 +725,12 @@ async fn detect_generated(
     let trace_id = current_trace_id();
     request.validate()?;
     let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
-    let task = DetectionOnGenerationTask::new(trace_id, request, headers);
+    let task = DetectionOnGenerationTask::new(
+        trace_id,
+        request,
+        headers,
+        tenant.map(|Extension(tenant)| tenant),
+    );
     match state.orchestrator.handle(task).await {
         Ok(response) => Ok(Json(response).into_response()),
         Err(error) => Err(error.into()),
@@ -319,13 +740,20 @@ async fn detect_generated(
 async fn chat_completions_detection(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
-    WithRejection(Json(request), _): WithRejection<Json<ChatCompletionsRequest>, Error>,
+    tenant: Option<Extension<Tenant>>,
+    WithRejection(Json(mut request), _): WithRejection<Json<ChatCompletionsRequest>, Error>,
 ) -> Result<impl IntoResponse, Error> {
     use ChatCompletionsResponse::*;
     let trace_id = current_trace_id();
+    request.apply_guardrail_profile(&state.orchestrator.config())?;
     request.validate()?;
     let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
-    let task = ChatCompletionsDetectionTask::new(trace_id, request, headers);
+    let task = ChatCompletionsDetectionTask::new(
+        trace_id,
+        request,
+        headers,
+        tenant.map(|Extension(tenant)| tenant),
+    );
     match state.orchestrator.handle(task).await {
         Ok(response) => match response {
             Unary(response) => Ok(Json(response).into_response()),
@@ -353,11 +781,132 @@ async fn chat_completions_detection(
     }
 }
 
+async fn completions_detection(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    tenant: Option<Extension<Tenant>>,
+    WithRejection(Json(mut request), _): WithRejection<Json<CompletionsRequest>, Error>,
+) -> Result<impl IntoResponse, Error> {
+    use CompletionsResponse::*;
+    let trace_id = current_trace_id();
+    request.apply_guardrail_profile(&state.orchestrator.config())?;
+    request.validate()?;
+    let headers = filter_headers(&state.orchestrator.config().passthrough_headers, headers);
+    let task = CompletionsDetectionTask::new(
+        trace_id,
+        request,
+        headers,
+        tenant.map(|Extension(tenant)| tenant),
+    );
+    match state.orchestrator.handle(task).await {
+        Ok(response) => match response {
+            Unary(response) => Ok(Json(response).into_response()),
+            Streaming(response_rx) => {
+                let response_stream = ReceiverStream::new(response_rx);
+                // Convert response stream to a stream of SSE events
+                let event_stream: BoxStream<Result<Event, Infallible>> = response_stream
+                    .map(|message| match message {
+                        Ok(Some(completion)) => Ok(Event::default().json_data(completion).unwrap()),
+                        Ok(None) => {
+                            // The stream completed, send [DONE] message
+                            Ok(Event::default().data("[DONE]"))
+                        }
+                        Err(error) => {
+                            let error: Error = error.into();
+                            Ok(Event::default().event("error").json_data(error).unwrap())
+                        }
+                    })
+                    .boxed();
+                let sse = Sse::new(event_stream).keep_alive(KeepAlive::default());
+                Ok(sse.into_response())
+            }
+        },
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Builds [`DetectorInfo`] for a detector, listing the enabled endpoints accepting it.
+fn detector_info(
+    config: &OrchestratorConfig,
+    health: &HealthCheckCache,
+    detector_id: &str,
+    detector: &DetectorConfig,
+) -> DetectorInfo {
+    let endpoints = DETECTOR_ENDPOINTS
+        .iter()
+        .filter(|(path, _, accepted)| {
+            let enabled = match *path {
+                "/api/v2/chat/completions-detection" => config.chat_completions.is_some(),
+                "/api/v2/completions-detection" => config.completions.is_some(),
+                _ => true,
+            };
+            enabled && accepted.accepts(detector)
+        })
+        .map(|(path, field, ..)| DetectorEndpoint {
+            path: path.to_string(),
+            field: field.to_string(),
+        })
+        .collect();
+    DetectorInfo {
+        id: detector_id.to_string(),
+        r#type: detector.r#type.clone(),
+        chunker_id: detector.chunker_id.clone(),
+        default_threshold: detector.default_threshold,
+        health: health.get(detector_id).cloned(),
+        endpoints,
+    }
+}
+
+/// Converts the result of a record of a batch to its response format.
+pub fn batch_detection_result(record: BatchRecordResult) -> BatchDetectionResult {
+    let (detections, warnings, error) = match record.result {
+        Ok(result) => (Some(result.detections), result.warnings, None),
+        Err(error) => {
+            // Convert orchestrator::Error to server::Error
+            let error: Error = error.into();
+            let error = BatchDetectionError {
+                code: error.code.as_u16(),
+                details: error.details,
+            };
+            (None, Vec::new(), Some(error))
+        }
+    };
+    BatchDetectionResult {
+        index: record.index,
+        id: record.id,
+        detections,
+        error,
+        warnings,
+    }
+}
+
+/// Validates the content-type from the header and ensures it is application/x-ndjson.
+/// If it's not, returns a UnsupportedContentType error with the appropriate message.
+fn validate_ndjson_content_type(headers: &HeaderMap) -> Result<(), Error> {
+    let content_type = headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    match content_type {
+        Some(content_type) if content_type.starts_with("application/x-ndjson") => Ok(()),
+        _ => Err(Error {
+            code: http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            details: "expected application/x-ndjson".into(),
+        }),
+    }
+}
+
 /// Filters a [`HeaderMap`] with a set of header names, returning a new [`HeaderMap`].
+/// The deadline set by the request timeout header, if any, is carried to downstream calls.
 pub fn filter_headers(passthrough_headers: &HashSet<String>, headers: HeaderMap) -> HeaderMap {
-    headers
+    let mut filtered: HeaderMap = headers
         .iter()
         .filter(|(name, _)| passthrough_headers.contains(&name.as_str().to_lowercase()))
         .map(|(name, value)| (name.clone(), value.clone()))
-        .collect()
+        .collect();
+    filtered.remove(REQUEST_DEADLINE_HEADER);
+    filtered.remove(TENANT_HEADER);
+    if let Some(deadline) = Deadline::from_request_headers(&headers) {
+        deadline.insert_header(&mut filtered);
+    }
+    filtered
 }
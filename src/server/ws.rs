@@ -0,0 +1,193 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+//! WebSocket transport for streaming content detection
+use std::{sync::Arc, time::Duration};
+
+use axum::extract::ws::{CloseFrame, Message, WebSocket, close_code};
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt, future, stream::SplitSink};
+use http::{HeaderMap, StatusCode};
+use opentelemetry::trace::TraceId;
+use tracing::{debug, warn};
+
+use super::{Error, ServerState, limits::StreamPermit};
+use crate::{
+    models::StreamingContentDetectionRequest,
+    orchestrator::{
+        self,
+        handlers::{Handle, StreamingContentDetectionTask},
+        types::Tenant,
+    },
+};
+
+/// Interval between pings sent to keep the connection alive.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// Maximum length of a close frame reason in bytes.
+const MAX_CLOSE_REASON_LEN: usize = 123;
+
+/// Runs streaming content detection over a WebSocket.
+///
+/// Text or binary frames hold [`StreamingContentDetectionRequest`] messages and are
+/// answered with `StreamingContentDetectionResponse` text frames. On error, the error
+/// is sent and the connection is closed with a close code matching the error.
+///
+/// Frames are read as the task consumes its input stream and responses are sent one
+/// at a time, so slow detectors or clients apply backpressure to the other side.
+/// The stream permit, if any, is held until the connection is closed.
+pub async fn stream_content_detection(
+    socket: WebSocket,
+    state: Arc<ServerState>,
+    trace_id: TraceId,
+    headers: HeaderMap,
+    tenant: Option<Tenant>,
+    _permit: Option<Arc<StreamPermit>>,
+) {
+    let (mut sender, receiver) = socket.split();
+
+    // Create input stream, ending when the client closes the connection
+    let input_stream = receiver
+        .take_while(|message| future::ready(!matches!(message, Ok(Message::Close(_)) | Err(_))))
+        .filter_map(|message| async move {
+            match message {
+                Ok(Message::Text(text)) => Some(parse_request(text.as_str().as_bytes())),
+                Ok(Message::Binary(bytes)) => Some(parse_request(&bytes)),
+                // Pings are answered automatically, pongs are ignored
+                _ => None,
+            }
+        })
+        .enumerate()
+        .boxed();
+
+    // Create task and submit to handler
+    let task = StreamingContentDetectionTask::new(trace_id, headers, input_stream, tenant);
+    let mut response_stream = match state.orchestrator.handle(task).await {
+        Ok(response_stream) => response_stream,
+        Err(error) => return close_with_error(&mut sender, error.into()).await,
+    };
+
+    let mut ping_interval = tokio::time::interval(PING_INTERVAL);
+    ping_interval.reset();
+    loop {
+        tokio::select! {
+            result = response_stream.next() => match result {
+                Some(Ok(response)) => {
+                    let message = serde_json::to_string(&response).unwrap();
+                    if sender.send(Message::Text(message.into())).await.is_err() {
+                        debug!("websocket closed by client");
+                        return;
+                    }
+                }
+                Some(Err(error)) => return close_with_error(&mut sender, error.into()).await,
+                None => {
+                    let _ = sender
+                        .send(Message::Close(Some(CloseFrame {
+                            code: close_code::NORMAL,
+                            reason: "".into(),
+                        })))
+                        .await;
+                    return;
+                }
+            },
+            _ = ping_interval.tick() => {
+                if sender.send(Message::Ping(Bytes::new())).await.is_err() {
+                    debug!("websocket closed by client");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Parses and validates a request frame.
+fn parse_request(data: &[u8]) -> Result<StreamingContentDetectionRequest, orchestrator::Error> {
+    let request: StreamingContentDetectionRequest = serde_json::from_slice(data)
+        .map_err(|error| orchestrator::Error::Validation(error.to_string()))?;
+    request.validate()?;
+    Ok(request)
+}
+
+/// Sends an error and closes the connection with a close code matching the error.
+async fn close_with_error(sender: &mut SplitSink<WebSocket, Message>, error: Error) {
+    warn!(%error, "closing websocket on error");
+    let close_frame = close_frame(&error);
+    let message = serde_json::to_string(&error).unwrap();
+    if sender.send(Message::Text(message.into())).await.is_ok() {
+        let _ = sender.send(Message::Close(Some(close_frame))).await;
+    }
+}
+
+/// Returns the close frame for an error.
+fn close_frame(error: &Error) -> CloseFrame {
+    let code = match *error.code() {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => close_code::INVALID,
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => {
+            close_code::POLICY
+        }
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => close_code::AGAIN,
+        _ => close_code::ERROR,
+    };
+    // Close frame reasons are limited in size, truncate on a char boundary
+    let mut len = error.details().len().min(MAX_CLOSE_REASON_LEN);
+    while !error.details().is_char_boundary(len) {
+        len -= 1;
+    }
+    CloseFrame {
+        code,
+        reason: error.details()[..len].into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_close_frame() {
+        let error = Error {
+            code: StatusCode::UNPROCESSABLE_ENTITY,
+            details: "invalid request".into(),
+        };
+        let frame = close_frame(&error);
+        assert_eq!(frame.code, close_code::INVALID);
+        assert_eq!(frame.reason.as_str(), "invalid request");
+
+        let error = Error {
+            code: StatusCode::NOT_FOUND,
+            details: "é".repeat(100),
+        };
+        let frame = close_frame(&error);
+        assert_eq!(frame.code, close_code::POLICY);
+        assert_eq!(frame.reason.as_str(), "é".repeat(61));
+
+        let error = Error {
+            code: StatusCode::INTERNAL_SERVER_ERROR,
+            details: "unexpected error occurred while processing request".into(),
+        };
+        assert_eq!(close_frame(&error).code, close_code::ERROR);
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let request = parse_request(br#"{"content": "hello"}"#).unwrap();
+        assert_eq!(request.content, "hello");
+        assert!(matches!(
+            parse_request(b"not json"),
+            Err(orchestrator::Error::Validation(_))
+        ));
+    }
+}
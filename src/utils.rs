@@ -1,6 +1,9 @@
 use hyper::Uri;
 use url::Url;
+pub mod deadline;
 pub mod json;
+pub mod metrics;
+pub mod task_group;
 pub mod tls;
 pub mod trace;
 
@@ -0,0 +1,150 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+//! Request deadlines propagated to downstream calls.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::{HeaderMap, HeaderValue};
+use tracing::warn;
+
+/// Header setting the time budget of a request in milliseconds.
+/// Also sent to HTTP services with the budget remaining for the call.
+pub const REQUEST_TIMEOUT_HEADER: &str = "x-request-timeout-ms";
+/// Header setting the time budget of a gRPC request.
+pub const GRPC_TIMEOUT_HEADER: &str = "grpc-timeout";
+/// Internal header carrying the request deadline in milliseconds since the Unix epoch.
+pub const REQUEST_DEADLINE_HEADER: &str = "x-request-deadline";
+
+/// Deadline of a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(SystemTime);
+
+impl Deadline {
+    /// Creates a deadline after a time budget from now.
+    pub fn after(timeout: Duration) -> Self {
+        Self(SystemTime::now() + timeout)
+    }
+
+    /// Returns the deadline set by a caller with the request timeout or gRPC timeout header.
+    pub fn from_request_headers(headers: &HeaderMap) -> Option<Self> {
+        let timeout = if let Some(value) = headers.get(REQUEST_TIMEOUT_HEADER) {
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_millis)
+        } else if let Some(value) = headers.get(GRPC_TIMEOUT_HEADER) {
+            value.to_str().ok().and_then(parse_grpc_timeout)
+        } else {
+            return None;
+        };
+        if timeout.is_none() {
+            warn!("ignoring invalid request timeout header");
+        }
+        timeout.map(Self::after)
+    }
+
+    /// Returns the deadline carried by the internal deadline header.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        headers
+            .get(REQUEST_DEADLINE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .map(|millis| Self(UNIX_EPOCH + Duration::from_millis(millis)))
+    }
+
+    /// Removes the internal deadline header, returning the deadline it carried.
+    pub fn take_from_headers(headers: &mut HeaderMap) -> Option<Self> {
+        let deadline = Self::from_headers(headers);
+        headers.remove(REQUEST_DEADLINE_HEADER);
+        deadline
+    }
+
+    /// Sets the internal deadline header.
+    pub fn insert_header(&self, headers: &mut HeaderMap) {
+        let millis = self
+            .0
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        headers.insert(REQUEST_DEADLINE_HEADER, HeaderValue::from(millis as u64));
+    }
+
+    /// Returns the time remaining until the deadline, zero if exceeded.
+    pub fn remaining(&self) -> Duration {
+        self.0.duration_since(SystemTime::now()).unwrap_or_default()
+    }
+
+    /// Returns true if the deadline has been exceeded.
+    pub fn is_exceeded(&self) -> bool {
+        self.remaining().is_zero()
+    }
+}
+
+/// Parses a gRPC timeout, e.g. `100m`, as defined by the gRPC over HTTP2 protocol.
+fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    if value.is_empty() || value.len() > 9 {
+        return None;
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount: u64 = amount.parse().ok()?;
+    match unit {
+        "H" => Some(Duration::from_secs(amount * 60 * 60)),
+        "M" => Some(Duration::from_secs(amount * 60)),
+        "S" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_millis(amount)),
+        "u" => Some(Duration::from_micros(amount)),
+        "n" => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(Deadline::from_request_headers(&headers), None);
+
+        headers.insert(REQUEST_TIMEOUT_HEADER, HeaderValue::from_static("60000"));
+        let deadline = Deadline::from_request_headers(&headers).unwrap();
+        assert!(deadline.remaining() > Duration::from_secs(59));
+
+        // Internal header roundtrip, truncated to milliseconds
+        deadline.insert_header(&mut headers);
+        let carried = Deadline::take_from_headers(&mut headers).unwrap();
+        assert!(deadline.remaining().abs_diff(carried.remaining()) < Duration::from_millis(10));
+        assert!(!headers.contains_key(REQUEST_DEADLINE_HEADER));
+
+        // Exceeded deadline
+        let deadline = Deadline::after(Duration::ZERO);
+        assert!(deadline.is_exceeded());
+        assert_eq!(deadline.remaining(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_parse_grpc_timeout() {
+        assert_eq!(parse_grpc_timeout("2S"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_grpc_timeout("150m"), Some(Duration::from_millis(150)));
+        assert_eq!(parse_grpc_timeout("1H"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_grpc_timeout("10"), None);
+        assert_eq!(parse_grpc_timeout("m"), None);
+        assert_eq!(parse_grpc_timeout("1234567890S"), None);
+    }
+}
@@ -0,0 +1,284 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+//! Prometheus metrics served on the health server.
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use futures::StreamExt;
+use http::header::CONTENT_TYPE;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder, core::Collector,
+};
+use tracing::error;
+
+use crate::orchestrator::{self, types::Detections};
+
+/// Content types of streaming responses.
+const STREAMING_CONTENT_TYPES: [&str; 2] = ["text/event-stream", "application/x-ndjson"];
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+static REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(
+        HistogramVec::new(
+            HistogramOpts::new(
+                "orchestrator_request_duration_seconds",
+                "Latency of requests until the response is returned, by endpoint and status code",
+            ),
+            &["endpoint", "status"],
+        )
+        .unwrap(),
+    )
+});
+
+static DETECTOR_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(
+        HistogramVec::new(
+            HistogramOpts::new(
+                "orchestrator_detector_request_duration_seconds",
+                "Latency of detector requests, by detector",
+            ),
+            &["detector_id"],
+        )
+        .unwrap(),
+    )
+});
+
+static CHUNKER_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(
+        HistogramVec::new(
+            HistogramOpts::new(
+                "orchestrator_chunker_request_duration_seconds",
+                "Latency of chunker requests, by chunker",
+            ),
+            &["chunker_id"],
+        )
+        .unwrap(),
+    )
+});
+
+static DETECTIONS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new(
+                "orchestrator_detections_total",
+                "Detections returned by detectors, by detector and detection class",
+            ),
+            &["detector_id", "detection"],
+        )
+        .unwrap(),
+    )
+});
+
+static ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new(
+                "orchestrator_errors_total",
+                "Errors returned by the orchestrator, by error",
+            ),
+            &["error"],
+        )
+        .unwrap(),
+    )
+});
+
+static IN_FLIGHT_STREAMS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(
+        IntGaugeVec::new(
+            Opts::new(
+                "orchestrator_in_flight_streams",
+                "Streaming responses, WebSocket connections and gRPC streams in flight, by endpoint",
+            ),
+            &["endpoint"],
+        )
+        .unwrap(),
+    )
+});
+
+static AUDIT_RECORDS_DROPPED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register(
+        IntCounter::new(
+            "orchestrator_audit_records_dropped_total",
+            "Audit records dropped as the buffer of the audit sink was full",
+        )
+        .unwrap(),
+    )
+});
+
+fn register<T: Collector + Clone + 'static>(metric: T) -> T {
+    REGISTRY.register(Box::new(metric.clone())).unwrap();
+    metric
+}
+
+/// Records the latency of a detector request.
+pub fn on_detector_request(detector_id: &str, latency: Duration) {
+    DETECTOR_REQUEST_DURATION
+        .with_label_values(&[detector_id])
+        .observe(latency.as_secs_f64());
+}
+
+/// Records the latency of a chunker request.
+pub fn on_chunker_request(chunker_id: &str, latency: Duration) {
+    CHUNKER_REQUEST_DURATION
+        .with_label_values(&[chunker_id])
+        .observe(latency.as_secs_f64());
+}
+
+/// Records detections returned by a detector.
+pub fn on_detections(detector_id: &str, detections: &Detections) {
+    for detection in detections.iter() {
+        DETECTIONS
+            .with_label_values(&[detector_id, &detection.detection])
+            .inc();
+    }
+}
+
+/// Records an error returned by the orchestrator.
+pub fn on_error(error: &orchestrator::Error) {
+    ERRORS.with_label_values(&[error.kind()]).inc();
+}
+
+/// Records an audit record dropped before being written to the audit sink.
+pub fn on_audit_record_dropped() {
+    AUDIT_RECORDS_DROPPED.inc();
+}
+
+/// Middleware recording the latency of requests and the streaming HTTP responses in flight.
+/// Requests are labeled with the route they matched to bound the number of series.
+/// Streams not served as streaming HTTP responses are tracked with [`track_stream`].
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let endpoint = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".into());
+    let now = Instant::now();
+    let response = next.run(request).await;
+    REQUEST_DURATION
+        .with_label_values(&[endpoint.as_str(), response.status().as_str()])
+        .observe(now.elapsed().as_secs_f64());
+    let streaming = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| {
+            STREAMING_CONTENT_TYPES
+                .iter()
+                .any(|streaming| content_type.starts_with(streaming))
+        });
+    if !streaming {
+        return response;
+    }
+    let guard = track_stream(&endpoint);
+    response.map(|body| {
+        Body::from_stream(body.into_data_stream().map(move |chunk| {
+            // Keep the guard alive until the stream is dropped
+            let _guard = &guard;
+            chunk
+        }))
+    })
+}
+
+/// Records a stream in flight, e.g. a WebSocket connection or a gRPC stream,
+/// until the returned guard is dropped.
+pub fn track_stream(endpoint: &str) -> StreamGuard {
+    StreamGuard::new(IN_FLIGHT_STREAMS.with_label_values(&[endpoint]))
+}
+
+/// Returns the metrics in the Prometheus text format.
+pub fn render() -> String {
+    let mut buffer = Vec::new();
+    if let Err(error) = TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer) {
+        error!(%error, "failed to encode metrics");
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// Increments an in-flight gauge, decremented when dropped.
+pub struct StreamGuard(IntGauge);
+
+impl StreamGuard {
+    fn new(gauge: IntGauge) -> Self {
+        gauge.inc();
+        Self(gauge)
+    }
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestrator::types::Detection;
+
+    #[test]
+    fn test_render() {
+        on_detector_request("test-detector", Duration::from_millis(50));
+        on_chunker_request("test-chunker", Duration::from_millis(10));
+        let detections: Detections = vec![
+            Detection {
+                detection: "has_HAP".into(),
+                ..Default::default()
+            },
+            Detection {
+                detection: "has_HAP".into(),
+                ..Default::default()
+            },
+        ]
+        .into();
+        on_detections("test-detector", &detections);
+        on_error(&orchestrator::Error::DetectorNotFound(
+            "test-detector".into(),
+        ));
+        on_audit_record_dropped();
+        let gauge = IN_FLIGHT_STREAMS.with_label_values(&["/test"]);
+        let guard = track_stream("/test");
+        assert_eq!(gauge.get(), 1);
+
+        let metrics = render();
+        assert!(metrics.contains(
+            r#"orchestrator_detector_request_duration_seconds_count{detector_id="test-detector"} 1"#
+        ));
+        assert!(metrics.contains(
+            r#"orchestrator_chunker_request_duration_seconds_count{chunker_id="test-chunker"} 1"#
+        ));
+        assert!(metrics.contains(
+            r#"orchestrator_detections_total{detection="has_HAP",detector_id="test-detector"} 2"#
+        ));
+        assert!(metrics.contains(r#"orchestrator_errors_total{error="detector_not_found"} 1"#));
+        assert!(metrics.contains("orchestrator_audit_records_dropped_total 1"));
+        assert!(metrics.contains(r#"orchestrator_in_flight_streams{endpoint="/test"} 1"#));
+
+        drop(guard);
+        assert_eq!(gauge.get(), 0);
+    }
+}
@@ -0,0 +1,120 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+//! Groups of tasks aborted together.
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use tokio::task::{AbortHandle, JoinHandle};
+
+tokio::task_local! {
+    static TASK_GROUP: TaskGroup;
+}
+
+/// Group of tasks aborted together, e.g. a job and the tasks spawned to run it.
+///
+/// Tasks spawned with [`spawn`] from a task of the group join the group.
+#[derive(Debug, Clone, Default)]
+pub struct TaskGroup(Arc<Mutex<TaskGroupState>>);
+
+#[derive(Debug, Default)]
+struct TaskGroupState {
+    aborted: bool,
+    handles: Vec<AbortHandle>,
+}
+
+impl TaskGroup {
+    /// Spawns a task in the group.
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let handle = tokio::spawn(TASK_GROUP.scope(self.clone(), future));
+        self.register(handle.abort_handle());
+        handle
+    }
+
+    /// Aborts all tasks of the group. Tasks spawned in the group afterwards are aborted immediately.
+    pub fn abort(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.aborted = true;
+        for handle in state.handles.drain(..) {
+            handle.abort();
+        }
+    }
+
+    fn register(&self, handle: AbortHandle) {
+        let mut state = self.0.lock().unwrap();
+        if state.aborted {
+            handle.abort();
+            return;
+        }
+        state.handles.retain(|handle| !handle.is_finished());
+        state.handles.push(handle);
+    }
+}
+
+/// Spawns a task, joining the [`TaskGroup`] of the current task, if any.
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match TASK_GROUP.try_with(|group| group.clone()) {
+        Ok(group) => group.spawn(future),
+        Err(_) => tokio::spawn(future),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_abort() {
+        let group = TaskGroup::default();
+        let (tx, rx) = oneshot::channel::<()>();
+        // Task spawned by a task of the group joins the group
+        let handle = group.spawn(async move {
+            let child = spawn(async move {
+                let _tx = tx;
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            });
+            let _ = child.await;
+        });
+        tokio::task::yield_now().await;
+        group.abort();
+        assert!(handle.await.unwrap_err().is_cancelled());
+        // Sender is dropped when the child task is aborted
+        assert!(
+            tokio::time::timeout(Duration::from_secs(1), rx)
+                .await
+                .unwrap()
+                .is_err()
+        );
+        // Tasks spawned after abort are aborted immediately
+        let handle = group.spawn(async {});
+        assert!(handle.await.unwrap_err().is_cancelled());
+    }
+}
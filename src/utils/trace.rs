@@ -37,7 +37,8 @@ use tracing_subscriber::{EnvFilter, Layer, layer::SubscriberExt};
 
 use crate::{
     args::{LogFormat, OtlpProtocol, TracingConfig},
-    clients::http::TracedResponse,
+    clients::{circuit_breaker::CircuitState, http::TracedResponse},
+    orchestrator::Stage,
 };
 
 fn resource(tracing_config: TracingConfig) -> Resource {
@@ -304,6 +305,77 @@ pub fn on_outgoing_eos(_trailers: Option<&HeaderMap>, stream_duration: Duration,
     );
 }
 
+/// Records a request rejected by a request rate or concurrent stream limit.
+/// `limit` is the kind of limit hit and `scope` is `global` or the tenant ID.
+pub fn on_limit_exceeded(limit: &'static str, scope: &str) {
+    info!(
+        limit,
+        scope,
+        monotonic_counter.limit_exceeded_count = 1,
+        "request rejected: limit exceeded"
+    );
+}
+
+/// Records a circuit breaker state transition.
+pub fn on_circuit_breaker_transition(service: &str, from: CircuitState, to: CircuitState) {
+    info!(
+        service,
+        %from,
+        %to,
+        monotonic_counter.circuit_breaker_transition_count = 1,
+        "circuit breaker state changed"
+    );
+}
+
+/// Records a request rejected by an open circuit breaker.
+pub fn on_circuit_breaker_rejected(service: &str) {
+    info!(
+        service,
+        monotonic_counter.circuit_breaker_rejected_count = 1,
+        "request rejected: circuit breaker open"
+    );
+}
+
+/// Records a request failed by its deadline while a stage was running.
+pub fn on_deadline_exceeded(stage: Stage) {
+    info!(
+        %stage,
+        monotonic_counter.deadline_exceeded_count = 1,
+        "request deadline exceeded"
+    );
+}
+
+/// Records a retried client request.
+pub fn on_client_retry(service: &str) {
+    info!(service, monotonic_counter.client_retry_count = 1);
+}
+
+/// Records a client request not retried because the retry budget is exhausted.
+pub fn on_retry_budget_exhausted(service: &str) {
+    info!(
+        service,
+        monotonic_counter.client_retry_budget_exhausted_count = 1,
+        "retry budget exhausted"
+    );
+}
+
+/// Records a detector cache lookup.
+pub fn on_detector_cache_lookup(detector_id: &str, hit: bool) {
+    if hit {
+        info!(
+            detector_id,
+            monotonic_counter.detector_cache_hit_count = 1,
+            "detector cache hit"
+        );
+    } else {
+        info!(
+            detector_id,
+            monotonic_counter.detector_cache_miss_count = 1,
+            "detector cache miss"
+        );
+    }
+}
+
 /// Injects the `traceparent` header into the header map from the current tracing span context.
 /// Also injects empty `tracestate` header by default. This can be used to propagate
 /// vendor-specific trace context.
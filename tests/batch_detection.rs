@@ -0,0 +1,198 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+use std::collections::HashMap;
+
+use common::{
+    detectors::{
+        DETECTOR_NAME_ANGLE_BRACKETS_WHOLE_DOC, NON_EXISTING_DETECTOR,
+        TEXT_CONTENTS_DETECTOR_ENDPOINT,
+    },
+    orchestrator::{
+        ORCHESTRATOR_BATCH_CONTENT_DETECTION_ENDPOINT, ORCHESTRATOR_CONFIG_FILE_PATH,
+        TestOrchestratorServer,
+    },
+};
+use fms_guardrails_orchestr8::{
+    clients::detector::{ContentAnalysisRequest, ContentAnalysisResponse},
+    models::{BatchDetectionRecord, BatchDetectionResult, DetectorParams, Metadata},
+    server,
+};
+use hyper::StatusCode;
+use mocktail::prelude::*;
+use test_log::test;
+use tracing::debug;
+
+pub mod common;
+
+/// Asserts a batch with valid and failing records.
+#[test(tokio::test)]
+async fn records_with_errors() -> Result<(), anyhow::Error> {
+    let detector_name = DETECTOR_NAME_ANGLE_BRACKETS_WHOLE_DOC;
+
+    let mut detector_mocks = MockSet::new();
+    detector_mocks.mock(|when, then| {
+        when.post()
+            .path(TEXT_CONTENTS_DETECTOR_ENDPOINT)
+            .json(ContentAnalysisRequest {
+                contents: vec!["This sentence has <a detection here>.".into()],
+                detector_params: DetectorParams::new(),
+            });
+        then.json([[ContentAnalysisResponse {
+            start: 18,
+            end: 35,
+            text: "a detection here".into(),
+            detection: "has_angle_brackets".into(),
+            detection_type: "angle_brackets".into(),
+            detector_id: Some(detector_name.into()),
+            score: 1.0,
+            evidence: None,
+            metadata: Metadata::new(),
+        }]]);
+    });
+    detector_mocks.mock(|when, then| {
+        when.post()
+            .path(TEXT_CONTENTS_DETECTOR_ENDPOINT)
+            .json(ContentAnalysisRequest {
+                contents: vec!["This sentence has no detections.".into()],
+                detector_params: DetectorParams::new(),
+            });
+        then.json([Vec::<ContentAnalysisResponse>::new()]);
+    });
+
+    // Start orchestrator server and its dependencies
+    let mock_detector_server = MockServer::new(detector_name).with_mocks(detector_mocks);
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .detector_servers([&mock_detector_server])
+        .build()
+        .await?;
+
+    let record = |id: &str, content: &str, detector: &str| {
+        serde_json::to_string(&BatchDetectionRecord {
+            id: id.into(),
+            content: content.into(),
+            detectors: HashMap::from([(detector.into(), DetectorParams::new())]),
+            guardrail_profile: None,
+        })
+        .unwrap()
+    };
+    let body = [
+        record("a", "This sentence has <a detection here>.", detector_name),
+        "not a record".into(),
+        record(
+            "c",
+            "This sentence has no detections.",
+            NON_EXISTING_DETECTOR,
+        ),
+        record("d", "", detector_name),
+        record("e", "This sentence has no detections.", detector_name),
+    ]
+    .join("\n");
+
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_BATCH_CONTENT_DETECTION_ENDPOINT)
+        .query(&[("concurrency", "2")])
+        .header("content-type", "application/x-ndjson")
+        .body(body)
+        .send()
+        .await?;
+    debug!("{response:#?}");
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let results = response
+        .text()
+        .await?
+        .lines()
+        .map(serde_json::from_str::<BatchDetectionResult>)
+        .collect::<Result<Vec<_>, _>>()?;
+    debug!("{results:#?}");
+
+    // Results are returned in input order
+    assert_eq!(
+        results
+            .iter()
+            .map(|result| result.index)
+            .collect::<Vec<_>>(),
+        [0, 1, 2, 3, 4]
+    );
+    assert_eq!(results[0].id.as_deref(), Some("a"));
+    assert_eq!(
+        results[0].detections,
+        Some(vec![ContentAnalysisResponse {
+            start: 18,
+            end: 35,
+            text: "a detection here".into(),
+            detection: "has_angle_brackets".into(),
+            detection_type: "angle_brackets".into(),
+            detector_id: Some(detector_name.into()),
+            score: 1.0,
+            evidence: None,
+            metadata: Metadata::new(),
+        }])
+    );
+    assert!(results[1].id.is_none());
+    assert_eq!(results[1].error.as_ref().unwrap().code, 422);
+    assert_eq!(results[2].id.as_deref(), Some("c"));
+    assert_eq!(
+        results[2].error.as_ref().unwrap().details,
+        format!("detector `{NON_EXISTING_DETECTOR}` not found")
+    );
+    assert_eq!(results[2].error.as_ref().unwrap().code, 404);
+    assert_eq!(
+        results[3].error.as_ref().unwrap().details,
+        "`content` is required"
+    );
+    assert_eq!(results[4].detections, Some(vec![]));
+    assert!(results[4].error.is_none());
+
+    Ok(())
+}
+
+/// Asserts batch request validation errors.
+#[test(tokio::test)]
+async fn request_validation() -> Result<(), anyhow::Error> {
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .build()
+        .await?;
+
+    // Invalid concurrency
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_BATCH_CONTENT_DETECTION_ENDPOINT)
+        .query(&[("concurrency", "0")])
+        .header("content-type", "application/x-ndjson")
+        .body("")
+        .send()
+        .await?;
+    debug!("{response:#?}");
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = response.json::<server::Error>().await?;
+    assert_eq!(response.details, "`concurrency` must be between 1 and 64");
+
+    // Invalid content type
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_BATCH_CONTENT_DETECTION_ENDPOINT)
+        .header("content-type", "application/json")
+        .body("")
+        .send()
+        .await?;
+    debug!("{response:#?}");
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    Ok(())
+}
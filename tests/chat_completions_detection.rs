@@ -19,29 +19,34 @@ use common::{
     chat_completions::CHAT_COMPLETIONS_ENDPOINT,
     chunker::CHUNKER_UNARY_ENDPOINT,
     detectors::{
-        ANSWER_RELEVANCE_DETECTOR, DETECTOR_NAME_ANGLE_BRACKETS_SENTENCE,
-        DETECTOR_NAME_ANGLE_BRACKETS_WHOLE_DOC, NON_EXISTING_DETECTOR,
-        TEXT_CONTENTS_DETECTOR_ENDPOINT,
+        ANSWER_RELEVANCE_DETECTOR, CHAT_DETECTOR_ENDPOINT, CONTEXT_DOC_DETECTOR_ENDPOINT,
+        DETECTION_ON_GENERATION_DETECTOR_ENDPOINT, DETECTOR_NAME_ANGLE_BRACKETS_SENTENCE,
+        DETECTOR_NAME_ANGLE_BRACKETS_WHOLE_DOC, FACT_CHECKING_DETECTOR, NON_EXISTING_DETECTOR,
+        PII_DETECTOR, TEXT_CONTENTS_DETECTOR_ENDPOINT,
     },
     errors::DetectorError,
     orchestrator::{
-        ORCHESTRATOR_CHAT_COMPLETIONS_DETECTION_ENDPOINT, ORCHESTRATOR_CONFIG_FILE_PATH,
+        ORCHESTRATOR_CHAT_COMPLETIONS_DETECTION_ENDPOINT, ORCHESTRATOR_CONFIG_FILE_PATH, SseStream,
         TestOrchestratorServer,
     },
 };
 use fms_guardrails_orchestr8::{
     clients::{
         chunker::MODEL_ID_HEADER_NAME as CHUNKER_MODEL_ID_HEADER_NAME,
-        detector::{ContentAnalysisRequest, ContentAnalysisResponse},
+        detector::{
+            ChatDetectionRequest, ContentAnalysisRequest, ContentAnalysisResponse,
+            ContextDocsDetectionRequest, ContextType, GenerationDetectionRequest,
+        },
         openai::{
-            ChatCompletion, ChatCompletionChoice, ChatCompletionMessage, ChatDetections, Content,
-            ContentPart, ContentType, InputDetectionResult, Message, OrchestratorWarning,
-            OutputDetectionResult, Role,
+            ChatCompletion, ChatCompletionChoice, ChatCompletionChunk, ChatCompletionChunkChoice,
+            ChatCompletionDelta, ChatCompletionMessage, ChatDetections, Content, ContentPart,
+            ContentType, InputDetectionResult, Message, OrchestratorWarning, OutputDetectionResult,
+            Role,
         },
     },
     models::{
-        DetectionWarningReason, DetectorParams, Metadata, UNSUITABLE_INPUT_MESSAGE,
-        UNSUITABLE_OUTPUT_MESSAGE,
+        DetectionResult, DetectionWarningReason, DetectorParams, Metadata,
+        UNSUITABLE_INPUT_MESSAGE, UNSUITABLE_OUTPUT_MESSAGE,
     },
     pb::{
         caikit::runtime::chunkers::ChunkerTokenizationTaskRequest,
@@ -49,6 +54,7 @@ use fms_guardrails_orchestr8::{
     },
     server,
 };
+use futures::TryStreamExt;
 use hyper::StatusCode;
 use mocktail::prelude::*;
 use serde_json::json;
@@ -974,6 +980,787 @@ async fn output_detections() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+// Validates that output detections of detectors with the `redact` action are masked in choices
+#[test(tokio::test)]
+async fn output_redaction() -> Result<(), anyhow::Error> {
+    let detector_name = DETECTOR_NAME_ANGLE_BRACKETS_SENTENCE;
+    let output_text = "Sure! Let me help you with <something>, just tell me what you need.";
+    let redacted_text =
+        "Sure! Let me help you with <[HAS_ANGLE_BRACKETS]>, just tell me what you need.";
+
+    let messages = vec![Message {
+        content: Some(Content::Text(
+            "Hi there! Can you help me with something?".into(),
+        )),
+        role: Role::User,
+        ..Default::default()
+    }];
+
+    // Add mocksets
+    let mut detector_mocks = MockSet::new();
+    let mut chat_mocks = MockSet::new();
+    let mut chunker_mocks = MockSet::new();
+
+    let detection = ContentAnalysisResponse {
+        start: 28,
+        end: 37,
+        text: "something".into(),
+        detection: "has_angle_brackets".into(),
+        detection_type: "angle_brackets".into(),
+        detector_id: Some(detector_name.into()),
+        score: 1.0,
+        evidence: None,
+        metadata: Metadata::new(),
+    };
+    let chat_completion = ChatCompletion {
+        model: MODEL_ID.into(),
+        choices: vec![ChatCompletionChoice {
+            message: ChatCompletionMessage {
+                role: Role::Assistant,
+                content: Some(output_text.into()),
+                refusal: None,
+                tool_calls: vec![],
+            },
+            index: 0,
+            logprobs: None,
+            finish_reason: "EOS_TOKEN".into(),
+            stop_reason: None,
+        }],
+        ..Default::default()
+    };
+
+    // Add detector mock for generated message
+    // NOTE: action params are not sent to detectors
+    detector_mocks.mock(|when, then| {
+        when.post()
+            .path(TEXT_CONTENTS_DETECTOR_ENDPOINT)
+            .json(ContentAnalysisRequest {
+                contents: vec![output_text.into()],
+                detector_params: DetectorParams::new(),
+            });
+        then.json([vec![&detection]]);
+    });
+
+    // Add chunker tokenization mock for generated message
+    chunker_mocks.mock(|when, then| {
+        when.path(CHUNKER_UNARY_ENDPOINT)
+            .header(CHUNKER_MODEL_ID_HEADER_NAME, CHUNKER_NAME_SENTENCE)
+            .pb(ChunkerTokenizationTaskRequest {
+                text: output_text.into(),
+            });
+        then.pb(TokenizationResults {
+            results: vec![Token {
+                start: 0,
+                end: output_text.len() as i64,
+                text: output_text.into(),
+            }],
+            token_count: 0,
+        });
+    });
+
+    // Add chat completions mock
+    chat_mocks.mock(|when, then| {
+        when.post().path(CHAT_COMPLETIONS_ENDPOINT).json(json!({
+            "model": MODEL_ID,
+            "messages": messages,
+        }));
+        then.json(&chat_completion);
+    });
+
+    // Start orchestrator server and its dependencies
+    let mock_detector_server = MockServer::new(detector_name).with_mocks(detector_mocks);
+    let mock_chat_completions_server = MockServer::new("chat_completions").with_mocks(chat_mocks);
+    let mock_chunker_server = MockServer::new(CHUNKER_NAME_SENTENCE)
+        .grpc()
+        .with_mocks(chunker_mocks);
+
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .detector_servers([&mock_detector_server])
+        .chunker_servers([&mock_chunker_server])
+        .chat_completions_server(&mock_chat_completions_server)
+        .build()
+        .await?;
+
+    // Make orchestrator call for output redaction
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_CHAT_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "detectors": {
+                "output": {
+                    detector_name: {
+                        "action": "redact",
+                    },
+                },
+            },
+            "messages": messages,
+        }))
+        .send()
+        .await?;
+
+    // Assertions for output redaction
+    assert_eq!(response.status(), StatusCode::OK);
+    let results = response.json::<ChatCompletion>().await?;
+    debug!("{results:#?}");
+    assert_eq!(
+        results.choices[0].message.content.as_deref(),
+        Some(redacted_text)
+    );
+    assert_eq!(
+        results.detections,
+        Some(ChatDetections {
+            input: vec![],
+            output: vec![OutputDetectionResult {
+                choice_index: 0,
+                results: vec![ContentAnalysisResponse {
+                    start: 28,
+                    end: 48,
+                    text: "[HAS_ANGLE_BRACKETS]".into(),
+                    ..detection
+                }],
+            }],
+        })
+    );
+    assert_eq!(
+        results.warnings,
+        vec![OrchestratorWarning::new(
+            DetectionWarningReason::UnsuitableOutput,
+            UNSUITABLE_OUTPUT_MESSAGE,
+        )]
+    );
+
+    Ok(())
+}
+
+// Validates that whole doc output detections of streaming chat completions are run on each
+// choice once it has finished and returned ordered by choice index in the final message
+#[test(tokio::test)]
+async fn streaming_whole_doc_output_detections() -> Result<(), anyhow::Error> {
+    let detector_name = DETECTOR_NAME_ANGLE_BRACKETS_WHOLE_DOC;
+    let messages = vec![Message {
+        content: Some(Content::Text("Say hello to Jane and John.".to_string())),
+        role: Role::User,
+        ..Default::default()
+    }];
+
+    // Add mocksets
+    let mut detector_mocks = MockSet::new();
+    let mut chat_mocks = MockSet::new();
+
+    // Add detector output mocks, matching only the full text of each choice
+    let expected_output = [("Hello <Jane>!", 6, 12), ("Hi <John>, bye.", 3, 9)]
+        .into_iter()
+        .enumerate()
+        .map(|(choice_index, (text, start, end))| {
+            let detections = vec![ContentAnalysisResponse {
+                start,
+                end,
+                text: text[start..end].into(),
+                detection: "has_angle_brackets".into(),
+                detection_type: "angle_brackets".into(),
+                detector_id: Some(detector_name.into()),
+                score: 1.0,
+                evidence: None,
+                metadata: Metadata::new(),
+            }];
+            detector_mocks.mock(|when, then| {
+                when.post()
+                    .path(TEXT_CONTENTS_DETECTOR_ENDPOINT)
+                    .json(ContentAnalysisRequest {
+                        contents: vec![text.into()],
+                        detector_params: DetectorParams::new(),
+                    });
+                then.json([&detections]);
+            });
+            OutputDetectionResult {
+                choice_index: choice_index as u32,
+                results: detections,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Add chat completions streaming mock, choice 0 finishes before choice 1
+    let chunks = [
+        (1, "Hi ", None),
+        (0, "Hello ", None),
+        (0, "<Jane>!", Some("stop")),
+        (1, "<John>,", None),
+        (1, " bye.", Some("stop")),
+    ]
+    .into_iter()
+    .map(|(index, content, finish_reason)| ChatCompletionChunk {
+        model: MODEL_ID.into(),
+        choices: vec![ChatCompletionChunkChoice {
+            index,
+            delta: ChatCompletionDelta {
+                content: Some(content.into()),
+                ..Default::default()
+            },
+            finish_reason: finish_reason.map(Into::into),
+            ..Default::default()
+        }],
+        ..Default::default()
+    })
+    .collect::<Vec<_>>();
+    let events = chunks
+        .iter()
+        .map(|chunk| format!("data: {}\n\n", serde_json::to_string(chunk).unwrap()))
+        .chain(["data: [DONE]\n\n".to_string()])
+        .collect::<Vec<_>>();
+    chat_mocks.mock(|when, then| {
+        when.post().path(CHAT_COMPLETIONS_ENDPOINT);
+        then.text_stream(events);
+    });
+
+    // Start orchestrator server and its dependencies
+    let mock_detector_server = MockServer::new(detector_name).with_mocks(detector_mocks);
+    let mock_chat_completions_server = MockServer::new("chat_completions").with_mocks(chat_mocks);
+
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .detector_servers([&mock_detector_server])
+        .chat_completions_server(&mock_chat_completions_server)
+        .build()
+        .await?;
+
+    // Make orchestrator call for streaming output detections
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_CHAT_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "stream": true,
+            "detectors": {
+                "input": {},
+                "output": {
+                    detector_name: {},
+                },
+            },
+            "messages": messages,
+        }))
+        .send()
+        .await?;
+
+    // Assertions for streaming output detections
+    assert_eq!(response.status(), StatusCode::OK);
+    let sse_stream: SseStream<ChatCompletionChunk> = SseStream::new(response.bytes_stream());
+    let results = sse_stream.try_collect::<Vec<_>>().await?;
+    debug!("{results:#?}");
+    // Chunks are forwarded as received, followed by a message with whole doc output detections
+    assert_eq!(results.len(), chunks.len() + 1);
+    for (result, chunk) in results.iter().zip(&chunks) {
+        assert_eq!(result.choices[0].index, chunk.choices[0].index);
+        assert_eq!(
+            result.choices[0].delta.content,
+            chunk.choices[0].delta.content
+        );
+    }
+    let last = results.last().unwrap();
+    assert!(last.choices.is_empty());
+    assert_eq!(
+        last.detections,
+        Some(ChatDetections {
+            input: vec![],
+            output: expected_output,
+        })
+    );
+    assert_eq!(
+        last.warnings,
+        vec![OrchestratorWarning::new(
+            DetectionWarningReason::UnsuitableOutput,
+            UNSUITABLE_OUTPUT_MESSAGE,
+        )]
+    );
+
+    Ok(())
+}
+
+// Validates that input detections of chat detectors, applied to all messages of the request,
+// block the chat completion
+#[test(tokio::test)]
+async fn input_chat_detections() -> Result<(), anyhow::Error> {
+    let detector_name = PII_DETECTOR;
+    let messages = vec![
+        Message {
+            content: Some(Content::Text("My email is jane@example.com.".into())),
+            role: Role::User,
+            ..Default::default()
+        },
+        Message {
+            content: Some(Content::Text("Can you send me a reminder?".into())),
+            role: Role::User,
+            ..Default::default()
+        },
+    ];
+
+    // Add detector mock
+    let mut detector_mocks = MockSet::new();
+    detector_mocks.mock(|when, then| {
+        when.post()
+            .path(CHAT_DETECTOR_ENDPOINT)
+            .json(ChatDetectionRequest {
+                messages: messages.clone(),
+                tools: vec![],
+                detector_params: DetectorParams::new(),
+            });
+        then.json([DetectionResult {
+            detection_type: "pii".into(),
+            detection: "is_pii".into(),
+            detector_id: Some(detector_name.into()),
+            score: 0.9,
+            evidence: None,
+            metadata: Metadata::new(),
+        }]);
+    });
+
+    // Start orchestrator server and its dependencies
+    let mock_detector_server = MockServer::new(detector_name).with_mocks(detector_mocks);
+    let mock_chat_completions_server = MockServer::new("chat_completions");
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .detector_servers([&mock_detector_server])
+        .chat_completions_server(&mock_chat_completions_server)
+        .build()
+        .await?;
+
+    // Make orchestrator call for input detections
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_CHAT_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "detectors": {
+                "input": {
+                    detector_name: {},
+                },
+            },
+            "messages": messages,
+        }))
+        .send()
+        .await?;
+
+    // Assertions for input detections
+    assert_eq!(response.status(), StatusCode::OK);
+    let results = response.json::<ChatCompletion>().await?;
+    assert!(results.choices.is_empty());
+    assert_eq!(
+        results.detections,
+        Some(ChatDetections {
+            input: vec![InputDetectionResult {
+                message_index: 1,
+                results: vec![ContentAnalysisResponse {
+                    start: 0,
+                    end: 0,
+                    text: "".into(),
+                    detection: "is_pii".into(),
+                    detection_type: "pii".into(),
+                    detector_id: Some(detector_name.into()),
+                    score: 0.9,
+                    evidence: None,
+                    metadata: Metadata::new(),
+                }],
+            }],
+            output: vec![],
+        })
+    );
+    assert_eq!(
+        results.warnings,
+        vec![OrchestratorWarning::new(
+            DetectionWarningReason::UnsuitableInput,
+            UNSUITABLE_INPUT_MESSAGE,
+        )]
+    );
+
+    Ok(())
+}
+
+// Validates that output detections of chat detectors are applied to the messages of the
+// request followed by each choice
+#[test(tokio::test)]
+async fn output_chat_detections() -> Result<(), anyhow::Error> {
+    let detector_name = PII_DETECTOR;
+    let input_text = "What is the email of Jane?";
+    let output_text = "Her email is jane@example.com.";
+    let messages = vec![Message {
+        content: Some(Content::Text(input_text.into())),
+        role: Role::User,
+        ..Default::default()
+    }];
+    let chat_completions_response = ChatCompletion {
+        model: MODEL_ID.into(),
+        choices: vec![ChatCompletionChoice {
+            message: ChatCompletionMessage {
+                role: Role::Assistant,
+                content: Some(output_text.into()),
+                refusal: None,
+                tool_calls: vec![],
+            },
+            index: 0,
+            logprobs: None,
+            finish_reason: "EOS_TOKEN".into(),
+            stop_reason: None,
+        }],
+        ..Default::default()
+    };
+
+    // Add detector mock for the messages followed by the choice
+    let mut detector_mocks = MockSet::new();
+    detector_mocks.mock(|when, then| {
+        when.post()
+            .path(CHAT_DETECTOR_ENDPOINT)
+            .json(ChatDetectionRequest {
+                messages: vec![
+                    messages[0].clone(),
+                    Message {
+                        content: Some(Content::Text(output_text.into())),
+                        role: Role::Assistant,
+                        ..Default::default()
+                    },
+                ],
+                tools: vec![],
+                detector_params: DetectorParams::new(),
+            });
+        then.json([DetectionResult {
+            detection_type: "pii".into(),
+            detection: "is_pii".into(),
+            detector_id: Some(detector_name.into()),
+            score: 0.9,
+            evidence: None,
+            metadata: Metadata::new(),
+        }]);
+    });
+
+    // Add chat completions mock
+    let mut chat_mocks = MockSet::new();
+    chat_mocks.mock(|when, then| {
+        when.post().path(CHAT_COMPLETIONS_ENDPOINT).json(json!({
+            "model": MODEL_ID,
+            "messages": messages,
+        }));
+        then.json(&chat_completions_response);
+    });
+
+    // Start orchestrator server and its dependencies
+    let mock_detector_server = MockServer::new(detector_name).with_mocks(detector_mocks);
+    let mock_chat_completions_server = MockServer::new("chat_completions").with_mocks(chat_mocks);
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .detector_servers([&mock_detector_server])
+        .chat_completions_server(&mock_chat_completions_server)
+        .build()
+        .await?;
+
+    // Make orchestrator call for output detections
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_CHAT_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "detectors": {
+                "output": {
+                    detector_name: {},
+                },
+            },
+            "messages": messages,
+        }))
+        .send()
+        .await?;
+
+    // Assertions for output detections, choice content is returned with a warning
+    assert_eq!(response.status(), StatusCode::OK);
+    let results = response.json::<ChatCompletion>().await?;
+    assert_eq!(results.choices, chat_completions_response.choices);
+    assert_eq!(
+        results.detections,
+        Some(ChatDetections {
+            input: vec![],
+            output: vec![OutputDetectionResult {
+                choice_index: 0,
+                results: vec![ContentAnalysisResponse {
+                    start: 0,
+                    end: 0,
+                    text: "".into(),
+                    detection: "is_pii".into(),
+                    detection_type: "pii".into(),
+                    detector_id: Some(detector_name.into()),
+                    score: 0.9,
+                    evidence: None,
+                    metadata: Metadata::new(),
+                }],
+            }],
+        })
+    );
+    assert_eq!(
+        results.warnings,
+        vec![OrchestratorWarning::new(
+            DetectionWarningReason::UnsuitableOutput,
+            UNSUITABLE_OUTPUT_MESSAGE,
+        )]
+    );
+
+    Ok(())
+}
+
+// Validates that output detections of generation detectors, applied to the last user message and
+// each choice, and of context docs detectors, applied to each choice with the content of system
+// messages as context, are merged per choice
+#[test(tokio::test)]
+async fn output_generation_and_context_docs_detections() -> Result<(), anyhow::Error> {
+    let context = "Jane's email is jane@example.com.";
+    let input_text = "What is the email of Jane?";
+    let output_text = "Her email is jane@example.org.";
+    let messages = vec![
+        Message {
+            content: Some(Content::Text(context.into())),
+            role: Role::System,
+            ..Default::default()
+        },
+        Message {
+            content: Some(Content::Text(input_text.into())),
+            role: Role::User,
+            ..Default::default()
+        },
+    ];
+    let chat_completions_response = ChatCompletion {
+        model: MODEL_ID.into(),
+        choices: vec![ChatCompletionChoice {
+            message: ChatCompletionMessage {
+                role: Role::Assistant,
+                content: Some(output_text.into()),
+                refusal: None,
+                tool_calls: vec![],
+            },
+            index: 0,
+            logprobs: None,
+            finish_reason: "EOS_TOKEN".into(),
+            stop_reason: None,
+        }],
+        ..Default::default()
+    };
+
+    // Add generation detector mock, with a detection below the default threshold
+    let mut generation_detector_mocks = MockSet::new();
+    generation_detector_mocks.mock(|when, then| {
+        when.post()
+            .path(DETECTION_ON_GENERATION_DETECTOR_ENDPOINT)
+            .json(GenerationDetectionRequest {
+                prompt: input_text.into(),
+                generated_text: output_text.into(),
+                detector_params: DetectorParams::new(),
+            });
+        then.json([DetectionResult {
+            detection_type: "relevance".into(),
+            detection: "is_irrelevant".into(),
+            detector_id: Some(ANSWER_RELEVANCE_DETECTOR.into()),
+            score: 0.1,
+            evidence: None,
+            metadata: Metadata::new(),
+        }]);
+    });
+
+    // Add context docs detector mock
+    let mut context_docs_detector_mocks = MockSet::new();
+    context_docs_detector_mocks.mock(|when, then| {
+        when.post()
+            .path(CONTEXT_DOC_DETECTOR_ENDPOINT)
+            .json(ContextDocsDetectionRequest {
+                content: output_text.into(),
+                context_type: ContextType::Document,
+                context: vec![context.into()],
+                detector_params: DetectorParams::new(),
+            });
+        then.json([DetectionResult {
+            detection_type: "groundedness".into(),
+            detection: "is_not_grounded".into(),
+            detector_id: Some(FACT_CHECKING_DETECTOR.into()),
+            score: 0.9,
+            evidence: None,
+            metadata: Metadata::new(),
+        }]);
+    });
+
+    // Add chat completions mock
+    let mut chat_mocks = MockSet::new();
+    chat_mocks.mock(|when, then| {
+        when.post().path(CHAT_COMPLETIONS_ENDPOINT).json(json!({
+            "model": MODEL_ID,
+            "messages": messages,
+        }));
+        then.json(&chat_completions_response);
+    });
+
+    // Start orchestrator server and its dependencies
+    let mock_generation_detector_server =
+        MockServer::new(ANSWER_RELEVANCE_DETECTOR).with_mocks(generation_detector_mocks);
+    let mock_context_docs_detector_server =
+        MockServer::new(FACT_CHECKING_DETECTOR).with_mocks(context_docs_detector_mocks);
+    let mock_chat_completions_server = MockServer::new("chat_completions").with_mocks(chat_mocks);
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .detector_servers([
+            &mock_generation_detector_server,
+            &mock_context_docs_detector_server,
+        ])
+        .chat_completions_server(&mock_chat_completions_server)
+        .build()
+        .await?;
+
+    // Make orchestrator call for output detections
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_CHAT_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "detectors": {
+                "output": {
+                    ANSWER_RELEVANCE_DETECTOR: {},
+                    FACT_CHECKING_DETECTOR: {},
+                },
+            },
+            "messages": messages,
+        }))
+        .send()
+        .await?;
+
+    // Assertions for output detections
+    assert_eq!(response.status(), StatusCode::OK);
+    let results = response.json::<ChatCompletion>().await?;
+    assert_eq!(results.choices, chat_completions_response.choices);
+    assert_eq!(
+        results.detections,
+        Some(ChatDetections {
+            input: vec![],
+            output: vec![OutputDetectionResult {
+                choice_index: 0,
+                results: vec![ContentAnalysisResponse {
+                    start: 0,
+                    end: 0,
+                    text: "".into(),
+                    detection: "is_not_grounded".into(),
+                    detection_type: "groundedness".into(),
+                    detector_id: Some(FACT_CHECKING_DETECTOR.into()),
+                    score: 0.9,
+                    evidence: None,
+                    metadata: Metadata::new(),
+                }],
+            }],
+        })
+    );
+    assert_eq!(
+        results.warnings,
+        vec![OrchestratorWarning::new(
+            DetectionWarningReason::UnsuitableOutput,
+            UNSUITABLE_OUTPUT_MESSAGE,
+        )]
+    );
+
+    Ok(())
+}
+
+// Validates that input detections of detectors with the `anonymize` action are replaced
+// with placeholders before chat completion and restored in choices
+#[test(tokio::test)]
+async fn input_anonymization() -> Result<(), anyhow::Error> {
+    let detector_name = DETECTOR_NAME_ANGLE_BRACKETS_WHOLE_DOC;
+    let input_text = "Hi, I am <Jane Doe>. Can you greet me?";
+    let anonymized_text = "Hi, I am <<SOMETHING_1>>. Can you greet me?";
+    let output_text = "Hello <Jane Doe>!";
+
+    // Add mocksets
+    let mut detector_mocks = MockSet::new();
+    let mut chat_mocks = MockSet::new();
+
+    // Add input detection mock
+    // NOTE: action params are not sent to detectors
+    detector_mocks.mock(|when, then| {
+        when.post()
+            .path(TEXT_CONTENTS_DETECTOR_ENDPOINT)
+            .json(ContentAnalysisRequest {
+                contents: vec![input_text.into()],
+                detector_params: DetectorParams::new(),
+            });
+        then.json([vec![ContentAnalysisResponse {
+            start: 10,
+            end: 18,
+            text: "Jane Doe".into(),
+            detection: "something".into(),
+            detection_type: "angle_brackets".into(),
+            detector_id: Some(detector_name.into()),
+            score: 1.0,
+            evidence: None,
+            metadata: Metadata::new(),
+        }]]);
+    });
+
+    // Add chat completions mock expecting the anonymized message
+    chat_mocks.mock(|when, then| {
+        when.post().path(CHAT_COMPLETIONS_ENDPOINT).json(json!({
+            "model": MODEL_ID,
+            "messages": [{
+                "role": "user",
+                "content": anonymized_text,
+            }],
+        }));
+        then.json(&ChatCompletion {
+            model: MODEL_ID.into(),
+            choices: vec![ChatCompletionChoice {
+                message: ChatCompletionMessage {
+                    role: Role::Assistant,
+                    content: Some("Hello <<SOMETHING_1>>!".into()),
+                    refusal: None,
+                    tool_calls: vec![],
+                },
+                index: 0,
+                logprobs: None,
+                finish_reason: "EOS_TOKEN".into(),
+                stop_reason: None,
+            }],
+            ..Default::default()
+        });
+    });
+
+    // Start orchestrator server and its dependencies
+    let mock_detector_server = MockServer::new(detector_name).with_mocks(detector_mocks);
+    let mock_chat_completions_server = MockServer::new("chat_completions").with_mocks(chat_mocks);
+
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .detector_servers([&mock_detector_server])
+        .chat_completions_server(&mock_chat_completions_server)
+        .build()
+        .await?;
+
+    // Make orchestrator call for input anonymization
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_CHAT_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "detectors": {
+                "input": {
+                    detector_name: {
+                        "action": "anonymize",
+                    },
+                },
+            },
+            "messages": [{
+                "role": "user",
+                "content": input_text,
+            }],
+        }))
+        .send()
+        .await?;
+
+    // Assertions for input anonymization
+    assert_eq!(response.status(), StatusCode::OK);
+    let results = response.json::<ChatCompletion>().await?;
+    debug!("{results:#?}");
+    assert_eq!(
+        results.choices[0].message.content.as_deref(),
+        Some(output_text)
+    );
+    assert!(results.detections.is_none());
+    assert!(results.warnings.is_empty());
+
+    Ok(())
+}
+
 // Validates that requests with output detector configured returns propagated errors
 // from detector, chunker and completions server when applicable
 #[test(tokio::test)]
@@ -1260,35 +2047,6 @@ async fn orchestrator_validation_error() -> Result<(), anyhow::Error> {
         "failed on non-existing input detector scenario"
     );
 
-    // Invalid output detector scenario
-    let response = orchestrator_server
-        .post(ORCHESTRATOR_CHAT_COMPLETIONS_DETECTION_ENDPOINT)
-        .json(&json!({
-            "model": MODEL_ID,
-            "detectors": {
-                "input": {},
-                "output": {
-                    ANSWER_RELEVANCE_DETECTOR: {},
-                },
-            },
-            "messages": messages,
-        }))
-        .send()
-        .await?;
-
-    let results = response.json::<server::Error>().await?;
-    debug!("{results:#?}");
-    assert_eq!(
-        results,
-        server::Error {
-            code: http::StatusCode::UNPROCESSABLE_ENTITY,
-            details: format!(
-                "detector `{ANSWER_RELEVANCE_DETECTOR}` is not supported by this endpoint"
-            )
-        },
-        "failed on invalid output detector scenario"
-    );
-
     // Non-existing output detector scenario
     let response = orchestrator_server
         .post(ORCHESTRATOR_CHAT_COMPLETIONS_DETECTION_ENDPOINT)
@@ -188,7 +188,8 @@ async fn detections() -> Result<(), anyhow::Error> {
     assert_eq!(
         response.json::<ChatDetectionResult>().await?,
         ChatDetectionResult {
-            detections: vec![detection]
+            detections: vec![detection],
+            warnings: vec![],
         }
     );
 
@@ -111,6 +111,7 @@ async fn no_detectors() -> Result<(), anyhow::Error> {
             inputs: inputs.into(),
             guardrail_config: None,
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -135,6 +136,7 @@ async fn no_detectors() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -164,6 +166,7 @@ async fn no_detectors() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -295,6 +298,7 @@ async fn no_detections() -> Result<(), anyhow::Error> {
             inputs: text_mock_input.clone(),
             guardrail_config: None,
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -325,6 +329,7 @@ async fn no_detections() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -354,6 +359,7 @@ async fn no_detections() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -548,6 +554,7 @@ async fn input_detector_detections() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -580,7 +587,8 @@ async fn input_detector_detections() -> Result<(), anyhow::Error> {
         results.warnings,
         Some(vec![DetectionWarning {
             id: Some(DetectionWarningReason::UnsuitableInput),
-            message: Some(ORCHESTRATOR_UNSUITABLE_INPUT_MESSAGE.into())
+            message: Some(ORCHESTRATOR_UNSUITABLE_INPUT_MESSAGE.into()),
+            policy: None,
         }])
     );
 
@@ -598,6 +606,7 @@ async fn input_detector_detections() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -752,6 +761,7 @@ async fn input_detector_client_error() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -777,6 +787,7 @@ async fn input_detector_client_error() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -802,6 +813,7 @@ async fn input_detector_client_error() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1029,6 +1041,7 @@ async fn output_detector_detections() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1070,6 +1083,7 @@ async fn output_detector_detections() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1258,6 +1272,7 @@ async fn output_detector_client_error() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1282,6 +1297,7 @@ async fn output_detector_client_error() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1306,6 +1322,7 @@ async fn output_detector_client_error() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1365,6 +1382,7 @@ async fn orchestrator_validation_error() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1396,6 +1414,7 @@ async fn orchestrator_validation_error() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1427,6 +1446,7 @@ async fn orchestrator_validation_error() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1457,6 +1477,7 @@ async fn orchestrator_validation_error() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
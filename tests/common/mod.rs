@@ -16,6 +16,7 @@
 */
 pub mod chat_completions;
 pub mod chunker;
+pub mod completions;
 pub mod detectors;
 pub mod errors;
 pub mod generation;
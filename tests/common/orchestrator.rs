@@ -50,6 +50,8 @@ pub const ORCHESTRATOR_GENERATION_WITH_DETECTION_ENDPOINT: &str =
     "/api/v2/text/generation-detection";
 
 pub const ORCHESTRATOR_CONTENT_DETECTION_ENDPOINT: &str = "/api/v2/text/detection/content";
+pub const ORCHESTRATOR_BATCH_CONTENT_DETECTION_ENDPOINT: &str =
+    "/api/v2/text/detection/content/batch";
 pub const ORCHESTRATOR_STREAM_CONTENT_DETECTION_ENDPOINT: &str =
     "/api/v2/text/detection/stream-content";
 pub const ORCHESTRATOR_DETECTION_ON_GENERATION_ENDPOINT: &str = "/api/v2/text/detection/generated";
@@ -58,6 +60,11 @@ pub const ORCHESTRATOR_CHAT_DETECTION_ENDPOINT: &str = "/api/v2/text/detection/c
 
 pub const ORCHESTRATOR_CHAT_COMPLETIONS_DETECTION_ENDPOINT: &str =
     "/api/v2/chat/completions-detection";
+pub const ORCHESTRATOR_COMPLETIONS_DETECTION_ENDPOINT: &str = "/api/v2/completions-detection";
+
+pub const ORCHESTRATOR_DETECTORS_ENDPOINT: &str = "/api/v1/detectors";
+pub const ORCHESTRATOR_CHUNKERS_ENDPOINT: &str = "/api/v1/chunkers";
+pub const ORCHESTRATOR_JOBS_ENDPOINT: &str = "/api/v2/jobs";
 
 // Messages
 pub const ORCHESTRATOR_UNSUITABLE_INPUT_MESSAGE: &str = "Unsuitable input detected. Please check the detected entities on your input and try again with the unsuitable input removed.";
@@ -73,6 +80,7 @@ pub struct TestOrchestratorServerBuilder<'a> {
     health_port: Option<u16>,
     generation_server: Option<&'a MockServer>,
     chat_completions_server: Option<&'a MockServer>,
+    completions_server: Option<&'a MockServer>,
     detector_servers: Option<Vec<&'a MockServer>>,
     chunker_servers: Option<Vec<&'a MockServer>>,
 }
@@ -107,6 +115,11 @@ impl<'a> TestOrchestratorServerBuilder<'a> {
         self
     }
 
+    pub fn completions_server(mut self, server: &'a MockServer) -> Self {
+        self.completions_server = Some(server);
+        self
+    }
+
     pub fn detector_servers(mut self, servers: impl IntoIterator<Item = &'a MockServer>) -> Self {
         self.detector_servers = Some(servers.into_iter().collect());
         self
@@ -127,6 +140,7 @@ impl<'a> TestOrchestratorServerBuilder<'a> {
         // Start & configure mock servers
         initialize_generation_server(self.generation_server, &mut config).await?;
         initialize_chat_completions_server(self.chat_completions_server, &mut config).await?;
+        initialize_completions_server(self.completions_server, &mut config).await?;
         initialize_detectors(self.detector_servers.as_deref(), &mut config).await?;
         initialize_chunkers(self.chunker_servers.as_deref(), &mut config).await?;
 
@@ -158,7 +172,18 @@ impl TestOrchestratorServer {
             let http_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), port);
             let health_http_addr: SocketAddr =
                 SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), health_port);
-            match server::run(http_addr, health_http_addr, None, None, None, orchestrator).await {
+            match server::run(
+                http_addr,
+                health_http_addr,
+                None,
+                None,
+                None,
+                None,
+                orchestrator,
+                None,
+            )
+            .await
+            {
                 Ok(_) => {
                     // Give the server time to become ready.
                     tokio::time::sleep(Duration::from_millis(10)).await;
@@ -198,6 +223,11 @@ impl TestOrchestratorServer {
         let url = self.server_url(path);
         self.client.post(url)
     }
+
+    pub fn delete(&self, path: &str) -> reqwest::RequestBuilder {
+        let url = self.server_url(path);
+        self.client.delete(url)
+    }
 }
 
 /// Starts and configures generation server.
@@ -226,6 +256,19 @@ async fn initialize_chat_completions_server(
     Ok(())
 }
 
+/// Starts and configures completions server.
+async fn initialize_completions_server(
+    completions_server: Option<&MockServer>,
+    config: &mut OrchestratorConfig,
+) -> Result<(), anyhow::Error> {
+    if let Some(completions_server) = completions_server {
+        completions_server.start().await?;
+        config.completions.as_mut().unwrap().service.port =
+            Some(completions_server.addr().unwrap().port());
+    };
+    Ok(())
+}
+
 /// Starts and configures detector servers.
 async fn initialize_detectors(
     detector_servers: Option<&[&MockServer]>,
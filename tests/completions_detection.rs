@@ -0,0 +1,456 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+use common::{
+    chunker::CHUNKER_UNARY_ENDPOINT,
+    completions::COMPLETIONS_ENDPOINT,
+    detectors::{
+        ANSWER_RELEVANCE_DETECTOR, DETECTOR_NAME_ANGLE_BRACKETS_SENTENCE,
+        TEXT_CONTENTS_DETECTOR_ENDPOINT,
+    },
+    orchestrator::{
+        ORCHESTRATOR_COMPLETIONS_DETECTION_ENDPOINT, ORCHESTRATOR_CONFIG_FILE_PATH, SseStream,
+        TestOrchestratorServer,
+    },
+};
+use fms_guardrails_orchestr8::{
+    clients::{
+        chunker::MODEL_ID_HEADER_NAME as CHUNKER_MODEL_ID_HEADER_NAME,
+        detector::{ContentAnalysisRequest, ContentAnalysisResponse},
+        openai::{
+            ChatDetections, Completion, CompletionChoice, InputDetectionResult,
+            OrchestratorWarning, OutputDetectionResult,
+        },
+    },
+    models::{
+        DetectionWarningReason, DetectorParams, Metadata, UNSUITABLE_INPUT_MESSAGE,
+        UNSUITABLE_OUTPUT_MESSAGE,
+    },
+    pb::{
+        caikit::runtime::chunkers::ChunkerTokenizationTaskRequest,
+        caikit_data_model::nlp::{Token, TokenizationResults},
+    },
+    server,
+};
+use futures::TryStreamExt;
+use hyper::StatusCode;
+use mocktail::prelude::*;
+use serde_json::json;
+use test_log::test;
+use tracing::debug;
+
+pub mod common;
+
+// Constants
+const CHUNKER_NAME_SENTENCE: &str = "sentence_chunker";
+const MODEL_ID: &str = "my-super-model-8B";
+
+// Validate passthrough scenario
+#[test(tokio::test)]
+async fn no_detectors() -> Result<(), anyhow::Error> {
+    let prompt = "Hi there!";
+
+    // Add mocksets
+    let mut completions_mocks = MockSet::new();
+
+    let completions_response = Completion {
+        model: MODEL_ID.into(),
+        choices: vec![CompletionChoice {
+            index: 0,
+            text: "Hello!".into(),
+            logprobs: None,
+            finish_reason: Some("stop".into()),
+            stop_reason: None,
+            prompt_logprobs: None,
+        }],
+        ..Default::default()
+    };
+
+    // Add completions mock
+    completions_mocks.mock(|when, then| {
+        when.post().path(COMPLETIONS_ENDPOINT).json(json!({
+            "model": MODEL_ID,
+            "prompt": prompt,
+        }));
+        then.json(&completions_response);
+    });
+
+    // Start orchestrator server and its dependencies
+    let mock_completions_server = MockServer::new("completions").with_mocks(completions_mocks);
+
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .completions_server(&mock_completions_server)
+        .build()
+        .await?;
+
+    // Missing `detectors` scenario
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "prompt": prompt,
+        }))
+        .send()
+        .await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let results = response.json::<Completion>().await?;
+    assert_eq!(results.choices, completions_response.choices);
+    assert_eq!(results.warnings, vec![]);
+    assert!(results.detections.is_none());
+
+    // `detectors` with empty `input` and `output` scenario
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "prompt": prompt,
+            "detectors": {
+                "input": {},
+                "output": {},
+            },
+        }))
+        .send()
+        .await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let results = response.json::<Completion>().await?;
+    assert_eq!(results.choices, completions_response.choices);
+    assert_eq!(results.warnings, vec![]);
+    assert!(results.detections.is_none());
+
+    Ok(())
+}
+
+// Validates that requests with input detector configured returns detections
+#[test(tokio::test)]
+async fn input_detections() -> Result<(), anyhow::Error> {
+    let detector_name = DETECTOR_NAME_ANGLE_BRACKETS_SENTENCE;
+    let prompt = "Hi there! Can you help me with <something>?";
+
+    // Add mocksets
+    let mut detector_mocks = MockSet::new();
+    let mut chunker_mocks = MockSet::new();
+
+    let expected_detections = vec![ContentAnalysisResponse {
+        start: 34,
+        end: 42,
+        text: "something".into(),
+        detection: "has_angle_brackets".into(),
+        detection_type: "angle_brackets".into(),
+        detector_id: Some(detector_name.into()),
+        score: 1.0,
+        evidence: None,
+        metadata: Metadata::new(),
+    }];
+
+    // Add chunker tokenization mock for input detection
+    chunker_mocks.mock(|when, then| {
+        when.path(CHUNKER_UNARY_ENDPOINT)
+            .header(CHUNKER_MODEL_ID_HEADER_NAME, CHUNKER_NAME_SENTENCE)
+            .pb(ChunkerTokenizationTaskRequest {
+                text: prompt.into(),
+            });
+        then.pb(TokenizationResults {
+            results: vec![Token {
+                start: 0,
+                end: prompt.len() as i64,
+                text: prompt.into(),
+            }],
+            token_count: 0,
+        });
+    });
+
+    // Add detector input mock
+    detector_mocks.mock(|when, then| {
+        when.post()
+            .path(TEXT_CONTENTS_DETECTOR_ENDPOINT)
+            .json(ContentAnalysisRequest {
+                contents: vec![prompt.into()],
+                detector_params: DetectorParams::new(),
+            });
+        then.json([&expected_detections]);
+    });
+
+    // Start orchestrator server and its dependencies
+    let mock_detector_server = MockServer::new(detector_name).with_mocks(detector_mocks);
+    let mock_chunker_server = MockServer::new(CHUNKER_NAME_SENTENCE)
+        .grpc()
+        .with_mocks(chunker_mocks);
+    let mock_completions_server = MockServer::new("completions");
+
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .detector_servers([&mock_detector_server])
+        .chunker_servers([&mock_chunker_server])
+        .completions_server(&mock_completions_server)
+        .build()
+        .await?;
+
+    let expected_detections = Some(ChatDetections {
+        input: vec![InputDetectionResult {
+            message_index: 0,
+            results: expected_detections,
+        }],
+        output: vec![],
+    });
+    let expected_warnings = vec![OrchestratorWarning::new(
+        DetectionWarningReason::UnsuitableInput,
+        UNSUITABLE_INPUT_MESSAGE,
+    )];
+
+    // Unary scenario
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "detectors": {
+                "input": {
+                    detector_name: {},
+                },
+                "output": {}
+            },
+            "prompt": prompt,
+        }))
+        .send()
+        .await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let results = response.json::<Completion>().await?;
+    assert_eq!(results.detections, expected_detections);
+    assert_eq!(results.choices, vec![]);
+    assert_eq!(results.warnings, expected_warnings);
+
+    // Streaming scenario
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "stream": true,
+            "detectors": {
+                "input": {
+                    detector_name: {},
+                },
+                "output": {}
+            },
+            "prompt": prompt,
+        }))
+        .send()
+        .await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let sse_stream: SseStream<Completion> = SseStream::new(response.bytes_stream());
+    let messages = sse_stream.try_collect::<Vec<_>>().await?;
+    debug!("{messages:#?}");
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].detections, expected_detections);
+    assert_eq!(messages[0].choices, vec![]);
+    assert_eq!(messages[0].warnings, expected_warnings);
+
+    Ok(())
+}
+
+// Validates that requests with output detector configured returns detections
+#[test(tokio::test)]
+async fn output_detections() -> Result<(), anyhow::Error> {
+    let detector_name = DETECTOR_NAME_ANGLE_BRACKETS_SENTENCE;
+    let prompt = "Hi there! Can you help me with something?";
+    let output_text = "Sure! Let me help you with <something>, just tell me what you need.";
+
+    // Add mocksets
+    let mut detector_mocks = MockSet::new();
+    let mut chunker_mocks = MockSet::new();
+    let mut completions_mocks = MockSet::new();
+
+    let expected_detections = vec![ContentAnalysisResponse {
+        start: 28,
+        end: 37,
+        text: "something".into(),
+        detection: "has_angle_brackets".into(),
+        detection_type: "angle_brackets".into(),
+        detector_id: Some(detector_name.into()),
+        score: 1.0,
+        evidence: None,
+        metadata: Metadata::new(),
+    }];
+
+    let completions_response = Completion {
+        model: MODEL_ID.into(),
+        choices: vec![CompletionChoice {
+            index: 0,
+            text: output_text.into(),
+            logprobs: None,
+            finish_reason: Some("stop".into()),
+            stop_reason: None,
+            prompt_logprobs: None,
+        }],
+        ..Default::default()
+    };
+
+    // Add chunker tokenization mock for output detection
+    chunker_mocks.mock(|when, then| {
+        when.path(CHUNKER_UNARY_ENDPOINT)
+            .header(CHUNKER_MODEL_ID_HEADER_NAME, CHUNKER_NAME_SENTENCE)
+            .pb(ChunkerTokenizationTaskRequest {
+                text: output_text.into(),
+            });
+        then.pb(TokenizationResults {
+            results: vec![Token {
+                start: 0,
+                end: output_text.len() as i64,
+                text: output_text.into(),
+            }],
+            token_count: 0,
+        });
+    });
+
+    // Add detector output mock
+    detector_mocks.mock(|when, then| {
+        when.post()
+            .path(TEXT_CONTENTS_DETECTOR_ENDPOINT)
+            .json(ContentAnalysisRequest {
+                contents: vec![output_text.into()],
+                detector_params: DetectorParams::new(),
+            });
+        then.json([&expected_detections]);
+    });
+
+    // Add completions mock
+    completions_mocks.mock(|when, then| {
+        when.post().path(COMPLETIONS_ENDPOINT).json(json!({
+            "model": MODEL_ID,
+            "prompt": prompt,
+        }));
+        then.json(&completions_response);
+    });
+
+    // Start orchestrator server and its dependencies
+    let mock_detector_server = MockServer::new(detector_name).with_mocks(detector_mocks);
+    let mock_chunker_server = MockServer::new(CHUNKER_NAME_SENTENCE)
+        .grpc()
+        .with_mocks(chunker_mocks);
+    let mock_completions_server = MockServer::new("completions").with_mocks(completions_mocks);
+
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .detector_servers([&mock_detector_server])
+        .chunker_servers([&mock_chunker_server])
+        .completions_server(&mock_completions_server)
+        .build()
+        .await?;
+
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "detectors": {
+                "input": {},
+                "output": {
+                    detector_name: {},
+                },
+            },
+            "prompt": prompt,
+        }))
+        .send()
+        .await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let results = response.json::<Completion>().await?;
+    assert_eq!(results.choices, completions_response.choices);
+    assert_eq!(
+        results.detections,
+        Some(ChatDetections {
+            input: vec![],
+            output: vec![OutputDetectionResult {
+                choice_index: 0,
+                results: expected_detections,
+            }],
+        })
+    );
+    assert_eq!(
+        results.warnings,
+        vec![OrchestratorWarning::new(
+            DetectionWarningReason::UnsuitableOutput,
+            UNSUITABLE_OUTPUT_MESSAGE,
+        )]
+    );
+
+    Ok(())
+}
+
+// Validate that invalid orchestrator requests returns 422 error
+#[test(tokio::test)]
+async fn orchestrator_validation_error() -> Result<(), anyhow::Error> {
+    // Start orchestrator server and its dependencies
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .build()
+        .await?;
+
+    // Empty prompt scenario
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "prompt": "",
+        }))
+        .send()
+        .await?;
+
+    let results = response.json::<server::Error>().await?;
+    debug!("{results:#?}");
+    assert_eq!(
+        results,
+        server::Error {
+            code: http::StatusCode::UNPROCESSABLE_ENTITY,
+            details: "`prompt` must not be empty".into(),
+        },
+        "failed on empty prompt scenario"
+    );
+
+    // Invalid input detector scenario
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_COMPLETIONS_DETECTION_ENDPOINT)
+        .json(&json!({
+            "model": MODEL_ID,
+            "detectors": {
+                "input": {
+                    ANSWER_RELEVANCE_DETECTOR: {},
+                },
+                "output": {}
+            },
+            "prompt": "Hi there!",
+        }))
+        .send()
+        .await?;
+
+    let results = response.json::<server::Error>().await?;
+    debug!("{results:#?}");
+    assert_eq!(
+        results,
+        server::Error {
+            code: http::StatusCode::UNPROCESSABLE_ENTITY,
+            details: format!(
+                "detector `{ANSWER_RELEVANCE_DETECTOR}` is not supported by this endpoint",
+            )
+        },
+        "failed on invalid input detector scenario"
+    );
+
+    Ok(())
+}
@@ -160,7 +160,8 @@ async fn detections() -> Result<(), anyhow::Error> {
     assert_eq!(
         response.json::<ContextDocsResult>().await?,
         ContextDocsResult {
-            detections: vec![detection]
+            detections: vec![detection],
+            warnings: vec![],
         }
     );
 
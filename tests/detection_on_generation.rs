@@ -157,7 +157,8 @@ async fn detections() -> Result<(), anyhow::Error> {
     assert_eq!(
         response.json::<DetectionOnGenerationResult>().await?,
         DetectionOnGenerationResult {
-            detections: vec![detection]
+            detections: vec![detection],
+            warnings: vec![],
         }
     );
 
@@ -0,0 +1,175 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+use common::{
+    chunker::CHUNKER_NAME_SENTENCE,
+    detectors::{
+        ANSWER_RELEVANCE_DETECTOR, DETECTOR_NAME_ANGLE_BRACKETS_SENTENCE,
+        DETECTOR_NAME_ANGLE_BRACKETS_WHOLE_DOC, NON_EXISTING_DETECTOR,
+    },
+    orchestrator::{
+        ORCHESTRATOR_CHUNKERS_ENDPOINT, ORCHESTRATOR_CONFIG_FILE_PATH,
+        ORCHESTRATOR_CONTENT_DETECTION_ENDPOINT, ORCHESTRATOR_DETECTION_ON_GENERATION_ENDPOINT,
+        ORCHESTRATOR_DETECTORS_ENDPOINT, ORCHESTRATOR_STREAM_CONTENT_DETECTION_ENDPOINT,
+        TestOrchestratorServer,
+    },
+};
+use fms_guardrails_orchestr8::server;
+use hyper::StatusCode;
+use serde_json::{Value, json};
+use test_log::test;
+
+pub mod common;
+
+/// Returns the (path, field) pairs of endpoints accepting a detector.
+fn endpoints(detector: &Value) -> Vec<(String, String)> {
+    detector["endpoints"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|endpoint| {
+            (
+                endpoint["path"].as_str().unwrap().to_string(),
+                endpoint["field"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[test(tokio::test)]
+async fn detectors() -> Result<(), anyhow::Error> {
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .build()
+        .await?;
+
+    let response = orchestrator_server
+        .get(ORCHESTRATOR_DETECTORS_ENDPOINT)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let results = response.json::<Value>().await?;
+    let detectors = results["detectors"].as_array().unwrap();
+    let detector = |detector_id: &str| {
+        detectors
+            .iter()
+            .find(|detector| detector["id"] == detector_id)
+            .unwrap()
+            .clone()
+    };
+
+    // Whole doc text contents detector is not accepted by streaming content detection
+    let whole_doc_detector = detector(DETECTOR_NAME_ANGLE_BRACKETS_WHOLE_DOC);
+    assert_eq!(whole_doc_detector["type"], "text_contents");
+    assert_eq!(whole_doc_detector["chunker_id"], "whole_doc_chunker");
+    assert_eq!(whole_doc_detector["default_threshold"], json!(0.5));
+    let whole_doc_endpoints = endpoints(&whole_doc_detector);
+    assert!(whole_doc_endpoints.contains(&(
+        ORCHESTRATOR_CONTENT_DETECTION_ENDPOINT.into(),
+        "detectors".into()
+    )));
+    assert!(!whole_doc_endpoints.contains(&(
+        ORCHESTRATOR_STREAM_CONTENT_DETECTION_ENDPOINT.into(),
+        "detectors".into()
+    )));
+
+    // Sentence text contents detector is accepted by streaming content detection
+    let sentence_detector = detector(DETECTOR_NAME_ANGLE_BRACKETS_SENTENCE);
+    assert_eq!(sentence_detector["chunker_id"], CHUNKER_NAME_SENTENCE);
+    assert!(endpoints(&sentence_detector).contains(&(
+        ORCHESTRATOR_STREAM_CONTENT_DETECTION_ENDPOINT.into(),
+        "detectors".into()
+    )));
+
+    // Text generation detector is only accepted by generation endpoints
+    let generation_detector = detector(ANSWER_RELEVANCE_DETECTOR);
+    assert_eq!(generation_detector["type"], "text_generation");
+    assert_eq!(
+        endpoints(&generation_detector),
+        vec![
+            (
+                "/api/v2/text/generation-detection".into(),
+                "detectors".into()
+            ),
+            (
+                ORCHESTRATOR_DETECTION_ON_GENERATION_ENDPOINT.into(),
+                "detectors".into()
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn detector() -> Result<(), anyhow::Error> {
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .build()
+        .await?;
+
+    let response = orchestrator_server
+        .get(&format!(
+            "{ORCHESTRATOR_DETECTORS_ENDPOINT}/{ANSWER_RELEVANCE_DETECTOR}"
+        ))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let results = response.json::<Value>().await?;
+    assert_eq!(results["id"], ANSWER_RELEVANCE_DETECTOR);
+    assert_eq!(results["type"], "text_generation");
+
+    // Asserts non-existing detector returns 404
+    let response = orchestrator_server
+        .get(&format!(
+            "{ORCHESTRATOR_DETECTORS_ENDPOINT}/{NON_EXISTING_DETECTOR}"
+        ))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let results = response.json::<server::Error>().await?;
+    assert_eq!(
+        results.details,
+        format!("detector `{NON_EXISTING_DETECTOR}` not found")
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn chunkers() -> Result<(), anyhow::Error> {
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .build()
+        .await?;
+
+    let response = orchestrator_server
+        .get(ORCHESTRATOR_CHUNKERS_ENDPOINT)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let results = response.json::<Value>().await?;
+    let chunker = results["chunkers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|chunker| chunker["id"] == CHUNKER_NAME_SENTENCE)
+        .unwrap();
+    assert_eq!(chunker["type"], "sentence");
+
+    Ok(())
+}
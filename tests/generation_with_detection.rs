@@ -205,7 +205,8 @@ async fn detections() -> Result<(), anyhow::Error> {
         GenerationWithDetectionResult {
             generated_text: generated_text.into(),
             detections: vec![detection.clone()],
-            input_token_count: 0
+            input_token_count: 0,
+            warnings: vec![],
         }
     );
 
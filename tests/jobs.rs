@@ -0,0 +1,161 @@
+/*
+ Copyright FMS Guardrails Orchestrator Authors
+
+ Licensed under the Apache License, Version 2.0 (the "License");
+ you may not use this file except in compliance with the License.
+ You may obtain a copy of the License at
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+ Unless required by applicable law or agreed to in writing, software
+ distributed under the License is distributed on an "AS IS" BASIS,
+ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ See the License for the specific language governing permissions and
+ limitations under the License.
+
+*/
+
+use std::time::Duration;
+
+use common::{
+    detectors::{DETECTOR_NAME_ANGLE_BRACKETS_WHOLE_DOC, TEXT_CONTENTS_DETECTOR_ENDPOINT},
+    orchestrator::{
+        ORCHESTRATOR_CONFIG_FILE_PATH, ORCHESTRATOR_JOBS_ENDPOINT, TestOrchestratorServer,
+    },
+};
+use fms_guardrails_orchestr8::{
+    clients::detector::{ContentAnalysisRequest, ContentAnalysisResponse},
+    models::{DetectorParams, Metadata, TextContentDetectionResult},
+    server,
+};
+use hyper::StatusCode;
+use mocktail::prelude::*;
+use serde_json::{Value, json};
+use test_log::test;
+use tracing::debug;
+
+pub mod common;
+
+/// Polls a job until it is finished.
+async fn wait_for_job(
+    orchestrator_server: &TestOrchestratorServer,
+    job_id: &str,
+) -> Result<Value, anyhow::Error> {
+    for _ in 0..100 {
+        let job = orchestrator_server
+            .get(&format!("{ORCHESTRATOR_JOBS_ENDPOINT}/{job_id}"))
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+        if job["status"] != "running" {
+            return Ok(job);
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    anyhow::bail!("job `{job_id}` did not finish")
+}
+
+/// Asserts a job running text content detection.
+#[test(tokio::test)]
+async fn text_content_detection_job() -> Result<(), anyhow::Error> {
+    let detector_name = DETECTOR_NAME_ANGLE_BRACKETS_WHOLE_DOC;
+    let detection = ContentAnalysisResponse {
+        start: 18,
+        end: 35,
+        text: "a detection here".into(),
+        detection: "has_angle_brackets".into(),
+        detection_type: "angle_brackets".into(),
+        detector_id: Some(detector_name.into()),
+        score: 1.0,
+        evidence: None,
+        metadata: Metadata::new(),
+    };
+
+    let mut detector_mocks = MockSet::new();
+    detector_mocks.mock(|when, then| {
+        when.post()
+            .path(TEXT_CONTENTS_DETECTOR_ENDPOINT)
+            .json(ContentAnalysisRequest {
+                contents: vec!["This sentence has <a detection here>.".into()],
+                detector_params: DetectorParams::new(),
+            });
+        then.json([[detection.clone()]]);
+    });
+
+    // Start orchestrator server and its dependencies
+    let mock_detector_server = MockServer::new(detector_name).with_mocks(detector_mocks);
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .detector_servers([&mock_detector_server])
+        .build()
+        .await?;
+
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_JOBS_ENDPOINT)
+        .json(&json!({
+            "task": "text_content_detection",
+            "request": {
+                "content": "This sentence has <a detection here>.",
+                "detectors": { detector_name: {} },
+            },
+        }))
+        .send()
+        .await?;
+    debug!("{response:#?}");
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let job = response.json::<Value>().await?;
+    assert_eq!(job["task"], "text_content_detection");
+    assert_eq!(job["progress"], json!({ "completed": 0, "total": 1 }));
+
+    let job = wait_for_job(&orchestrator_server, job["id"].as_str().unwrap()).await?;
+    debug!("{job:#?}");
+    assert_eq!(job["status"], "completed");
+    assert_eq!(job["progress"], json!({ "completed": 1, "total": 1 }));
+    assert_eq!(
+        serde_json::from_value::<TextContentDetectionResult>(job["result"].clone())?,
+        TextContentDetectionResult {
+            detections: vec![detection],
+            warnings: vec![],
+        }
+    );
+
+    Ok(())
+}
+
+/// Asserts job request validation and unknown jobs.
+#[test(tokio::test)]
+async fn job_errors() -> Result<(), anyhow::Error> {
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .build()
+        .await?;
+
+    // Invalid task request
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_JOBS_ENDPOINT)
+        .json(&json!({
+            "task": "text_content_detection",
+            "request": { "content": "", "detectors": {} },
+        }))
+        .send()
+        .await?;
+    debug!("{response:#?}");
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = response.json::<server::Error>().await?;
+    assert_eq!(response.details, "`content` is required");
+
+    // Unknown job
+    let response = orchestrator_server
+        .get(&format!("{ORCHESTRATOR_JOBS_ENDPOINT}/unknown"))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = orchestrator_server
+        .delete(&format!("{ORCHESTRATOR_JOBS_ENDPOINT}/unknown"))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
@@ -134,6 +134,7 @@ async fn no_detectors() -> Result<(), anyhow::Error> {
             inputs: "Hi there! How are you?".into(),
             guardrail_config: None,
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -159,6 +160,7 @@ async fn no_detectors() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -189,6 +191,7 @@ async fn no_detectors() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -306,6 +309,7 @@ async fn input_detector_no_detections() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -456,6 +460,7 @@ async fn input_detector_detections() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -493,7 +498,8 @@ async fn input_detector_detections() -> Result<(), anyhow::Error> {
         messages[0].warnings,
         Some(vec![DetectionWarning {
             id: Some(fms_guardrails_orchestr8::models::DetectionWarningReason::UnsuitableInput),
-            message: Some(ORCHESTRATOR_UNSUITABLE_INPUT_MESSAGE.into())
+            message: Some(ORCHESTRATOR_UNSUITABLE_INPUT_MESSAGE.into()),
+            policy: None,
         }])
     );
 
@@ -517,6 +523,7 @@ async fn input_detector_detections() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -676,6 +683,7 @@ async fn input_detector_client_error() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -707,6 +715,7 @@ async fn input_detector_client_error() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -739,6 +748,7 @@ async fn input_detector_client_error() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -813,6 +823,7 @@ async fn orchestrator_validation_error() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -849,6 +860,7 @@ async fn orchestrator_validation_error() -> Result<(), anyhow::Error> {
                 output: None,
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -885,6 +897,7 @@ async fn orchestrator_validation_error() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -923,6 +936,7 @@ async fn orchestrator_validation_error() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -958,6 +972,7 @@ async fn orchestrator_validation_error() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1144,6 +1159,7 @@ async fn output_detectors_no_detections() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1188,6 +1204,7 @@ async fn output_detectors_no_detections() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1425,6 +1442,7 @@ async fn output_detectors_detections() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1493,6 +1511,7 @@ async fn output_detectors_detections() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1776,6 +1795,7 @@ async fn output_detector_client_error() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -1806,6 +1826,7 @@ async fn output_detector_client_error() -> Result<(), anyhow::Error> {
                 }),
             }),
             text_gen_parameters: None,
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -192,11 +192,13 @@ async fn no_detections() -> Result<(), anyhow::Error> {
             detections: vec![],
             start_index: 0,
             processed_index: 9,
+            warnings: vec![],
         },
         StreamingContentDetectionResponse {
             detections: vec![],
             start_index: 9,
             processed_index: 22,
+            warnings: vec![],
         },
     ];
     assert_eq!(
@@ -248,11 +250,13 @@ async fn no_detections() -> Result<(), anyhow::Error> {
             detections: vec![],
             start_index: 0,
             processed_index: 9,
+            warnings: vec![],
         },
         StreamingContentDetectionResponse {
             detections: vec![],
             start_index: 9,
             processed_index: 22,
+            warnings: vec![],
         },
     ];
     assert_eq!(
@@ -412,6 +416,7 @@ async fn detections() -> Result<(), anyhow::Error> {
             detections: vec![],
             start_index: 0,
             processed_index: 11,
+            warnings: vec![],
         },
         StreamingContentDetectionResponse {
             detections: vec![ContentAnalysisResponse {
@@ -427,6 +432,7 @@ async fn detections() -> Result<(), anyhow::Error> {
             }],
             start_index: 11,
             processed_index: 26,
+            warnings: vec![],
         },
     ];
     assert_eq!(
@@ -472,6 +478,7 @@ async fn detections() -> Result<(), anyhow::Error> {
             }],
             start_index: 0,
             processed_index: 11,
+            warnings: vec![],
         },
         StreamingContentDetectionResponse {
             detections: vec![ContentAnalysisResponse {
@@ -487,6 +494,7 @@ async fn detections() -> Result<(), anyhow::Error> {
             }],
             start_index: 11,
             processed_index: 26,
+            warnings: vec![],
         },
     ];
     assert_eq!(
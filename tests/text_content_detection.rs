@@ -132,6 +132,7 @@ async fn no_detections() -> Result<(), anyhow::Error> {
         .json(&TextContentDetectionHttpRequest {
             content: "This sentence has no detections.".into(),
             detectors: HashMap::from([(whole_doc_detector.into(), DetectorParams::new())]),
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -154,6 +155,7 @@ async fn no_detections() -> Result<(), anyhow::Error> {
         .json(&TextContentDetectionHttpRequest {
             content: "This sentence does not have a detection. Neither does this one.".into(),
             detectors: HashMap::from([(sentence_detector.into(), DetectorParams::new())]),
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -274,6 +276,7 @@ async fn detections() -> Result<(), anyhow::Error> {
         .json(&TextContentDetectionHttpRequest {
             content: "This sentence has <a detection here>.".into(),
             detectors: HashMap::from([(whole_doc_detector.into(), DetectorParams::new())]),
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -300,6 +303,7 @@ async fn detections() -> Result<(), anyhow::Error> {
                 evidence: None,
                 metadata: Metadata::new(),
             }],
+            warnings: vec![],
         },
         "error on whole doc detector response body assertion"
     );
@@ -310,6 +314,7 @@ async fn detections() -> Result<(), anyhow::Error> {
         .json(&TextContentDetectionHttpRequest {
             content: "This sentence does not have a detection. But <this one does>.".into(),
             detectors: HashMap::from([(sentence_detector.into(), DetectorParams::new())]),
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -336,6 +341,7 @@ async fn detections() -> Result<(), anyhow::Error> {
                 evidence: None,
                 metadata: Metadata::new(),
             }],
+            warnings: vec![],
         },
         "error on sentence detector response body assertion"
     );
@@ -343,6 +349,93 @@ async fn detections() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Asserts detections with detectors provided by a guardrail profile.
+#[test(tokio::test)]
+async fn guardrail_profile() -> Result<(), anyhow::Error> {
+    let whole_doc_detector = DETECTOR_NAME_ANGLE_BRACKETS_WHOLE_DOC;
+
+    let mut whole_doc_detector_mocks = MockSet::new();
+    whole_doc_detector_mocks.mock(|when, then| {
+        when.post()
+            .path(TEXT_CONTENTS_DETECTOR_ENDPOINT)
+            .json(ContentAnalysisRequest {
+                contents: vec!["This sentence has <a detection here>.".into()],
+                detector_params: DetectorParams::new(),
+            });
+        then.json([[ContentAnalysisResponse {
+            start: 18,
+            end: 35,
+            text: "a detection here".into(),
+            detection: "has_angle_brackets".into(),
+            detection_type: "angle_brackets".into(),
+            detector_id: Some(whole_doc_detector.into()),
+            score: 1.0,
+            evidence: None,
+            metadata: Metadata::new(),
+        }]]);
+    });
+
+    // Start orchestrator server and its dependencies
+    let mock_whole_doc_detector_server =
+        MockServer::new(whole_doc_detector).with_mocks(whole_doc_detector_mocks);
+    let orchestrator_server = TestOrchestratorServer::builder()
+        .config_path(ORCHESTRATOR_CONFIG_FILE_PATH)
+        .detector_servers([&mock_whole_doc_detector_server])
+        .build()
+        .await?;
+
+    // Assert detectors are provided by the `angle_brackets` profile
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_CONTENT_DETECTION_ENDPOINT)
+        .json(&json!({
+            "content": "This sentence has <a detection here>.",
+            "guardrail_profile": "angle_brackets",
+        }))
+        .send()
+        .await?;
+    debug!("{response:#?}");
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = response.json::<TextContentDetectionResult>().await?;
+    debug!("{response:#?}");
+    assert_eq!(
+        response,
+        TextContentDetectionResult {
+            detections: vec![ContentAnalysisResponse {
+                start: 18,
+                end: 35,
+                text: "a detection here".into(),
+                detection: "has_angle_brackets".into(),
+                detection_type: "angle_brackets".into(),
+                detector_id: Some(whole_doc_detector.into()),
+                score: 1.0,
+                evidence: None,
+                metadata: Metadata::new(),
+            }],
+            warnings: vec![],
+        }
+    );
+
+    // Assert profile detector params are overridden by request detector params
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_CONTENT_DETECTION_ENDPOINT)
+        .json(&json!({
+            "content": "This sentence has <a detection here>.",
+            "guardrail_profile": "angle_brackets",
+            "detectors": {whole_doc_detector: {"threshold": 1.1}},
+        }))
+        .send()
+        .await?;
+    debug!("{response:#?}");
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = response.json::<TextContentDetectionResult>().await?;
+    debug!("{response:#?}");
+    assert_eq!(response, TextContentDetectionResult::default());
+
+    Ok(())
+}
+
 /// Asserts clients returning errors.
 #[test(tokio::test)]
 async fn client_error() -> Result<(), anyhow::Error> {
@@ -378,6 +471,7 @@ async fn client_error() -> Result<(), anyhow::Error> {
         .json(&TextContentDetectionHttpRequest {
             content: "This should return a 500".into(),
             detectors: HashMap::from([(detector_name.into(), DetectorParams::new())]),
+            guardrail_profile: None,
         })
         .send()
         .await?;
@@ -442,7 +536,27 @@ async fn orchestrator_validation_error() -> Result<(), anyhow::Error> {
     let response: server::Error = response.json().await?;
     debug!("orchestrator json response body:\n{response:#?}");
     assert_eq!(response.code, 422);
-    assert!(response.details.starts_with("missing field `detectors`"));
+    assert_eq!(response.details, "`detectors` is required");
+
+    // assert request with non-existing `guardrail_profile`
+    let response = orchestrator_server
+        .post(ORCHESTRATOR_CONTENT_DETECTION_ENDPOINT)
+        .json(&json!({
+            "content": "This sentence has no detections.",
+            "guardrail_profile": "non_existing_profile",
+        }))
+        .send()
+        .await?;
+    debug!("{response:#?}");
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response: server::Error = response.json().await?;
+    debug!("orchestrator json response body:\n{response:#?}");
+    assert_eq!(response.code, 422);
+    assert_eq!(
+        response.details,
+        "guardrail profile `non_existing_profile` not found"
+    );
 
     // assert request missing `content`
     let response = orchestrator_server